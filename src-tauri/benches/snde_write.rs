@@ -0,0 +1,165 @@
+//! SNDE write path: every worker funnelling seek+write through one
+//! `Mutex<File>` (what SNDE used to do), lock-free positional writes, and
+//! per-worker buffered handles (what SNDE does today).
+//!
+//! Run with `cargo bench --features bench --bench snde_write`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ownstash_downloader_lib::bench_support;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::runtime::Runtime;
+use tokio::sync::Mutex;
+
+const FILE_SIZE: u64 = 64 * 1024 * 1024;
+/// Roughly what one reqwest body frame delivers
+const PIECE_SIZE: usize = 16 * 1024;
+
+fn scratch_file() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ownstash-bench-snde-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("target.bin")
+}
+
+fn preallocated(path: &Path) -> std::fs::File {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .read(true)
+        .write(true)
+        .open(path)
+        .unwrap();
+    file.set_len(FILE_SIZE).unwrap();
+    file
+}
+
+#[cfg(unix)]
+fn write_all_at(file: &std::fs::File, buf: &[u8], offset: u64) -> std::io::Result<()> {
+    std::os::unix::fs::FileExt::write_all_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn write_all_at(file: &std::fs::File, mut buf: &[u8], mut offset: u64) -> std::io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        let written = file.seek_write(buf, offset)?;
+        buf = &buf[written..];
+        offset += written as u64;
+    }
+    Ok(())
+}
+
+/// Each worker owns one contiguous chunk, like SNDE's range split
+fn chunk_ranges(workers: u64) -> Vec<(u64, u64)> {
+    let chunk = FILE_SIZE / workers;
+    (0..workers).map(|i| (i * chunk, chunk)).collect()
+}
+
+async fn mutex_writes(file: Arc<Mutex<tokio::fs::File>>, workers: u64) {
+    let tasks: Vec<_> = chunk_ranges(workers)
+        .into_iter()
+        .map(|(start, len)| {
+            let file = file.clone();
+            tokio::spawn(async move {
+                let piece = vec![0xABu8; PIECE_SIZE];
+                let mut position = start;
+                while position < start + len {
+                    let mut file = file.lock().await;
+                    file.seek(std::io::SeekFrom::Start(position)).await.unwrap();
+                    file.write_all(&piece).await.unwrap();
+                    position += PIECE_SIZE as u64;
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    // tokio's File completes writes in the background; wait for the last one
+    file.lock().await.flush().await.unwrap();
+}
+
+async fn positional_writes(file: Arc<std::fs::File>, workers: u64) {
+    let tasks: Vec<_> = chunk_ranges(workers)
+        .into_iter()
+        .map(|(start, len)| {
+            let file = file.clone();
+            tokio::spawn(async move {
+                let piece = Arc::new(vec![0xABu8; PIECE_SIZE]);
+                let mut position = start;
+                while position < start + len {
+                    let (file, piece) = (file.clone(), piece.clone());
+                    tokio::task::spawn_blocking(move || write_all_at(&file, &piece, position))
+                        .await
+                        .unwrap()
+                        .unwrap();
+                    position += PIECE_SIZE as u64;
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+}
+
+async fn buffered_writes(path: &Path, workers: u64) {
+    let tasks: Vec<_> = chunk_ranges(workers)
+        .into_iter()
+        .map(|(start, len)| {
+            let path = path.to_path_buf();
+            tokio::spawn(async move {
+                let mut writer = bench_support::ChunkWriter::open(&path).await.unwrap();
+                writer.seek_to(start).await.unwrap();
+                let piece = vec![0xABu8; PIECE_SIZE];
+                let mut position = start;
+                while position < start + len {
+                    writer.write(&piece).await.unwrap();
+                    position += PIECE_SIZE as u64;
+                }
+                writer.flush().await.unwrap();
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+}
+
+fn bench_write_paths(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let path = scratch_file();
+    let mut group = c.benchmark_group("snde_write");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(FILE_SIZE));
+
+    for workers in [1u64, 4, 8, 16] {
+        group.bench_function(BenchmarkId::new("mutex_seek_write", workers), |b| {
+            b.iter(|| {
+                let file = Arc::new(Mutex::new(tokio::fs::File::from_std(preallocated(&path))));
+                rt.block_on(mutex_writes(file, workers));
+            })
+        });
+        group.bench_function(BenchmarkId::new("positional_write", workers), |b| {
+            b.iter(|| {
+                let file = Arc::new(preallocated(&path));
+                rt.block_on(positional_writes(file, workers));
+            })
+        });
+        group.bench_function(BenchmarkId::new("per_worker_buffered", workers), |b| {
+            b.iter(|| {
+                drop(preallocated(&path));
+                rt.block_on(buffered_writes(&path, workers));
+            })
+        });
+    }
+
+    group.finish();
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+criterion_group!(benches, bench_write_paths);
+criterion_main!(benches);
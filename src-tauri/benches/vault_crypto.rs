@@ -0,0 +1,96 @@
+//! Vault encryption throughput: chunked AES-GCM (with and without zstd) and the
+//! folder ZIP-then-encrypt flow.
+//!
+//! Run with `cargo bench --features bench --bench vault_crypto`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ownstash_downloader_lib::bench_support;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::fs;
+use std::path::PathBuf;
+
+const KEY: [u8; 32] = [7u8; 32];
+const MIB: usize = 1024 * 1024;
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ownstash-bench-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Random bytes model already-compressed media; a repeating pattern models text
+fn sample(len: usize, compressible: bool) -> Vec<u8> {
+    if compressible {
+        return (0..len).map(|i| (i * 31 % 251) as u8).collect();
+    }
+    let mut data = vec![0u8; len];
+    StdRng::seed_from_u64(42).fill_bytes(&mut data);
+    data
+}
+
+fn bench_chunked_encryption(c: &mut Criterion) {
+    let dir = scratch_dir("slv2");
+    let mut group = c.benchmark_group("slv2");
+    group.sample_size(10);
+
+    for size_mib in [1, 16, 64] {
+        let len = size_mib * MIB;
+        let random = dir.join(format!("random-{}", size_mib));
+        let text = dir.join(format!("text-{}", size_mib));
+        let sealed = dir.join(format!("sealed-{}", size_mib));
+        let opened = dir.join(format!("opened-{}", size_mib));
+        fs::write(&random, sample(len, false)).unwrap();
+        fs::write(&text, sample(len, true)).unwrap();
+
+        group.throughput(Throughput::Bytes(len as u64));
+        let label = format!("{}MiB", size_mib);
+
+        group.bench_function(BenchmarkId::new("encrypt", &label), |b| {
+            b.iter(|| bench_support::encrypt_file(&KEY, &random, &sealed, false).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("encrypt_zstd_compressible", &label), |b| {
+            b.iter(|| bench_support::encrypt_file(&KEY, &text, &sealed, true).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("encrypt_zstd_incompressible", &label), |b| {
+            b.iter(|| bench_support::encrypt_file(&KEY, &random, &sealed, true).unwrap())
+        });
+
+        bench_support::encrypt_file(&KEY, &random, &sealed, false).unwrap();
+        group.bench_function(BenchmarkId::new("decrypt", &label), |b| {
+            b.iter(|| bench_support::decrypt_file(&KEY, &sealed, &opened).unwrap())
+        });
+    }
+
+    group.finish();
+    let _ = fs::remove_dir_all(&dir);
+}
+
+fn bench_folder_flow(c: &mut Criterion) {
+    let dir = scratch_dir("folder");
+    let mut group = c.benchmark_group("vault_folder");
+    group.sample_size(10);
+
+    // Many small files vs a few large ones, half text and half random
+    for (files, file_size) in [(256, 64 * 1024), (8, 4 * MIB)] {
+        let source = dir.join(format!("source-{}x{}", files, file_size));
+        fs::create_dir_all(source.join("nested")).unwrap();
+        for i in 0..files {
+            let sub = if i % 2 == 0 { source.clone() } else { source.join("nested") };
+            fs::write(sub.join(format!("file-{}.bin", i)), sample(file_size, i % 2 == 0)).unwrap();
+        }
+
+        let zip_path = dir.join("folder.zip");
+        let output = dir.join("folder.slasshy");
+        group.throughput(Throughput::Bytes((files * file_size) as u64));
+        group.bench_function(BenchmarkId::new("zip_then_encrypt", format!("{}x{}KiB", files, file_size / 1024)), |b| {
+            b.iter(|| bench_support::zip_then_encrypt(&KEY, &source, &zip_path, &output).unwrap())
+        });
+    }
+
+    group.finish();
+    let _ = fs::remove_dir_all(&dir);
+}
+
+criterion_group!(benches, bench_chunked_encryption, bench_folder_flow);
+criterion_main!(benches);
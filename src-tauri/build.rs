@@ -6,7 +6,7 @@ fn main() {
     
     if binaries_dir.exists() {
         let required_binaries = if cfg!(windows) {
-            vec!["yt-dlp.exe", "ffmpeg.exe", "ffprobe.exe"]
+            vec!["yt-dlp.exe", "ffmpeg.exe", "ffprobe.exe", "aria2c.exe"]
         } else if cfg!(target_os = "macos") {
             vec!["yt-dlp_macos", "ffmpeg", "ffprobe"]
         } else {
@@ -1,4 +1,4 @@
-# PowerShell script to download yt-dlp, spotdl, and ffmpeg binaries for Windows
+# PowerShell script to download yt-dlp, spotdl, ffmpeg and aria2 binaries for Windows
 # Run this script before building the app
 
 $binariesDir = Join-Path $PSScriptRoot "binaries"
@@ -66,6 +66,25 @@ Write-Host "Cleaning up..." -ForegroundColor Cyan
 Remove-Item $ffmpegZipPath -Force
 Remove-Item $ffmpegExtractPath -Recurse -Force
 
+Write-Host "Downloading aria2..." -ForegroundColor Cyan
+$aria2ReleasesUrl = "https://api.github.com/repos/aria2/aria2/releases/latest"
+try {
+    $aria2Release = Invoke-RestMethod -Uri $aria2ReleasesUrl -Headers @{ "User-Agent" = "PowerShell" }
+    $aria2Asset = $aria2Release.assets | Where-Object { $_.name -match "win-64bit.*\.zip$" } | Select-Object -First 1
+    $aria2ZipPath = Join-Path $binariesDir "aria2.zip"
+    $aria2ExtractPath = Join-Path $binariesDir "aria2-temp"
+    Invoke-WebRequest -Uri $aria2Asset.browser_download_url -OutFile $aria2ZipPath
+    Expand-Archive -Path $aria2ZipPath -DestinationPath $aria2ExtractPath -Force
+    $aria2Exe = Get-ChildItem -Path $aria2ExtractPath -Recurse -Filter "aria2c.exe" | Select-Object -First 1
+    Copy-Item $aria2Exe.FullName -Destination (Join-Path $binariesDir "aria2c.exe")
+    Write-Host "  Copied aria2c.exe to binaries folder" -ForegroundColor Green
+    Remove-Item $aria2ZipPath -Force
+    Remove-Item $aria2ExtractPath -Recurse -Force
+} catch {
+    Write-Host "  Error downloading aria2: $_" -ForegroundColor Red
+    Write-Host "  You may need to download manually from: https://github.com/aria2/aria2/releases" -ForegroundColor Yellow
+}
+
 Write-Host "`nDone! Binaries downloaded to: $binariesDir" -ForegroundColor Green
 Get-ChildItem $binariesDir | ForEach-Object { Write-Host "  - $($_.Name) ($([math]::Round($_.Length / 1MB, 2)) MB)" }
 
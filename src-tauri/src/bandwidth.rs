@@ -0,0 +1,256 @@
+//! Bandwidth Limiter
+//!
+//! Token-bucket rate limiting shared by the download engines so users on
+//! shared connections can cap how much of the link the app uses.
+//!
+//! Key Features:
+//! - One global limit applied across every active SNDE download
+//! - Optional per-download limits layered on top of the global one
+//! - Limits can be changed at any time and apply to running SNDE workers immediately
+//! - yt-dlp downloads receive the effective limit as `--limit-rate` when they start
+
+use crate::commands::AppState;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::State;
+// Tokio's clock, so tests can pause time; it's the system clock otherwise
+use tokio::time::Instant;
+
+/// Settings key for the persisted global limit (KB/s, 0 = unlimited)
+pub const BANDWIDTH_LIMIT_SETTING: &str = "bandwidth_limit_kbps";
+
+/// Longest a waiting worker sleeps before re-checking the limit
+const MAX_WAIT_SLICE: Duration = Duration::from_millis(250);
+
+/// Bucket fill state
+#[derive(Debug)]
+struct BucketState {
+    /// Available bytes (negative while a worker is paying off a burst)
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl BucketState {
+    fn refill(&mut self, rate_bps: u64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+        // Allow at most one second worth of burst
+        self.tokens = (self.tokens + elapsed * rate_bps as f64).min(rate_bps as f64);
+    }
+}
+
+/// A live-adjustable token bucket
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    /// Limit in bytes per second (0 = unlimited)
+    rate_bps: AtomicU64,
+    state: Mutex<BucketState>,
+}
+
+impl BandwidthLimiter {
+    /// Create a limiter with the given limit in KB/s (0 = unlimited)
+    pub fn new(limit_kbps: u64) -> Self {
+        Self {
+            rate_bps: AtomicU64::new(limit_kbps.saturating_mul(1024)),
+            state: Mutex::new(BucketState {
+                tokens: 0.0,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Current limit in KB/s (0 = unlimited)
+    pub fn limit_kbps(&self) -> u64 {
+        self.rate_bps.load(Ordering::Relaxed) / 1024
+    }
+
+    /// Change the limit; waiting workers pick it up on their next check
+    pub fn set_limit_kbps(&self, limit_kbps: u64) {
+        self.rate_bps
+            .store(limit_kbps.saturating_mul(1024), Ordering::Relaxed);
+        if let Ok(mut state) = self.state.lock() {
+            // Drop any accumulated debt so a raised limit takes effect right away
+            state.tokens = state.tokens.max(0.0);
+            state.last_refill = Instant::now();
+        }
+    }
+
+    /// Account for `bytes` just transferred, sleeping until the bucket allows it
+    pub async fn acquire(&self, bytes: u64) {
+        let rate = self.rate_bps.load(Ordering::Relaxed);
+        if rate == 0 || bytes == 0 {
+            return;
+        }
+
+        if let Ok(mut state) = self.state.lock() {
+            state.refill(rate);
+            state.tokens -= bytes as f64;
+        }
+
+        loop {
+            let rate = self.rate_bps.load(Ordering::Relaxed);
+            if rate == 0 {
+                return;
+            }
+
+            let deficit = match self.state.lock() {
+                Ok(mut state) => {
+                    state.refill(rate);
+                    -state.tokens
+                }
+                Err(_) => return,
+            };
+
+            if deficit <= 0.0 {
+                return;
+            }
+
+            let wait = Duration::from_secs_f64(deficit / rate as f64).min(MAX_WAIT_SLICE);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Limit shared by every download
+    pub static ref GLOBAL_BANDWIDTH_LIMITER: BandwidthLimiter = BandwidthLimiter::new(0);
+
+    /// Per-download limiters keyed by download ID
+    static ref DOWNLOAD_LIMITERS: Mutex<HashMap<String, Arc<BandwidthLimiter>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Get (or lazily create) the limiter for a single download
+pub fn download_limiter(id: &str) -> Arc<BandwidthLimiter> {
+    let mut limiters = DOWNLOAD_LIMITERS.lock().unwrap();
+    limiters
+        .entry(id.to_string())
+        .or_insert_with(|| Arc::new(BandwidthLimiter::new(0)))
+        .clone()
+}
+
+/// Set the limit for a single download (0 = only the global limit applies)
+pub fn set_download_limit(id: &str, limit_kbps: u64) {
+    download_limiter(id).set_limit_kbps(limit_kbps);
+}
+
+/// Forget a finished download's limiter
+pub fn remove_download_limit(id: &str) {
+    let mut limiters = DOWNLOAD_LIMITERS.lock().unwrap();
+    limiters.remove(id);
+}
+
+/// Set a running download's limit. Limiters are removed when a download finishes, so one
+/// set for an id that isn't running would never be.
+fn limit_running_download(id: &str, limit_kbps: u64) -> Result<(), String> {
+    set_download_limit(id, limit_kbps);
+    // Checked after setting, so a download finishing in between still has it removed
+    if !crate::downloader::is_active_download(id) {
+        remove_download_limit(id);
+        return Err(format!("Download {} isn't running", id));
+    }
+    Ok(())
+}
+
+/// The tightest limit that applies to a download, in KB/s (0 = unlimited)
+pub fn effective_limit_kbps(id: &str) -> u64 {
+    let global = GLOBAL_BANDWIDTH_LIMITER.limit_kbps();
+    let per_download = {
+        let limiters = DOWNLOAD_LIMITERS.lock().unwrap();
+        limiters.get(id).map(|l| l.limit_kbps()).unwrap_or(0)
+    };
+
+    match (global, per_download) {
+        (0, d) => d,
+        (g, 0) => g,
+        (g, d) => g.min(d),
+    }
+}
+
+/// Apply the persisted global limit at startup
+pub fn load_from_settings(db: &crate::database::Database) {
+    let limit = db
+        .get_setting(BANDWIDTH_LIMIT_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+
+    GLOBAL_BANDWIDTH_LIMITER.set_limit_kbps(limit);
+    if limit > 0 {
+        println!("[Bandwidth] Global limit restored: {} KB/s", limit);
+    }
+}
+
+/// Set the global limit, or a single download's limit when `download_id` is given.
+/// A limit of 0 removes the cap.
+#[tauri::command]
+pub async fn set_bandwidth_limit(
+    state: State<'_, AppState>,
+    limit_kbps: u64,
+    download_id: Option<String>,
+) -> Result<(), String> {
+    if let Some(id) = download_id {
+        limit_running_download(&id, limit_kbps)?;
+        println!("[Bandwidth] Download {} limit set to {} KB/s", id, limit_kbps);
+        return Ok(());
+    }
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(BANDWIDTH_LIMIT_SETTING, &limit_kbps.to_string())
+        .map_err(|e| e.to_string())?;
+    drop(db);
+
+    GLOBAL_BANDWIDTH_LIMITER.set_limit_kbps(limit_kbps);
+    println!("[Bandwidth] Global limit set to {} KB/s", limit_kbps);
+    Ok(())
+}
+
+/// Get the global limit in KB/s (0 = unlimited)
+#[tauri::command]
+pub async fn get_bandwidth_limit() -> Result<u64, String> {
+    Ok(GLOBAL_BANDWIDTH_LIMITER.limit_kbps())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unlimited_does_not_wait() {
+        let limiter = BandwidthLimiter::new(0);
+        let start = Instant::now();
+        limiter.acquire(100 * 1024 * 1024).await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_limit_throttles() {
+        tokio::time::pause();
+        // 100 KB/s, 50 KB should take half a second
+        let limiter = BandwidthLimiter::new(100);
+        let start = Instant::now();
+        limiter.acquire(50 * 1024).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(500), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(600), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_limit_for_a_download_that_is_not_running_is_not_kept() {
+        assert!(limit_running_download("test-not-running", 200).is_err());
+        assert!(!DOWNLOAD_LIMITERS.lock().unwrap().contains_key("test-not-running"));
+    }
+
+    #[test]
+    fn test_effective_limit() {
+        GLOBAL_BANDWIDTH_LIMITER.set_limit_kbps(0);
+        set_download_limit("test-effective", 500);
+        assert_eq!(effective_limit_kbps("test-effective"), 500);
+        remove_download_limit("test-effective");
+        assert_eq!(effective_limit_kbps("test-effective"), 0);
+    }
+}
@@ -0,0 +1,38 @@
+//! Benchmark Support
+//!
+//! Public wrappers over internal hot paths so the criterion benchmarks in `benches/`
+//! can measure them. Only compiled with the `bench` feature.
+//!
+//! Key Features:
+//! - SLV2 chunked AES-GCM encryption and decryption
+//! - The ZIP-then-encrypt flow behind `vault_add_folder`
+//! - SNDE's per-worker buffered write path
+
+use std::path::PathBuf;
+
+/// The handle each SNDE worker writes its chunks through
+pub use crate::snde::ChunkWriter;
+
+/// Encrypt a file to SLV2, optionally zstd compressing each chunk
+pub fn encrypt_file(key: &[u8; 32], input: &PathBuf, output: &PathBuf, compress: bool) -> Result<(), String> {
+    crate::vault::encrypt_file_with(key, input, output, compress)
+}
+
+pub fn decrypt_file(key: &[u8; 32], input: &PathBuf, output: &PathBuf) -> Result<(), String> {
+    crate::vault::decrypt_file(key, input, output)
+}
+
+/// ZIP a folder to `zip_path`, then encrypt the ZIP to `output`; returns the encrypted size
+pub fn zip_then_encrypt(
+    key: &[u8; 32],
+    source_dir: &PathBuf,
+    zip_path: &PathBuf,
+    output: &PathBuf,
+) -> Result<u64, String> {
+    let (entries, _) = crate::vault::collect_folder_entries(source_dir);
+    crate::vault::write_folder_zip(source_dir, &entries, zip_path)?;
+    crate::vault::encrypt_file_with(key, zip_path, output, false)?;
+    std::fs::metadata(output)
+        .map(|m| m.len())
+        .map_err(|e| format!("Failed to read encrypted size: {}", e))
+}
@@ -0,0 +1,187 @@
+//! Engine Capabilities
+//!
+//! One report of which download engines can run on this machine, so the UI can enable or
+//! disable options up front instead of letting a download fail when it starts.
+//!
+//! Key Features:
+//! - SNDE is always available; the other engines depend on their binaries
+//! - Versions of yt-dlp, SpotDL, aria2c, gallery-dl and ffmpeg
+//! - Per-engine feature lists, with ffmpeg-dependent features dropped when it's missing
+//! - Checks run concurrently
+
+use crate::downloader::{Downloader, YtDlpChannel};
+use crate::spotify_downloader::SpotifyDownloader;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineCapability {
+    /// Stable identifier: "snde", "media", "spotify", "aria2", "gallery", "ftp", "torrent"
+    pub id: String,
+    pub name: String,
+    pub available: bool,
+    pub version: Option<String>,
+    /// Why the engine can't be used, when it can't
+    pub unavailable_reason: Option<String>,
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineCapabilities {
+    pub engines: Vec<EngineCapability>,
+    pub ffmpeg_version: Option<String>,
+}
+
+fn features(list: &[&str]) -> Vec<String> {
+    list.iter().map(|f| f.to_string()).collect()
+}
+
+fn engine(id: &str, name: &str, version: Result<String, String>, engine_features: Vec<String>) -> EngineCapability {
+    let available = version.is_ok();
+    EngineCapability {
+        id: id.to_string(),
+        name: name.to_string(),
+        available,
+        unavailable_reason: version.as_ref().err().cloned(),
+        version: version.ok(),
+        features: if available { engine_features } else { Vec::new() },
+    }
+}
+
+/// What the Media Engine can do; merging and conversions need ffmpeg
+fn media_engine_features(has_ffmpeg: bool) -> Vec<String> {
+    let mut list = features(&["playlists", "subtitles", "sponsorblock", "cookies", "download_archive"]);
+    if has_ffmpeg {
+        list.extend(features(&[
+            "merge_formats",
+            "audio_extraction",
+            "remux",
+            "clips",
+            "embed_thumbnail",
+            "embed_chapters",
+            "device_transcode",
+        ]));
+    }
+    list
+}
+
+/// First line of `<binary> --version`
+async fn binary_version(binary: &Path) -> Result<String, String> {
+    let mut cmd = crate::exec_guard::tokio_command(binary)?;
+    cmd.arg("--version");
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {:?}: {}", binary, e))?;
+    if !output.status.success() {
+        return Err(format!("{:?} returned an error", binary));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_string())
+}
+
+async fn aria2_version(app_handle: &AppHandle) -> Result<String, String> {
+    let binary = crate::aria2::find_aria2c(app_handle).ok_or("aria2c is not bundled")?;
+    if !crate::aria2::is_enabled() {
+        return Err("Disabled in settings".to_string());
+    }
+    // "aria2 version 1.37.0"
+    let line = binary_version(&binary).await?;
+    Ok(line.rsplit(' ').next().unwrap_or(&line).to_string())
+}
+
+async fn gallery_dl_version(app_handle: &AppHandle) -> Result<String, String> {
+    let binary = crate::gallery::find_gallery_dl(app_handle).ok_or("gallery-dl is not installed")?;
+    binary_version(&binary).await
+}
+
+/// Which engines can run here, their versions and what they support
+#[tauri::command]
+pub async fn get_engine_capabilities(app_handle: AppHandle) -> Result<EngineCapabilities, String> {
+    let downloader = Downloader::new(&app_handle);
+    let spotify = SpotifyDownloader::new(&app_handle);
+    let (yt_dlp, spotdl, ffmpeg, aria2, gallery) = tokio::join!(
+        downloader.check_yt_dlp(false, YtDlpChannel::default()),
+        spotify.check_spotdl(false),
+        crate::ffmpeg::check_ffmpeg_status(app_handle.clone(), Some(false)),
+        aria2_version(&app_handle),
+        gallery_dl_version(&app_handle),
+    );
+    let ffmpeg_version = ffmpeg.ok().filter(|info| info.is_available).map(|info| info.version);
+    let has_ffmpeg = ffmpeg_version.is_some();
+
+    let engines = vec![
+        EngineCapability {
+            id: "snde".to_string(),
+            name: "SNDE".to_string(),
+            available: true,
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            unavailable_reason: None,
+            features: features(&[
+                "parallel_ranges",
+                "bandwidth_limit",
+                "custom_headers",
+                "warc_capture",
+                #[cfg(feature = "http3")]
+                "http3",
+            ]),
+        },
+        engine(
+            "media",
+            "Media Engine",
+            yt_dlp.map(|info| info.version),
+            media_engine_features(has_ffmpeg),
+        ),
+        engine(
+            "spotify",
+            "SpotDL",
+            spotdl.map(|info| info.version),
+            features(&["tracks", "albums", "playlists", "artists"]),
+        ),
+        engine("aria2", "aria2", aria2, features(&["hls", "parallel_segments"])),
+        engine("gallery", "gallery-dl", gallery, features(&["galleries", "profiles", "cookies"])),
+        engine(
+            "ftp",
+            "FTP/SFTP",
+            Ok(env!("CARGO_PKG_VERSION").to_string()),
+            features(&["ftp", "sftp", "resume", "bandwidth_limit"]),
+        ),
+        engine(
+            "torrent",
+            "Torrent",
+            Err("Torrent downloads aren't supported in this build".to_string()),
+            Vec::new(),
+        ),
+    ];
+
+    Ok(EngineCapabilities { engines, ffmpeg_version })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_report() {
+        let available = engine("media", "Media Engine", Ok("2025.01.01".to_string()), media_engine_features(false));
+        assert!(available.available);
+        assert!(available.features.contains(&"subtitles".to_string()));
+        assert!(!available.features.contains(&"merge_formats".to_string()));
+        assert!(media_engine_features(true).contains(&"merge_formats".to_string()));
+
+        let missing = engine("spotify", "SpotDL", Err("spotdl not found".to_string()), features(&["tracks"]));
+        assert!(!missing.available);
+        assert!(missing.features.is_empty());
+        assert_eq!(missing.unavailable_reason.as_deref(), Some("spotdl not found"));
+    }
+}
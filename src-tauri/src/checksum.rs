@@ -0,0 +1,246 @@
+//! Checksum Verification
+//!
+//! Verifies finished direct downloads against an expected MD5, SHA-1 or SHA-256 hash,
+//! given in the request or read from a checksum file published alongside the download.
+//!
+//! Key Features:
+//! - `algorithm:hex` or bare hex, with the algorithm told apart by hash length
+//! - Reads `sha256sum`-style, BSD-style and single-hash checksum files
+//! - Hashes in a blocking task so multi-gigabyte files don't stall the runtime
+
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Progress status for a download whose hash didn't match
+pub const CHECKSUM_MISMATCH_STATUS: &str = "checksum_mismatch";
+
+/// Largest checksum file we'll read; SHA256SUMS for a whole release is a few KB
+const MAX_SIDECAR_BYTES: usize = 1024 * 1024;
+
+const SIDECAR_TIMEOUT_SECS: u64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "md5" => Some(Self::Md5),
+            "sha1" => Some(Self::Sha1),
+            "sha256" => Some(Self::Sha256),
+            _ => None,
+        }
+    }
+
+    fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            32 => Some(Self::Md5),
+            40 => Some(Self::Sha1),
+            64 => Some(Self::Sha256),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpectedChecksum {
+    pub algorithm: HashAlgorithm,
+    /// Lowercase hex digest
+    pub hex: String,
+}
+
+impl ExpectedChecksum {
+    fn new(algorithm: HashAlgorithm, hex: &str) -> Result<Self, String> {
+        let hex = hex.trim();
+        if HashAlgorithm::from_hex_len(hex.len()) != Some(algorithm) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Not a valid {} hash: {}", algorithm.label(), hex));
+        }
+        Ok(Self { algorithm, hex: hex.to_ascii_lowercase() })
+    }
+
+    /// Parse `sha256:abcd...` or a bare hex digest
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        match spec.split_once(':') {
+            Some((name, hex)) => {
+                let algorithm = HashAlgorithm::from_name(name)
+                    .ok_or_else(|| format!("Unsupported checksum algorithm: {}", name))?;
+                Self::new(algorithm, hex)
+            }
+            None => {
+                let algorithm = HashAlgorithm::from_hex_len(spec.len())
+                    .ok_or("Expected an MD5, SHA-1 or SHA-256 hash")?;
+                Self::new(algorithm, spec)
+            }
+        }
+    }
+}
+
+/// One `(file name, checksum)` entry of a checksum file line
+fn parse_sidecar_line(line: &str) -> Option<(Option<String>, ExpectedChecksum)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    // BSD style: "SHA256 (file.iso) = abcd..."
+    if let Some((head, hex)) = line.rsplit_once(" = ") {
+        let (name, file) = head.split_once(" (")?;
+        let file = file.strip_suffix(')')?;
+        let checksum = ExpectedChecksum::new(HashAlgorithm::from_name(name)?, hex).ok()?;
+        return Some((Some(file.to_string()), checksum));
+    }
+
+    // GNU style: "abcd...  file.iso" or "abcd... *file.iso", or just the hash
+    let (hex, file) = match line.split_once(char::is_whitespace) {
+        Some((hex, file)) => (hex, Some(file.trim().trim_start_matches('*').to_string())),
+        None => (line, None),
+    };
+    Some((file, ExpectedChecksum::parse(hex).ok()?))
+}
+
+/// The checksum for `file_name` in a checksum file. A file with a single entry is taken
+/// as being for this download whatever name it lists.
+pub fn parse_sidecar(contents: &str, file_name: &str) -> Option<ExpectedChecksum> {
+    let entries: Vec<(Option<String>, ExpectedChecksum)> = contents.lines().filter_map(parse_sidecar_line).collect();
+    let matching = entries.iter().find(|(file, _)| {
+        file.as_deref()
+            .map(|f| Path::new(f).file_name().and_then(|n| n.to_str()) == Some(file_name))
+            .unwrap_or(false)
+    });
+    match (matching, entries.len()) {
+        (Some((_, checksum)), _) => Some(checksum.clone()),
+        (None, 1) => Some(entries[0].1.clone()),
+        _ => None,
+    }
+}
+
+/// Download a checksum file and pick out the hash for `file_name`
+pub async fn fetch_sidecar(url: &str, file_name: &str) -> Result<ExpectedChecksum, String> {
+    let client = crate::proxy::apply_to_client(reqwest::Client::builder())
+        .timeout(std::time::Duration::from_secs(SIDECAR_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let bytes = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch checksum file: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read checksum file: {}", e))?;
+    if bytes.len() > MAX_SIDECAR_BYTES {
+        return Err("Checksum file is too large".to_string());
+    }
+    parse_sidecar(&String::from_utf8_lossy(&bytes), file_name)
+        .ok_or_else(|| format!("No checksum listed for {}", file_name))
+}
+
+/// Hex digest of a file, streamed so large files don't have to fit in memory
+fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Result<String, String> {
+    use sha2::Digest;
+
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let mut md5_context = md5::Context::new();
+    let mut sha1 = sha1::Sha1::new();
+    let mut sha256 = sha2::Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        if read == 0 {
+            break;
+        }
+        match algorithm {
+            HashAlgorithm::Md5 => md5_context.consume(&buffer[..read]),
+            HashAlgorithm::Sha1 => sha1.update(&buffer[..read]),
+            HashAlgorithm::Sha256 => sha256.update(&buffer[..read]),
+        }
+    }
+    Ok(match algorithm {
+        HashAlgorithm::Md5 => format!("{:x}", md5_context.finalize()),
+        HashAlgorithm::Sha1 => format!("{:x}", sha1.finalize()),
+        HashAlgorithm::Sha256 => format!("{:x}", sha256.finalize()),
+    })
+}
+
+/// Hash `path` off the async runtime and compare it with `expected`
+pub async fn verify(path: PathBuf, expected: ExpectedChecksum) -> Result<(), String> {
+    let algorithm = expected.algorithm;
+    let actual = tokio::task::spawn_blocking(move || hash_file(&path, algorithm))
+        .await
+        .map_err(|e| format!("Checksum task failed: {}", e))??;
+    if actual != expected.hex {
+        return Err(format!(
+            "{} checksum mismatch: expected {}, got {}",
+            algorithm.label(),
+            expected.hex,
+            actual
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+    const ABC_SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn test_parse_expected() {
+        assert_eq!(ExpectedChecksum::parse(ABC_MD5).unwrap().algorithm, HashAlgorithm::Md5);
+        assert_eq!(ExpectedChecksum::parse(&format!("SHA-1:{}", ABC_SHA1)).unwrap().algorithm, HashAlgorithm::Sha1);
+        assert_eq!(
+            ExpectedChecksum::parse(&ABC_SHA256.to_uppercase()).unwrap().hex,
+            ABC_SHA256
+        );
+        // Length has to fit the named algorithm
+        assert!(ExpectedChecksum::parse(&format!("sha256:{}", ABC_MD5)).is_err());
+        assert!(ExpectedChecksum::parse("crc32:cafebabe").is_err());
+        assert!(ExpectedChecksum::parse("not-a-hash").is_err());
+    }
+
+    #[test]
+    fn test_parse_sidecar() {
+        let gnu = format!("{}  other.iso\n{} *ubuntu.iso\n", ABC_SHA1, ABC_SHA256);
+        assert_eq!(parse_sidecar(&gnu, "ubuntu.iso").unwrap().hex, ABC_SHA256);
+        assert!(parse_sidecar(&gnu, "missing.iso").is_none());
+
+        let bsd = format!("# comment\nMD5 (./ubuntu.iso) = {}\n", ABC_MD5);
+        assert_eq!(parse_sidecar(&bsd, "ubuntu.iso").unwrap().algorithm, HashAlgorithm::Md5);
+
+        // A lone hash belongs to whatever file it was published next to
+        assert_eq!(parse_sidecar(&format!("{}\n", ABC_SHA256), "anything.bin").unwrap().hex, ABC_SHA256);
+    }
+
+    #[tokio::test]
+    async fn test_verify_file() {
+        let path = std::env::temp_dir().join(format!("ownstash-checksum-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"abc").unwrap();
+
+        for hash in [ABC_MD5, ABC_SHA1, ABC_SHA256] {
+            assert!(verify(path.clone(), ExpectedChecksum::parse(hash).unwrap()).await.is_ok());
+        }
+        let wrong = ExpectedChecksum::parse(&"0".repeat(64)).unwrap();
+        assert!(verify(path.clone(), wrong).await.unwrap_err().contains("mismatch"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,238 @@
+//! Request Coalescing
+//!
+//! When the same URL is added again with the same options while the first download is still
+//! running (a double click, or the extension and the app both adding it), the second request
+//! follows the first download instead of starting a duplicate that writes the same file.
+//!
+//! Key Features:
+//! - Keyed by canonical URL (tracking parameters and fragments dropped) plus an options hash
+//! - Followers get the primary's `download-progress` events under their own id
+//! - Followers' history entries get the primary's final path and size
+//! - Cancelling a follower detaches it; cancelling the primary cancels its followers too
+
+use crate::commands::AppState;
+use crate::download_lifecycle::TERMINAL_STATUSES;
+use crate::downloader::DownloadRequest;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use url::Url;
+
+/// Query parameters that don't change what gets downloaded, on any site
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "igshid"];
+
+/// Share and referral parameters that are only tracking on these sites (and their
+/// subdomains); elsewhere `si`, `feature` or `ref` may well pick the file
+const SITE_TRACKING_PARAMS: &[(&str, &[&str])] = &[
+    ("youtube.com", &["si", "feature", "pp"]),
+    ("youtu.be", &["si", "feature"]),
+    ("open.spotify.com", &["si"]),
+    ("soundcloud.com", &["si", "ref"]),
+    ("twitter.com", &["ref_src", "ref_url"]),
+    ("x.com", &["ref_src", "ref_url"]),
+];
+
+/// Whether `key` is a tracking parameter on `host`
+fn is_tracking_param(host: &str, key: &str) -> bool {
+    key.starts_with("utm_")
+        || TRACKING_PARAMS.contains(&key)
+        || SITE_TRACKING_PARAMS.iter().any(|(site, params)| {
+            params.contains(&key) && crate::host_reputation::host_and_parents(host).any(|candidate| candidate == *site)
+        })
+}
+
+/// Keep forwarding for a while after the primary finishes; some engines send a second
+/// "completed" event once the file has been moved into place
+const FINISHED_GROUP_TTL: Duration = Duration::from_secs(30);
+
+struct Group {
+    key: String,
+    followers: Vec<String>,
+    finished_at: Option<Instant>,
+}
+
+lazy_static::lazy_static! {
+    /// Downloads that others can attach to, keyed by the primary's id
+    static ref GROUPS: Mutex<HashMap<String, Group>> = Mutex::new(HashMap::new());
+}
+
+/// The URL with its host lowercased, fragment and tracking parameters removed and the
+/// remaining query sorted
+pub fn canonical_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url.trim()) else {
+        return url.trim().to_string();
+    };
+    parsed.set_fragment(None);
+    if let Some(host) = parsed.host_str().map(|h| h.trim_start_matches("www.").to_string()) {
+        let _ = parsed.set_host(Some(&host));
+    }
+
+    let host = parsed.host_str().unwrap_or_default().to_string();
+    let mut query: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(k, _)| !is_tracking_param(&host, k))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    query.sort();
+    if query.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(query);
+    }
+
+    let path = parsed.path().trim_end_matches('/').to_string();
+    parsed.set_path(if path.is_empty() { "/" } else { &path });
+    parsed.to_string()
+}
+
+/// Canonical URL plus a hash of every option except the id
+fn request_key(request: &DownloadRequest) -> String {
+    let mut options = serde_json::to_value(request).unwrap_or_default();
+    if let Some(map) = options.as_object_mut() {
+        map.remove("id");
+        map.remove("url");
+    }
+    format!("{}#{:x}", canonical_url(&request.url), md5::compute(options.to_string()))
+}
+
+fn prune(groups: &mut HashMap<String, Group>) {
+    groups.retain(|_, g| g.finished_at.is_none_or(|at| at.elapsed() < FINISHED_GROUP_TTL));
+}
+
+/// Attach `request` to a running download of the same thing and return that download's id,
+/// or register it as one others can attach to and return None
+pub fn attach(request: &DownloadRequest) -> Option<String> {
+    let key = request_key(request);
+    let mut groups = GROUPS.lock().unwrap();
+    prune(&mut groups);
+
+    if let Some((primary, group)) = groups
+        .iter_mut()
+        .find(|(id, g)| g.key == key && g.finished_at.is_none() && **id != request.id)
+    {
+        group.followers.push(request.id.clone());
+        return Some(primary.clone());
+    }
+    groups.insert(request.id.clone(), Group { key, followers: Vec::new(), finished_at: None });
+    None
+}
+
+/// Whether `id` is following another download; it has a "downloading" row but no engine
+/// of its own
+pub fn is_following(id: &str) -> bool {
+    let mut groups = GROUPS.lock().unwrap();
+    prune(&mut groups);
+    groups.values().any(|g| g.followers.iter().any(|f| f == id))
+}
+
+/// Stop forwarding to a follower. Returns false if `id` isn't following anything.
+pub fn detach(id: &str) -> bool {
+    let mut groups = GROUPS.lock().unwrap();
+    for group in groups.values_mut() {
+        if let Some(index) = group.followers.iter().position(|f| f == id) {
+            group.followers.remove(index);
+            return true;
+        }
+    }
+    false
+}
+
+/// The primary failed before it could report anything; fail its followers with the same error
+pub fn abandon(app_handle: &AppHandle, id: &str, error: &str) {
+    let Some(group) = GROUPS.lock().unwrap().remove(id) else {
+        return;
+    };
+    for follower in group.followers {
+        let _ = app_handle.emit("download-progress", crate::downloader::DownloadProgress {
+            id: follower,
+            status: "failed".to_string(),
+            error: Some(error.to_string()),
+            ..Default::default()
+        });
+    }
+}
+
+/// Followers of `id`, marking the group finished when `status` is final
+fn followers_for(id: &str, status: &str) -> Vec<String> {
+    let mut groups = GROUPS.lock().unwrap();
+    let Some(group) = groups.get_mut(id) else {
+        return Vec::new();
+    };
+    if TERMINAL_STATUSES.contains(&status) && group.finished_at.is_none() {
+        group.finished_at = Some(Instant::now());
+    }
+    group.followers.clone()
+}
+
+/// Forward primaries' progress events to their followers; call once at startup
+pub fn install(app_handle: &AppHandle) {
+    let handle = app_handle.clone();
+    crate::download_lifecycle::on_progress(app_handle, move |update| {
+        let followers = followers_for(&update.id, &update.status);
+        if followers.is_empty() {
+            return;
+        }
+
+        let file_path = update.file_path.as_deref().filter(|_| update.status == "completed");
+        for follower in followers {
+            let mut forwarded = update.payload.clone();
+            forwarded["id"] = serde_json::Value::String(follower.clone());
+            let _ = handle.emit(update.event, forwarded);
+
+            if let (Some(path), Some(state)) = (file_path, handle.try_state::<AppState>()) {
+                if let Ok(db) = state.db.lock() {
+                    let _ = db.update_download_file(&follower, path, update.total_bytes.map(|b| b as i64));
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::download_request as request;
+
+    #[test]
+    fn test_canonical_url() {
+        assert_eq!(
+            canonical_url("https://WWW.YouTube.com/watch?v=abc&utm_source=x&si=123#t=5"),
+            canonical_url("https://youtube.com/watch?v=abc")
+        );
+        assert_eq!(canonical_url("https://example.com/a/?b=2&a=1"), "https://example.com/a?a=1&b=2");
+        assert_ne!(canonical_url("https://example.com/a?v=1"), canonical_url("https://example.com/a?v=2"));
+        assert_eq!(
+            canonical_url("https://music.youtube.com/watch?v=abc&feature=share"),
+            canonical_url("https://music.youtube.com/watch?v=abc")
+        );
+        // Only tracking where the site is known to use it that way
+        assert_ne!(canonical_url("https://example.com/get?ref=v2"), canonical_url("https://example.com/get?ref=v1"));
+        assert_eq!(canonical_url("https://cdn.example.com/a.zip?si=2"), "https://cdn.example.com/a.zip?si=2");
+    }
+
+    #[test]
+    fn test_attach_and_detach() {
+        let url = format!("https://example.com/{}", uuid::Uuid::new_v4());
+        let first = request("coalesce-a", &url, false);
+        let duplicate = request("coalesce-b", &format!("{}?utm_campaign=x", url), false);
+        let audio = request("coalesce-c", &url, true);
+
+        assert_eq!(attach(&first), None);
+        assert_eq!(attach(&duplicate).as_deref(), Some("coalesce-a"));
+        // Different options download something else
+        assert_eq!(attach(&audio), None);
+
+        assert_eq!(followers_for("coalesce-a", "downloading"), vec!["coalesce-b".to_string()]);
+        assert!(is_following("coalesce-b"));
+        assert!(!is_following("coalesce-a"));
+        assert!(detach("coalesce-b"));
+        assert!(!detach("coalesce-b"));
+        assert!(!is_following("coalesce-b"));
+
+        // A finished download takes no new followers
+        followers_for("coalesce-a", "completed");
+        assert_eq!(attach(&duplicate), None);
+        GROUPS.lock().unwrap().retain(|id, _| !id.starts_with("coalesce-"));
+    }
+}
@@ -1,7 +1,9 @@
-use crate::database::{Database, Download, SearchHistory, Setting};
-use tauri::{AppHandle, Manager, State};
+use crate::database::{
+    ArchiveEntry, Database, DbRecoveryReport, Download, InterventionRecord, SearchHistory, Setting,
+};
+use crate::folder_stats::{run_blocking_scan, ScanControl};
+use tauri::{AppHandle, Emitter, Manager, State};
 use std::sync::Mutex;
-use std::process::Command;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -10,6 +12,13 @@ pub struct AppState {
     pub db: Mutex<Database>,
 }
 
+/// Read a setting from outside a command (returns None if unset or the DB is unavailable)
+pub fn read_setting(app_handle: &AppHandle, key: &str) -> Option<String> {
+    let state = app_handle.try_state::<AppState>()?;
+    let db = state.db.lock().ok()?;
+    db.get_setting(key).ok().flatten()
+}
+
 // Utility command to open a folder in the system file explorer and optionally highlight a file
 #[tauri::command]
 pub async fn open_folder(path: String, file_name: Option<String>) -> Result<(), String> {
@@ -58,13 +67,13 @@ fn open_path_in_explorer(path: &std::path::Path) -> Result<(), String> {
             // explorer.exe /select,<path> needs the comma directly attached
             let path_str = path.to_string_lossy().replace("/", "\\");
             let full_arg = format!("/select,{}", path_str);
-            Command::new("explorer.exe")
+            crate::exec_guard::command("explorer.exe")?
                 .raw_arg(&full_arg)
                 .spawn()
                 .map_err(|e| format!("Failed to open folder: {}", e))?;
         } else {
             // If it's a directory, just open it
-            Command::new("explorer")
+            crate::exec_guard::command("explorer")?
                 .arg(path)
                 .spawn()
                 .map_err(|e| format!("Failed to open folder: {}", e))?;
@@ -75,13 +84,13 @@ fn open_path_in_explorer(path: &std::path::Path) -> Result<(), String> {
     {
         if path.is_file() {
             // On macOS, use -R to reveal the file in Finder
-            Command::new("open")
+            crate::exec_guard::command("open")?
                 .arg("-R")
                 .arg(path)
                 .spawn()
                 .map_err(|e| format!("Failed to open folder: {}", e))?;
         } else {
-            Command::new("open")
+            crate::exec_guard::command("open")?
                 .arg(path)
                 .spawn()
                 .map_err(|e| format!("Failed to open folder: {}", e))?;
@@ -97,7 +106,7 @@ fn open_path_in_explorer(path: &std::path::Path) -> Result<(), String> {
         } else {
             path
         };
-        Command::new("xdg-open")
+        crate::exec_guard::command("xdg-open")?
             .arg(target)
             .spawn()
             .map_err(|e| format!("Failed to open folder: {}", e))?;
@@ -126,7 +135,7 @@ pub async fn open_with_external_player(file_path: String, player_path: Option<St
             
             #[cfg(target_os = "windows")]
             {
-                Command::new(&player)
+                crate::exec_guard::user_command(&player)?
                     .arg(&file_path)
                     .creation_flags(0x08000000) // CREATE_NO_WINDOW
                     .spawn()
@@ -135,7 +144,7 @@ pub async fn open_with_external_player(file_path: String, player_path: Option<St
             
             #[cfg(not(target_os = "windows"))]
             {
-                Command::new(&player)
+                crate::exec_guard::user_command(&player)?
                     .arg(&file_path)
                     .spawn()
                     .map_err(|e| format!("Failed to open with player: {}", e))?;
@@ -150,243 +159,161 @@ pub async fn open_with_external_player(file_path: String, player_path: Option<St
     Ok(())
 }
 
-// Play a file with the default system application
-#[tauri::command]
-pub async fn play_file(path: String, title: String) -> Result<(), String> {
-    let base_path = std::path::Path::new(&path);
-    
-    if !base_path.exists() {
-        return Err(format!("Path does not exist: {}", path));
+const MEDIA_EXTENSIONS: &[&str] = &["mp4", "mkv", "webm", "avi", "mov", "mp3", "m4a", "flac", "wav", "ogg", "opus"];
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "m4a", "flac", "wav", "ogg", "opus"];
+
+/// How well a file stem matches a download title; 0 means no match
+fn title_match_score(stem: &str, sanitized_title: &str, title_words: &[&str]) -> i32 {
+    let stem_lower = stem.to_lowercase();
+
+    // Exact match
+    if stem_lower == sanitized_title {
+        100
     }
-    
-    // Collect all media files in the directory
-    let media_extensions = ["mp4", "mkv", "webm", "avi", "mov", "mp3", "m4a", "flac", "wav", "ogg", "opus"];
-    let mut candidates: Vec<(std::path::PathBuf, std::time::SystemTime, i32)> = Vec::new();
-    
-    if let Ok(entries) = std::fs::read_dir(base_path) {
-        // Sanitize the title for matching
-        let sanitized_title = title.chars()
-            .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
-            .collect::<String>()
-            .to_lowercase();
-        
-        let title_words: Vec<&str> = sanitized_title.split_whitespace().collect();
-        
-        for entry in entries.flatten() {
-            let path = entry.path();
-            
-            // Check if it's a media file
-            let ext = path.extension()
-                .and_then(|e| e.to_str())
-                .map(|e| e.to_lowercase())
-                .unwrap_or_default();
-            
-            if !media_extensions.contains(&ext.as_str()) {
-                continue;
-            }
-            
-            let modified = entry.metadata()
-                .and_then(|m| m.modified())
-                .unwrap_or(std::time::UNIX_EPOCH);
-            
-            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                let stem_lower = stem.to_lowercase();
-                
-                // Score based on how well it matches
-                let mut score = 0;
-                
-                // Exact match
-                if stem_lower == sanitized_title {
-                    score = 100;
-                }
-                // Starts with the title
-                else if stem_lower.starts_with(&sanitized_title) {
-                    score = 80;
-                }
-                // Title starts with the stem
-                else if sanitized_title.starts_with(&stem_lower) {
-                    score = 70;
-                }
-                // Contains the title
-                else if stem_lower.contains(&sanitized_title) {
-                    score = 60;
-                }
-                // Title contains the stem
-                else if sanitized_title.contains(&stem_lower) {
-                    score = 50;
-                }
-                // Word-based matching
-                else {
-                    let matching_words = title_words.iter()
-                        .filter(|w| w.len() > 2 && stem_lower.contains(*w))
-                        .count();
-                    if matching_words >= 2 || (matching_words == 1 && title_words.len() == 1) {
-                        score = 30 + matching_words as i32 * 5;
-                    }
-                }
-                
-                if score > 0 {
-                    candidates.push((path, modified, score));
-                }
-            }
-        }
+    // Starts with the title
+    else if stem_lower.starts_with(sanitized_title) {
+        80
     }
-    
-    // Sort by score (descending), then by modification time (most recent first)
-    candidates.sort_by(|a, b| {
-        b.2.cmp(&a.2).then_with(|| b.1.cmp(&a.1))
-    });
-    
-    // Try to play the best match
-    if let Some((best_match, _, _)) = candidates.first() {
-        println!("[PlayFile] Found match: {:?} for title: {}", best_match, title);
-        return open_file_with_default_app(best_match);
+    // Title starts with the stem
+    else if sanitized_title.starts_with(&stem_lower) {
+        70
     }
-    
-    // If no match by title, try to find the most recently modified media file
-    if let Ok(entries) = std::fs::read_dir(base_path) {
-        let mut recent_files: Vec<(std::path::PathBuf, std::time::SystemTime)> = entries
-            .flatten()
-            .filter_map(|e| {
-                let path = e.path();
-                let ext = path.extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|e| e.to_lowercase())
-                    .unwrap_or_default();
-                
-                if media_extensions.contains(&ext.as_str()) {
-                    e.metadata().and_then(|m| m.modified()).ok()
-                        .map(|t| (path, t))
-                } else {
-                    None
-                }
-            })
-            .collect();
-        
-        recent_files.sort_by(|a, b| b.1.cmp(&a.1));
-        
-        if let Some((most_recent, _)) = recent_files.first() {
-            println!("[PlayFile] No title match, playing most recent: {:?}", most_recent);
-            return open_file_with_default_app(most_recent);
+    // Contains the title
+    else if stem_lower.contains(sanitized_title) {
+        60
+    }
+    // Title contains the stem
+    else if sanitized_title.contains(&stem_lower) {
+        50
+    }
+    // Word-based matching
+    else {
+        let matching_words = title_words.iter()
+            .filter(|w| w.len() > 2 && stem_lower.contains(*w))
+            .count();
+        if matching_words >= 2 || (matching_words == 1 && title_words.len() == 1) {
+            30 + matching_words as i32 * 5
+        } else {
+            0
         }
     }
-    
-    Err(format!("Could not find media file for: {}", title))
 }
 
-/// Find a media file and return its path for in-app playback
-#[tauri::command]
-pub async fn find_media_file(path: String, title: String) -> Result<MediaFileInfo, String> {
-    let base_path = std::path::Path::new(&path);
-    
-    if !base_path.exists() {
-        return Err(format!("Path does not exist: {}", path));
-    }
-    
-    let media_extensions = ["mp4", "mkv", "webm", "avi", "mov", "mp3", "m4a", "flac", "wav", "ogg", "opus"];
-    let audio_extensions = ["mp3", "m4a", "flac", "wav", "ogg", "opus"];
+/// Find the media file in `base_path` that best matches `title`, falling back to the most
+/// recently modified one if `fallback_to_recent`. Blocking; run it through `run_blocking_scan`.
+pub(crate) fn find_media_candidate(
+    base_path: &std::path::Path,
+    title: &str,
+    fallback_to_recent: bool,
+    control: &ScanControl,
+) -> Result<Option<MediaFileInfo>, String> {
+    let Ok(entries) = std::fs::read_dir(base_path) else {
+        return Ok(None);
+    };
+
+    // Sanitize the title for matching
+    let sanitized_title = title.chars()
+        .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+        .collect::<String>()
+        .to_lowercase();
+    let title_words: Vec<&str> = sanitized_title.split_whitespace().collect();
+
+    // (path, modified, score, is_audio) of every media file
     let mut candidates: Vec<(std::path::PathBuf, std::time::SystemTime, i32, bool)> = Vec::new();
-    
-    if let Ok(entries) = std::fs::read_dir(base_path) {
-        let sanitized_title = title.chars()
-            .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
-            .collect::<String>()
-            .to_lowercase();
-        
-        let title_words: Vec<&str> = sanitized_title.split_whitespace().collect();
-        
-        for entry in entries.flatten() {
-            let file_path = entry.path();
-            
-            let ext = file_path.extension()
-                .and_then(|e| e.to_str())
-                .map(|e| e.to_lowercase())
-                .unwrap_or_default();
-            
-            if !media_extensions.contains(&ext.as_str()) {
-                continue;
-            }
-            
-            let is_audio = audio_extensions.contains(&ext.as_str());
-            let modified = entry.metadata()
-                .and_then(|m| m.modified())
-                .unwrap_or(std::time::UNIX_EPOCH);
-            
-            if let Some(stem) = file_path.file_stem().and_then(|s| s.to_str()) {
-                let stem_lower = stem.to_lowercase();
-                let mut score = 0;
-                
-                if stem_lower == sanitized_title {
-                    score = 100;
-                } else if stem_lower.starts_with(&sanitized_title) {
-                    score = 80;
-                } else if sanitized_title.starts_with(&stem_lower) {
-                    score = 70;
-                } else if stem_lower.contains(&sanitized_title) {
-                    score = 60;
-                } else if sanitized_title.contains(&stem_lower) {
-                    score = 50;
-                } else {
-                    let matching_words = title_words.iter()
-                        .filter(|w| w.len() > 2 && stem_lower.contains(*w))
-                        .count();
-                    if matching_words >= 2 || (matching_words == 1 && title_words.len() == 1) {
-                        score = 30 + matching_words as i32 * 5;
-                    }
-                }
-                
-                if score > 0 {
-                    candidates.push((file_path, modified, score, is_audio));
-                }
-            }
+    for entry in entries.flatten() {
+        control.advance(1)?;
+        let path = entry.path();
+
+        // Check if it's a media file
+        let ext = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        if !MEDIA_EXTENSIONS.contains(&ext.as_str()) {
+            continue;
         }
+
+        let modified = entry.metadata()
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::UNIX_EPOCH);
+        let score = path.file_stem()
+            .and_then(|s| s.to_str())
+            .map(|stem| title_match_score(stem, &sanitized_title, &title_words))
+            .unwrap_or(0);
+        candidates.push((path, modified, score, AUDIO_EXTENSIONS.contains(&ext.as_str())));
     }
-    
+
+    // Sort by score (descending), then by modification time (most recent first).
+    // Without any title match this picks the most recent media file.
     candidates.sort_by(|a, b| {
         b.2.cmp(&a.2).then_with(|| b.1.cmp(&a.1))
     });
-    
-    if let Some((best_match, _, _, is_audio)) = candidates.first() {
-        println!("[FindMediaFile] Found: {:?} (audio: {})", best_match, is_audio);
-        return Ok(MediaFileInfo {
-            file_path: best_match.to_string_lossy().to_string(),
-            is_audio: *is_audio,
-        });
-    }
-    
-    // Fallback to most recent
-    if let Ok(entries) = std::fs::read_dir(base_path) {
-        let mut recent: Vec<(std::path::PathBuf, std::time::SystemTime, bool)> = entries
-            .flatten()
-            .filter_map(|e| {
-                let file_path = e.path();
-                let ext = file_path.extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|e| e.to_lowercase())
-                    .unwrap_or_default();
-                
-                if media_extensions.contains(&ext.as_str()) {
-                    let is_audio = audio_extensions.contains(&ext.as_str());
-                    e.metadata().and_then(|m| m.modified()).ok()
-                        .map(|t| (file_path, t, is_audio))
-                } else {
-                    None
-                }
-            })
-            .collect();
-        
-        recent.sort_by(|a, b| b.1.cmp(&a.1));
-        
-        if let Some((most_recent, _, is_audio)) = recent.first() {
-            println!("[FindMediaFile] Fallback to most recent: {:?}", most_recent);
-            return Ok(MediaFileInfo {
-                file_path: most_recent.to_string_lossy().to_string(),
-                is_audio: *is_audio,
-            });
+
+    let best = candidates.into_iter().next().filter(|(_, _, score, _)| fallback_to_recent || *score > 0);
+    Ok(best.map(|(path, _, score, is_audio)| {
+        if score > 0 {
+            println!("[FindMediaFile] Found: {:?} for title: {}", path, title);
+        } else {
+            println!("[FindMediaFile] No title match, using most recent: {:?}", path);
         }
+        MediaFileInfo {
+            file_path: path.to_string_lossy().to_string(),
+            is_audio,
+        }
+    }))
+}
+
+/// Look up the media file for a download without blocking the async runtime
+async fn locate_media_file(
+    app_handle: &AppHandle,
+    path: String,
+    title: String,
+    scan_id: Option<String>,
+) -> Result<MediaFileInfo, String> {
+    if !std::path::Path::new(&path).exists() {
+        return Err(format!("Path does not exist: {}", path));
     }
-    
-    Err(format!("Could not find media file for: {}", title))
+
+    // Downloads record the finished file itself; only older entries hold just the folder
+    let file = std::path::Path::new(&path);
+    if file.is_file() {
+        let is_audio = file
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+        return Ok(MediaFileInfo { file_path: path, is_audio });
+    }
+
+    let control = ScanControl::new(app_handle, scan_id);
+    let lookup_title = title.clone();
+    run_blocking_scan(control, move |control| {
+        find_media_candidate(std::path::Path::new(&path), &lookup_title, true, control)
+    })
+    .await?
+    .ok_or_else(|| format!("Could not find media file for: {}", title))
+}
+
+// Play a file with the default system application
+#[tauri::command]
+pub async fn play_file(
+    app_handle: AppHandle,
+    path: String,
+    title: String,
+    scan_id: Option<String>,
+) -> Result<(), String> {
+    let media = locate_media_file(&app_handle, path, title, scan_id).await?;
+    open_file_with_default_app(std::path::Path::new(&media.file_path))
+}
+
+/// Find a media file and return its path for in-app playback
+#[tauri::command]
+pub async fn find_media_file(
+    app_handle: AppHandle,
+    path: String,
+    title: String,
+    scan_id: Option<String>,
+) -> Result<MediaFileInfo, String> {
+    locate_media_file(&app_handle, path, title, scan_id).await
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -402,7 +329,7 @@ fn open_file_with_default_app(path: &std::path::Path) -> Result<(), String> {
 
     #[cfg(target_os = "windows")]
     {
-        Command::new("cmd")
+        crate::exec_guard::command("cmd")?
             .args(["/C", "start", "", &path.to_string_lossy()])
             .creation_flags(0x08000000) // CREATE_NO_WINDOW
             .spawn()
@@ -411,7 +338,7 @@ fn open_file_with_default_app(path: &std::path::Path) -> Result<(), String> {
 
     #[cfg(target_os = "macos")]
     {
-        Command::new("open")
+        crate::exec_guard::command("open")?
             .arg(path)
             .spawn()
             .map_err(|e| format!("Failed to open file: {}", e))?;
@@ -419,7 +346,7 @@ fn open_file_with_default_app(path: &std::path::Path) -> Result<(), String> {
 
     #[cfg(target_os = "linux")]
     {
-        Command::new("xdg-open")
+        crate::exec_guard::command("xdg-open")?
             .arg(path)
             .spawn()
             .map_err(|e| format!("Failed to open file: {}", e))?;
@@ -428,6 +355,16 @@ fn open_file_with_default_app(path: &std::path::Path) -> Result<(), String> {
     Ok(())
 }
 
+/// Where transcoded files are kept; the staging folder when one is set
+pub(crate) fn transcode_cache_dir(app_handle: &AppHandle) -> Result<std::path::PathBuf, String> {
+    if let Some(dir) = crate::staging::dir(crate::staging::TRANSCODE) {
+        return Ok(dir);
+    }
+    app_handle.path().app_cache_dir()
+        .map(|dir| dir.join("transcoded"))
+        .map_err(|e| format!("Failed to get cache dir: {}", e))
+}
+
 /// Transcode a media file to MP4 for web playback
 /// Returns the path to the transcoded file
 #[tauri::command]
@@ -437,7 +374,6 @@ pub async fn transcode_for_playback(
     force: Option<bool>,
 ) -> Result<TranscodeResult, String> {
     use std::path::PathBuf;
-    use tokio::process::Command as TokioCommand;
     
     let input = PathBuf::from(&input_path);
     if !input.exists() {
@@ -488,9 +424,7 @@ pub async fn transcode_for_playback(
         .ok_or_else(|| "FFmpeg not found. Cannot transcode.".to_string())?;
     
     // Create cache directory for transcoded files
-    let cache_dir = app_handle.path().app_cache_dir()
-        .map_err(|e| format!("Failed to get cache dir: {}", e))?
-        .join("transcoded");
+    let cache_dir = transcode_cache_dir(&app_handle)?;
     std::fs::create_dir_all(&cache_dir)
         .map_err(|e| format!("Failed to create cache dir: {}", e))?;
     
@@ -514,7 +448,7 @@ pub async fn transcode_for_playback(
     
     // Run FFmpeg transcoding
     // Use fast settings for quick playback
-    let mut cmd = TokioCommand::new(&ffmpeg_path);
+    let mut cmd = crate::exec_guard::tokio_command(&ffmpeg_path)?;
     cmd.args([
         "-y",                           // Overwrite output
         "-i", &input_path,              // Input file
@@ -555,8 +489,262 @@ pub struct TranscodeResult {
     pub was_transcoded: bool,
 }
 
-fn find_ffmpeg(app_handle: &AppHandle) -> Option<String> {
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RemuxResult {
+    pub output_path: String,
+    pub original_removed: bool,
+}
+
+/// ffmpeg arguments that copy every stream into a new container
+fn remux_ffmpeg_args(input_path: &str, output_path: &str, container: &str) -> Vec<String> {
+    let mut args: Vec<String> = ["-y", "-i", input_path].iter().map(|s| s.to_string()).collect();
+    match container {
+        // MP4-family containers only take text subtitles as mov_text and reject
+        // attachments/data streams, so keep video, audio and subtitles only
+        "mp4" | "m4v" | "mov" => args.extend(
+            ["-map", "0:v?", "-map", "0:a?", "-map", "0:s?", "-c", "copy", "-c:s", "mov_text", "-movflags", "+faststart"]
+                .iter()
+                .map(|s| s.to_string()),
+        ),
+        "webm" => args.extend(["-map", "0:v?", "-map", "0:a?", "-c", "copy"].iter().map(|s| s.to_string())),
+        _ => args.extend(["-map", "0", "-c", "copy"].iter().map(|s| s.to_string())),
+    }
+    args.push(output_path.to_string());
+    args
+}
+
+/// Rewrap a media file into another container without re-encoding (`ffmpeg -c copy`).
+/// Unlike `transcode_for_playback` this is lossless and writes next to the original.
+#[tauri::command]
+pub async fn remux_media(
+    app_handle: AppHandle,
+    input_path: String,
+    container: String,
+    replace_original: Option<bool>,
+) -> Result<RemuxResult, String> {
+    use std::path::PathBuf;
+
+    let input = PathBuf::from(&input_path);
+    if !input.is_file() {
+        return Err(format!("Input file does not exist: {}", input_path));
+    }
+
+    let container = container.trim().to_lowercase();
+    if !crate::downloader::REMUX_CONTAINERS.contains(&container.as_str()) {
+        return Err(format!(
+            "Unsupported container '{}'. Use one of: {}",
+            container,
+            crate::downloader::REMUX_CONTAINERS.join(", ")
+        ));
+    }
+    let current = input.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+    if current.as_deref() == Some(container.as_str()) {
+        return Err(format!("File is already a .{} file", container));
+    }
+
+    let output = input.with_extension(&container);
+    if output.exists() {
+        return Err(format!("{} already exists", output.display()));
+    }
+    // ffmpeg picks the muxer from the extension, so the temp file keeps it
+    let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("remux");
+    let temp = input.with_file_name(format!("{}.remuxing.{}", stem, container));
+
+    let ffmpeg_path = find_ffmpeg(&app_handle)
+        .ok_or_else(|| "FFmpeg not found. Cannot remux.".to_string())?;
+
+    println!("[Remux] {:?} -> {:?}", input, output);
+    let mut cmd = crate::exec_guard::tokio_command(&ffmpeg_path)?;
+    cmd.args(remux_ffmpeg_args(&input_path, &temp.to_string_lossy(), &container));
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let result = cmd.output().await.map_err(|e| format!("Failed to run FFmpeg: {}", e))?;
+    if !result.status.success() {
+        let _ = std::fs::remove_file(&temp);
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let last_line = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("");
+        return Err(format!(
+            "Remux failed; the streams may not fit in a .{} container: {}",
+            container, last_line
+        ));
+    }
+
+    std::fs::rename(&temp, &output).map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        format!("Failed to finalize remuxed file: {}", e)
+    })?;
+
+    let original_removed = replace_original.unwrap_or(false) && std::fs::remove_file(&input).is_ok();
+    println!("[Remux] Completed: {:?}", output);
+
+    Ok(RemuxResult {
+        output_path: output.to_string_lossy().to_string(),
+        original_removed,
+    })
+}
+
+/// Audio formats `extract_audio` can produce
+pub const AUDIO_EXTRACT_FORMATS: &[&str] = &["mp3", "m4a", "flac"];
+
+/// ffmpeg arguments that encode the first audio track of `input_path` to `format`
+fn extract_audio_args(input_path: &str, output_path: &str, format: &str) -> Vec<String> {
+    let mut args: Vec<&str> = vec!["-y", "-i", input_path, "-map", "0:a:0", "-vn"];
+    match format {
+        "mp3" => args.extend(["-c:a", "libmp3lame", "-q:a", "0"]),
+        "m4a" => args.extend(["-c:a", "aac", "-b:a", "256k", "-movflags", "+faststart"]),
+        _ => args.extend(["-c:a", "flac"]),
+    }
+    args.push(output_path);
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+/// Outcome of a queued audio extraction, sent as `audio-extraction-finished`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AudioExtractionFinished {
+    /// The video download the audio was taken from
+    pub source_id: String,
+    /// The new history entry for the audio file
+    pub download: Option<Download>,
+    pub error: Option<String>,
+}
+
+/// Make an audio-only copy of a finished video download and add it to the history,
+/// so getting the audio doesn't mean downloading again. The encode is queued behind the
+/// idle policy; `audio-extraction-finished` reports the new entry.
+#[tauri::command]
+pub async fn extract_audio(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    format: String,
+) -> Result<(), String> {
+
+    let format = format.trim().to_lowercase();
+    if !AUDIO_EXTRACT_FORMATS.contains(&format.as_str()) {
+        return Err(format!(
+            "Unsupported audio format '{}'. Use one of: {}",
+            format,
+            AUDIO_EXTRACT_FORMATS.join(", ")
+        ));
+    }
+
+    let download = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.get_download(&id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Download {} not found", id))?
+    };
+    if download.status != "completed" {
+        return Err("Only completed downloads can be converted to audio".to_string());
+    }
+
+    // Only the recorded file or one named after the download; never some other file nearby
+    let lookup = download.clone();
+    let input = tokio::task::spawn_blocking(move || crate::download_card::resolve_media_file(&lookup))
+        .await
+        .map_err(|e| format!("Failed to locate download: {}", e))?
+        .ok_or_else(|| format!("Could not find the file for: {}; it may have been moved or renamed", download.title))?;
+    let is_audio = input
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    if is_audio {
+        return Err("This download is already audio-only".to_string());
+    }
+
+    let output = input.with_extension(&format);
+    if output.exists() {
+        return Err(format!("{} already exists", output.display()));
+    }
+
+    let ffmpeg_path = find_ffmpeg(&app_handle)
+        .ok_or_else(|| "FFmpeg not found. Cannot extract audio.".to_string())?;
+
+    let app = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        crate::idle::wait_for_heavy_job(&app, "audio extraction", Some(&id)).await;
+        let result = run_audio_extraction(&app, &ffmpeg_path, &input, &output, &format, download).await;
+        if let Err(e) = &result {
+            println!("[ExtractAudio] {}", e);
+        }
+        let _ = app.emit("audio-extraction-finished", AudioExtractionFinished {
+            source_id: id,
+            download: result.as_ref().ok().cloned(),
+            error: result.err(),
+        });
+    });
+    Ok(())
+}
+
+/// Encode the first audio track of `input` to `output` and add it to the history
+async fn run_audio_extraction(
+    app_handle: &AppHandle,
+    ffmpeg_path: &str,
+    input: &std::path::Path,
+    output: &std::path::Path,
+    format: &str,
+    download: Download,
+) -> Result<Download, String> {
+    let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("audio");
+    let temp = input.with_file_name(format!("{}.extracting.{}", stem, format));
+
+    println!("[ExtractAudio] {:?} -> {:?}", input, output);
+    let mut cmd = crate::exec_guard::tokio_command(ffmpeg_path)?;
+    cmd.args(extract_audio_args(&input.to_string_lossy(), &temp.to_string_lossy(), format));
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let result = cmd.output().await.map_err(|e| format!("Failed to run FFmpeg: {}", e))?;
+    if !result.status.success() {
+        let _ = std::fs::remove_file(&temp);
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let last_line = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("");
+        return Err(format!("Audio extraction failed: {}", last_line));
+    }
+    std::fs::rename(&temp, output).map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        format!("Failed to finalize audio file: {}", e)
+    })?;
+
+    // The new entry points at the audio file itself so it isn't confused with the video
+    let entry = Download {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: download.title,
+        url: download.url,
+        format: format!("{} audio", format),
+        path: output.to_string_lossy().to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        status: "completed".to_string(),
+        size_bytes: output.metadata().ok().and_then(|m| i64::try_from(m.len()).ok()),
+        platform: download.platform,
+        thumbnail: download.thumbnail,
+        on_complete: None,
+    };
+    {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.add_download(&entry).map_err(|e| e.to_string())?;
+    }
+    println!("[ExtractAudio] Completed: {:?}", output);
+    Ok(entry)
+}
+
+pub(crate) fn find_ffmpeg(app_handle: &AppHandle) -> Option<String> {
     use tauri::Manager;
+
+    // ffmpeg installed by ensure_ffmpeg
+    if let Some(managed_path) = crate::ffmpeg::find_managed_ffmpeg(app_handle) {
+        return Some(managed_path);
+    }
     
     // Try resource dir first
     if let Ok(resource_dir) = app_handle.path().resource_dir() {
@@ -632,9 +820,73 @@ fn find_ffprobe(app_handle: &AppHandle) -> Option<String> {
     None
 }
 
+/// First video stream of a file, as reported by ffprobe
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct VideoStreamInfo {
+    pub codec: String,
+    pub height: Option<u32>,
+    pub fps: Option<f64>,
+}
+
+/// Parse ffprobe's `default=noprint_wrappers=1` key=value output
+fn parse_video_stream_info(output: &str) -> Option<VideoStreamInfo> {
+    let mut info = VideoStreamInfo {
+        codec: String::new(),
+        height: None,
+        fps: None,
+    };
+    for line in output.lines() {
+        match line.trim().split_once('=') {
+            Some(("codec_name", value)) => info.codec = value.trim().to_lowercase(),
+            Some(("height", value)) => info.height = value.trim().parse().ok(),
+            Some(("avg_frame_rate", value)) => {
+                info.fps = match value.trim().split_once('/') {
+                    Some((num, den)) => match (num.parse::<f64>(), den.parse::<f64>()) {
+                        (Ok(num), Ok(den)) if den > 0.0 => Some(num / den),
+                        _ => None,
+                    },
+                    None => value.trim().parse().ok(),
+                };
+            }
+            _ => {}
+        }
+    }
+    (!info.codec.is_empty()).then_some(info)
+}
+
+pub(crate) fn probe_video_stream(app_handle: &AppHandle, input_path: &str) -> Option<VideoStreamInfo> {
+    let ffprobe_path = find_ffprobe(app_handle)?;
+    let mut cmd = crate::exec_guard::command(ffprobe_path).ok()?;
+
+    #[cfg(target_os = "windows")]
+    {
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=codec_name,height,avg_frame_rate",
+            "-of",
+            "default=noprint_wrappers=1",
+            input_path,
+        ])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    parse_video_stream_info(&String::from_utf8_lossy(&output.stdout))
+}
+
 fn probe_primary_video_codec(app_handle: &AppHandle, input_path: &str) -> Option<String> {
     let ffprobe_path = find_ffprobe(app_handle)?;
-    let mut cmd = Command::new(ffprobe_path);
+    let mut cmd = crate::exec_guard::command(ffprobe_path).ok()?;
 
     #[cfg(target_os = "windows")]
     {
@@ -719,6 +971,58 @@ pub async fn clear_downloads(state: State<'_, AppState>) -> Result<(), String> {
     db.clear_downloads().map_err(|e| e.to_string())
 }
 
+/// Watchdog interventions recorded for a download, oldest first
+#[tauri::command]
+pub async fn get_intervention_history(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Vec<InterventionRecord>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_interventions(&id).map_err(|e| e.to_string())
+}
+
+/// Details of the startup database repair, if one happened this session
+#[tauri::command]
+pub async fn get_database_recovery_report(
+    state: State<'_, AppState>,
+) -> Result<Option<DbRecoveryReport>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(db.recovery_report())
+}
+
+// Download archive commands
+#[tauri::command]
+pub async fn get_download_archive(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+) -> Result<Vec<ArchiveEntry>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_archive_entries(limit).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_download_archive_count(state: State<'_, AppState>) -> Result<i64, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.count_archive_entries().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_download_archive_entry(
+    state: State<'_, AppState>,
+    extractor: String,
+    item_id: String,
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.delete_archive_entry(&extractor, &item_id).map_err(|e| e.to_string())
+}
+
+/// Clear the archive so previously downloaded items are fetched again. Returns the number removed.
+#[tauri::command]
+pub async fn clear_download_archive(state: State<'_, AppState>) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.clear_archive().map_err(|e| e.to_string())
+}
+
 // Search history commands
 #[tauri::command]
 pub async fn add_search(
@@ -777,3 +1081,78 @@ pub async fn delete_setting(state: State<'_, AppState>, key: String) -> Result<(
     let db = state.db.lock().map_err(|e| e.to_string())?;
     db.delete_setting(&key).map_err(|e| e.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_video_stream_info() {
+        let info = parse_video_stream_info("codec_name=HEVC\nheight=2160\navg_frame_rate=60000/1001\n").unwrap();
+        assert_eq!(info.codec, "hevc");
+        assert_eq!(info.height, Some(2160));
+        assert!((info.fps.unwrap() - 59.94).abs() < 0.01);
+
+        assert_eq!(parse_video_stream_info("avg_frame_rate=0/0\n"), None);
+    }
+
+    #[test]
+    fn test_extract_audio_args() {
+        assert_eq!(
+            extract_audio_args("in.mp4", "out.mp3", "mp3").join(" "),
+            "-y -i in.mp4 -map 0:a:0 -vn -c:a libmp3lame -q:a 0 out.mp3"
+        );
+        assert_eq!(
+            extract_audio_args("in.webm", "out.flac", "flac").join(" "),
+            "-y -i in.webm -map 0:a:0 -vn -c:a flac out.flac"
+        );
+    }
+
+    #[test]
+    fn test_find_media_candidate() {
+        let dir = std::env::temp_dir().join(format!("ownstash-find-media-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("My Song.mp3"), b"a").unwrap();
+        std::fs::write(dir.join("Other Video.mp4"), b"v").unwrap();
+        std::fs::write(dir.join("My Song.txt"), b"t").unwrap();
+
+        let control = ScanControl::default();
+        let found = find_media_candidate(&dir, "My Song", true, &control).unwrap().unwrap();
+        assert!(found.file_path.ends_with("My Song.mp3"));
+        assert!(found.is_audio);
+
+        // No title match still finds a media file, unless only a match will do
+        assert!(find_media_candidate(&dir, "Unrelated", true, &control).unwrap().is_some());
+        assert!(find_media_candidate(&dir, "Unrelated", false, &control).unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_extract_audio_source_needs_title_match() {
+        let dir = std::env::temp_dir().join(format!("ownstash-extract-source-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Someone Else's Video.mp4"), b"v").unwrap();
+        let mut download = crate::database::Download {
+            id: "d1".to_string(),
+            title: "Conference Talk".to_string(),
+            url: "https://example.com/watch?v=1".to_string(),
+            format: "mp4".to_string(),
+            path: dir.to_string_lossy().to_string(),
+            timestamp: 0,
+            status: "completed".to_string(),
+            size_bytes: None,
+            platform: None,
+            thumbnail: None,
+            on_complete: None,
+        };
+        assert_eq!(crate::download_card::resolve_media_file(&download), None);
+
+        std::fs::write(dir.join("Conference Talk.mp4"), b"v").unwrap();
+        assert_eq!(crate::download_card::resolve_media_file(&download), Some(dir.join("Conference Talk.mp4")));
+        download.path = dir.join("Someone Else's Video.mp4").to_string_lossy().to_string();
+        assert_eq!(crate::download_card::resolve_media_file(&download), Some(dir.join("Someone Else's Video.mp4")));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
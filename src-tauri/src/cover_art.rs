@@ -0,0 +1,169 @@
+//! Cover Art for Ogg Audio
+//!
+//! yt-dlp's `--embed-thumbnail` can't embed into Opus/Vorbis files without mutagen, and
+//! only from a JPEG or PNG. For those formats the thumbnail is written out as a JPEG instead
+//! and embedded here as a `METADATA_BLOCK_PICTURE` comment with FFmpeg.
+//!
+//! Key Features:
+//! - `needs_picture_block` picks out the formats yt-dlp's embed can't handle
+//! - FLAC picture block built from the thumbnail and written with a stream copy
+//! - Falls back to `cover.jpg` folder art when embedding fails
+
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Audio formats whose cover art is a FLAC picture block in a Vorbis comment
+const PICTURE_BLOCK_FORMATS: &[&str] = &["opus", "ogg", "vorbis"];
+
+/// Picture type 3 in the FLAC spec: front cover
+const FRONT_COVER: u32 = 3;
+
+/// Whether the embed has to go through `embed` instead of yt-dlp
+pub fn needs_picture_block(audio_only: bool, audio_format: &str) -> bool {
+    audio_only && PICTURE_BLOCK_FORMATS.contains(&audio_format.trim().to_lowercase().as_str())
+}
+
+/// Thumbnail format to convert to for a picture block: the requested one if it can go in
+/// one, otherwise JPEG
+pub fn picture_format(requested: Option<&str>) -> &'static str {
+    match requested {
+        Some("png") => "png",
+        _ => "jpg",
+    }
+}
+
+/// A FLAC `METADATA_BLOCK_PICTURE`. Dimensions are left 0; players read them from the image.
+fn picture_block(image: &[u8], mime: &str) -> Vec<u8> {
+    let mut block = Vec::with_capacity(image.len() + 32 + mime.len());
+    block.extend(FRONT_COVER.to_be_bytes());
+    block.extend((mime.len() as u32).to_be_bytes());
+    block.extend(mime.as_bytes());
+    // Description, width, height, colour depth, indexed colours
+    for field in [0u32, 0, 0, 0, 0] {
+        block.extend(field.to_be_bytes());
+    }
+    block.extend((image.len() as u32).to_be_bytes());
+    block.extend(image);
+    block
+}
+
+/// An ffmetadata file giving the first stream the picture block. It goes through a file
+/// because the base64 block is too long for a command line.
+fn picture_metadata(image: &[u8], mime: &str) -> String {
+    use base64::Engine;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(picture_block(image, mime));
+    format!(
+        ";FFMETADATA1\n[STREAM]\nMETADATA_BLOCK_PICTURE={}\n",
+        crate::downloader::escape_ffmetadata(&encoded)
+    )
+}
+
+/// Where the thumbnail yt-dlp wrote for `audio` ends up
+pub fn thumbnail_for(audio: &Path, format: &str) -> PathBuf {
+    audio.with_extension(format)
+}
+
+async fn write_picture(app_handle: &AppHandle, audio: &Path, thumbnail: &Path, format: &str) -> Result<(), String> {
+    let ffmpeg = crate::commands::find_ffmpeg(app_handle).ok_or("FFmpeg not found")?;
+    let image = tokio::fs::read(thumbnail)
+        .await
+        .map_err(|e| format!("Failed to read thumbnail {:?}: {}", thumbnail, e))?;
+    let mime = if format == "png" { "image/png" } else { "image/jpeg" };
+
+    let metadata = audio.with_extension("cover.ffmeta");
+    tokio::fs::write(&metadata, picture_metadata(&image, mime))
+        .await
+        .map_err(|e| format!("Failed to write cover metadata: {}", e))?;
+    let extension = audio.extension().and_then(|e| e.to_str()).unwrap_or("opus");
+    let temp = audio.with_extension(format!("cover.{}", extension));
+
+    let mut cmd = crate::exec_guard::tokio_command(&ffmpeg)?;
+    cmd.arg("-y")
+        .arg("-i")
+        .arg(audio)
+        .args(["-f", "ffmetadata", "-i"])
+        .arg(&metadata)
+        // Keep the existing tags and add the picture to the audio stream's comments
+        .args(["-map", "0", "-c", "copy", "-map_metadata", "0"])
+        .args(["-map_metadata:s:a:0", "0:s:a:0", "-map_metadata:s:a:0", "1:s:0"])
+        .arg(&temp)
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let result = cmd.output().await;
+    let _ = tokio::fs::remove_file(&metadata).await;
+    let result = result.map_err(|e| format!("Failed to run FFmpeg: {}", e))?;
+    if !result.status.success() {
+        let _ = tokio::fs::remove_file(&temp).await;
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let last_line = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("");
+        return Err(format!("Embedding cover art failed: {}", last_line));
+    }
+    tokio::fs::rename(&temp, audio)
+        .await
+        .map_err(|e| format!("Failed to replace download: {}", e))
+}
+
+/// Embed the thumbnail yt-dlp wrote next to `audio`. If that fails it's kept as
+/// `cover.<ext>` in the folder (unless one is already there). Returns an error only when
+/// there was no thumbnail to use.
+pub async fn embed(app_handle: &AppHandle, audio: &Path, format: &str, keep_thumbnail: bool) -> Result<(), String> {
+    let thumbnail = thumbnail_for(audio, format);
+    if !thumbnail.exists() {
+        return Err(format!("No thumbnail was written for {:?}", audio));
+    }
+
+    match write_picture(app_handle, audio, &thumbnail, format).await {
+        Ok(()) => {
+            println!("[CoverArt] Embedded cover art in {:?}", audio);
+            if !keep_thumbnail {
+                let _ = tokio::fs::remove_file(&thumbnail).await;
+            }
+        }
+        Err(e) => {
+            println!("[CoverArt] {}", e);
+            let folder_art = thumbnail.with_file_name(format!("cover.{}", format));
+            if keep_thumbnail || folder_art.exists() {
+                return Ok(());
+            }
+            match tokio::fs::rename(&thumbnail, &folder_art).await {
+                Ok(()) => println!("[CoverArt] Saved folder art to {:?}", folder_art),
+                Err(e) => println!("[CoverArt] Failed to save folder art: {}", e),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_picture_block() {
+        assert!(needs_picture_block(true, "opus"));
+        assert!(needs_picture_block(true, "OGG"));
+        assert!(!needs_picture_block(true, "mp3"));
+        // Video downloads embed into the container yt-dlp merges
+        assert!(!needs_picture_block(false, "opus"));
+    }
+
+    #[test]
+    fn test_picture_block_layout() {
+        let block = picture_block(&[1, 2, 3], "image/jpeg");
+        assert_eq!(&block[..4], &FRONT_COVER.to_be_bytes());
+        assert_eq!(&block[4..8], &10u32.to_be_bytes());
+        assert_eq!(&block[8..18], b"image/jpeg");
+        assert_eq!(&block[38..42], &3u32.to_be_bytes());
+        assert_eq!(&block[42..], &[1, 2, 3]);
+
+        // Base64 padding has to be escaped in the ffmetadata file
+        let metadata = picture_metadata(&[1, 2, 3, 4], "image/jpeg");
+        assert!(metadata.starts_with(";FFMETADATA1\n[STREAM]\nMETADATA_BLOCK_PICTURE="));
+        assert!(metadata.ends_with("\\=\\=\n"));
+    }
+}
@@ -0,0 +1,348 @@
+//! Crash Resume
+//!
+//! Finds SNDE downloads a crash or forced quit left half-done and restarts them from the
+//! ranges already on disk.
+//!
+//! Key Features:
+//! - Scans at startup: the download folders of unfinished history entries plus the default
+//!   folder, for a `.downloading` file with its `.snde-state` sidecar
+//! - History entries stuck on "downloading" from the crash are marked "paused"
+//! - Nothing restarts by itself; the UI is told what was found and offers to resume
+//! - Resumed downloads keep their history id and skip every completed range
+//! - The original request is saved with the chunk state, so a resume keeps its options;
+//!   credential headers go to secure storage instead of the download folder
+
+use crate::commands::AppState;
+use crate::database::{Database, Download};
+use crate::downloader::DownloadRequest;
+use crate::snde::PartialDownload;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// History statuses of downloads that may have left a partial file behind
+const UNFINISHED_STATUSES: &[&str] = &["downloading", "paused"];
+
+/// Request headers kept out of the chunk state sidecar
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "cookie", "proxy-authorization"];
+
+/// Secure storage key prefix for a download's credential headers (JSON map)
+const HEADERS_SECRET_PREFIX: &str = "resume_headers:";
+
+/// A partial download that can be resumed
+#[derive(Debug, Clone, Serialize)]
+pub struct IncompleteDownload {
+    /// History id; downloads missing from the history get a new one
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    /// Folder the download goes to
+    pub output_path: String,
+    pub file_path: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: u64,
+    /// Whether it has a history entry
+    pub in_history: bool,
+    /// The request that started it, as saved with the chunk state
+    #[serde(skip)]
+    pub request: Option<serde_json::Value>,
+}
+
+lazy_static::lazy_static! {
+    /// What the last scan found, until it's resumed
+    static ref INCOMPLETE: Mutex<Vec<IncompleteDownload>> = Mutex::new(Vec::new());
+}
+
+/// Pair partial files with the history entries that started them, by folder and URL
+fn match_history(partials: Vec<(String, PartialDownload)>, history: &[Download]) -> Vec<IncompleteDownload> {
+    partials
+        .into_iter()
+        .map(|(folder, partial)| {
+            let entry = history.iter().find(|d| d.url == partial.url && Path::new(&d.path) == Path::new(&folder));
+            let file_name = partial
+                .file_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| partial.url.clone());
+            IncompleteDownload {
+                id: entry.map(|d| d.id.clone()).unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                title: entry.map(|d| d.title.clone()).unwrap_or(file_name),
+                url: partial.url,
+                output_path: folder,
+                file_path: partial.file_path.to_string_lossy().to_string(),
+                downloaded_bytes: partial.downloaded_bytes,
+                total_bytes: partial.total_bytes,
+                in_history: entry.is_some(),
+                request: partial.request,
+            }
+        })
+        .collect()
+}
+
+/// Partial downloads in the folders unfinished downloads were going to
+fn scan(history: &[Download], default_folder: Option<String>) -> Vec<IncompleteDownload> {
+    let folders: BTreeSet<String> = history
+        .iter()
+        .filter(|d| UNFINISHED_STATUSES.contains(&d.status.as_str()))
+        .map(|d| d.path.clone())
+        .chain(default_folder)
+        .collect();
+    let partials = folders
+        .into_iter()
+        .flat_map(|folder| {
+            crate::snde::partial_downloads(Path::new(&folder))
+                .into_iter()
+                .map(move |partial| (folder.clone(), partial))
+        })
+        .collect();
+    match_history(partials, history)
+}
+
+async fn rescan(app_handle: &AppHandle) -> Result<Vec<IncompleteDownload>, String> {
+    let history = {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.get_downloads().map_err(|e| e.to_string())?
+    };
+    let default_folder = crate::downloader::get_default_download_path(app_handle.clone()).await.ok();
+    let mut found = tokio::task::spawn_blocking(move || scan(&history, default_folder))
+        .await
+        .map_err(|e| format!("Scanning for incomplete downloads failed: {}", e))?;
+    // A running download has the same files on disk
+    found.retain(|d| !crate::downloader::is_active_download(&d.id));
+    *INCOMPLETE.lock().unwrap() = found.clone();
+    Ok(found)
+}
+
+/// Look for downloads a crash left behind; call once at startup. Rows stuck on
+/// "downloading" without a partial file to resume are then failed as stale.
+pub fn install(app_handle: &AppHandle) {
+    let app = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        match rescan(&app).await {
+            Ok(found) if !found.is_empty() => {
+                println!("[CrashResume] Found {} incomplete downloads", found.len());
+
+                // Nothing is running yet, so "downloading" means the run was cut short
+                if let Some(state) = app.try_state::<AppState>() {
+                    if let Ok(db) = state.db.lock() {
+                        mark_interrupted(&db, &found);
+                    }
+                }
+                let _ = app.emit("incomplete-downloads", &found);
+            }
+            Ok(_) => {}
+            Err(e) => println!("[CrashResume] Scan failed: {}", e),
+        }
+        crate::stale_downloads::sweep(&app, true);
+    });
+}
+
+/// What the last scan found that hasn't been resumed
+pub fn incomplete() -> Vec<IncompleteDownload> {
+    INCOMPLETE.lock().unwrap().clone()
+}
+
+fn mark_interrupted(db: &Database, found: &[IncompleteDownload]) {
+    for download in found.iter().filter(|d| d.in_history) {
+        if let Ok(Some(entry)) = db.get_download(&download.id) {
+            if entry.status == "downloading" {
+                let _ = db.update_download_status(&download.id, "paused");
+            }
+        }
+    }
+}
+
+fn headers_key(id: &str) -> String {
+    format!("{}{}", HEADERS_SECRET_PREFIX, id)
+}
+
+/// Split credential headers off `headers`
+fn take_credentials(headers: &mut HashMap<String, String>) -> HashMap<String, String> {
+    let names: Vec<String> = headers
+        .keys()
+        .filter(|name| CREDENTIAL_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+        .cloned()
+        .collect();
+    names.into_iter().filter_map(|name| headers.remove_entry(&name)).collect()
+}
+
+/// `request` as it's saved with SNDE's chunk state; its credential headers go to secure storage
+pub fn persist_request(app_handle: &AppHandle, request: &DownloadRequest) -> Option<serde_json::Value> {
+    let mut saved = request.clone();
+    let credentials = take_credentials(&mut saved.request_headers);
+    if !credentials.is_empty() {
+        let stored = serde_json::to_string(&credentials)
+            .map_err(|e| e.to_string())
+            .and_then(|json| crate::secure_storage::save_secret(app_handle, &headers_key(&request.id), &json));
+        if let Err(e) = stored {
+            println!("[CrashResume] Couldn't keep the headers of {}: {}", request.id, e);
+        }
+    }
+    serde_json::to_value(saved).ok()
+}
+
+/// Drop the credential headers saved for download `id` once it can't be resumed anymore
+pub fn forget_request(app_handle: &AppHandle, id: &str) {
+    if let Some(state) = app_handle.try_state::<AppState>() {
+        if let Ok(db) = state.db.lock() {
+            let _ = db.delete_setting(&headers_key(id));
+        }
+    }
+}
+
+/// The request to resume `download` with: the saved one, else the defaults for its URL
+fn restore_request(app: &AppHandle, download: &IncompleteDownload) -> Result<DownloadRequest, String> {
+    let saved = download
+        .request
+        .clone()
+        .and_then(|json| serde_json::from_value::<DownloadRequest>(json).ok())
+        .filter(|saved| saved.url == download.url);
+    let Some(mut request) = saved else {
+        return DownloadRequest::with_defaults(&download.url, &download.output_path);
+    };
+    if let Some(json) = crate::secure_storage::load_secret(app, &headers_key(&request.id))? {
+        let credentials: HashMap<String, String> =
+            serde_json::from_str(&json).map_err(|e| format!("Saved headers for {} are unreadable: {}", download.url, e))?;
+        request.request_headers.extend(credentials);
+    }
+    if request.id != download.id {
+        forget_request(app, &request.id);
+    }
+    request.output_path = download.output_path.clone();
+    Ok(request)
+}
+
+async fn resume(app: &AppHandle, download: IncompleteDownload) -> Result<(), String> {
+    let mut request = restore_request(app, &download)?;
+    request.id = download.id.clone();
+    request.estimated_size = Some(download.total_bytes);
+
+    let history_entry = (!download.in_history).then(|| Download {
+        id: request.id.clone(),
+        title: download.title.clone(),
+        url: request.url.clone(),
+        format: String::new(),
+        path: request.output_path.clone(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        status: "downloading".to_string(),
+        size_bytes: Some(download.total_bytes as i64),
+        platform: None,
+        thumbnail: None,
+        on_complete: None,
+    });
+    println!(
+        "[CrashResume] Resuming {} from {} of {} bytes",
+        download.url, download.downloaded_bytes, download.total_bytes
+    );
+
+    crate::downloader::run_download(app, request, history_entry.as_ref()).await.map(|_| ())
+}
+
+/// Partial downloads found on disk, scanned again
+#[tauri::command]
+pub async fn get_incomplete_downloads(app_handle: AppHandle) -> Result<Vec<IncompleteDownload>, String> {
+    rescan(&app_handle).await
+}
+
+/// Restart the given incomplete downloads (all of them without `ids`) from the ranges
+/// already on disk. Returns the ids that were started.
+#[tauri::command]
+pub async fn resume_incomplete_downloads(
+    app_handle: AppHandle,
+    ids: Option<Vec<String>>,
+) -> Result<Vec<String>, String> {
+    let selected: Vec<IncompleteDownload> = {
+        let mut incomplete = INCOMPLETE.lock().unwrap();
+        let (selected, rest) = incomplete
+            .drain(..)
+            .partition(|d| match &ids {
+                Some(ids) => ids.contains(&d.id),
+                None => true,
+            });
+        *incomplete = rest;
+        selected
+    };
+
+    let started = selected.iter().map(|d| d.id.clone()).collect();
+    for download in selected {
+        let app = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let url = download.url.clone();
+            if let Err(e) = resume(&app, download).await {
+                println!("[CrashResume] Failed to resume {}: {}", url, e);
+            }
+        });
+    }
+    Ok(started)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn history_entry(id: &str, url: &str, path: &str, status: &str) -> Download {
+        Download {
+            id: id.to_string(),
+            title: format!("Title {}", id),
+            url: url.to_string(),
+            format: String::new(),
+            path: path.to_string(),
+            timestamp: 0,
+            status: status.to_string(),
+            size_bytes: None,
+            platform: None,
+            thumbnail: None,
+            on_complete: None,
+        }
+    }
+
+    fn partial(url: &str, file: &str) -> PartialDownload {
+        PartialDownload {
+            url: url.to_string(),
+            file_path: PathBuf::from(file),
+            downloaded_bytes: 10,
+            total_bytes: 100,
+            request: None,
+        }
+    }
+
+    #[test]
+    fn test_partials_match_history_by_folder_and_url() {
+        let history = vec![
+            history_entry("a", "https://example.com/a.iso", "/dl", "downloading"),
+            history_entry("b", "https://example.com/b.iso", "/other", "paused"),
+        ];
+        let found = match_history(
+            vec![
+                ("/dl".to_string(), partial("https://example.com/a.iso", "/dl/a.iso")),
+                ("/dl".to_string(), partial("https://example.com/b.iso", "/dl/b.iso")),
+            ],
+            &history,
+        );
+
+        assert_eq!(found[0].id, "a");
+        assert_eq!(found[0].title, "Title a");
+        assert!(found[0].in_history);
+        // Same URL, different folder: a download of its own
+        assert!(!found[1].in_history);
+        assert_ne!(found[1].id, "b");
+        assert_eq!(found[1].title, "b.iso");
+    }
+
+    #[test]
+    fn test_credential_headers_stay_out_of_the_sidecar() {
+        let mut headers = HashMap::from([
+            ("Referer".to_string(), "https://example.com/".to_string()),
+            ("Authorization".to_string(), "Bearer abc".to_string()),
+            ("cookie".to_string(), "session=1".to_string()),
+        ]);
+        let credentials = take_credentials(&mut headers);
+        assert_eq!(headers.keys().collect::<Vec<_>>(), vec!["Referer"]);
+        assert_eq!(credentials.len(), 2);
+        assert_eq!(credentials["Authorization"], "Bearer abc");
+    }
+}
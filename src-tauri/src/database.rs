@@ -2,7 +2,7 @@ use rusqlite::{Connection, Result, params};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -27,6 +27,9 @@ pub struct Download {
     pub size_bytes: Option<i64>,
     pub platform: Option<String>,
     pub thumbnail: Option<String>,
+    /// JSON encoded completion action chosen at enqueue time
+    #[serde(default)]
+    pub on_complete: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -38,14 +41,186 @@ pub struct SearchHistory {
     pub thumbnail: Option<String>,
 }
 
+/// An item recorded in the download archive (yt-dlp's `--download-archive` format)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchiveEntry {
+    pub extractor: String,
+    pub item_id: String,
+    pub added_at: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Setting {
     pub key: String,
     pub value: String,
 }
 
+/// A watchdog intervention on a download (connection collapse, safe mode, ...)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InterventionRecord {
+    pub id: i64,
+    pub download_id: String,
+    pub event_type: String,
+    pub message: String,
+    pub throughput_bps: Option<i64>,
+    pub active_connections: Option<i64>,
+    pub created_at: i64,
+}
+
+/// An entry in the notification center. Repeats of an unread entry are folded into it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationRecord {
+    pub id: i64,
+    /// "download_complete", "download_failed", "watchdog" or "update"
+    pub kind: String,
+    pub title: String,
+    pub message: String,
+    pub download_id: Option<String>,
+    pub read: bool,
+    /// How many times this notification fired while unread
+    pub count: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Notifications kept; older ones are dropped as new ones arrive
+const NOTIFICATION_LIMIT: i64 = 500;
+
+/// A Spotify playlist/album download, persisted so it can resume after a restart
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SpotifyJob {
+    pub id: String,
+    pub url: String,
+    /// JSON encoded `SpotifyDownloadRequest`
+    pub request_json: String,
+    /// "running", "cancelled", "completed", "failed"
+    pub status: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub tracks: Vec<SpotifyJobTrack>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SpotifyJobTrack {
+    pub track_index: i64,
+    /// JSON of the track as returned by `spotdl save`
+    pub track_json: String,
+    pub display_name: String,
+    /// "pending", "completed", "failed", "skipped_drm"
+    pub status: String,
+    pub error: Option<String>,
+}
+
+/// A named bundle of download options or a device profile (see `presets.rs`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PresetRecord {
+    pub id: String,
+    pub name: String,
+    /// "download" or "device"
+    pub kind: String,
+    /// JSON encoded `PresetSettings` or `DeviceProfile`, depending on `kind`
+    pub settings_json: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// A channel or playlist kept in sync (see `subscriptions.rs`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Subscription {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    pub output_path: String,
+    /// Download preset applied to every new entry
+    pub preset_id: Option<String>,
+    pub interval_minutes: i64,
+    pub enabled: bool,
+    pub last_synced_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+}
+
+/// A URL saved to download later
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedItem {
+    pub id: String,
+    pub url: String,
+    pub note: Option<String>,
+    pub title: Option<String>,
+    pub thumbnail: Option<String>,
+    pub platform: Option<String>,
+    pub duration: Option<i64>,
+    pub created_at: i64,
+}
+
+/// Estimated energy and carbon cost of one finished download
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DownloadFootprint {
+    pub download_id: String,
+    pub bytes: i64,
+    pub duration_secs: f64,
+    pub energy_wh: f64,
+    pub carbon_g: f64,
+    pub created_at: i64,
+}
+
+/// Footprint estimates summed over a period
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FootprintTotals {
+    pub downloads: i64,
+    pub bytes: i64,
+    pub energy_wh: f64,
+    pub carbon_g: f64,
+}
+
+/// Rows salvaged from one table of a corrupted database
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TableRecovery {
+    pub table: String,
+    pub recovered_rows: usize,
+    /// False when unreadable pages stopped the copy early
+    pub complete: bool,
+}
+
+/// What happened when a corrupted database was replaced at startup
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DbRecoveryReport {
+    /// Where the damaged database was kept
+    pub backup_path: String,
+    /// Problems reported by the integrity check
+    pub problems: Vec<String>,
+    pub tables: Vec<TableRecovery>,
+}
+
+/// Whether an error means the file is damaged rather than, say, busy
+fn is_corruption(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseCorrupt | rusqlite::ErrorCode::NotADatabase)
+    )
+}
+
+/// Tables copied out of a damaged database, in dependency-free order
+const RECOVERABLE_TABLES: &[&str] = &[
+    "settings",
+    "downloads",
+    "search_history",
+    "host_reputation",
+    "host_header_overrides",
+    "download_archive",
+    "watchdog_interventions",
+    "spotify_jobs",
+    "spotify_job_tracks",
+    "presets",
+    "subscriptions",
+    "notifications",
+    "saved_items",
+    "download_footprints",
+];
+
 pub struct Database {
     conn: Connection,
+    recovery_report: Option<DbRecoveryReport>,
 }
 
 impl Database {
@@ -54,14 +229,166 @@ impl Database {
         std::fs::create_dir_all(&app_data_dir)?;
         
         let db_path = app_data_dir.join("db.sqlite");
+        if db_path.exists() {
+            let problems = Self::integrity_check(&db_path)?;
+            if !problems.is_empty() {
+                println!("[Database] Integrity check failed: {:?}", problems);
+                return Self::recover(&db_path, problems);
+            }
+        }
+
         let conn = Connection::open(&db_path)?;
         
-        let db = Self { conn };
+        let db = Self { conn, recovery_report: None };
         db.initialize_tables()?;
         
         Ok(db)
     }
 
+    /// Report from a startup recovery, if the database had to be rebuilt
+    pub fn recovery_report(&self) -> Option<DbRecoveryReport> {
+        self.recovery_report.clone()
+    }
+
+    /// Run `PRAGMA integrity_check`, returning the problems it found (none when the
+    /// database is healthy). Errors that don't mean corruption, like a database locked by
+    /// another process, are returned as errors so the caller doesn't rebuild a good file.
+    fn integrity_check(db_path: &Path) -> DbResult<Vec<String>> {
+        let results = Connection::open(db_path).and_then(|conn| {
+            let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            rows.collect::<Result<Vec<_>, _>>()
+        });
+
+        match results {
+            Ok(results) if results.len() == 1 && results[0] == "ok" => Ok(Vec::new()),
+            Ok(results) => Ok(results),
+            Err(e) if is_corruption(&e) => Ok(vec![e.to_string()]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Move the damaged database aside and salvage what we can into a fresh one
+    fn recover(db_path: &Path, problems: Vec<String>) -> DbResult<Self> {
+        let backup_path = db_path.with_file_name(format!(
+            "db.corrupt-{}.sqlite",
+            Utc::now().format("%Y%m%d-%H%M%S")
+        ));
+        std::fs::rename(db_path, &backup_path)?;
+        for suffix in ["-wal", "-shm"] {
+            let side_file = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+            if side_file.exists() {
+                let _ = std::fs::rename(&side_file, format!("{}{}", backup_path.display(), suffix));
+            }
+        }
+
+        let conn = Connection::open(db_path)?;
+        let mut db = Self { conn, recovery_report: None };
+        db.initialize_tables()?;
+
+        let mut tables = Vec::new();
+        let backup = backup_path.to_string_lossy().to_string();
+        match db.conn.execute("ATTACH DATABASE ?1 AS damaged", params![backup]) {
+            Ok(_) => {
+                for table in RECOVERABLE_TABLES {
+                    tables.push(db.salvage_table(table));
+                }
+                let _ = db.conn.execute("DETACH DATABASE damaged", []);
+            }
+            Err(e) => println!("[Database] Could not open damaged database for salvage: {}", e),
+        }
+
+        println!("[Database] Rebuilt database, damaged copy kept at {}", backup);
+        for table in &tables {
+            println!(
+                "[Database] Recovered {} row(s) from {}{}",
+                table.recovered_rows,
+                table.table,
+                if table.complete { "" } else { " (partial)" }
+            );
+        }
+
+        db.recovery_report = Some(DbRecoveryReport {
+            backup_path: backup,
+            problems,
+            tables,
+        });
+        Ok(db)
+    }
+
+    /// Copy readable rows of one table from the attached damaged database
+    fn salvage_table(&self, table: &str) -> TableRecovery {
+        let mut recovery = TableRecovery {
+            table: table.to_string(),
+            recovered_rows: 0,
+            complete: false,
+        };
+
+        let columns = match (
+            self.table_columns("damaged", table),
+            self.table_columns("main", table),
+        ) {
+            (Ok(old), Ok(new)) => old.into_iter().filter(|c| new.contains(c)).collect::<Vec<_>>(),
+            _ => return recovery,
+        };
+        if columns.is_empty() {
+            return recovery;
+        }
+        let column_list = columns.join(", ");
+
+        // Fast path: the whole table is still readable
+        if let Ok(copied) = self.conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO main.{table} ({column_list}) SELECT {column_list} FROM damaged.{table}"
+            ),
+            [],
+        ) {
+            recovery.recovered_rows = copied;
+            recovery.complete = true;
+            return recovery;
+        }
+
+        // Slow path: copy row by row until the first unreadable page
+        let placeholders = (1..=columns.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let insert = format!("INSERT OR IGNORE INTO main.{table} ({column_list}) VALUES ({placeholders})");
+        let Ok(mut select) = self.conn.prepare(&format!("SELECT {column_list} FROM damaged.{table}")) else {
+            return recovery;
+        };
+        let Ok(mut rows) = select.query([]) else {
+            return recovery;
+        };
+
+        loop {
+            match rows.next() {
+                Ok(Some(row)) => {
+                    let values: Vec<rusqlite::types::Value> = (0..columns.len())
+                        .map(|i| row.get(i).unwrap_or(rusqlite::types::Value::Null))
+                        .collect();
+                    if let Ok(inserted) = self.conn.execute(&insert, rusqlite::params_from_iter(values)) {
+                        recovery.recovered_rows += inserted;
+                    }
+                }
+                Ok(None) => {
+                    recovery.complete = true;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+        recovery
+    }
+
+    fn table_columns(&self, schema: &str, table: &str) -> DbResult<Vec<String>> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA {}.table_info({})", schema, table))?;
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(columns)
+    }
+
     fn initialize_tables(&self) -> DbResult<()> {
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS downloads (
@@ -115,6 +442,142 @@ impl Database {
             [],
         )?;
 
+        // Per-host User-Agent and header overrides set by the user
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS host_header_overrides (
+                domain TEXT PRIMARY KEY,
+                user_agent TEXT,
+                headers TEXT NOT NULL DEFAULT '{}',
+                last_updated INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Download archive: items already fetched, so playlist/channel re-downloads skip them
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS download_archive (
+                extractor TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                added_at INTEGER NOT NULL,
+                PRIMARY KEY (extractor, item_id)
+            )",
+            [],
+        )?;
+
+        // Spotify jobs and their per-track progress, so playlists resume after a restart
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS spotify_jobs (
+                id TEXT PRIMARY KEY,
+                url TEXT NOT NULL,
+                request_json TEXT NOT NULL,
+                status TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS spotify_job_tracks (
+                job_id TEXT NOT NULL,
+                track_index INTEGER NOT NULL,
+                track_json TEXT NOT NULL,
+                display_name TEXT NOT NULL,
+                status TEXT NOT NULL,
+                error TEXT,
+                PRIMARY KEY (job_id, track_index)
+            )",
+            [],
+        )?;
+
+        // Download presets ("Music 320k", "Archive 4K mkv", ...) and device profiles
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS presets (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                kind TEXT NOT NULL DEFAULT 'download',
+                settings_json TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Channels / playlists synced in the background
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS subscriptions (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                url TEXT NOT NULL UNIQUE,
+                output_path TEXT NOT NULL,
+                preset_id TEXT,
+                interval_minutes INTEGER NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                last_synced_at INTEGER,
+                last_error TEXT,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // URLs saved to download later
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS saved_items (
+                id TEXT PRIMARY KEY,
+                url TEXT NOT NULL UNIQUE,
+                note TEXT,
+                title TEXT,
+                thumbnail TEXT,
+                platform TEXT,
+                duration INTEGER,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Estimated energy / carbon per finished download
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS download_footprints (
+                download_id TEXT PRIMARY KEY,
+                bytes INTEGER NOT NULL,
+                duration_secs REAL NOT NULL,
+                energy_wh REAL NOT NULL,
+                carbon_g REAL NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Watchdog intervention audit trail, so users can see why a download slowed down
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS watchdog_interventions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                download_id TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                message TEXT NOT NULL,
+                throughput_bps INTEGER,
+                active_connections INTEGER,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Notification center, so notifications can be reviewed after they vanish
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS notifications (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                title TEXT NOT NULL,
+                message TEXT NOT NULL,
+                download_id TEXT,
+                read INTEGER NOT NULL DEFAULT 0,
+                count INTEGER NOT NULL DEFAULT 1,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
         // Create indexes for faster queries
         self.conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_downloads_timestamp ON downloads(timestamp DESC)",
@@ -126,6 +589,11 @@ impl Database {
             [],
         )?;
 
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_watchdog_interventions_download ON watchdog_interventions(download_id)",
+            [],
+        )?;
+
         // V2.0: Host reputation index
         self.conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_host_reputation_domain ON host_reputation(domain)",
@@ -134,6 +602,23 @@ impl Database {
 
         // Migration: Add thumbnail column to downloads if it doesn't exist
         let _ = self.conn.execute("ALTER TABLE downloads ADD COLUMN thumbnail TEXT", []);
+
+        // Migration: Add per-download completion action
+        let _ = self.conn.execute("ALTER TABLE downloads ADD COLUMN on_complete TEXT", []);
+
+        // Migration: why the app failed a download that no engine was running
+        let _ = self.conn.execute("ALTER TABLE downloads ADD COLUMN status_reason TEXT", []);
+
+        // Migration: failures the user hasn't seen yet, for the badge. Failures from before
+        // the column existed count as seen.
+        if self
+            .conn
+            .execute("ALTER TABLE downloads ADD COLUMN failure_acknowledged INTEGER NOT NULL DEFAULT 0", [])
+            .is_ok()
+        {
+            let _ = self.conn.execute("UPDATE downloads SET failure_acknowledged = 1", []);
+        }
+
         
         // Migration: Add title and thumbnail columns to search_history if they don't exist
         let _ = self.conn.execute("ALTER TABLE search_history ADD COLUMN title TEXT", []);
@@ -145,8 +630,8 @@ impl Database {
     // Download operations
     pub fn add_download(&self, download: &Download) -> DbResult<()> {
         self.conn.execute(
-            "INSERT INTO downloads (id, title, url, format, path, timestamp, status, size_bytes, platform, thumbnail)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            "INSERT INTO downloads (id, title, url, format, path, timestamp, status, size_bytes, platform, thumbnail, on_complete)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 download.id,
                 download.title,
@@ -158,6 +643,7 @@ impl Database {
                 download.size_bytes,
                 download.platform,
                 download.thumbnail,
+                download.on_complete,
             ],
         )?;
         Ok(())
@@ -165,7 +651,7 @@ impl Database {
 
     pub fn get_downloads(&self) -> DbResult<Vec<Download>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, title, url, format, path, timestamp, status, size_bytes, platform, thumbnail
+            "SELECT id, title, url, format, path, timestamp, status, size_bytes, platform, thumbnail, on_complete
              FROM downloads ORDER BY timestamp DESC"
         )?;
 
@@ -181,6 +667,7 @@ impl Database {
                 size_bytes: row.get(7)?,
                 platform: row.get(8)?,
                 thumbnail: row.get(9)?,
+                on_complete: row.get(10)?,
             })
         })?
         .collect::<Result<Vec<_>, _>>()?;
@@ -188,21 +675,619 @@ impl Database {
         Ok(downloads)
     }
 
+    pub fn get_download(&self, id: &str) -> DbResult<Option<Download>> {
+        let result = self.conn.query_row(
+            "SELECT id, title, url, format, path, timestamp, status, size_bytes, platform, thumbnail, on_complete
+             FROM downloads WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(Download {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    url: row.get(2)?,
+                    format: row.get(3)?,
+                    path: row.get(4)?,
+                    timestamp: row.get(5)?,
+                    status: row.get(6)?,
+                    size_bytes: row.get(7)?,
+                    platform: row.get(8)?,
+                    thumbnail: row.get(9)?,
+                    on_complete: row.get(10)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(download) => Ok(Some(download)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     pub fn update_download_status(&self, id: &str, status: &str) -> DbResult<()> {
         self.conn.execute(
-            "UPDATE downloads SET status = ?1 WHERE id = ?2",
+            "UPDATE downloads SET status = ?1, status_reason = NULL, failure_acknowledged = 0 WHERE id = ?2",
             params![status, id],
         )?;
         Ok(())
     }
 
+    /// Fail a download stuck on "downloading", saying why. False if it had moved on.
+    pub fn fail_stale_download(&self, id: &str, reason: &str) -> DbResult<bool> {
+        let changed = self.conn.execute(
+            "UPDATE downloads SET status = 'failed', status_reason = ?2, failure_acknowledged = 0
+             WHERE id = ?1 AND status = 'downloading'",
+            params![id, reason],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Downloads the app failed as stale, with the reason, newest first
+    pub fn get_stale_downloads(&self) -> DbResult<Vec<(Download, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, title, url, format, path, timestamp, status, size_bytes, platform, thumbnail, on_complete, status_reason
+             FROM downloads WHERE status = 'failed' AND status_reason IS NOT NULL ORDER BY timestamp DESC"
+        )?;
+
+        let downloads = stmt.query_map([], |row| {
+            Ok((
+                Download {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    url: row.get(2)?,
+                    format: row.get(3)?,
+                    path: row.get(4)?,
+                    timestamp: row.get(5)?,
+                    status: row.get(6)?,
+                    size_bytes: row.get(7)?,
+                    platform: row.get(8)?,
+                    thumbnail: row.get(9)?,
+                    on_complete: row.get(10)?,
+                },
+                row.get(11)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(downloads)
+    }
+
+    /// Take a download off the recovery list, keeping its status
+    pub fn dismiss_stale_download(&self, id: &str) -> DbResult<()> {
+        self.conn.execute("UPDATE downloads SET status_reason = NULL WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Downloads running, waiting to start, and failed without the user having seen it
+    pub fn get_badge_counts(&self) -> DbResult<(i64, i64, i64)> {
+        Ok(self.conn.query_row(
+            "SELECT
+                COALESCE(SUM(status = 'downloading'), 0),
+                COALESCE(SUM(status IN ('queued', 'pending')), 0),
+                COALESCE(SUM(status = 'failed' AND failure_acknowledged = 0), 0)
+             FROM downloads",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?)
+    }
+
+    /// Mark failed downloads as seen (all of them when `ids` is omitted); returns how many changed
+    pub fn acknowledge_failed_downloads(&self, ids: Option<&[String]>) -> DbResult<usize> {
+        let Some(ids) = ids else {
+            return Ok(self.conn.execute(
+                "UPDATE downloads SET failure_acknowledged = 1 WHERE status = 'failed' AND failure_acknowledged = 0",
+                [],
+            )?);
+        };
+        let mut changed = 0;
+        for id in ids {
+            changed += self.conn.execute(
+                "UPDATE downloads SET failure_acknowledged = 1 WHERE id = ?1 AND status = 'failed' AND failure_acknowledged = 0",
+                params![id],
+            )?;
+        }
+        Ok(changed)
+    }
+
+    /// Point an entry at its finished file
+    /// Record where a finished download ended up and its size (kept as-is when unknown)
+    pub fn update_download_file(&self, id: &str, path: &str, size_bytes: Option<i64>) -> DbResult<()> {
+        self.conn.execute(
+            "UPDATE downloads SET path = ?1, size_bytes = COALESCE(?2, size_bytes) WHERE id = ?3",
+            params![path, size_bytes, id],
+        )?;
+        Ok(())
+    }
+
     pub fn delete_download(&self, id: &str) -> DbResult<()> {
         self.conn.execute("DELETE FROM downloads WHERE id = ?1", params![id])?;
+        self.conn.execute("DELETE FROM watchdog_interventions WHERE download_id = ?1", params![id])?;
+        self.conn.execute("DELETE FROM download_footprints WHERE download_id = ?1", params![id])?;
         Ok(())
     }
 
     pub fn clear_downloads(&self) -> DbResult<()> {
         self.conn.execute("DELETE FROM downloads", [])?;
+        self.conn.execute("DELETE FROM watchdog_interventions", [])?;
+        self.conn.execute("DELETE FROM download_footprints", [])?;
+        Ok(())
+    }
+
+    // Download footprint operations
+    pub fn save_footprint(&self, footprint: &DownloadFootprint) -> DbResult<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO download_footprints (download_id, bytes, duration_secs, energy_wh, carbon_g, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                footprint.download_id,
+                footprint.bytes,
+                footprint.duration_secs,
+                footprint.energy_wh,
+                footprint.carbon_g,
+                footprint.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_footprint(&self, download_id: &str) -> DbResult<Option<DownloadFootprint>> {
+        let result = self.conn.query_row(
+            "SELECT download_id, bytes, duration_secs, energy_wh, carbon_g, created_at
+             FROM download_footprints WHERE download_id = ?1",
+            params![download_id],
+            |row| {
+                Ok(DownloadFootprint {
+                    download_id: row.get(0)?,
+                    bytes: row.get(1)?,
+                    duration_secs: row.get(2)?,
+                    energy_wh: row.get(3)?,
+                    carbon_g: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(footprint) => Ok(Some(footprint)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Totals for footprints recorded at or after `since` (ms since epoch)
+    pub fn get_footprint_totals(&self, since: i64) -> DbResult<FootprintTotals> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(bytes), 0), COALESCE(SUM(energy_wh), 0.0), COALESCE(SUM(carbon_g), 0.0)
+             FROM download_footprints WHERE created_at >= ?1",
+            params![since],
+            |row| {
+                Ok(FootprintTotals {
+                    downloads: row.get(0)?,
+                    bytes: row.get(1)?,
+                    energy_wh: row.get(2)?,
+                    carbon_g: row.get(3)?,
+                })
+            },
+        )?)
+    }
+
+    // Watchdog intervention operations
+    pub fn add_intervention(
+        &self,
+        download_id: &str,
+        event_type: &str,
+        message: &str,
+        throughput_bps: Option<i64>,
+        active_connections: Option<i64>,
+    ) -> DbResult<()> {
+        self.conn.execute(
+            "INSERT INTO watchdog_interventions (download_id, event_type, message, throughput_bps, active_connections, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                download_id,
+                event_type,
+                message,
+                throughput_bps,
+                active_connections,
+                Utc::now().timestamp(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Notification center operations
+
+    /// Record a notification. An unread one of the same kind for the same download (or with
+    /// the same message, when there is no download) is updated instead of duplicated.
+    pub fn add_notification(
+        &self,
+        kind: &str,
+        title: &str,
+        message: &str,
+        download_id: Option<&str>,
+    ) -> DbResult<i64> {
+        let now = Utc::now().timestamp_millis();
+        let existing: Option<i64> = match self.conn.query_row(
+            "SELECT id FROM notifications
+             WHERE kind = ?1 AND read = 0 AND download_id IS ?2 AND (?2 IS NOT NULL OR message = ?3)
+             ORDER BY updated_at DESC LIMIT 1",
+            params![kind, download_id, message],
+            |row| row.get(0),
+        ) {
+            Ok(id) => Some(id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        if let Some(id) = existing {
+            self.conn.execute(
+                "UPDATE notifications SET title = ?1, message = ?2, count = count + 1, updated_at = ?3 WHERE id = ?4",
+                params![title, message, now, id],
+            )?;
+            return Ok(id);
+        }
+
+        self.conn.execute(
+            "INSERT INTO notifications (kind, title, message, download_id, read, count, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, 0, 1, ?5, ?5)",
+            params![kind, title, message, download_id, now],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.conn.execute(
+            "DELETE FROM notifications WHERE id NOT IN
+             (SELECT id FROM notifications ORDER BY updated_at DESC, id DESC LIMIT ?1)",
+            params![NOTIFICATION_LIMIT],
+        )?;
+        Ok(id)
+    }
+
+    /// Newest first
+    pub fn get_notifications(&self, unread_only: bool) -> DbResult<Vec<NotificationRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, kind, title, message, download_id, read, count, created_at, updated_at
+             FROM notifications WHERE read = 0 OR ?1 = 0 ORDER BY updated_at DESC, id DESC"
+        )?;
+
+        let records = stmt.query_map(params![unread_only], |row| {
+            Ok(NotificationRecord {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                title: row.get(2)?,
+                message: row.get(3)?,
+                download_id: row.get(4)?,
+                read: row.get(5)?,
+                count: row.get(6)?,
+                created_at: row.get(7)?,
+                updated_at: row.get(8)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    /// Mark the given notifications as read, or all of them; returns how many changed
+    pub fn mark_notifications_read(&self, ids: Option<&[i64]>) -> DbResult<usize> {
+        let Some(ids) = ids else {
+            return Ok(self.conn.execute("UPDATE notifications SET read = 1 WHERE read = 0", [])?);
+        };
+        let mut changed = 0;
+        for id in ids {
+            changed += self.conn.execute(
+                "UPDATE notifications SET read = 1 WHERE id = ?1 AND read = 0",
+                params![id],
+            )?;
+        }
+        Ok(changed)
+    }
+
+    pub fn get_interventions(&self, download_id: &str) -> DbResult<Vec<InterventionRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, download_id, event_type, message, throughput_bps, active_connections, created_at
+             FROM watchdog_interventions WHERE download_id = ?1 ORDER BY created_at ASC, id ASC"
+        )?;
+
+        let records = stmt.query_map(params![download_id], |row| {
+            Ok(InterventionRecord {
+                id: row.get(0)?,
+                download_id: row.get(1)?,
+                event_type: row.get(2)?,
+                message: row.get(3)?,
+                throughput_bps: row.get(4)?,
+                active_connections: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    // Spotify job operations
+    pub fn save_spotify_job(
+        &self,
+        id: &str,
+        url: &str,
+        request_json: &str,
+        tracks: &[(String, String)],
+    ) -> DbResult<()> {
+        let now = Utc::now().timestamp();
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO spotify_jobs (id, url, request_json, status, created_at, updated_at)
+             VALUES (?1, ?2, ?3, 'running', ?4, ?4)",
+            params![id, url, request_json, now],
+        )?;
+        tx.execute("DELETE FROM spotify_job_tracks WHERE job_id = ?1", params![id])?;
+        for (index, (track_json, display_name)) in tracks.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO spotify_job_tracks (job_id, track_index, track_json, display_name, status)
+                 VALUES (?1, ?2, ?3, ?4, 'pending')",
+                params![id, index as i64, track_json, display_name],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn set_spotify_job_status(&self, id: &str, status: &str) -> DbResult<()> {
+        self.conn.execute(
+            "UPDATE spotify_jobs SET status = ?1, updated_at = ?2 WHERE id = ?3",
+            params![status, Utc::now().timestamp(), id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_spotify_track_status(
+        &self,
+        job_id: &str,
+        track_index: i64,
+        status: &str,
+        error: Option<&str>,
+    ) -> DbResult<()> {
+        self.conn.execute(
+            "UPDATE spotify_job_tracks SET status = ?1, error = ?2 WHERE job_id = ?3 AND track_index = ?4",
+            params![status, error, job_id, track_index],
+        )?;
+        self.conn.execute(
+            "UPDATE spotify_jobs SET updated_at = ?1 WHERE id = ?2",
+            params![Utc::now().timestamp(), job_id],
+        )?;
+        Ok(())
+    }
+
+    fn get_spotify_job_tracks(&self, job_id: &str) -> DbResult<Vec<SpotifyJobTrack>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT track_index, track_json, display_name, status, error
+             FROM spotify_job_tracks WHERE job_id = ?1 ORDER BY track_index ASC"
+        )?;
+
+        let tracks = stmt.query_map(params![job_id], |row| {
+            Ok(SpotifyJobTrack {
+                track_index: row.get(0)?,
+                track_json: row.get(1)?,
+                display_name: row.get(2)?,
+                status: row.get(3)?,
+                error: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tracks)
+    }
+
+    pub fn get_spotify_job(&self, id: &str) -> DbResult<Option<SpotifyJob>> {
+        let mut jobs = self.query_spotify_jobs("WHERE id = ?1", params![id])?;
+        Ok(jobs.pop())
+    }
+
+    /// Jobs that were interrupted, cancelled or left with failed tracks
+    pub fn get_unfinished_spotify_jobs(&self) -> DbResult<Vec<SpotifyJob>> {
+        self.query_spotify_jobs("WHERE status IN ('running', 'cancelled', 'failed')", params![])
+    }
+
+    fn query_spotify_jobs(&self, filter: &str, params: impl rusqlite::Params) -> DbResult<Vec<SpotifyJob>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, url, request_json, status, created_at, updated_at FROM spotify_jobs {} ORDER BY created_at DESC",
+            filter
+        ))?;
+
+        let jobs = stmt.query_map(params, |row| {
+            Ok(SpotifyJob {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                request_json: row.get(2)?,
+                status: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+                tracks: Vec::new(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        jobs.into_iter()
+            .map(|mut job| {
+                job.tracks = self.get_spotify_job_tracks(&job.id)?;
+                Ok(job)
+            })
+            .collect()
+    }
+
+    pub fn delete_spotify_job(&self, id: &str) -> DbResult<()> {
+        self.conn.execute("DELETE FROM spotify_job_tracks WHERE job_id = ?1", params![id])?;
+        self.conn.execute("DELETE FROM spotify_jobs WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // Preset operations
+    /// Insert or update a preset; names must be unique
+    pub fn save_preset(&self, id: &str, name: &str, kind: &str, settings_json: &str) -> DbResult<()> {
+        let now = Utc::now().timestamp();
+        self.conn.execute(
+            "INSERT INTO presets (id, name, kind, settings_json, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, settings_json = excluded.settings_json,
+             updated_at = excluded.updated_at",
+            params![id, name, kind, settings_json, now],
+        )?;
+        Ok(())
+    }
+
+    /// Presets of one kind ("download" or "device"), by name
+    pub fn get_presets(&self, kind: &str) -> DbResult<Vec<PresetRecord>> {
+        self.query_presets("WHERE kind = ?1", params![kind])
+    }
+
+    pub fn get_preset(&self, id: &str) -> DbResult<Option<PresetRecord>> {
+        Ok(self.query_presets("WHERE id = ?1", params![id])?.pop())
+    }
+
+    fn query_presets(&self, filter: &str, params: impl rusqlite::Params) -> DbResult<Vec<PresetRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, name, kind, settings_json, created_at, updated_at FROM presets {} ORDER BY name COLLATE NOCASE",
+            filter
+        ))?;
+
+        let presets = stmt.query_map(params, |row| {
+            Ok(PresetRecord {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                kind: row.get(2)?,
+                settings_json: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(presets)
+    }
+
+    pub fn delete_preset(&self, id: &str) -> DbResult<()> {
+        self.conn.execute("DELETE FROM presets WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // Subscription operations
+    /// Insert or update a subscription; sync bookkeeping is left alone on update
+    pub fn save_subscription(&self, subscription: &Subscription) -> DbResult<()> {
+        self.conn.execute(
+            "INSERT INTO subscriptions (id, name, url, output_path, preset_id, interval_minutes, enabled, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, url = excluded.url,
+             output_path = excluded.output_path, preset_id = excluded.preset_id,
+             interval_minutes = excluded.interval_minutes, enabled = excluded.enabled",
+            params![
+                subscription.id,
+                subscription.name,
+                subscription.url,
+                subscription.output_path,
+                subscription.preset_id,
+                subscription.interval_minutes,
+                subscription.enabled,
+                subscription.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_subscriptions(&self) -> DbResult<Vec<Subscription>> {
+        self.query_subscriptions("", [])
+    }
+
+    pub fn get_subscription(&self, id: &str) -> DbResult<Option<Subscription>> {
+        Ok(self.query_subscriptions("WHERE id = ?1", params![id])?.pop())
+    }
+
+    fn query_subscriptions(&self, filter: &str, params: impl rusqlite::Params) -> DbResult<Vec<Subscription>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, name, url, output_path, preset_id, interval_minutes, enabled, last_synced_at, last_error, created_at
+             FROM subscriptions {} ORDER BY name COLLATE NOCASE",
+            filter
+        ))?;
+
+        let subscriptions = stmt.query_map(params, |row| {
+            Ok(Subscription {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                url: row.get(2)?,
+                output_path: row.get(3)?,
+                preset_id: row.get(4)?,
+                interval_minutes: row.get(5)?,
+                enabled: row.get(6)?,
+                last_synced_at: row.get(7)?,
+                last_error: row.get(8)?,
+                created_at: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(subscriptions)
+    }
+
+    /// Record the outcome of a sync attempt
+    pub fn record_subscription_sync(&self, id: &str, error: Option<&str>) -> DbResult<()> {
+        self.conn.execute(
+            "UPDATE subscriptions SET last_synced_at = ?1, last_error = ?2 WHERE id = ?3",
+            params![Utc::now().timestamp(), error, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_subscription(&self, id: &str) -> DbResult<()> {
+        self.conn.execute("DELETE FROM subscriptions WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Save an item, updating the note and metadata if the URL is already saved
+    pub fn save_item(&self, item: &SavedItem) -> DbResult<()> {
+        self.conn.execute(
+            "INSERT INTO saved_items (id, url, note, title, thumbnail, platform, duration, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(url) DO UPDATE SET note = COALESCE(excluded.note, note),
+             title = COALESCE(excluded.title, title), thumbnail = COALESCE(excluded.thumbnail, thumbnail),
+             platform = COALESCE(excluded.platform, platform), duration = COALESCE(excluded.duration, duration)",
+            params![
+                item.id,
+                item.url,
+                item.note,
+                item.title,
+                item.thumbnail,
+                item.platform,
+                item.duration,
+                item.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_saved_items(&self) -> DbResult<Vec<SavedItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, url, note, title, thumbnail, platform, duration, created_at
+             FROM saved_items ORDER BY created_at DESC",
+        )?;
+        let items = stmt
+            .query_map([], |row| {
+                Ok(SavedItem {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    note: row.get(2)?,
+                    title: row.get(3)?,
+                    thumbnail: row.get(4)?,
+                    platform: row.get(5)?,
+                    duration: row.get(6)?,
+                    created_at: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    pub fn get_saved_item_by_url(&self, url: &str) -> DbResult<Option<SavedItem>> {
+        Ok(self.get_saved_items()?.into_iter().find(|item| item.url == url))
+    }
+
+    pub fn delete_saved_item(&self, id: &str) -> DbResult<()> {
+        self.conn.execute("DELETE FROM saved_items WHERE id = ?1", params![id])?;
         Ok(())
     }
 
@@ -243,6 +1328,54 @@ impl Database {
         Ok(())
     }
 
+    // Download archive operations
+    /// Record archive entries, ignoring ones already present. Returns how many were new.
+    pub fn add_archive_entries(&self, entries: &[(String, String)]) -> DbResult<usize> {
+        let timestamp = Utc::now().timestamp();
+        let mut added = 0;
+        for (extractor, item_id) in entries {
+            added += self.conn.execute(
+                "INSERT OR IGNORE INTO download_archive (extractor, item_id, added_at) VALUES (?1, ?2, ?3)",
+                params![extractor, item_id, timestamp],
+            )?;
+        }
+        Ok(added)
+    }
+
+    pub fn get_archive_entries(&self, limit: Option<i64>) -> DbResult<Vec<ArchiveEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT extractor, item_id, added_at FROM download_archive
+             ORDER BY added_at DESC LIMIT ?1"
+        )?;
+
+        let entries = stmt.query_map(params![limit.unwrap_or(-1)], |row| {
+            Ok(ArchiveEntry {
+                extractor: row.get(0)?,
+                item_id: row.get(1)?,
+                added_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    pub fn count_archive_entries(&self) -> DbResult<i64> {
+        Ok(self.conn.query_row("SELECT COUNT(*) FROM download_archive", [], |row| row.get(0))?)
+    }
+
+    pub fn delete_archive_entry(&self, extractor: &str, item_id: &str) -> DbResult<()> {
+        self.conn.execute(
+            "DELETE FROM download_archive WHERE extractor = ?1 AND item_id = ?2",
+            params![extractor, item_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_archive(&self) -> DbResult<usize> {
+        Ok(self.conn.execute("DELETE FROM download_archive", [])?)
+    }
+
     // Settings operations
     pub fn save_setting(&self, key: &str, value: &str) -> DbResult<()> {
         self.conn.execute(
@@ -285,3 +1418,258 @@ impl Database {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, TestDb};
+
+    #[test]
+    fn test_recovers_from_corrupt_file() {
+        let dir = test_support::scratch_dir();
+        std::fs::write(dir.join("db.sqlite"), b"definitely not a sqlite database").unwrap();
+
+        let mut db = TestDb::open(dir);
+        let report = db.recovery_report().expect("corruption should be reported");
+        assert!(Path::new(&report.backup_path).exists());
+        assert!(db.get_downloads().unwrap().is_empty());
+
+        // The rebuilt database opens cleanly next time
+        db.reopen();
+        assert!(db.recovery_report().is_none());
+    }
+
+    #[test]
+    fn test_locked_database_is_not_rebuilt() {
+        let dir = test_support::scratch_dir();
+        drop(Database::new(dir.clone()).unwrap());
+
+        // Another process holding the database makes the check fail with SQLITE_BUSY
+        // (after the default busy timeout); that's no reason to move the file aside
+        let holder = Connection::open(dir.join("db.sqlite")).unwrap();
+        holder.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        assert!(Database::new(dir.clone()).is_err());
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().flatten().map(|e| e.file_name()).collect();
+        assert!(files.iter().all(|name| !name.to_string_lossy().starts_with("db.corrupt-")));
+
+        holder.execute_batch("ROLLBACK").unwrap();
+        drop(holder);
+        let db = TestDb::open(dir);
+        assert!(db.recovery_report().is_none());
+    }
+
+    #[test]
+    fn test_stale_downloads() {
+        let db = TestDb::new();
+        for (id, status) in [("running", "downloading"), ("done", "completed")] {
+            db.add_download(&Download {
+                id: id.to_string(),
+                title: id.to_string(),
+                url: format!("https://example.com/{}", id),
+                format: String::new(),
+                path: "/dl".to_string(),
+                timestamp: 0,
+                status: status.to_string(),
+                size_bytes: None,
+                platform: None,
+                thumbnail: None,
+                on_complete: None,
+            })
+            .unwrap();
+        }
+
+        assert!(db.fail_stale_download("running", "Interrupted").unwrap());
+        assert!(!db.fail_stale_download("done", "Interrupted").unwrap());
+        let stale = db.get_stale_downloads().unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!((stale[0].0.status.as_str(), stale[0].1.as_str()), ("failed", "Interrupted"));
+
+        // Retrying clears the reason
+        db.update_download_status("running", "downloading").unwrap();
+        assert!(db.get_stale_downloads().unwrap().is_empty());
+
+        // Failures count towards the badge until acknowledged
+        assert_eq!(db.get_badge_counts().unwrap(), (1, 0, 0));
+        db.update_download_status("running", "failed").unwrap();
+        assert_eq!(db.get_badge_counts().unwrap(), (0, 0, 1));
+        assert_eq!(db.acknowledge_failed_downloads(None).unwrap(), 1);
+        assert_eq!(db.get_badge_counts().unwrap(), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_notifications_deduplicate() {
+        let db = TestDb::new();
+
+        let first = db.add_notification("watchdog", "Download needs attention", "Slow", Some("dl-1")).unwrap();
+        let again = db.add_notification("watchdog", "Download needs attention", "Stalled", Some("dl-1")).unwrap();
+        assert_eq!(first, again);
+        db.add_notification("download_complete", "Download complete", "a.zip", Some("dl-1")).unwrap();
+        db.add_notification("update", "Update available", "v2", None).unwrap();
+        db.add_notification("update", "Update available", "v2", None).unwrap();
+        db.add_notification("update", "Update available", "v3", None).unwrap();
+
+        let all = db.get_notifications(false).unwrap();
+        assert_eq!(all.len(), 4);
+        let watchdog = all.iter().find(|n| n.id == first).unwrap();
+        assert_eq!((watchdog.message.as_str(), watchdog.count), ("Stalled", 2));
+
+        assert_eq!(db.mark_notifications_read(Some(&[first])).unwrap(), 1);
+        assert_eq!(db.get_notifications(true).unwrap().len(), 3);
+        // Once read, the next event starts a new entry
+        assert_ne!(db.add_notification("watchdog", "Download needs attention", "Slow", Some("dl-1")).unwrap(), first);
+
+        assert_eq!(db.mark_notifications_read(None).unwrap(), 4);
+        assert!(db.get_notifications(true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_intervention_history() {
+        let db = TestDb::new();
+
+        db.add_intervention("dl-1", "ConnectionsCollapsed", "Connections reduced to 4", Some(1024), Some(4))
+            .unwrap();
+        db.add_intervention("dl-1", "SafeModeActivated", "Safe Mode enabled", None, Some(1))
+            .unwrap();
+        db.add_intervention("dl-2", "SafeModeActivated", "Safe Mode enabled", None, None)
+            .unwrap();
+
+        let history = db.get_interventions("dl-1").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].event_type, "ConnectionsCollapsed");
+
+        db.delete_download("dl-1").unwrap();
+        assert!(db.get_interventions("dl-1").unwrap().is_empty());
+        assert_eq!(db.get_interventions("dl-2").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_spotify_job_persistence() {
+        let db = TestDb::new();
+
+        let tracks = vec![
+            ("{\"name\":\"One\"}".to_string(), "A - One".to_string()),
+            ("{\"name\":\"Two\"}".to_string(), "A - Two".to_string()),
+        ];
+        db.save_spotify_job("job-1", "https://open.spotify.com/album/x", "{}", &tracks)
+            .unwrap();
+        db.set_spotify_track_status("job-1", 0, "completed", None).unwrap();
+
+        let job = db.get_spotify_job("job-1").unwrap().unwrap();
+        assert_eq!(job.status, "running");
+        assert_eq!(job.tracks.len(), 2);
+        assert_eq!(job.tracks[0].status, "completed");
+        assert_eq!(job.tracks[1].status, "pending");
+        assert_eq!(db.get_unfinished_spotify_jobs().unwrap().len(), 1);
+
+        db.set_spotify_job_status("job-1", "completed").unwrap();
+        assert!(db.get_unfinished_spotify_jobs().unwrap().is_empty());
+
+        db.delete_spotify_job("job-1").unwrap();
+        assert!(db.get_spotify_job("job-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_presets() {
+        let db = TestDb::new();
+
+        db.save_preset("p1", "Music 320k", "download", "{}").unwrap();
+        db.save_preset("p2", "Archive 4K mkv", "download", "{}").unwrap();
+        db.save_preset("d1", "Living room TV", "device", "{}").unwrap();
+        assert!(db.save_preset("p3", "Music 320k", "download", "{}").is_err(), "names are unique");
+
+        db.save_preset("p1", "Music 320k", "download", "{\"audio_only\":true}").unwrap();
+        let presets = db.get_presets("download").unwrap();
+        assert_eq!(presets.len(), 2);
+        assert_eq!(presets[0].name, "Archive 4K mkv");
+        assert_eq!(db.get_presets("device").unwrap()[0].kind, "device");
+        assert_eq!(db.get_preset("p1").unwrap().unwrap().settings_json, "{\"audio_only\":true}");
+
+        db.delete_preset("p1").unwrap();
+        assert!(db.get_preset("p1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let db = TestDb::new();
+
+        let mut subscription = Subscription {
+            id: "s1".to_string(),
+            name: "Channel".to_string(),
+            url: "https://example.com/@channel".to_string(),
+            output_path: "/downloads".to_string(),
+            preset_id: None,
+            interval_minutes: 60,
+            enabled: true,
+            last_synced_at: None,
+            last_error: None,
+            created_at: 1,
+        };
+        db.save_subscription(&subscription).unwrap();
+        db.record_subscription_sync("s1", Some("offline")).unwrap();
+
+        // Updating keeps the sync bookkeeping
+        subscription.enabled = false;
+        db.save_subscription(&subscription).unwrap();
+        let saved = db.get_subscription("s1").unwrap().unwrap();
+        assert!(!saved.enabled);
+        assert!(saved.last_synced_at.is_some());
+        assert_eq!(saved.last_error.as_deref(), Some("offline"));
+
+        db.delete_subscription("s1").unwrap();
+        assert!(db.get_subscriptions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_saved_items() {
+        let db = TestDb::new();
+
+        let item = SavedItem {
+            id: "s1".to_string(),
+            url: "https://example.com/watch?v=1".to_string(),
+            note: Some("later".to_string()),
+            title: Some("Clip".to_string()),
+            thumbnail: None,
+            platform: Some("youtube".to_string()),
+            duration: Some(60),
+            created_at: 1,
+        };
+        db.save_item(&item).unwrap();
+        // Saving the URL again keeps the first entry and its note
+        db.save_item(&SavedItem { id: "s2".to_string(), note: None, created_at: 2, ..item.clone() }).unwrap();
+
+        let items = db.get_saved_items().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!((items[0].id.as_str(), items[0].note.as_deref()), ("s1", Some("later")));
+
+        db.delete_saved_item("s1").unwrap();
+        assert!(db.get_saved_items().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_update_download_file() {
+        let db = TestDb::new();
+
+        db.add_download(&Download {
+            id: "dl-1".to_string(),
+            title: "Clip".to_string(),
+            url: "https://example.com/clip".to_string(),
+            format: "mp4".to_string(),
+            path: "/downloads".to_string(),
+            timestamp: 1,
+            status: "completed".to_string(),
+            size_bytes: Some(10),
+            platform: None,
+            thumbnail: None,
+            on_complete: None,
+        })
+        .unwrap();
+
+        db.update_download_file("dl-1", "/downloads/Clip.mp4", Some(2048)).unwrap();
+        let saved = db.get_download("dl-1").unwrap().unwrap();
+        assert_eq!((saved.path.as_str(), saved.size_bytes), ("/downloads/Clip.mp4", Some(2048)));
+
+        // An unknown size keeps the recorded one
+        db.update_download_file("dl-1", "/downloads/Video/Clip.mp4", None).unwrap();
+        assert_eq!(db.get_download("dl-1").unwrap().unwrap().size_bytes, Some(2048));
+    }
+}
@@ -0,0 +1,336 @@
+//! Download Cards
+//!
+//! A short, shareable summary of something that was downloaded, for citing a source
+//! or showing what was archived.
+//!
+//! Key Features:
+//! - Markdown or plain text output
+//! - Title, source URL, platform, quality, size, SHA-256 and download date
+//! - Saved next to the downloaded file as `<name>.card.md` / `<name>.card.txt`
+//! - Optionally saves the thumbnail alongside (`<name>.card.jpg`), linked from the markdown
+
+use crate::commands::{find_media_candidate, AppState};
+use crate::database::Download;
+use crate::folder_stats::ScanControl;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+const THUMBNAIL_TIMEOUT_SECS: u64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardFormat {
+    Markdown,
+    Text,
+}
+
+impl CardFormat {
+    fn suffix(self) -> &'static str {
+        match self {
+            CardFormat::Markdown => "card.md",
+            CardFormat::Text => "card.txt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadCard {
+    pub content: String,
+    /// Where the card was saved; None when the download folder no longer exists
+    pub card_path: Option<String>,
+    pub thumbnail_path: Option<String>,
+}
+
+/// What goes on a card besides the history entry itself
+#[derive(Debug, Default)]
+struct CardDetails {
+    file_name: Option<String>,
+    size_bytes: Option<u64>,
+    sha256: Option<String>,
+    /// Thumbnail file name, relative to the card
+    thumbnail: Option<String>,
+}
+
+/// History timestamps are milliseconds since the epoch
+fn format_date(timestamp_ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|d| d.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Label / value rows shared by both formats
+fn card_rows(download: &Download, details: &CardDetails) -> Vec<(&'static str, String)> {
+    let mut rows = vec![("Source", download.url.clone())];
+    if let Some(platform) = download.platform.as_deref().filter(|p| !p.is_empty()) {
+        rows.push(("Platform", platform.to_string()));
+    }
+    if !download.format.is_empty() {
+        rows.push(("Quality", download.format.clone()));
+    }
+    let size = details.size_bytes.or(download.size_bytes.and_then(|s| u64::try_from(s).ok()));
+    if let Some(size) = size {
+        rows.push(("Size", crate::watchdog::format_bytes(size)));
+    }
+    if let Some(sha256) = &details.sha256 {
+        rows.push(("SHA-256", sha256.clone()));
+    }
+    rows.push(("Downloaded", format_date(download.timestamp)));
+    if let Some(file_name) = &details.file_name {
+        rows.push(("File", file_name.clone()));
+    }
+    rows
+}
+
+fn render_card(download: &Download, details: &CardDetails, format: CardFormat) -> String {
+    let rows = card_rows(download, details);
+    match format {
+        CardFormat::Markdown => {
+            let mut out = format!("# {}\n\n", download.title.replace('\n', " "));
+            if let Some(thumbnail) = &details.thumbnail {
+                out.push_str(&format!("![Thumbnail](<{}>)\n\n", thumbnail));
+            }
+            out.push_str("| | |\n|---|---|\n");
+            for (label, value) in rows {
+                let value = value.replace('|', "\\|");
+                let value = match label {
+                    "Source" => format!("<{}>", value),
+                    "SHA-256" => format!("`{}`", value),
+                    _ => value,
+                };
+                out.push_str(&format!("| **{}** | {} |\n", label, value));
+            }
+            out
+        }
+        CardFormat::Text => {
+            let mut out = format!("Title: {}\n", download.title.replace('\n', " "));
+            for (label, value) in rows {
+                out.push_str(&format!("{}: {}\n", label, value));
+            }
+            if let Some(thumbnail) = &details.thumbnail {
+                out.push_str(&format!("Thumbnail: {}\n", thumbnail));
+            }
+            out
+        }
+    }
+}
+
+/// SHA-256 of a file, streamed so large media doesn't have to fit in memory
+fn file_sha256(path: &Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The downloaded file: the history path itself, or the best title match in that folder.
+/// Without a match there's no file; the newest one in the folder may be anything.
+pub(crate) fn resolve_media_file(download: &Download) -> Option<PathBuf> {
+    let path = Path::new(&download.path);
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    find_media_candidate(path, &download.title, false, &ScanControl::default())
+        .ok()
+        .flatten()
+        .map(|media| PathBuf::from(media.file_path))
+}
+
+/// `<stem>.<suffix>`; unlike `with_extension` this keeps dots inside the stem
+fn card_file(stem: &Path, suffix: &str) -> PathBuf {
+    let mut name = stem.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Save the thumbnail as `<stem>.card.<ext>` next to the card
+async fn save_thumbnail(url: &str, card_stem: &Path) -> Result<PathBuf, String> {
+    let client = crate::proxy::apply_to_client(reqwest::Client::builder())
+        .timeout(std::time::Duration::from_secs(THUMBNAIL_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch thumbnail: {}", e))?;
+    let extension = match response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(t) if t.contains("png") => "png",
+        Some(t) if t.contains("webp") => "webp",
+        _ => "jpg",
+    };
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read thumbnail: {}", e))?;
+    let path = card_file(card_stem, &format!("card.{}", extension));
+    tokio::fs::write(&path, &bytes)
+        .await
+        .map_err(|e| format!("Failed to save thumbnail: {}", e))?;
+    Ok(path)
+}
+
+/// Export a summary card for a history entry and save it next to the downloaded file
+#[tauri::command]
+pub async fn export_download_card(
+    state: State<'_, AppState>,
+    id: String,
+    format: CardFormat,
+    include_thumbnail: Option<bool>,
+) -> Result<DownloadCard, String> {
+    let download = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.get_download(&id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Download {} not found", id))?
+    };
+
+    // Locate and hash the file off the async runtime
+    let lookup = download.clone();
+    let (media_file, sha256) = tokio::task::spawn_blocking(move || {
+        let media_file = resolve_media_file(&lookup);
+        let sha256 = media_file.as_deref().and_then(|f| match file_sha256(f) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                println!("[DownloadCard] {}", e);
+                None
+            }
+        });
+        (media_file, sha256)
+    })
+    .await
+    .map_err(|e| format!("Card task failed: {}", e))?;
+
+    // The media file's stem, or the title inside the download folder
+    let card_stem = match &media_file {
+        Some(file) => Some(file.with_extension("")),
+        None => Some(Path::new(&download.path))
+            .filter(|dir| dir.is_dir())
+            .map(|dir| dir.join(sanitize_card_name(&download.title))),
+    };
+
+    let mut details = CardDetails {
+        file_name: media_file
+            .as_ref()
+            .and_then(|f| f.file_name())
+            .map(|n| n.to_string_lossy().to_string()),
+        size_bytes: media_file.as_ref().and_then(|f| f.metadata().ok()).map(|m| m.len()),
+        sha256,
+        thumbnail: None,
+    };
+
+    let mut thumbnail_path = None;
+    if include_thumbnail.unwrap_or(false) {
+        if let (Some(url), Some(stem)) = (download.thumbnail.as_deref(), &card_stem) {
+            match save_thumbnail(url, stem).await {
+                Ok(path) => {
+                    details.thumbnail = path.file_name().map(|n| n.to_string_lossy().to_string());
+                    thumbnail_path = Some(path.to_string_lossy().to_string());
+                }
+                Err(e) => println!("[DownloadCard] {}", e),
+            }
+        }
+    }
+
+    let content = render_card(&download, &details, format);
+    let card_path = match card_stem {
+        Some(stem) => {
+            let path = card_file(&stem, format.suffix());
+            tokio::fs::write(&path, &content)
+                .await
+                .map_err(|e| format!("Failed to save download card: {}", e))?;
+            Some(path.to_string_lossy().to_string())
+        }
+        None => None,
+    };
+
+    Ok(DownloadCard {
+        content,
+        card_path,
+        thumbnail_path,
+    })
+}
+
+/// Title as a file name stem
+fn sanitize_card_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') { c } else { '_' })
+        .collect();
+    match name.trim() {
+        "" => "download".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn download() -> Download {
+        Download {
+            id: "d1".to_string(),
+            title: "Talk | Part 1".to_string(),
+            url: "https://example.com/watch?v=1".to_string(),
+            format: "1080p mp4".to_string(),
+            path: "/nonexistent".to_string(),
+            timestamp: 1_700_000_000_000,
+            status: "completed".to_string(),
+            size_bytes: Some(5 * 1024 * 1024),
+            platform: Some("youtube".to_string()),
+            thumbnail: None,
+            on_complete: None,
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_card() {
+        let details = CardDetails {
+            file_name: Some("talk.mp4".to_string()),
+            sha256: Some("ab12".to_string()),
+            thumbnail: Some("talk.card.jpg".to_string()),
+            ..Default::default()
+        };
+        let card = render_card(&download(), &details, CardFormat::Markdown);
+        assert!(card.starts_with("# Talk | Part 1\n\n![Thumbnail](<talk.card.jpg>)\n"));
+        assert!(card.contains("| **Source** | <https://example.com/watch?v=1> |\n"));
+        assert!(card.contains("| **Size** | 5.00 MB |\n"));
+        assert!(card.contains("| **SHA-256** | `ab12` |\n"));
+        assert!(card.contains("| **Downloaded** | 2023-11-14 22:13 UTC |\n"));
+    }
+
+    #[test]
+    fn test_render_text_card() {
+        let card = render_card(&download(), &CardDetails::default(), CardFormat::Text);
+        assert_eq!(
+            card,
+            "Title: Talk | Part 1\nSource: https://example.com/watch?v=1\nPlatform: youtube\n\
+             Quality: 1080p mp4\nSize: 5.00 MB\nDownloaded: 2023-11-14 22:13 UTC\n"
+        );
+    }
+
+    #[test]
+    fn test_card_paths() {
+        let stem = PathBuf::from("/d/talk.v2.mp4").with_extension("");
+        assert_eq!(card_file(&stem, CardFormat::Markdown.suffix()), PathBuf::from("/d/talk.v2.card.md"));
+        let stem = PathBuf::from("/d").join(sanitize_card_name("A/B: c.d"));
+        assert_eq!(card_file(&stem, CardFormat::Text.suffix()), PathBuf::from("/d/A_B_ c_d.card.txt"));
+        assert_eq!(sanitize_card_name(" ?? "), "__");
+        assert_eq!(sanitize_card_name("  "), "download");
+    }
+}
@@ -0,0 +1,212 @@
+//! Download Event Log
+//!
+//! A coarse, instance-wide feed of download lifecycle events for companion tools (stream
+//! deck plugins, scripts), streamed as server-sent events at `/events` on the extension
+//! server. Clients send `X-Extension-Request: true` and address the server as 127.0.0.1 or
+//! localhost.
+//!
+//! Key Features:
+//! - Built from every engine's progress events: started, progress in 10% steps, completed, failed and
+//!   cancelled, so subscribers aren't flooded with per-chunk updates
+//! - Every event has a sequence number; the last events are kept so a reconnecting client
+//!   sending `Last-Event-ID` gets what it missed
+//! - No CORS headers are sent, so web pages can't read the feed
+
+use crate::download_lifecycle::{DownloadTracker, ProgressUpdate, Transition};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tokio::sync::broadcast;
+
+/// Events kept for clients catching up after a reconnect
+const BACKLOG_SIZE: usize = 200;
+
+/// Percentage step between "progress" events
+const PROGRESS_STEP: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadEventKind {
+    Started,
+    Progress,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl DownloadEventKind {
+    /// SSE event name
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DownloadEventKind::Started => "started",
+            DownloadEventKind::Progress => "progress",
+            DownloadEventKind::Completed => "completed",
+            DownloadEventKind::Failed => "failed",
+            DownloadEventKind::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadEvent {
+    /// Sequence number, used as the SSE event id
+    pub seq: u64,
+    pub kind: DownloadEventKind,
+    pub id: String,
+    /// Percent, rounded down to the step for "progress"
+    pub progress: f64,
+    pub filename: Option<String>,
+    pub file_path: Option<String>,
+    pub error: Option<String>,
+    pub timestamp: i64,
+}
+
+/// What's been announced per download
+#[derive(Default)]
+struct Tracker {
+    /// Last progress step announced per running download
+    running: DownloadTracker<u32>,
+    /// Recently finished downloads; some engines report "completed" twice
+    finished: VecDeque<String>,
+}
+
+#[derive(Default)]
+struct Log {
+    next_seq: u64,
+    tracker: Tracker,
+    backlog: VecDeque<DownloadEvent>,
+}
+
+lazy_static::lazy_static! {
+    static ref LOG: Mutex<Log> = Mutex::new(Log::default());
+    static ref EVENTS: broadcast::Sender<DownloadEvent> = broadcast::channel(BACKLOG_SIZE).0;
+}
+
+/// The lifecycle event a progress update amounts to, if any
+fn classify(update: &ProgressUpdate, tracker: &mut Tracker) -> Option<(DownloadEventKind, f64)> {
+    let percent = update.progress.clamp(0.0, 100.0);
+    let step = (percent / PROGRESS_STEP).floor() as u32;
+
+    match tracker.running.track(update, || step) {
+        Transition::Finished(_) => {
+            if tracker.finished.contains(&update.id) {
+                return None;
+            }
+            if tracker.finished.len() == BACKLOG_SIZE {
+                tracker.finished.pop_front();
+            }
+            tracker.finished.push_back(update.id.clone());
+            let kind = match update.status.as_str() {
+                "completed" => DownloadEventKind::Completed,
+                "cancelled" => DownloadEventKind::Cancelled,
+                _ => DownloadEventKind::Failed,
+            };
+            Some((kind, percent))
+        }
+        Transition::Running { entry, new } => {
+            // A retry starts over
+            tracker.finished.retain(|finished| *finished != update.id);
+            if new {
+                Some((DownloadEventKind::Started, percent))
+            } else if step > *entry {
+                *entry = step;
+                Some((DownloadEventKind::Progress, step as f64 * PROGRESS_STEP))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn record(update: &ProgressUpdate) {
+    let mut log = LOG.lock().unwrap();
+    let Some((kind, percent)) = classify(update, &mut log.tracker) else {
+        return;
+    };
+    let event = DownloadEvent {
+        seq: log.next_seq,
+        kind,
+        id: update.id.clone(),
+        progress: percent,
+        filename: update.filename.clone(),
+        file_path: update.file_path.clone(),
+        error: update.error.clone(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    };
+    log.next_seq += 1;
+    if log.backlog.len() == BACKLOG_SIZE {
+        log.backlog.pop_front();
+    }
+    log.backlog.push_back(event.clone());
+    // Sent under the lock so a subscriber can't miss an event between backlog and channel
+    let _ = EVENTS.send(event);
+}
+
+/// Turn download progress into lifecycle events; call once at startup
+pub fn install(app_handle: &AppHandle) {
+    crate::download_lifecycle::on_progress(app_handle, record);
+}
+
+/// Events after `last_seen` still in the backlog, and a receiver for the ones to come
+pub fn subscribe(last_seen: Option<u64>) -> (Vec<DownloadEvent>, broadcast::Receiver<DownloadEvent>) {
+    let log = LOG.lock().unwrap();
+    let missed = match last_seen {
+        Some(seen) => log.backlog.iter().filter(|e| e.seq > seen).cloned().collect(),
+        None => Vec::new(),
+    };
+    (missed, EVENTS.subscribe())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(id: &str, status: &str, percent: f64) -> ProgressUpdate {
+        ProgressUpdate::from_download_progress(serde_json::json!({ "id": id, "status": status, "progress": percent }))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_classify_is_coarse() {
+        let mut tracker = Tracker::default();
+        let kinds: Vec<_> = [
+            progress("a", "downloading", 0.0),
+            progress("a", "downloading", 4.0),
+            progress("a", "downloading", 12.5),
+            progress("a", "downloading", 19.9),
+            progress("a", "downloading", 47.0),
+            progress("a", "completed", 100.0),
+            // Reported again once the file is moved into place
+            progress("a", "completed", 100.0),
+        ]
+        .iter()
+        .filter_map(|p| classify(p, &mut tracker))
+        .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                (DownloadEventKind::Started, 0.0),
+                (DownloadEventKind::Progress, 10.0),
+                (DownloadEventKind::Progress, 40.0),
+                (DownloadEventKind::Completed, 100.0),
+            ]
+        );
+        assert!(tracker.running.is_empty());
+
+        // Retried
+        let retried = classify(&progress("a", "downloading", 0.0), &mut tracker);
+        assert_eq!(retried.map(|(kind, _)| kind), Some(DownloadEventKind::Started));
+    }
+
+    #[test]
+    fn test_classify_terminal_statuses() {
+        let mut tracker = Tracker::default();
+        let mut kind = |p: ProgressUpdate| classify(&p, &mut tracker).map(|(k, _)| k);
+        assert_eq!(kind(progress("b", "drm_protected", 0.0)), Some(DownloadEventKind::Failed));
+        assert_eq!(kind(progress("c", "cancelled", 30.0)), Some(DownloadEventKind::Cancelled));
+        let spotify = ProgressUpdate::from_spotify_progress(serde_json::json!({ "id": "d", "status": "completed" }));
+        assert_eq!(kind(spotify.unwrap()), Some(DownloadEventKind::Completed));
+    }
+}
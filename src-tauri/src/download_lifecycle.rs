@@ -0,0 +1,211 @@
+//! Download Lifecycle Events
+//!
+//! Several modules follow downloads through their progress events (coalescing, summaries,
+//! footprints, takeovers, the event log, the badge). This module is the one place that
+//! listens to every engine and tells them apart from the terminal statuses.
+//!
+//! Key Features:
+//! - `download-progress` (yt-dlp, SNDE, aria2, gallery-dl, FTP, relays) and
+//!   `spotify-download-progress` (spotDL) delivered as one `ProgressUpdate`
+//! - `TERMINAL_STATUSES` shared by every listener
+//! - `DownloadTracker` keeps per-download state and drops it once the download ends
+
+use std::collections::HashMap;
+use tauri::{AppHandle, Listener};
+
+/// Progress event emitted by every engine except spotDL
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download-progress";
+
+/// Progress event emitted by the spotDL engine
+pub const SPOTIFY_PROGRESS_EVENT: &str = "spotify-download-progress";
+
+/// Statuses after which a download sends no more progress
+pub const TERMINAL_STATUSES: &[&str] = &["completed", "failed", "cancelled", "drm_protected", "checksum_mismatch"];
+
+/// One progress event, whichever engine sent it
+#[derive(Debug, Clone)]
+pub struct ProgressUpdate {
+    /// Event it arrived on, for listeners that re-emit it
+    pub event: &'static str,
+    pub id: String,
+    pub status: String,
+    /// Percent
+    pub progress: f64,
+    pub downloaded_bytes: Option<u64>,
+    pub total_bytes: Option<u64>,
+    /// Speed as displayed, e.g. "1.2MiB/s"
+    pub speed: Option<String>,
+    pub filename: Option<String>,
+    pub file_path: Option<String>,
+    pub error: Option<String>,
+    /// The payload as sent
+    pub payload: serde_json::Value,
+}
+
+impl ProgressUpdate {
+    /// A `download-progress` payload
+    pub fn from_download_progress(payload: serde_json::Value) -> Option<Self> {
+        let text = |key: &str| payload[key].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+        Some(Self {
+            event: DOWNLOAD_PROGRESS_EVENT,
+            id: payload["id"].as_str()?.to_string(),
+            status: payload["status"].as_str().unwrap_or_default().to_string(),
+            progress: payload["progress"].as_f64().unwrap_or(0.0),
+            downloaded_bytes: payload["downloaded_bytes"].as_u64(),
+            total_bytes: payload["total_bytes"].as_u64(),
+            speed: text("speed"),
+            filename: text("filename"),
+            file_path: text("file_path"),
+            error: text("error"),
+            payload,
+        })
+    }
+
+    /// A `spotify-download-progress` payload. Tracks skipped for DRM don't end the job, its
+    /// `speed` holds a track counter, and `current_track` carries the error once it fails.
+    pub fn from_spotify_progress(payload: serde_json::Value) -> Option<Self> {
+        let status = match payload["status"].as_str().unwrap_or_default() {
+            "skipped_drm" => "downloading",
+            status => status,
+        };
+        let error = match status {
+            "failed" | "drm_protected" => payload["current_track"].as_str().map(str::to_string),
+            _ => None,
+        };
+        Some(Self {
+            event: SPOTIFY_PROGRESS_EVENT,
+            id: payload["id"].as_str()?.to_string(),
+            status: status.to_string(),
+            progress: payload["progress"].as_f64().unwrap_or(0.0),
+            downloaded_bytes: None,
+            total_bytes: None,
+            speed: None,
+            filename: None,
+            file_path: None,
+            error,
+            payload,
+        })
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        TERMINAL_STATUSES.contains(&self.status.as_str())
+    }
+}
+
+/// Call `handler` with every engine's progress events; call from an `install` at startup
+pub fn on_progress<F>(app_handle: &AppHandle, handler: F)
+where
+    F: Fn(&ProgressUpdate) + Send + Sync + 'static,
+{
+    let handler = std::sync::Arc::new(handler);
+    let sources: [(&str, fn(serde_json::Value) -> Option<ProgressUpdate>); 2] = [
+        (DOWNLOAD_PROGRESS_EVENT, ProgressUpdate::from_download_progress),
+        (SPOTIFY_PROGRESS_EVENT, ProgressUpdate::from_spotify_progress),
+    ];
+    for (event_name, parse) in sources {
+        let handler = handler.clone();
+        app_handle.listen(event_name, move |event| {
+            let update = serde_json::from_str(event.payload()).ok().and_then(parse);
+            if let Some(update) = update {
+                handler(&update);
+            }
+        });
+    }
+}
+
+/// Where a download stands after an update
+pub enum Transition<'a, T> {
+    /// Still going; `new` on its first update since it started (or was retried)
+    Running { entry: &'a mut T, new: bool },
+    /// Reached a terminal status; what was tracked, if it was seen running
+    Finished(Option<T>),
+}
+
+/// Per-download state for the downloads that haven't finished yet
+pub struct DownloadTracker<T> {
+    running: HashMap<String, T>,
+}
+
+impl<T> Default for DownloadTracker<T> {
+    fn default() -> Self {
+        Self { running: HashMap::new() }
+    }
+}
+
+impl<T> DownloadTracker<T> {
+    /// Follow one update, starting the entry with `start` when the download is new
+    pub fn track(&mut self, update: &ProgressUpdate, start: impl FnOnce() -> T) -> Transition<'_, T> {
+        if update.is_terminal() {
+            return Transition::Finished(self.running.remove(&update.id));
+        }
+        let new = !self.running.contains_key(&update.id);
+        let entry = self.running.entry(update.id.clone()).or_insert_with(start);
+        Transition::Running { entry, new }
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.running.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.running.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.running.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.running.clear();
+    }
+
+    #[cfg(test)]
+    pub fn insert(&mut self, id: &str, entry: T) {
+        self.running.insert(id.to_string(), entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spotify_progress_is_normalized() {
+        let update = |status: &str| {
+            ProgressUpdate::from_spotify_progress(serde_json::json!({
+                "id": "job", "progress": 40.0, "status": status,
+                "current_track": "Track unavailable", "speed": "Track 2/5",
+            }))
+            .unwrap()
+        };
+        let skipped = update("skipped_drm");
+        assert_eq!(skipped.status, "downloading");
+        assert!(!skipped.is_terminal());
+        assert_eq!(skipped.speed, None);
+
+        let failed = update("failed");
+        assert!(failed.is_terminal());
+        assert_eq!(failed.error.as_deref(), Some("Track unavailable"));
+        assert_eq!(failed.event, SPOTIFY_PROGRESS_EVENT);
+    }
+
+    #[test]
+    fn test_tracker_drops_finished_downloads() {
+        let progress = |status: &str| {
+            ProgressUpdate::from_download_progress(serde_json::json!({ "id": "a", "status": status })).unwrap()
+        };
+        let mut tracker = DownloadTracker::default();
+        assert!(matches!(tracker.track(&progress("downloading"), || 1), Transition::Running { new: true, .. }));
+        match tracker.track(&progress("downloading"), || 1) {
+            Transition::Running { entry, new } => {
+                assert!(!new);
+                *entry = 2;
+            }
+            Transition::Finished(_) => panic!("still running"),
+        }
+        assert!(matches!(tracker.track(&progress("cancelled"), || 1), Transition::Finished(Some(2))));
+        assert!(matches!(tracker.track(&progress("completed"), || 1), Transition::Finished(None)));
+        assert!(tracker.is_empty());
+        assert!(ProgressUpdate::from_download_progress(serde_json::json!({ "status": "downloading" })).is_none());
+    }
+}
@@ -8,14 +8,32 @@
 //! 2. Probes - Check for Range request support with timeout
 //! 3. Historical Memory - Consult Host Reputation Table
 //! 4. Final Routing - Select SNDE or Media Engine
+//!
+//! Image galleries (imgur albums, Reddit galleries, Instagram profiles) skip all of
+//! this and go to the gallery engine.
+//!
+//! Sites where SNDE keeps failing can be pinned to the Media Engine with a
+//! user-approved rule, so later downloads skip the failing SNDE attempt.
 
+use crate::commands::AppState;
+use crate::database::Database;
 use crate::health_metrics::DownloadEngine;
-use crate::host_reputation::{HostReputationManager, HostReputation, extract_domain};
+use crate::host_reputation::{HostReputationManager, HostReputation, extract_domain, host_and_parents, normalize_host};
+use reqwest::header::HeaderMap;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::State;
 use url::Url;
 
+/// Settings key for hosts the user chose to always send to the Media Engine (JSON list)
+pub const MEDIA_ENGINE_HOSTS_SETTING: &str = "router_media_engine_hosts";
+
+/// Consecutive SNDE failures on a host before suggesting the Media Engine
+pub const SNDE_FAILURE_THRESHOLD: u32 = 2;
+
 /// Known media platform domains that should use Media Engine (yt-dlp)
 const MEDIA_DOMAINS: &[&str] = &[
     // Video platforms
@@ -43,6 +61,15 @@ const MEDIA_DOMAINS: &[&str] = &[
     "imgur.com", "i.imgur.com",
 ];
 
+/// Gallery pages for the gallery engine: host and the path prefixes that mark a gallery
+const GALLERY_RULES: &[(&str, &[&str])] = &[
+    ("imgur.com", &["/a/", "/gallery/"]),
+    ("reddit.com", &["/gallery/"]),
+];
+
+/// Instagram paths that aren't profiles (posts, reels, stories...)
+const INSTAGRAM_NON_PROFILE_PATHS: &[&str] = &["p", "reel", "reels", "tv", "stories", "explore", "accounts", "direct"];
+
 /// File extensions that indicate static files suitable for SNDE
 const STATIC_EXTENSIONS: &[&str] = &[
     // Archives
@@ -81,6 +108,16 @@ pub struct ProbeResult {
     pub error: Option<String>,
 }
 
+impl ProbeResult {
+    /// Whether the URL answered with a web page rather than a file
+    pub fn is_web_page(&self) -> bool {
+        self.content_type.as_deref().is_some_and(|ct| {
+            let ct = ct.trim().to_lowercase();
+            ct.starts_with("text/html") || ct.starts_with("application/xhtml+xml")
+        })
+    }
+}
+
 impl Default for ProbeResult {
     fn default() -> Self {
         Self {
@@ -107,6 +144,9 @@ pub struct RoutingDecision {
     pub reason: String,
     /// Whether to force HTTP/1.1
     pub force_http1: bool,
+    /// Host advertised HTTP/3; SNDE tries QUIC first and falls back if the handshake fails
+    #[serde(default)]
+    pub prefer_http3: bool,
     /// File size if known
     pub file_size: Option<u64>,
     /// Host reputation data
@@ -119,37 +159,62 @@ pub struct RoutingDecision {
 
 /// Download Router - makes intelligent routing decisions
 pub struct DownloadRouter {
-    client: Client,
+    /// Probe client, rebuilt when proxy settings change
+    client: std::sync::RwLock<Client>,
     probe_timeout: Duration,
 }
 
 impl DownloadRouter {
     /// Create a new router with default settings
     pub fn new() -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .connect_timeout(Duration::from_secs(10))
-            .build()
-            .unwrap_or_default();
+        Self::with_timeout(Duration::from_secs(2))
+    }
 
+    /// Create router with custom timeout
+    pub fn with_timeout(probe_timeout: Duration) -> Self {
         Self {
-            client,
-            probe_timeout: Duration::from_secs(2),
+            client: std::sync::RwLock::new(Self::build_client()),
+            probe_timeout,
         }
     }
 
-    /// Create router with custom timeout
-    pub fn with_timeout(probe_timeout: Duration) -> Self {
-        let client = Client::builder()
+    fn build_client() -> Client {
+        crate::proxy::apply_to_client(Client::builder())
             .timeout(Duration::from_secs(30))
             .connect_timeout(Duration::from_secs(10))
             .build()
-            .unwrap_or_default();
+            .unwrap_or_default()
+    }
 
-        Self {
-            client,
-            probe_timeout,
+    /// Rebuild the probe client (e.g. after proxy settings change)
+    pub fn rebuild_client(&self) {
+        let client = Self::build_client();
+        if let Ok(mut current) = self.client.write() {
+            *current = client;
+        }
+    }
+
+    /// Check if URL is an image gallery or profile that gallery-dl handles better than yt-dlp
+    pub fn is_gallery_url(&self, url: &str) -> bool {
+        let Ok(parsed) = Url::parse(url) else {
+            return false;
+        };
+        let host = parsed.host_str().unwrap_or("").to_lowercase();
+        let on = |domain: &str| host == domain || host.ends_with(&format!(".{}", domain));
+        let path = parsed.path();
+
+        if GALLERY_RULES
+            .iter()
+            .any(|(domain, prefixes)| on(domain) && prefixes.iter().any(|p| path.starts_with(p)))
+        {
+            return true;
+        }
+        // Instagram profile pages: a single path segment that isn't a post or reel
+        if on("instagram.com") {
+            let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+            return segments.len() == 1 && !INSTAGRAM_NON_PROFILE_PATHS.contains(&segments[0]);
         }
+        false
     }
 
     /// Check if URL matches a known media platform
@@ -188,14 +253,19 @@ impl DownloadRouter {
         false
     }
 
-    /// Perform a Range request probe to check server capabilities
-    pub async fn probe_url(&self, url: &str) -> ProbeResult {
+    /// Perform a Range request probe to check server capabilities, sending `extra_headers`
+    /// on top of the header profile
+    pub async fn probe_url(&self, url: &str, extra_headers: &HeaderMap) -> ProbeResult {
         let start = std::time::Instant::now();
         
+        let client = self.client.read().unwrap().clone();
+        let mut headers = crate::header_profiles::headers_for_url(url);
+        headers.extend(extra_headers.clone());
         let result = tokio::time::timeout(
             self.probe_timeout,
-            self.client
+            client
                 .get(url)
+                .headers(headers)
                 .header("Range", "bytes=0-0")
                 .send()
         ).await;
@@ -239,6 +309,10 @@ impl DownloadRouter {
                     .and_then(|v| v.to_str().ok())
                     .map(|s| s.to_string());
 
+                if let Some(alt_svc) = headers.get("alt-svc").and_then(|v| v.to_str().ok()) {
+                    remember_quic_host(url, alt_svc);
+                }
+
                 // Detect HTTP version
                 let protocol = match response.version() {
                     reqwest::Version::HTTP_09 => "http0.9".to_string(),
@@ -280,10 +354,54 @@ impl DownloadRouter {
         &self,
         url: &str,
         reputation_manager: Option<&HostReputationManager>,
+    ) -> RoutingDecision {
+        self.route_with_headers(url, reputation_manager, &HeaderMap::new()).await
+    }
+
+    /// `route` for a download that sends its own headers (Referer, Authorization, cookies),
+    /// so hosts that need them aren't mistaken for failing
+    pub async fn route_with_headers(
+        &self,
+        url: &str,
+        reputation_manager: Option<&HostReputationManager>,
+        extra_headers: &HeaderMap,
     ) -> RoutingDecision {
         // Step 1: Check heuristics first (fastest)
-        let is_media = self.is_media_domain(url);
         let is_static = self.is_static_file(url);
+        let is_media = self.is_media_domain(url);
+        // The extractor lookup goes by host name alone, so it only decides once the probe
+        // shows a web page rather than a file
+        let has_extractor = !is_static && crate::extractor_index::has_extractor(url);
+
+        // FTP and SFTP can't be probed over HTTP; they have their own engine
+        if let Some(protocol) = crate::ftp::protocol_of(url) {
+            return RoutingDecision {
+                engine: DownloadEngine::Ftp,
+                recommended_connections: 1,
+                reason: format!("{} URL - using the FTP/SFTP engine", protocol.badge()),
+                force_http1: false,
+                prefer_http3: false,
+                file_size: None,
+                host_reputation: None,
+                probe_result: None,
+                badge: protocol.badge().to_string(),
+            };
+        }
+
+        // Galleries go to gallery-dl; the downloader falls back if it isn't installed
+        if self.is_gallery_url(url) {
+            return RoutingDecision {
+                engine: DownloadEngine::Gallery,
+                recommended_connections: 1,
+                reason: "Image gallery detected - using gallery-dl".to_string(),
+                force_http1: false,
+                prefer_http3: false,
+                file_size: None,
+                host_reputation: None,
+                probe_result: None,
+                badge: crate::gallery::GALLERY_BADGE.to_string(),
+            };
+        }
 
         // Media domains always go to Media Engine
         if is_media {
@@ -292,6 +410,7 @@ impl DownloadRouter {
                 recommended_connections: 1,
                 reason: "Media platform detected - using yt-dlp for best compatibility".to_string(),
                 force_http1: false,
+                prefer_http3: false,
                 file_size: None,
                 host_reputation: None,
                 probe_result: None,
@@ -301,13 +420,34 @@ impl DownloadRouter {
 
         // Step 2: Get host reputation if available
         let domain = extract_domain(url);
+
+        // Sites the user pinned to the Media Engine after SNDE kept failing
+        if domain.as_deref().is_some_and(has_media_engine_rule) {
+            return RoutingDecision {
+                engine: DownloadEngine::MediaEngine,
+                recommended_connections: 1,
+                reason: "Site rule - always using Media Engine for this host".to_string(),
+                force_http1: false,
+                prefer_http3: false,
+                file_size: None,
+                host_reputation: None,
+                probe_result: None,
+                badge: "MEDIA ENGINE".to_string(),
+            };
+        }
+
         let host_reputation = match (&domain, reputation_manager) {
             (Some(d), Some(rm)) => rm.get_reputation(d).ok(),
             _ => None,
         };
 
+        // HLS playlists go to aria2c when it's enabled; the downloader falls back if it can't
+        if crate::aria2::is_enabled() && crate::aria2::is_hls_playlist(url, None) {
+            return aria2_decision("HLS playlist - segments fetched in parallel by aria2c", host_reputation, None);
+        }
+
         // Step 3: Perform probe
-        let probe_result = self.probe_url(url).await;
+        let probe_result = self.probe_url(url, extra_headers).await;
 
         // Step 4: Make decision based on all data
         if !probe_result.success {
@@ -320,6 +460,7 @@ impl DownloadRouter {
                     probe_result.error.as_deref().unwrap_or("unknown error")
                 ),
                 force_http1: false,
+                prefer_http3: false,
                 file_size: None,
                 host_reputation,
                 probe_result: Some(probe_result),
@@ -328,7 +469,23 @@ impl DownloadRouter {
         }
 
         // Probe succeeded
-        if probe_result.supports_range {
+        if has_extractor && probe_result.is_web_page() {
+            RoutingDecision {
+                engine: DownloadEngine::MediaEngine,
+                recommended_connections: 1,
+                reason: "Web page on a site yt-dlp has an extractor for - using Media Engine".to_string(),
+                force_http1: false,
+                prefer_http3: false,
+                file_size: None,
+                host_reputation,
+                probe_result: Some(probe_result),
+                badge: "MEDIA ENGINE".to_string(),
+            }
+        } else if crate::aria2::is_enabled()
+            && crate::aria2::is_hls_playlist(url, probe_result.content_type.as_deref())
+        {
+            aria2_decision("HLS content type - segments fetched in parallel by aria2c", host_reputation, Some(probe_result))
+        } else if probe_result.supports_range {
             // Server supports Range - use SNDE
             let recommended_connections = if let Some(ref rep) = host_reputation {
                 // Use historical data
@@ -363,6 +520,7 @@ impl DownloadRouter {
                     if host_reputation.is_some() { "known host" } else { "new host" }
                 ),
                 force_http1: engine == DownloadEngine::SNDE, // Force HTTP/1.1 for parallel SNDE
+                prefer_http3: knows_quic(url),
                 file_size: probe_result.content_length,
                 host_reputation,
                 probe_result: Some(probe_result),
@@ -375,6 +533,7 @@ impl DownloadRouter {
                 recommended_connections: 1,
                 reason: "Static file detected but no Range support - using safe single connection".to_string(),
                 force_http1: false,
+                prefer_http3: knows_quic(url),
                 file_size: probe_result.content_length,
                 host_reputation,
                 probe_result: Some(probe_result),
@@ -387,6 +546,7 @@ impl DownloadRouter {
                 recommended_connections: 1,
                 reason: "No Range support and not a static file - Media Engine for safety".to_string(),
                 force_http1: false,
+                prefer_http3: false,
                 file_size: probe_result.content_length,
                 host_reputation,
                 probe_result: Some(probe_result),
@@ -407,6 +567,171 @@ lazy_static::lazy_static! {
     pub static ref DOWNLOAD_ROUTER: DownloadRouter = DownloadRouter::new();
 }
 
+lazy_static::lazy_static! {
+    /// Hosts pinned to the Media Engine
+    static ref MEDIA_ENGINE_HOSTS: RwLock<BTreeSet<String>> = RwLock::new(BTreeSet::new());
+
+    /// Consecutive SNDE failures per host, reset by a successful SNDE download
+    static ref SNDE_FAILURES: Mutex<HashMap<String, u32>> = Mutex::new(HashMap::new());
+
+    /// Hosts that advertised HTTP/3 over Alt-Svc, until the advertisement expires
+    static ref QUIC_HOSTS: RwLock<HashMap<String, Instant>> = RwLock::new(HashMap::new());
+}
+
+/// Alt-Svc's default lifetime when `ma` is missing
+const ALT_SVC_DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Longest an HTTP/3 advertisement is trusted, whatever `ma` says
+const ALT_SVC_MAX_AGE_CAP: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How long an Alt-Svc header says HTTP/3 is offered for, if it offers it on the same host
+fn alt_svc_h3_max_age(alt_svc: &str) -> Option<Duration> {
+    alt_svc.split(',').find_map(|service| {
+        let mut params = service.split(';').map(str::trim);
+        let (protocol, authority) = params.next()?.split_once('=')?;
+        // Only "h3" itself, not drafts, and only on this host (":443", not "other.host:443")
+        if protocol.trim() != "h3" || !authority.trim_matches('"').starts_with(':') {
+            return None;
+        }
+        let max_age = params
+            .filter_map(|param| param.strip_prefix("ma="))
+            .find_map(|secs| secs.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(ALT_SVC_DEFAULT_MAX_AGE);
+        Some(max_age.min(ALT_SVC_MAX_AGE_CAP))
+    })
+}
+
+/// Remember that the URL's host serves HTTP/3, or forget it on `Alt-Svc: clear`
+fn remember_quic_host(url: &str, alt_svc: &str) {
+    let Some(host) = extract_domain(url) else { return };
+    let mut hosts = QUIC_HOSTS.write().unwrap();
+    if alt_svc.trim() == "clear" {
+        hosts.remove(&host);
+    } else if let Some(max_age) = alt_svc_h3_max_age(alt_svc) {
+        hosts.insert(host, Instant::now() + max_age);
+    }
+}
+
+/// Whether the URL's host is known to serve HTTP/3
+pub fn knows_quic(url: &str) -> bool {
+    extract_domain(url)
+        .and_then(|host| QUIC_HOSTS.read().unwrap().get(&host).copied())
+        .is_some_and(|expires| expires > Instant::now())
+}
+
+/// Stop preferring HTTP/3 for the URL's host, e.g. after a failed QUIC handshake
+pub fn forget_quic_host(url: &str) {
+    if let Some(host) = extract_domain(url) {
+        QUIC_HOSTS.write().unwrap().remove(&host);
+    }
+}
+
+/// Whether a host (or one of its parent domains) is pinned to the Media Engine
+fn host_matches(rules: &BTreeSet<String>, host: &str) -> bool {
+    host_and_parents(host).any(|candidate| rules.contains(candidate))
+}
+
+fn has_media_engine_rule(host: &str) -> bool {
+    MEDIA_ENGINE_HOSTS
+        .read()
+        .map(|rules| host_matches(&rules, &host.to_lowercase()))
+        .unwrap_or(false)
+}
+
+/// Record an SNDE failure for the URL's host, returning the consecutive failure count
+pub fn record_snde_failure(url: &str) -> Option<(String, u32)> {
+    let host = extract_domain(url)?.to_lowercase();
+    let mut failures = SNDE_FAILURES.lock().unwrap();
+    let count = failures.entry(host.clone()).or_insert(0);
+    *count += 1;
+    Some((host, *count))
+}
+
+/// Forget SNDE failures for the URL's host after a successful download
+pub fn record_snde_success(url: &str) {
+    if let Some(host) = extract_domain(url) {
+        SNDE_FAILURES.lock().unwrap().remove(&host.to_lowercase());
+    }
+}
+
+/// aria2 decision for an HLS stream it can fetch as-is
+fn aria2_decision(
+    reason: &str,
+    host_reputation: Option<HostReputation>,
+    probe_result: Option<ProbeResult>,
+) -> RoutingDecision {
+    RoutingDecision {
+        engine: DownloadEngine::Aria2,
+        recommended_connections: 8,
+        reason: reason.to_string(),
+        force_http1: false,
+        prefer_http3: false,
+        file_size: None,
+        host_reputation,
+        probe_result,
+        badge: crate::aria2::ARIA2_BADGE.to_string(),
+    }
+}
+
+/// Media Engine decision for downloads another engine had to give up before starting
+pub(crate) fn media_engine_fallback(reason: String) -> RoutingDecision {
+    RoutingDecision {
+        engine: DownloadEngine::MediaEngine,
+        recommended_connections: 1,
+        reason,
+        force_http1: false,
+        prefer_http3: false,
+        file_size: None,
+        host_reputation: None,
+        probe_result: None,
+        badge: "MEDIA ENGINE".to_string(),
+    }
+}
+
+/// Apply the persisted site rules at startup
+pub fn load_from_settings(db: &Database) {
+    let hosts: BTreeSet<String> = db
+        .get_setting(MEDIA_ENGINE_HOSTS_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    *MEDIA_ENGINE_HOSTS.write().unwrap() = hosts;
+}
+
+/// Hosts that always use the Media Engine
+#[tauri::command]
+pub async fn get_media_engine_rules() -> Result<Vec<String>, String> {
+    Ok(MEDIA_ENGINE_HOSTS.read().unwrap().iter().cloned().collect())
+}
+
+/// Remember ("always do this for this site") or forget a Media Engine rule for a host
+#[tauri::command]
+pub async fn set_media_engine_rule(
+    state: State<'_, AppState>,
+    host: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let host = normalize_host(&host)?;
+
+    let mut hosts = MEDIA_ENGINE_HOSTS.read().unwrap().clone();
+    if enabled {
+        hosts.insert(host.clone());
+    } else {
+        hosts.remove(&host);
+    }
+
+    let json = serde_json::to_string(&hosts).map_err(|e| e.to_string())?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(MEDIA_ENGINE_HOSTS_SETTING, &json)
+        .map_err(|e| e.to_string())?;
+    *MEDIA_ENGINE_HOSTS.write().unwrap() = hosts;
+
+    SNDE_FAILURES.lock().unwrap().remove(&host);
+    println!("[Router] Media Engine rule for {} {}", host, if enabled { "saved" } else { "removed" });
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -424,6 +749,39 @@ mod tests {
         assert!(!router.is_media_domain("https://cdn.example.com/video.mp4"));
     }
 
+    #[test]
+    fn test_probe_web_page() {
+        let probe = |content_type: Option<&str>| ProbeResult {
+            success: true,
+            supports_range: true,
+            content_length: None,
+            content_type: content_type.map(str::to_string),
+            protocol: "http1".to_string(),
+            response_time_ms: 0,
+            server: None,
+            error: None,
+        };
+        assert!(probe(Some("text/html; charset=utf-8")).is_web_page());
+        assert!(probe(Some("application/xhtml+xml")).is_web_page());
+        assert!(!probe(Some("application/zip")).is_web_page());
+        assert!(!probe(None).is_web_page());
+    }
+
+    #[test]
+    fn test_alt_svc_h3() {
+        assert_eq!(alt_svc_h3_max_age(r#"h3=":443"; ma=3600"#), Some(Duration::from_secs(3600)));
+        assert_eq!(alt_svc_h3_max_age(r#"h2=":443", h3=":443""#), Some(ALT_SVC_DEFAULT_MAX_AGE));
+        assert_eq!(alt_svc_h3_max_age(r#"h3=":443"; ma=99999999"#), Some(ALT_SVC_MAX_AGE_CAP));
+        assert_eq!(alt_svc_h3_max_age(r#"h3-29=":443""#), None);
+        assert_eq!(alt_svc_h3_max_age(r#"h3="alt.example.com:443""#), None);
+
+        let url = "https://quic.example.org/file.iso";
+        remember_quic_host(url, r#"h3=":443"; ma=60"#);
+        assert!(knows_quic(url));
+        remember_quic_host(url, "clear");
+        assert!(!knows_quic(url));
+    }
+
     #[test]
     fn test_static_file_detection() {
         let router = DownloadRouter::new();
@@ -435,4 +793,84 @@ mod tests {
         assert!(!router.is_static_file("https://youtube.com/watch?v=abc"));
         assert!(!router.is_static_file("https://example.com/page"));
     }
+
+    #[test]
+    fn test_gallery_detection() {
+        let router = DownloadRouter::new();
+
+        assert!(router.is_gallery_url("https://imgur.com/a/AbC123"));
+        assert!(router.is_gallery_url("https://www.reddit.com/gallery/1abcd"));
+        assert!(router.is_gallery_url("https://www.instagram.com/someone/"));
+
+        assert!(!router.is_gallery_url("https://www.instagram.com/p/Cxyz/"));
+        assert!(!router.is_gallery_url("https://www.instagram.com/reel/Cxyz/"));
+        assert!(!router.is_gallery_url("https://i.imgur.com/abc.gifv"));
+        assert!(!router.is_gallery_url("https://www.reddit.com/r/videos/comments/abc/x/"));
+        assert!(!router.is_gallery_url("https://notimgur.com/a/abc"));
+    }
+
+    #[test]
+    fn test_media_engine_rule_matching() {
+        let rules: BTreeSet<String> = ["example.com".to_string()].into_iter().collect();
+
+        assert!(host_matches(&rules, "example.com"));
+        assert!(host_matches(&rules, "cdn.example.com"));
+        assert!(!host_matches(&rules, "example.org"));
+        assert!(!host_matches(&rules, "notexample.com"));
+    }
+
+    #[test]
+    fn test_snde_failure_counting() {
+        let url = "https://failing.router-test.invalid/file.zip";
+        assert_eq!(record_snde_failure(url).map(|(_, n)| n), Some(1));
+        assert_eq!(record_snde_failure(url).map(|(_, n)| n), Some(2));
+        record_snde_success(url);
+        assert_eq!(record_snde_failure(url).map(|(_, n)| n), Some(1));
+    }
+
+    // ---- Routing decisions against the local mock server ----
+
+    use crate::test_support::{test_body, MockBehavior, MockServer};
+
+    #[tokio::test]
+    async fn test_route_range_capable_server_to_snde() {
+        let server = MockServer::start(test_body(2_000_000), MockBehavior::default()).await;
+        let decision = DownloadRouter::with_timeout(Duration::from_secs(5))
+            .route(&server.url("/data.bin"), None)
+            .await;
+
+        assert_eq!(decision.engine, DownloadEngine::SNDE);
+        assert_eq!(decision.file_size, Some(2_000_000));
+        assert_eq!(decision.recommended_connections, 4);
+        assert!(decision.force_http1);
+    }
+
+    #[tokio::test]
+    async fn test_route_without_range_support() {
+        let server = MockServer::start(test_body(1000), MockBehavior {
+            supports_range: false,
+            ..Default::default()
+        })
+        .await;
+        let router = DownloadRouter::with_timeout(Duration::from_secs(5));
+
+        // Static files still get a single safe connection; anything else goes to yt-dlp
+        assert_eq!(router.route(&server.url("/setup.zip"), None).await.engine, DownloadEngine::SNDESafe);
+        assert_eq!(router.route(&server.url("/page"), None).await.engine, DownloadEngine::MediaEngine);
+    }
+
+    #[tokio::test]
+    async fn test_route_falls_back_when_rate_limited() {
+        let server = MockServer::start(test_body(1000), MockBehavior {
+            rate_limit_first: 1,
+            ..Default::default()
+        })
+        .await;
+        let decision = DownloadRouter::with_timeout(Duration::from_secs(5))
+            .route(&server.url("/file.zip"), None)
+            .await;
+
+        assert_eq!(decision.engine, DownloadEngine::MediaEngine);
+        assert!(!decision.probe_result.unwrap().success);
+    }
 }
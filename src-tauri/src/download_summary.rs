@@ -0,0 +1,302 @@
+//! Download Summaries
+//!
+//! Progress events arrive several times a second per download, far too fast for a screen
+//! reader. This module keeps the latest state of every download and periodically emits a
+//! `download-summary` event with a short sentence describing the whole queue.
+//!
+//! Key Features:
+//! - Configurable interval (off by default), persisted in settings
+//! - Sentences like "3 downloads active, fastest 12 MB/s, next completion in 4 minutes"
+//! - Finished and failed downloads since the last summary are mentioned once; cancels are
+//!   neither
+//! - Covers every engine, Spotify and gallery downloads included
+//! - Nothing is emitted while the queue is idle and nothing changed
+
+use crate::commands::AppState;
+use crate::database::Database;
+use crate::download_lifecycle::{DownloadTracker, ProgressUpdate, Transition};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+/// Settings key for the summary interval in seconds; 0 turns summaries off
+pub const SUMMARY_INTERVAL_SETTING: &str = "download_summary_interval_secs";
+
+const MIN_INTERVAL_SECS: u64 = 5;
+const MAX_INTERVAL_SECS: u64 = 600;
+
+/// How often the loop wakes while summaries are off
+const DISABLED_POLL: Duration = Duration::from_secs(5);
+
+static INTERVAL_SECS: AtomicU64 = AtomicU64::new(0);
+
+/// Latest progress of one download
+#[derive(Debug, Clone, Default)]
+struct Tracked {
+    status: String,
+    downloaded_bytes: Option<u64>,
+    total_bytes: Option<u64>,
+    speed_bps: Option<f64>,
+}
+
+#[derive(Default)]
+struct SummaryState {
+    downloads: DownloadTracker<Tracked>,
+    finished: usize,
+    failed: usize,
+    /// Whether the last summary was emitted with nothing going on
+    idle_announced: bool,
+}
+
+lazy_static::lazy_static! {
+    static ref STATE: Mutex<SummaryState> = Mutex::new(SummaryState::default());
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadSummary {
+    pub text: String,
+    pub active: usize,
+    pub paused: usize,
+    pub finished: usize,
+    pub failed: usize,
+}
+
+fn clamp_interval(secs: u64) -> u64 {
+    if secs == 0 {
+        0
+    } else {
+        secs.clamp(MIN_INTERVAL_SECS, MAX_INTERVAL_SECS)
+    }
+}
+
+/// Restore the summary interval; call once at startup
+pub fn load_from_settings(db: &Database) {
+    let secs = db
+        .get_setting(SUMMARY_INTERVAL_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    INTERVAL_SECS.store(clamp_interval(secs), Ordering::Relaxed);
+}
+
+/// "12 MB/s", "1.5 MB/s", "800 KB/s"
+fn spoken_speed(bps: f64) -> String {
+    const UNITS: [&str; 4] = ["bytes", "KB", "MB", "GB"];
+    let mut value = bps;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value >= 10.0 || unit == 0 {
+        format!("{:.0} {}/s", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}/s", value, UNITS[unit])
+    }
+}
+
+fn plural(count: u64, word: &str) -> String {
+    format!("{} {}{}", count, word, if count == 1 { "" } else { "s" })
+}
+
+/// "under a minute", "4 minutes", "1 hour 5 minutes"
+fn spoken_duration(secs: u64) -> String {
+    let minutes = (secs + 30) / 60;
+    match minutes {
+        0 => "under a minute".to_string(),
+        m if m < 60 => plural(m, "minute"),
+        m if m % 60 == 0 => plural(m / 60, "hour"),
+        m => format!("{} {}", plural(m / 60, "hour"), plural(m % 60, "minute")),
+    }
+}
+
+fn summarize(state: &SummaryState) -> DownloadSummary {
+    let active: Vec<&Tracked> = state.downloads.values().filter(|d| d.status != "paused").collect();
+    let paused = state.downloads.len() - active.len();
+
+    let mut parts = Vec::new();
+    if active.is_empty() {
+        parts.push("No downloads active".to_string());
+    } else {
+        parts.push(format!("{} active", plural(active.len() as u64, "download")));
+        let fastest = active.iter().filter_map(|d| d.speed_bps).filter(|s| *s > 0.0).fold(0.0, f64::max);
+        if fastest > 0.0 {
+            parts.push(format!("fastest {}", spoken_speed(fastest)));
+        }
+        let next = active
+            .iter()
+            .filter_map(|d| {
+                let remaining = d.total_bytes?.saturating_sub(d.downloaded_bytes?);
+                let speed = d.speed_bps.filter(|s| *s > 0.0)?;
+                Some((remaining as f64 / speed).round() as u64)
+            })
+            .min();
+        if let Some(secs) = next {
+            parts.push(format!("next completion in {}", spoken_duration(secs)));
+        }
+    }
+    if paused > 0 {
+        parts.push(format!("{} paused", paused));
+    }
+    if state.finished > 0 {
+        parts.push(format!("{} finished", state.finished));
+    }
+    if state.failed > 0 {
+        parts.push(format!("{} failed", state.failed));
+    }
+
+    DownloadSummary {
+        text: parts.join(", "),
+        active: active.len(),
+        paused,
+        finished: state.finished,
+        failed: state.failed,
+    }
+}
+
+fn record(state: &mut SummaryState, update: &ProgressUpdate) {
+    match state.downloads.track(update, Tracked::default) {
+        Transition::Finished(tracked) => {
+            if tracked.is_some() || update.status == "completed" {
+                match update.status.as_str() {
+                    "completed" => state.finished += 1,
+                    "cancelled" => {}
+                    _ => state.failed += 1,
+                }
+            }
+        }
+        Transition::Running { entry, .. } => {
+            entry.status = update.status.clone();
+            entry.downloaded_bytes = update.downloaded_bytes.or(entry.downloaded_bytes);
+            entry.total_bytes = update.total_bytes.or(entry.total_bytes);
+            entry.speed_bps = update.speed.as_deref().and_then(crate::downloader::parse_speed_to_bps);
+        }
+    }
+}
+
+/// The summary for the current queue, resetting the finished / failed counts once announced
+fn take_summary() -> Option<DownloadSummary> {
+    let mut state = STATE.lock().unwrap();
+    let summary = summarize(&state);
+    let idle = summary.active == 0 && summary.paused == 0 && summary.finished == 0 && summary.failed == 0;
+    if idle && state.idle_announced {
+        return None;
+    }
+    state.idle_announced = idle;
+    state.finished = 0;
+    state.failed = 0;
+    Some(summary)
+}
+
+/// Track progress events and emit `download-summary` on the configured interval; call once
+/// at startup
+pub fn install(app_handle: &AppHandle) {
+    crate::download_lifecycle::on_progress(app_handle, |update| record(&mut STATE.lock().unwrap(), update));
+
+    let app = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let secs = INTERVAL_SECS.load(Ordering::Relaxed);
+            if secs == 0 {
+                tokio::time::sleep(DISABLED_POLL).await;
+                continue;
+            }
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            if INTERVAL_SECS.load(Ordering::Relaxed) == 0 {
+                continue;
+            }
+            if let Some(summary) = take_summary() {
+                let _ = app.emit("download-summary", &summary);
+            }
+        }
+    });
+}
+
+/// The current summary without waiting for the next event
+#[tauri::command]
+pub async fn get_download_summary() -> Result<DownloadSummary, String> {
+    Ok(summarize(&STATE.lock().unwrap()))
+}
+
+#[tauri::command]
+pub async fn get_download_summary_interval() -> Result<u64, String> {
+    Ok(INTERVAL_SECS.load(Ordering::Relaxed))
+}
+
+/// Set the summary interval in seconds (0 turns summaries off); returns the clamped value
+#[tauri::command]
+pub async fn set_download_summary_interval(state: State<'_, AppState>, secs: u64) -> Result<u64, String> {
+    let secs = clamp_interval(secs);
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(SUMMARY_INTERVAL_SETTING, &secs.to_string())
+            .map_err(|e| e.to_string())?;
+    }
+    INTERVAL_SECS.store(secs, Ordering::Relaxed);
+    Ok(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked(status: &str, downloaded: u64, total: u64, speed: f64) -> Tracked {
+        Tracked {
+            status: status.to_string(),
+            downloaded_bytes: Some(downloaded),
+            total_bytes: Some(total),
+            speed_bps: Some(speed),
+        }
+    }
+
+    #[test]
+    fn test_summary_text() {
+        const MB: f64 = 1024.0 * 1024.0;
+        let mut state = SummaryState::default();
+        state.downloads.insert("a", tracked("downloading", 0, 3_000 * MB as u64, 12.0 * MB));
+        state.downloads.insert("b", tracked("downloading", 0, 240 * MB as u64, 1.0 * MB));
+        state.downloads.insert("c", tracked("downloading", 0, 0, 0.0));
+        assert_eq!(
+            summarize(&state).text,
+            "3 downloads active, fastest 12 MB/s, next completion in 4 minutes"
+        );
+
+        state.downloads.clear();
+        state.downloads.insert("d", tracked("paused", 0, 0, 0.0));
+        state.finished = 1;
+        assert_eq!(summarize(&state).text, "No downloads active, 1 paused, 1 finished");
+    }
+
+    #[test]
+    fn test_cancels_are_not_failures() {
+        let ytdlp = |status: &str| {
+            ProgressUpdate::from_download_progress(serde_json::json!({ "id": "yt", "status": status })).unwrap()
+        };
+        let spotify = |status: &str| {
+            ProgressUpdate::from_spotify_progress(serde_json::json!({ "id": "sp", "status": status })).unwrap()
+        };
+        let mut state = SummaryState::default();
+        record(&mut state, &ytdlp("downloading"));
+        record(&mut state, &spotify("downloading"));
+        assert_eq!(summarize(&state).active, 2);
+
+        record(&mut state, &ytdlp("cancelled"));
+        record(&mut state, &spotify("failed"));
+        let summary = summarize(&state);
+        assert_eq!((summary.active, summary.finished, summary.failed), (0, 0, 1));
+    }
+
+    #[test]
+    fn test_spoken_units() {
+        assert_eq!(spoken_speed(1.5 * 1024.0 * 1024.0), "1.5 MB/s");
+        assert_eq!(spoken_speed(800.0 * 1024.0), "800 KB/s");
+        assert_eq!(spoken_duration(20), "under a minute");
+        assert_eq!(spoken_duration(60), "1 minute");
+        assert_eq!(spoken_duration(3900), "1 hour 5 minutes");
+        assert_eq!(clamp_interval(1), MIN_INTERVAL_SECS);
+        assert_eq!(clamp_interval(0), 0);
+    }
+}
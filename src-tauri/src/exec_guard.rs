@@ -0,0 +1,465 @@
+//! External Process Guard
+//!
+//! Every program the app runs goes through here first. The program is resolved to a full
+//! path and checked before it may be spawned, and every attempt is logged for diagnostics.
+//!
+//! Key Features:
+//! - Managed binaries (in `<app data>/binaries`) are checked against the SHA-256 recorded
+//!   when the app installed them
+//! - OS helpers (explorer, cmd, open, xdg-open, ...) must resolve into a system directory
+//! - Media tools (yt-dlp, ffmpeg, ...) may run from the app bundle or from paths the user
+//!   trusted in Settings; a copy picked up from PATH is refused otherwise
+//! - Programs the user configured themselves (hooks, external players) are only logged
+//! - Recent invocations are kept in memory for `get_process_invocations`
+
+use serde::{Deserialize, Serialize};
+use crate::database::Database;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::SystemTime;
+
+/// Invocations kept for the diagnostics view
+const MAX_LOGGED_INVOCATIONS: usize = 200;
+
+/// Name of the hash manifest in the managed binaries directory
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// OS helpers the app shells out to; they must live in a system directory
+const SYSTEM_TOOLS: &[&str] = &["explorer", "cmd", "where", "which", "open", "ioreg", "xdg-open", "loginctl", "xprintidle"];
+
+/// Settings key holding the JSON list of media tool paths the user trusts
+pub const TRUSTED_TOOL_PATHS_SETTING: &str = "trusted_tool_paths";
+
+/// Media tools allowed from the app bundle or a trusted path when there is no managed copy
+const MEDIA_TOOLS: &[&str] = &["yt-dlp", "yt-dlp_macos", "ffmpeg", "ffprobe", "spotdl", "gallery-dl", "aria2c"];
+
+/// Why a program was allowed to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgramKind {
+    /// Installed by the app and hash-verified
+    Managed,
+    /// OS helper in a system directory
+    System,
+    /// Known media tool from the app bundle or a trusted path
+    MediaTool,
+    /// Configured by the user
+    User,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessInvocation {
+    pub program: String,
+    pub resolved_path: Option<String>,
+    pub kind: Option<ProgramKind>,
+    pub allowed: bool,
+    /// Why the program was refused
+    pub reason: Option<String>,
+    pub timestamp: i64,
+}
+
+/// Hash of a managed binary and the file state it was computed for
+#[derive(Clone)]
+struct VerifiedBinary {
+    len: u64,
+    modified: Option<SystemTime>,
+    sha256: String,
+}
+
+lazy_static::lazy_static! {
+    static ref BINARIES_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+    static ref RESOURCE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+    static ref TRUSTED_TOOLS: RwLock<HashSet<PathBuf>> = RwLock::new(HashSet::new());
+    /// Held across manifest read-modify-write so concurrent installs don't drop entries
+    static ref MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+    static ref VERIFIED: Mutex<HashMap<PathBuf, VerifiedBinary>> = Mutex::new(HashMap::new());
+    static ref INVOCATIONS: Mutex<VecDeque<ProcessInvocation>> = Mutex::new(VecDeque::new());
+}
+
+/// Point the guard at the managed binaries directory and the app bundle; call once at startup
+pub fn init(binaries_dir: &Path, resource_dir: Option<&Path>) {
+    adopt_existing_binaries(binaries_dir);
+    *BINARIES_DIR.write().unwrap() = Some(binaries_dir.to_path_buf());
+    *RESOURCE_DIR.write().unwrap() = resource_dir.and_then(|dir| dir.canonicalize().ok());
+}
+
+/// Canonical form of each configured path that still exists
+fn canonical_tools(paths: &[String]) -> HashSet<PathBuf> {
+    paths
+        .iter()
+        .filter_map(|path| Path::new(path.trim()).canonicalize().ok())
+        .collect()
+}
+
+/// Restore the trusted media tool paths at startup
+pub fn load_from_settings(db: &Database) {
+    let paths: Vec<String> = db
+        .get_setting(TRUSTED_TOOL_PATHS_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    *TRUSTED_TOOLS.write().unwrap() = canonical_tools(&paths);
+}
+
+fn tool_name(path: &Path) -> String {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_lowercase();
+    name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+}
+
+/// Full path of `program`, searching PATH for bare names
+fn resolve(program: &OsStr) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 || path.is_absolute() {
+        return path.canonicalize().ok();
+    }
+    let extensions: &[&str] = if cfg!(windows) { &["", ".exe", ".cmd", ".bat"] } else { &[""] };
+    std::env::split_paths(&std::env::var_os("PATH")?).find_map(|dir| {
+        extensions.iter().find_map(|ext| {
+            let mut name = program.to_os_string();
+            name.push(ext);
+            let candidate = dir.join(name);
+            candidate.is_file().then(|| candidate.canonicalize().ok()).flatten()
+        })
+    })
+}
+
+/// Directories OS helpers are expected in
+fn system_dirs() -> Vec<PathBuf> {
+    if cfg!(windows) {
+        std::env::var_os("SystemRoot")
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(r"C:\Windows")))
+            .into_iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .collect()
+    } else {
+        ["/bin", "/sbin", "/usr/bin", "/usr/sbin", "/usr/local/bin", "/opt/homebrew/bin", "/run/current-system/sw/bin"]
+            .iter()
+            .filter_map(|dir| Path::new(dir).canonicalize().ok())
+            .collect()
+    }
+}
+
+fn is_system_path(path: &Path, system_dirs: &[PathBuf]) -> bool {
+    system_dirs.iter().any(|dir| path.starts_with(dir))
+}
+
+fn manifest_path(binaries_dir: &Path) -> PathBuf {
+    binaries_dir.join(MANIFEST_FILE)
+}
+
+fn read_manifest(binaries_dir: &Path) -> HashMap<String, String> {
+    std::fs::read_to_string(manifest_path(binaries_dir))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn write_manifest(binaries_dir: &Path, manifest: &HashMap<String, String>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(manifest).map_err(|e| format!("Failed to serialize binary manifest: {}", e))?;
+    std::fs::write(manifest_path(binaries_dir), json).map_err(|e| format!("Failed to write binary manifest: {}", e))
+}
+
+fn file_sha256(path: &Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// SHA-256 of `path`, reusing the last result while the file is unchanged
+fn cached_sha256(path: &Path) -> Result<String, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| format!("Failed to inspect {:?}: {}", path, e))?;
+    let (len, modified) = (metadata.len(), metadata.modified().ok());
+    if let Some(cached) = VERIFIED.lock().unwrap().get(path) {
+        if cached.len == len && cached.modified == modified {
+            return Ok(cached.sha256.clone());
+        }
+    }
+    let sha256 = file_sha256(path)?;
+    VERIFIED
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), VerifiedBinary { len, modified, sha256: sha256.clone() });
+    Ok(sha256)
+}
+
+fn update_manifest(path: &Path, sha256: Option<String>) -> Result<(), String> {
+    let binaries_dir = path.parent().ok_or("Managed binary has no parent directory")?;
+    let name = path.file_name().and_then(|n| n.to_str()).ok_or("Managed binary has no file name")?;
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+    let mut manifest = read_manifest(binaries_dir);
+    match sha256 {
+        Some(sha256) => manifest.insert(name.to_string(), sha256),
+        None => manifest.remove(name),
+    };
+    write_manifest(binaries_dir, &manifest)
+}
+
+/// Record the hash of a binary the app just downloaded and verified into the managed directory
+pub fn record_managed_binary(path: &Path) -> Result<(), String> {
+    let sha256 = cached_sha256(path)?;
+    update_manifest(path, Some(sha256))
+}
+
+/// Drop the manifest entry of a managed binary that was moved or removed
+pub fn forget_managed_binary(path: &Path) -> Result<(), String> {
+    update_manifest(path, None)
+}
+
+/// Record the media tools an earlier version installed before there was a manifest, so an
+/// upgrade doesn't refuse them. Runs once: afterwards the manifest exists.
+fn adopt_existing_binaries(binaries_dir: &Path) {
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+    if manifest_path(binaries_dir).exists() {
+        return;
+    }
+    let manifest: HashMap<String, String> = std::fs::read_dir(binaries_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|entry| MEDIA_TOOLS.contains(&tool_name(&entry.path()).as_str()))
+        .filter_map(|entry| Some((entry.file_name().into_string().ok()?, file_sha256(&entry.path()).ok()?)))
+        .collect();
+    match write_manifest(binaries_dir, &manifest) {
+        Ok(()) => println!("[ExecGuard] Recorded {} binaries installed before the manifest", manifest.len()),
+        Err(e) => println!("[ExecGuard] {}", e),
+    }
+}
+
+/// Check a managed binary against the manifest. Anything the app didn't record is refused.
+fn verify_managed(path: &Path, binaries_dir: &Path) -> Result<(), String> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let actual = cached_sha256(path)?;
+    let manifest = {
+        let _guard = MANIFEST_LOCK.lock().unwrap();
+        read_manifest(binaries_dir)
+    };
+    match manifest.get(name) {
+        Some(expected) if expected.eq_ignore_ascii_case(&actual) => Ok(()),
+        Some(_) => Err(format!("{} does not match the copy the app installed; reinstall it from Settings", name)),
+        None => Err(format!("{} was not installed by the app; reinstall it from Settings", name)),
+    }
+}
+
+/// Where a media tool may run from besides the managed directory
+struct MediaToolOrigins<'a> {
+    resource_dir: Option<&'a Path>,
+    trusted: &'a HashSet<PathBuf>,
+}
+
+/// Decide whether `resolved` may run
+fn classify(
+    resolved: &Path,
+    binaries_dir: Option<&Path>,
+    system_dirs: &[PathBuf],
+    origins: &MediaToolOrigins,
+) -> Result<ProgramKind, String> {
+    let name = tool_name(resolved);
+    let managed_dir = binaries_dir.and_then(|dir| dir.canonicalize().ok());
+    if let Some(dir) = managed_dir.as_deref().filter(|dir| resolved.starts_with(dir)) {
+        verify_managed(resolved, dir)?;
+        return Ok(ProgramKind::Managed);
+    }
+    if SYSTEM_TOOLS.contains(&name.as_str()) {
+        if is_system_path(resolved, system_dirs) {
+            return Ok(ProgramKind::System);
+        }
+        return Err(format!("{} resolved outside the system directories: {:?}", name, resolved));
+    }
+    if MEDIA_TOOLS.contains(&name.as_str()) {
+        let bundled = origins.resource_dir.is_some_and(|dir| resolved.starts_with(dir));
+        if bundled || origins.trusted.contains(resolved) {
+            return Ok(ProgramKind::MediaTool);
+        }
+        return Err(format!(
+            "{} at {:?} is not bundled with the app; add it to the trusted tool paths in Settings to use it",
+            name, resolved
+        ));
+    }
+    Err(format!("{} is not on the list of programs the app runs", name))
+}
+
+fn log_invocation(invocation: ProcessInvocation) {
+    match &invocation.reason {
+        Some(reason) => println!("[ExecGuard] Refused {}: {}", invocation.program, reason),
+        None => println!(
+            "[ExecGuard] Running {} ({:?})",
+            invocation.resolved_path.as_deref().unwrap_or(&invocation.program),
+            invocation.kind
+        ),
+    }
+    let mut invocations = INVOCATIONS.lock().unwrap();
+    if invocations.len() >= MAX_LOGGED_INVOCATIONS {
+        invocations.pop_front();
+    }
+    invocations.push_back(invocation);
+}
+
+/// Resolve and check `program`, returning the path to run. `user_configured` programs are
+/// logged but not held to the allowlist.
+pub fn check(program: impl AsRef<OsStr>, user_configured: bool) -> Result<PathBuf, String> {
+    let program = program.as_ref();
+    let resolved = resolve(program);
+    let verdict = match &resolved {
+        None => Err(format!("{} was not found", program.to_string_lossy())),
+        Some(_) if user_configured => Ok(ProgramKind::User),
+        Some(path) => {
+            let resource_dir = RESOURCE_DIR.read().unwrap();
+            let trusted = TRUSTED_TOOLS.read().unwrap();
+            let origins = MediaToolOrigins { resource_dir: resource_dir.as_deref(), trusted: &trusted };
+            classify(path, BINARIES_DIR.read().unwrap().as_deref(), &system_dirs(), &origins)
+        }
+    };
+
+    log_invocation(ProcessInvocation {
+        program: program.to_string_lossy().to_string(),
+        resolved_path: resolved.as_ref().map(|p| p.to_string_lossy().to_string()),
+        kind: verdict.as_ref().ok().copied(),
+        allowed: verdict.is_ok(),
+        reason: verdict.as_ref().err().cloned(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    });
+    verdict.map(|_| resolved.expect("resolved when allowed"))
+}
+
+/// A checked `std::process::Command` for an app-chosen program
+pub fn command(program: impl AsRef<OsStr>) -> Result<std::process::Command, String> {
+    check(program, false).map(std::process::Command::new)
+}
+
+/// A checked `tokio::process::Command` for an app-chosen program, with the proxy
+/// credentials (if any) in its environment
+pub fn tokio_command(program: impl AsRef<OsStr>) -> Result<tokio::process::Command, String> {
+    let mut cmd = check(program, false).map(tokio::process::Command::new)?;
+    crate::proxy::apply_to_command(&mut cmd);
+    Ok(cmd)
+}
+
+/// A logged `std::process::Command` for a program the user picked (e.g. a media player)
+pub fn user_command(program: impl AsRef<OsStr>) -> Result<std::process::Command, String> {
+    check(program, true).map(std::process::Command::new)
+}
+
+/// A logged `tokio::process::Command` for a program the user picked (e.g. a hook)
+pub fn user_tokio_command(program: impl AsRef<OsStr>) -> Result<tokio::process::Command, String> {
+    check(program, true).map(tokio::process::Command::new)
+}
+
+/// Media tool paths the user trusts to run outside the app bundle
+#[tauri::command]
+pub async fn get_trusted_tool_paths(state: tauri::State<'_, crate::commands::AppState>) -> Result<Vec<String>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(db
+        .get_setting(TRUSTED_TOOL_PATHS_SETTING)
+        .map_err(|e| e.to_string())?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn set_trusted_tool_paths(
+    state: tauri::State<'_, crate::commands::AppState>,
+    paths: Vec<String>,
+) -> Result<(), String> {
+    let paths: Vec<String> = paths.iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect();
+    if let Some(missing) = paths.iter().find(|p| !Path::new(p).is_file()) {
+        return Err(format!("{} is not a file", missing));
+    }
+    let json = serde_json::to_string(&paths).map_err(|e| format!("Failed to serialize trusted tool paths: {}", e))?;
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(TRUSTED_TOOL_PATHS_SETTING, &json).map_err(|e| e.to_string())?;
+    }
+    *TRUSTED_TOOLS.write().unwrap() = canonical_tools(&paths);
+    Ok(())
+}
+
+/// Recent external process invocations, newest first
+#[tauri::command]
+pub async fn get_process_invocations() -> Result<Vec<ProcessInvocation>, String> {
+    Ok(INVOCATIONS.lock().unwrap().iter().rev().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_managed_binary_hash_is_enforced() {
+        let dir = std::env::temp_dir().join(format!("ownstash-exec-guard-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let binary = dir.join("yt-dlp");
+        std::fs::write(&binary, b"original").unwrap();
+        let trusted = HashSet::new();
+        let origins = MediaToolOrigins { resource_dir: None, trusted: &trusted };
+        assert!(classify(&binary, Some(&dir), &[], &origins).unwrap_err().contains("not installed by the app"));
+
+        record_managed_binary(&binary).unwrap();
+        assert_eq!(classify(&binary, Some(&dir), &[], &origins), Ok(ProgramKind::Managed));
+
+        std::fs::write(&binary, b"tampered binary").unwrap();
+        assert!(classify(&binary, Some(&dir), &[], &origins).unwrap_err().contains("does not match"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_binaries_from_before_the_manifest_are_adopted_once() {
+        let dir = crate::test_support::scratch_dir().canonicalize().unwrap();
+        let binary = dir.join("ffmpeg");
+        std::fs::write(&binary, b"installed by an earlier version").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"not a tool").unwrap();
+        let trusted = HashSet::new();
+        let origins = MediaToolOrigins { resource_dir: None, trusted: &trusted };
+
+        adopt_existing_binaries(&dir);
+        assert_eq!(classify(&binary, Some(&dir), &[], &origins), Ok(ProgramKind::Managed));
+        assert!(!read_manifest(&dir).contains_key("notes.txt"));
+
+        // With a manifest in place, nothing new is adopted
+        let added = dir.join("yt-dlp");
+        std::fs::write(&added, b"dropped in later").unwrap();
+        adopt_existing_binaries(&dir);
+        assert!(classify(&added, Some(&dir), &[], &origins).unwrap_err().contains("not installed by the app"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_system_tools_must_live_in_system_dirs() {
+        let system = [PathBuf::from("/usr/bin")];
+        let trusted = HashSet::new();
+        let origins = MediaToolOrigins { resource_dir: None, trusted: &trusted };
+        assert_eq!(classify(Path::new("/usr/bin/xdg-open"), None, &system, &origins), Ok(ProgramKind::System));
+        assert!(classify(Path::new("/home/user/bin/xdg-open"), None, &system, &origins).is_err());
+        assert!(classify(Path::new("/tmp/payload"), None, &system, &origins).is_err());
+    }
+
+    #[test]
+    fn test_media_tools_only_from_bundle_or_trusted_paths() {
+        let trusted = HashSet::from([PathBuf::from("/opt/tools/spotdl")]);
+        let origins = MediaToolOrigins { resource_dir: Some(Path::new("/opt/ownstash/resources")), trusted: &trusted };
+        let kind = |path: &str| classify(Path::new(path), None, &[], &origins);
+        assert_eq!(kind("/opt/ownstash/resources/binaries/ffmpeg"), Ok(ProgramKind::MediaTool));
+        assert_eq!(kind("/opt/tools/spotdl"), Ok(ProgramKind::MediaTool));
+        // Found first on PATH, but neither bundled nor trusted
+        assert!(kind("/home/user/bin/ffmpeg").unwrap_err().contains("trusted tool paths"));
+        assert!(kind("/opt/tools/gallery-dl").is_err());
+    }
+}
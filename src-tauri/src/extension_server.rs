@@ -1,13 +1,71 @@
 // Extension Server Module
 // Provides a local HTTP server for Chrome extension communication
 
-use std::sync::Arc;
+use crate::commands::AppState;
+use crate::media_server::ServerStatus;
+use futures_util::StreamExt;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use tauri::{AppHandle, Emitter, Manager};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
 use warp::Filter;
 
 const EXTENSION_SERVER_PORT: u16 = 47152; // Random port for extension communication
 
+/// Settings key for turning the extension server off ("false" disables it)
+pub const EXTENSION_SERVER_ENABLED_SETTING: &str = "extension_server_enabled";
+
+/// The server thread and the signal that stops it
+struct ExtensionServerHandle {
+    shutdown: tokio::sync::oneshot::Sender<()>,
+    thread: thread::JoinHandle<()>,
+}
+
+lazy_static::lazy_static! {
+    static ref SERVER_HANDLE: Mutex<Option<ExtensionServerHandle>> = Mutex::new(None);
+    /// Held while a server starts, so two launches don't race for the port. Kept apart
+    /// from `SERVER_HANDLE` so status queries don't wait out a slow bind.
+    static ref LAUNCH_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn emit_status(app_handle: &AppHandle, status: &ServerStatus) {
+    let _ = app_handle.emit("extension-server-status", status);
+}
+
+fn current_status() -> ServerStatus {
+    ServerStatus {
+        running: SERVER_HANDLE.lock().unwrap().is_some(),
+        port: EXTENSION_SERVER_PORT,
+        error: None,
+    }
+}
+
+/// Whether a `Host` header names this machine's loopback interface. A page that rebinds
+/// its own domain to 127.0.0.1 still sends its domain here.
+fn is_loopback_host(host: &str) -> bool {
+    let name = host.rsplit_once(':').map_or(host, |(name, _)| name);
+    name == "127.0.0.1" || name.eq_ignore_ascii_case("localhost")
+}
+
+/// Rejects requests that weren't addressed to 127.0.0.1 or localhost
+fn loopback_host() -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("host")
+        .and_then(|host: Option<String>| async move {
+            if host.as_deref().is_some_and(is_loopback_host) {
+                Ok(())
+            } else {
+                Err(warp::reject::not_found())
+            }
+        })
+        .untuple_one()
+}
+
+/// Requests that act for the user straight away: addressed to loopback and sent by the
+/// extension, so neither a rebound page nor a plain cross-site form can make them
+fn local_extension_request() -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    loopback_host().and(warp::header::exact("x-extension-request", "true"))
+}
+
 /// Helper function to bring the main window to the front
 fn bring_window_to_front(app_handle: &AppHandle) {
     if let Some(window) = app_handle.get_webview_window("main") {
@@ -24,12 +82,35 @@ fn bring_window_to_front(app_handle: &AppHandle) {
     }
 }
 
-/// Starts a local HTTP server for the Chrome extension to communicate with
+/// Starts a local HTTP server for the Chrome extension to communicate with,
+/// unless it has been disabled in settings
 pub fn start_extension_server(app_handle: AppHandle) {
+    let enabled = crate::commands::read_setting(&app_handle, EXTENSION_SERVER_ENABLED_SETTING)
+        .map(|v| v != "false")
+        .unwrap_or(true);
+    if !enabled {
+        println!("[ExtensionServer] Disabled in settings");
+        emit_status(&app_handle, &current_status());
+        return;
+    }
+
+    let status = launch(app_handle.clone());
+    emit_status(&app_handle, &status);
+}
+
+/// Spawn the server thread and wait for it to bind
+fn launch(app_handle: AppHandle) -> ServerStatus {
+    let _launching = LAUNCH_LOCK.lock().unwrap();
+    if SERVER_HANDLE.lock().unwrap().is_some() {
+        return ServerStatus { running: true, port: EXTENSION_SERVER_PORT, error: None };
+    }
+
     let handle = Arc::new(app_handle);
-    
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let (bound_tx, bound_rx) = mpsc::channel::<Result<(), String>>();
+
     // Spawn a new thread with its own tokio runtime
-    thread::spawn(move || {
+    let thread = thread::spawn(move || {
         // Create a new tokio runtime for this thread
         let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
         
@@ -53,6 +134,9 @@ pub fn start_extension_server(app_handle: AppHandle) {
                 .map(move |body: serde_json::Value| {
                     if let Some(url) = body.get("url").and_then(|v| v.as_str()) {
                         println!("[ExtensionServer] Received download request: {}", url);
+
+                        // Referer, cookies or tokens the page had, for hosts that insist on them
+                        crate::request_headers::stash(url, crate::request_headers::from_extension_body(&body));
                         
                         // Bring the window to front
                         bring_window_to_front(&handle_clone);
@@ -99,31 +183,232 @@ pub fn start_extension_server(app_handle: AppHandle) {
                     // Bring the window to front
                     bring_window_to_front(&handle_clone3);
 
-                    // Emit vault download event to frontend
-                    let _ = handle_clone3.emit("extension-vault-download-request", serde_json::json!({
-                        "url": url,
-                        "filename": filename,
-                        "fileSize": file_size,
-                        "source": source
-                    }));
+                    // The user approves each vault write from the extension before it starts
+                    let app = handle_clone3.as_ref().clone();
+                    let (url, filename, source) = (url.to_string(), filename.to_string(), source.to_string());
+                    tauri::async_runtime::spawn(async move {
+                        match crate::vault_consent::request_consent(&app, &source, &url, &filename).await {
+                            Ok(grant) => {
+                                // Emit vault download event to frontend
+                                let _ = app.emit("extension-vault-download-request", serde_json::json!({
+                                    "url": url,
+                                    "filename": filename,
+                                    "fileSize": file_size,
+                                    "source": source,
+                                    "origin": "extension",
+                                    "grant": grant
+                                }));
+                            }
+                            Err(e) => println!("[ExtensionServer] Vault download of {} not started: {}", url, e),
+                        }
+                    });
 
                     warp::reply::json(&serde_json::json!({
                         "success": true,
-                        "message": "Download queued for vault"
+                        "message": "Waiting for confirmation in the app"
+                    }))
+                });
+
+            // Takeover - a browser download handed over with the page's cookies and headers
+            let handle_clone4 = handle.clone();
+            let takeover = warp::path("takeover")
+                .and(warp::path::end())
+                .and(warp::post())
+                .and(local_extension_request())
+                .and(warp::body::json())
+                .map(move |body: serde_json::Value| {
+                    warp::reply::json(&crate::takeover::accept(&handle_clone4, &body))
+                });
+
+            // Takeover threshold, so the extension only intercepts what the app would take
+            let takeover_config = warp::path!("takeover" / "config")
+                .and(warp::get())
+                .and(loopback_host())
+                .map(|| {
+                    let settings = crate::takeover::settings();
+                    warp::reply::json(&serde_json::json!({
+                        "enabled": settings.enabled,
+                        "minSizeBytes": settings.min_size_bytes
                     }))
                 });
 
+            // Takeover outcome, polled by the extension after handing a download over
+            let takeover_outcome = warp::path!("takeover" / String)
+                .and(warp::get())
+                .and(loopback_host())
+                .map(|takeover_id: String| match crate::takeover::outcome(&takeover_id) {
+                    Some(outcome) => warp::reply::json(&outcome),
+                    None => warp::reply::json(&serde_json::json!({
+                        "takeover_id": takeover_id,
+                        "status": "unknown"
+                    })),
+                });
+
+            // Support check - lets the extension show its button only on supported sites
+            let supported = warp::path("supported")
+                .and(warp::get())
+                .and(warp::query::<std::collections::HashMap<String, String>>())
+                .map(|query: std::collections::HashMap<String, String>| {
+                    let url = query.get("url").map(String::as_str).unwrap_or("");
+                    warp::reply::json(&serde_json::json!({
+                        "url": url,
+                        "supported": crate::extractor_index::has_extractor(url)
+                    }))
+                });
+
+            // Download lifecycle events as server-sent events, for companion tools. They
+            // carry file paths, so only local clients sending the extension header get them.
+            let events = warp::path("events")
+                .and(warp::path::end())
+                .and(warp::get())
+                .and(local_extension_request())
+                .and(warp::header::optional::<u64>("last-event-id"))
+                .map(|last_event_id: Option<u64>| {
+                    let (missed, receiver) = crate::download_events::subscribe(last_event_id);
+                    let upcoming = futures_util::stream::unfold(receiver, |mut receiver| async move {
+                        loop {
+                            match receiver.recv().await {
+                                Ok(event) => return Some((event, receiver)),
+                                // A slow client skips what it fell behind on
+                                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                                Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+                            }
+                        }
+                    });
+                    let stream = futures_util::stream::iter(missed).chain(upcoming).map(|event| {
+                        warp::sse::Event::default()
+                            .id(event.seq.to_string())
+                            .event(event.kind.as_str())
+                            .json_data(&event)
+                    });
+                    warp::sse::reply(warp::sse::keep_alive().stream(stream))
+                });
+
             // Combine routes
             let routes = health
                 .or(download)
-                .or(vault_download);
+                .or(vault_download)
+                .or(takeover)
+                .or(takeover_config)
+                .or(takeover_outcome)
+                .or(supported)
+                .or(events);
 
             println!("[ExtensionServer] Starting on port {}", EXTENSION_SERVER_PORT);
             
-            // Start the server
-            warp::serve(routes)
-                .run(([127, 0, 0, 1], EXTENSION_SERVER_PORT))
-                .await;
+            // Start the server; it stops when the shutdown signal fires
+            match warp::serve(routes).try_bind_with_graceful_shutdown(
+                ([127, 0, 0, 1], EXTENSION_SERVER_PORT),
+                async {
+                    let _ = shutdown_rx.await;
+                },
+            ) {
+                Ok((_, server)) => {
+                    let _ = bound_tx.send(Ok(()));
+                    server.await;
+                    println!("[ExtensionServer] Stopped");
+                }
+                Err(e) => {
+                    let _ = bound_tx.send(Err(format!(
+                        "Failed to bind port {}: {}",
+                        EXTENSION_SERVER_PORT, e
+                    )));
+                }
+            }
         });
     });
+
+    match bound_rx.recv_timeout(Duration::from_secs(5)) {
+        Ok(Ok(())) => {
+            *SERVER_HANDLE.lock().unwrap() = Some(ExtensionServerHandle { shutdown: shutdown_tx, thread });
+            ServerStatus { running: true, port: EXTENSION_SERVER_PORT, error: None }
+        }
+        Ok(Err(e)) => {
+            println!("[ExtensionServer] {}", e);
+            ServerStatus { running: false, port: EXTENSION_SERVER_PORT, error: Some(e) }
+        }
+        Err(_) => {
+            // Nothing keeps track of a server that binds after this; have it stop right away
+            let _ = shutdown_tx.send(());
+            println!("[ExtensionServer] Did not start in time; a late bind will shut down");
+            ServerStatus {
+                running: false,
+                port: EXTENSION_SERVER_PORT,
+                error: Some("Extension server did not start in time".to_string()),
+            }
+        }
+    }
+}
+
+/// Stop the server and wait for its thread to exit so the port is released
+async fn stop() {
+    let server = SERVER_HANDLE.lock().unwrap().take();
+    if let Some(server) = server {
+        let _ = server.shutdown.send(());
+        let _ = tokio::task::spawn_blocking(move || server.thread.join()).await;
+    }
+}
+
+/// Enable or disable the extension server without restarting the app
+#[tauri::command]
+pub async fn set_extension_server_enabled(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<ServerStatus, String> {
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(EXTENSION_SERVER_ENABLED_SETTING, if enabled { "true" } else { "false" })
+            .map_err(|e| e.to_string())?;
+    }
+
+    stop().await;
+    let status = if enabled {
+        let app = app_handle.clone();
+        tokio::task::spawn_blocking(move || launch(app))
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        current_status()
+    };
+
+    emit_status(&app_handle, &status);
+    match status.error {
+        Some(ref e) => Err(e.clone()),
+        None => Ok(status),
+    }
+}
+
+#[tauri::command]
+pub async fn get_extension_server_status() -> Result<ServerStatus, String> {
+    Ok(current_status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_loopback_host() {
+        assert!(is_loopback_host("127.0.0.1:47152"));
+        assert!(is_loopback_host("localhost:47152"));
+        assert!(is_loopback_host("LocalHost"));
+        assert!(!is_loopback_host("attacker.example:47152"));
+        assert!(!is_loopback_host("127.0.0.1.attacker.example"));
+    }
+
+    #[tokio::test]
+    async fn test_takeover_needs_a_loopback_host() {
+        let takeover = |host: &str| {
+            warp::test::request()
+                .method("POST")
+                .path("/takeover")
+                .header("host", host)
+                .header("x-extension-request", "true")
+        };
+        let filter = local_extension_request();
+        assert!(takeover("127.0.0.1:47152").filter(&filter).await.is_ok());
+        assert!(takeover("attacker.example:47152").filter(&filter).await.is_err());
+        assert!(warp::test::request().header("host", "localhost").filter(&filter).await.is_err());
+    }
 }
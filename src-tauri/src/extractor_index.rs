@@ -0,0 +1,227 @@
+//! Extractor Index
+//!
+//! The list of extractors the installed yt-dlp ships, so the router and the browser
+//! extension can tell whether a site is supported instead of relying on a hardcoded
+//! platform list.
+//!
+//! Key Features:
+//! - `yt-dlp --list-extractors` runs once per yt-dlp version, in the background
+//! - Cached in app data, so startup doesn't wait on yt-dlp
+//! - Host based lookup of a dedicated (non-generic) extractor for a URL
+//! - Commands: `is_url_supported`, `get_supported_sites`
+
+use crate::downloader::{Downloader, YtDlpChannel};
+use crate::host_reputation::extract_domain;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager};
+
+const INDEX_FILE: &str = "extractor_index.json";
+
+/// Suffix yt-dlp prints after extractors that are known not to work
+const BROKEN_SUFFIX: &str = "(CURRENTLY BROKEN)";
+
+/// Second-level labels of country TLDs like "co.uk" / "com.br"
+const SECOND_LEVEL_LABELS: &[&str] = &["co", "com", "org", "net", "gov", "ac", "edu", "ne", "or"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CachedIndex {
+    yt_dlp_version: String,
+    extractors: Vec<String>,
+}
+
+/// The cached list plus the lower-case site keys used for lookups
+struct ExtractorIndex {
+    cached: CachedIndex,
+    site_keys: HashSet<String>,
+}
+
+impl ExtractorIndex {
+    fn new(cached: CachedIndex) -> Self {
+        let site_keys = cached.extractors.iter().filter_map(|name| site_key(name)).collect();
+        Self { cached, site_keys }
+    }
+
+    fn supports(&self, url: &str) -> bool {
+        extract_domain(url)
+            .map(|host| host_candidates(&host).iter().any(|c| self.site_keys.contains(c)))
+            .unwrap_or(false)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref INDEX: RwLock<Option<ExtractorIndex>> = RwLock::new(None);
+}
+
+/// Lower-case site part of an extractor name ("youtube:tab" -> "youtube").
+/// Broken extractors and the generic fallback don't count.
+fn site_key(name: &str) -> Option<String> {
+    let name = name.trim();
+    if name.ends_with(BROKEN_SUFFIX) {
+        return None;
+    }
+    let site = name.split(':').next()?.trim().to_lowercase();
+    (!site.is_empty() && site != "generic").then_some(site)
+}
+
+/// Names an extractor for `host` might have: the host itself, the host without dots
+/// ("archive.org" -> "archiveorg") and the site label ("www.bbc.co.uk" -> "bbc")
+fn host_candidates(host: &str) -> Vec<String> {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(&host)
+        .to_string();
+
+    let mut labels: Vec<&str> = host.split('.').collect();
+    let mut candidates = vec![host.clone(), host.replace('.', "")];
+    if labels.len() >= 2 {
+        labels.pop();
+        if labels.len() >= 2 && SECOND_LEVEL_LABELS.contains(labels.last().unwrap()) {
+            labels.pop();
+        }
+        if let Some(site) = labels.last() {
+            candidates.push(site.to_string());
+        }
+    }
+    candidates
+}
+
+fn index_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join(INDEX_FILE))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+fn load_cached(app_handle: &AppHandle) -> Option<CachedIndex> {
+    let json = std::fs::read_to_string(index_path(app_handle).ok()?).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn save_cached(app_handle: &AppHandle, cached: &CachedIndex) -> Result<(), String> {
+    let path = index_path(app_handle)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    }
+    let json = serde_json::to_string(cached).map_err(|e| format!("Failed to serialize extractor index: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write extractor index: {}", e))
+}
+
+/// Rebuild the index when yt-dlp's version differs from the cached one
+async fn refresh(app_handle: &AppHandle) -> Result<(), String> {
+    if INDEX.read().map(|i| i.is_none()).unwrap_or(false) {
+        if let Some(cached) = load_cached(app_handle) {
+            println!("[Extractors] Loaded {} cached extractors", cached.extractors.len());
+            if let Ok(mut index) = INDEX.write() {
+                *index = Some(ExtractorIndex::new(cached));
+            }
+        }
+    }
+
+    let downloader = Downloader::new(app_handle);
+    let version = downloader.check_yt_dlp(false, YtDlpChannel::default()).await?.version;
+    let up_to_date = INDEX
+        .read()
+        .map(|i| i.as_ref().is_some_and(|i| i.cached.yt_dlp_version == version))
+        .unwrap_or(false);
+    if up_to_date {
+        return Ok(());
+    }
+
+    let cached = CachedIndex {
+        yt_dlp_version: version,
+        extractors: downloader.list_extractors().await?,
+    };
+    println!(
+        "[Extractors] Indexed {} extractors for yt-dlp {}",
+        cached.extractors.len(),
+        cached.yt_dlp_version
+    );
+    save_cached(app_handle, &cached)?;
+    if let Ok(mut index) = INDEX.write() {
+        *index = Some(ExtractorIndex::new(cached));
+    }
+    Ok(())
+}
+
+/// Load the cached index and refresh it if yt-dlp changed; called at startup and after updates
+pub fn refresh_in_background(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = refresh(&app_handle).await {
+            println!("[Extractors] Failed to index extractors: {}", e);
+        }
+    });
+}
+
+/// Whether the URL's site has a dedicated extractor. False until the index is built.
+pub fn has_extractor(url: &str) -> bool {
+    INDEX
+        .read()
+        .map(|i| i.as_ref().is_some_and(|i| i.supports(url)))
+        .unwrap_or(false)
+}
+
+/// Whether yt-dlp has a dedicated extractor for this URL's site
+#[tauri::command]
+pub async fn is_url_supported(url: String) -> Result<bool, String> {
+    Ok(has_extractor(&url))
+}
+
+/// Every extractor the installed yt-dlp ships, without broken ones
+#[tauri::command]
+pub async fn get_supported_sites() -> Result<Vec<String>, String> {
+    let index = INDEX.read().map_err(|e| e.to_string())?;
+    let index = index
+        .as_ref()
+        .ok_or_else(|| "The supported site list is still being built".to_string())?;
+    Ok(index
+        .cached
+        .extractors
+        .iter()
+        .filter(|name| !name.ends_with(BROKEN_SUFFIX))
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(names: &[&str]) -> ExtractorIndex {
+        ExtractorIndex::new(CachedIndex {
+            yt_dlp_version: "2025.01.01".to_string(),
+            extractors: names.iter().map(|n| n.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn test_site_key() {
+        assert_eq!(site_key("youtube:tab").as_deref(), Some("youtube"));
+        assert_eq!(site_key("Vimeo").as_deref(), Some("vimeo"));
+        assert_eq!(site_key("generic"), None);
+        assert_eq!(site_key("Foo (CURRENTLY BROKEN)"), None);
+    }
+
+    #[test]
+    fn test_host_candidates() {
+        assert_eq!(host_candidates("www.bbc.co.uk"), vec!["bbc.co.uk", "bbcouk", "bbc"]);
+        assert_eq!(host_candidates("archive.org"), vec!["archive.org", "archiveorg", "archive"]);
+        assert_eq!(host_candidates("clips.twitch.tv"), vec!["clips.twitch.tv", "clipstwitchtv", "twitch"]);
+    }
+
+    #[test]
+    fn test_supports_url() {
+        let index = index(&["youtube", "youtube:tab", "BBC", "ArchiveOrg", "generic", "Odd (CURRENTLY BROKEN)"]);
+        assert!(index.supports("https://m.youtube.com/watch?v=abc"));
+        assert!(index.supports("https://www.bbc.co.uk/iplayer/episode/x"));
+        assert!(index.supports("https://archive.org/details/x"));
+        assert!(!index.supports("https://odd.com/video"));
+        assert!(!index.supports("https://example.com/file.zip"));
+        assert!(!index.supports("not a url"));
+    }
+}
@@ -0,0 +1,184 @@
+//! Extractor Workarounds
+//!
+//! Global yt-dlp options for region-locked or throttled extractions, so they can be
+//! tuned from settings instead of code. Cached here like the proxy so every yt-dlp
+//! invocation (downloads, media info, vault downloads) gets the same options.
+//!
+//! Key Features:
+//! - `--geo-bypass-country` with an ISO 3166-1 alpha-2 code
+//! - Any number of `--extractor-args` entries (e.g. "youtube:player_client=android,web")
+
+use crate::commands::AppState;
+use crate::database::Database;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use tauri::State;
+
+/// Settings key holding the JSON encoded `ExtractorSettings`
+pub const EXTRACTOR_SETTING: &str = "extractor_settings";
+
+const MAX_EXTRACTOR_ARGS: usize = 16;
+const MAX_EXTRACTOR_ARG_LEN: usize = 512;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtractorSettings {
+    /// Two-letter country code to pretend to be in, e.g. "US"
+    pub geo_bypass_country: Option<String>,
+    /// `IE_KEY:ARG=VALUE[;ARG=VALUE]` entries passed as `--extractor-args`
+    pub extractor_args: Vec<String>,
+}
+
+impl ExtractorSettings {
+    /// Validate and normalize (trim, upper-case the country, drop empty entries)
+    fn normalized(self) -> Result<Self, String> {
+        let geo_bypass_country = match self.geo_bypass_country.map(|c| c.trim().to_ascii_uppercase()) {
+            Some(country) if country.is_empty() => None,
+            Some(country) => {
+                if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return Err(format!("Invalid country code '{}'. Use a two-letter code like US", country));
+                }
+                Some(country)
+            }
+            None => None,
+        };
+
+        let extractor_args: Vec<String> = self
+            .extractor_args
+            .iter()
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect();
+        if extractor_args.len() > MAX_EXTRACTOR_ARGS {
+            return Err(format!("At most {} extractor argument entries are allowed", MAX_EXTRACTOR_ARGS));
+        }
+        for arg in &extractor_args {
+            validate_extractor_arg(arg)?;
+        }
+
+        Ok(Self {
+            geo_bypass_country,
+            extractor_args,
+        })
+    }
+
+    /// yt-dlp arguments for these settings
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(country) = &self.geo_bypass_country {
+            args.extend(["--geo-bypass-country".to_string(), country.clone()]);
+        }
+        for arg in &self.extractor_args {
+            args.extend(["--extractor-args".to_string(), arg.clone()]);
+        }
+        args
+    }
+}
+
+/// An entry must name an extractor and set at least one argument
+fn validate_extractor_arg(arg: &str) -> Result<(), String> {
+    let invalid = || format!("Invalid extractor argument '{}'. Use the form youtube:player_client=web", arg);
+    if arg.len() > MAX_EXTRACTOR_ARG_LEN || arg.chars().any(char::is_control) {
+        return Err(invalid());
+    }
+    let (extractor, values) = arg.split_once(':').ok_or_else(invalid)?;
+    let valid_extractor = !extractor.is_empty()
+        && extractor.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '*'));
+    if !valid_extractor || extractor.starts_with('-') || !values.contains('=') {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+lazy_static::lazy_static! {
+    static ref CURRENT_SETTINGS: RwLock<ExtractorSettings> = RwLock::new(ExtractorSettings::default());
+}
+
+/// yt-dlp arguments for the saved geo-bypass / extractor options
+pub fn extractor_args() -> Vec<String> {
+    CURRENT_SETTINGS.read().map(|s| s.args()).unwrap_or_default()
+}
+
+/// Apply the persisted options at startup
+pub fn load_from_settings(db: &Database) {
+    let settings = db
+        .get_setting(EXTRACTOR_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str::<ExtractorSettings>(&json).ok())
+        .and_then(|s| s.normalized().ok())
+        .unwrap_or_default();
+
+    if let Ok(mut current) = CURRENT_SETTINGS.write() {
+        *current = settings;
+    }
+}
+
+#[tauri::command]
+pub async fn get_extractor_settings() -> Result<ExtractorSettings, String> {
+    CURRENT_SETTINGS
+        .read()
+        .map(|s| s.clone())
+        .map_err(|e| e.to_string())
+}
+
+/// Save geo-bypass / extractor options; they apply to yt-dlp runs started afterwards
+#[tauri::command]
+pub async fn set_extractor_settings(
+    state: State<'_, AppState>,
+    settings: ExtractorSettings,
+) -> Result<ExtractorSettings, String> {
+    let settings = settings.normalized()?;
+
+    let json = serde_json::to_string(&settings)
+        .map_err(|e| format!("Failed to serialize extractor settings: {}", e))?;
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(EXTRACTOR_SETTING, &json).map_err(|e| e.to_string())?;
+    }
+
+    if let Ok(mut current) = CURRENT_SETTINGS.write() {
+        *current = settings.clone();
+    }
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_settings_args() {
+        let settings = ExtractorSettings {
+            geo_bypass_country: Some(" de ".to_string()),
+            extractor_args: vec!["youtube:player_client=android,web".to_string(), "  ".to_string()],
+        }
+        .normalized()
+        .unwrap();
+        assert_eq!(
+            settings.args(),
+            vec![
+                "--geo-bypass-country",
+                "DE",
+                "--extractor-args",
+                "youtube:player_client=android,web"
+            ]
+        );
+        assert!(ExtractorSettings::default().normalized().unwrap().args().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_settings_rejected() {
+        let country = |c: &str| ExtractorSettings {
+            geo_bypass_country: Some(c.to_string()),
+            ..Default::default()
+        };
+        assert!(country("USA").normalized().is_err());
+        assert!(country("1A").normalized().is_err());
+
+        assert!(validate_extractor_arg("youtube:player_client=web").is_ok());
+        assert!(validate_extractor_arg("youtube").is_err());
+        assert!(validate_extractor_arg("--exec:x=y").is_err());
+        assert!(validate_extractor_arg("youtube:skip=dash\nhls").is_err());
+    }
+}
@@ -0,0 +1,409 @@
+//! Managed FFmpeg
+//!
+//! yt-dlp and SpotDL need ffmpeg for merging, remuxing and audio extraction, and PATH
+//! lookup is deliberately disabled. When no bundled ffmpeg works, `ensure_ffmpeg`
+//! installs a static build into the app's binaries directory, like the SpotDL updater.
+//!
+//! Key Features:
+//! - Static builds from the ffmpeg-static GitHub releases, picked per OS / architecture
+//! - Downloads checked against the SHA-256 digest GitHub publishes for the asset
+//! - Version check against the latest release
+//! - Progress reported via `ffmpeg-install-progress`
+
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+const FFMPEG_RELEASES_URL: &str = "https://api.github.com/repos/eugeneware/ffmpeg-static/releases/latest";
+
+/// Progress events are emitted at most this often (in bytes)
+const PROGRESS_STEP: u64 = 512 * 1024;
+
+lazy_static::lazy_static! {
+    /// Only one install runs at a time
+    static ref INSTALL_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FfmpegInfo {
+    pub version: String,
+    pub path: String,
+    pub is_available: bool,
+    /// Whether the binary lives in app data and can be updated in-app
+    pub is_managed: bool,
+    pub latest_version: Option<String>,
+    pub update_available: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FfmpegInstallProgress {
+    /// "downloading", "extracting", "verifying", "completed" or "failed"
+    pub status: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct GithubAsset {
+    name: String,
+    size: u64,
+    browser_download_url: String,
+    /// "sha256:<hex>"
+    digest: Option<String>,
+}
+
+impl GithubAsset {
+    fn sha256(&self) -> Option<&str> {
+        self.digest.as_deref()?.strip_prefix("sha256:")
+    }
+}
+
+fn binary_name() -> &'static str {
+    if cfg!(windows) {
+        "ffmpeg.exe"
+    } else {
+        "ffmpeg"
+    }
+}
+
+/// Where the managed ffmpeg is installed; the downloaders look here first
+pub fn managed_ffmpeg_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to access app data directory: {}", e))?;
+    Ok(app_data_dir.join("binaries").join(binary_name()))
+}
+
+/// The managed ffmpeg, if one is installed
+pub fn find_managed_ffmpeg(app_handle: &AppHandle) -> Option<String> {
+    managed_ffmpeg_path(app_handle)
+        .ok()
+        .filter(|path| path.exists())
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Release asset for this platform, e.g. "ffmpeg-linux-x64.gz"
+fn asset_name() -> Option<String> {
+    let platform = match std::env::consts::OS {
+        "windows" => "win32",
+        "macos" => "darwin",
+        "linux" => "linux",
+        _ => return None,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        "x86" => "ia32",
+        _ => return None,
+    };
+    Some(format!("ffmpeg-{}-{}.gz", platform, arch))
+}
+
+/// "ffmpeg version 6.0-static https://..." -> "6.0"
+fn parse_version_output(output: &str) -> Option<String> {
+    let line = output.lines().next()?;
+    let version = line.strip_prefix("ffmpeg version ")?.split_whitespace().next()?;
+    let version = version.trim_start_matches('n');
+    let end = version
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(version.len());
+    let version = version[..end].trim_end_matches('.');
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+/// Release tags look like "b6.0"
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches(['b', 'v']).to_string()
+}
+
+fn is_newer(current: &str, latest: &str) -> bool {
+    let segments = |v: &str| -> Vec<u32> { v.split('.').filter_map(|s| s.parse().ok()).collect() };
+    let (current, latest) = (segments(current), segments(latest));
+    if current.is_empty() || latest.is_empty() {
+        return false;
+    }
+    latest > current
+}
+
+async fn probe_version(path: &str) -> Result<String, String> {
+    let mut cmd = crate::exec_guard::tokio_command(path)?;
+    cmd.arg("-version").kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(15), cmd.output())
+        .await
+        .map_err(|_| "ffmpeg did not respond".to_string())?
+        .map_err(|e| format!("ffmpeg not working: {}", e))?;
+    if !output.status.success() {
+        return Err("ffmpeg returned an error".to_string());
+    }
+    parse_version_output(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| "Could not read the ffmpeg version".to_string())
+}
+
+async fn fetch_latest_release() -> Result<GithubRelease, String> {
+    let client = crate::proxy::apply_to_client(reqwest::Client::builder())
+        .user_agent("OwnstashDownloader/1.0")
+        .timeout(std::time::Duration::from_secs(20))
+        .build()
+        .map_err(|e| format!("Failed to initialize HTTP client: {}", e))?;
+
+    client
+        .get(FFMPEG_RELEASES_URL)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch ffmpeg release metadata: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("ffmpeg release request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse ffmpeg release metadata: {}", e))
+}
+
+fn emit_progress(app_handle: &AppHandle, status: &str, downloaded: u64, total: Option<u64>, error: Option<String>) {
+    let _ = app_handle.emit(
+        "ffmpeg-install-progress",
+        FfmpegInstallProgress {
+            status: status.to_string(),
+            downloaded,
+            total,
+            error,
+        },
+    );
+}
+
+async fn download_and_install(app_handle: &AppHandle, asset: &GithubAsset, target_path: &Path) -> Result<(), String> {
+    use futures_util::StreamExt;
+
+    let expected_sha256 = asset
+        .sha256()
+        .ok_or_else(|| format!("No published checksum for {}", asset.name))?;
+
+    let client = crate::proxy::apply_to_client(reqwest::Client::builder())
+        .user_agent("OwnstashDownloader/1.0")
+        .timeout(std::time::Duration::from_secs(600))
+        .build()
+        .map_err(|e| format!("Failed to initialize HTTP client: {}", e))?;
+
+    let response = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download ffmpeg: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download ffmpeg (HTTP {})", response.status()));
+    }
+
+    let total = response.content_length().or(Some(asset.size));
+    let mut compressed = Vec::with_capacity(total.unwrap_or(0) as usize);
+    let mut stream = response.bytes_stream();
+    let mut last_reported = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to download ffmpeg: {}", e))?;
+        compressed.extend_from_slice(&chunk);
+        let downloaded = compressed.len() as u64;
+        if downloaded - last_reported >= PROGRESS_STEP {
+            last_reported = downloaded;
+            emit_progress(app_handle, "downloading", downloaded, total, None);
+        }
+    }
+    if compressed.len() as u64 != asset.size {
+        return Err(format!(
+            "ffmpeg download is incomplete ({} of {} bytes)",
+            compressed.len(),
+            asset.size
+        ));
+    }
+    let actual_sha256 = sha256_hex(&compressed);
+    if !actual_sha256.eq_ignore_ascii_case(expected_sha256) {
+        return Err(format!(
+            "ffmpeg download checksum mismatch: expected {}, got {}",
+            expected_sha256, actual_sha256
+        ));
+    }
+
+    emit_progress(app_handle, "extracting", compressed.len() as u64, total, None);
+    let binary = tokio::task::spawn_blocking(move || {
+        let mut decoder = flate2::read::GzDecoder::new(compressed.as_slice());
+        let mut binary = Vec::new();
+        decoder
+            .read_to_end(&mut binary)
+            .map(|_| binary)
+            .map_err(|e| format!("Failed to extract ffmpeg: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Failed to extract ffmpeg: {}", e))??;
+
+    let parent = target_path
+        .parent()
+        .ok_or_else(|| "Invalid ffmpeg destination path".to_string())?;
+    tokio::fs::create_dir_all(parent)
+        .await
+        .map_err(|e| format!("Failed to prepare binaries directory: {}", e))?;
+
+    let temp_path = target_path.with_extension("download.tmp");
+    tokio::fs::write(&temp_path, binary)
+        .await
+        .map_err(|e| format!("Failed to write ffmpeg binary: {}", e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o755))
+            .await
+            .map_err(|e| format!("Failed to set ffmpeg executable permissions: {}", e))?;
+    }
+
+    // Make sure the new build actually runs before replacing anything
+    emit_progress(app_handle, "verifying", asset.size, total, None);
+    crate::exec_guard::record_managed_binary(&temp_path)?;
+    if let Err(e) = probe_version(&temp_path.to_string_lossy()).await {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        let _ = crate::exec_guard::forget_managed_binary(&temp_path);
+        return Err(format!("Downloaded ffmpeg does not run on this system: {}", e));
+    }
+
+    let replaced = crate::downloader::Downloader::replace_binary(&temp_path, target_path).await;
+    crate::exec_guard::forget_managed_binary(&temp_path)?;
+    replaced?;
+    crate::exec_guard::record_managed_binary(target_path)
+}
+
+/// Check the ffmpeg the downloaders would use, optionally against the latest release
+async fn check_ffmpeg(app_handle: &AppHandle, latest: Option<&GithubRelease>) -> Result<FfmpegInfo, String> {
+    let path = crate::downloader::Downloader::find_ffmpeg(app_handle)
+        .ok_or_else(|| "ffmpeg not found".to_string())?;
+    let version = probe_version(&path).await?;
+    let is_managed = managed_ffmpeg_path(app_handle)
+        .map(|managed| Path::new(&path) == managed)
+        .unwrap_or(false);
+    let latest_version = latest.map(|release| normalize_tag(&release.tag_name));
+    let update_available = is_managed
+        && latest_version
+            .as_deref()
+            .is_some_and(|latest| is_newer(&version, latest));
+
+    Ok(FfmpegInfo {
+        version,
+        path,
+        is_available: true,
+        is_managed,
+        latest_version,
+        update_available,
+    })
+}
+
+/// Make sure a working ffmpeg is available, downloading a static build if needed.
+/// With `update`, a managed ffmpeg is also replaced when a newer release exists.
+#[tauri::command]
+pub async fn ensure_ffmpeg(app_handle: AppHandle, update: Option<bool>) -> Result<FfmpegInfo, String> {
+    let _guard = INSTALL_LOCK.lock().await;
+    let update = update.unwrap_or(false);
+
+    let release = if update { Some(fetch_latest_release().await?) } else { None };
+    match check_ffmpeg(&app_handle, release.as_ref()).await {
+        Ok(info) if !info.update_available => return Ok(info),
+        Ok(info) => println!(
+            "[FFmpeg] Updating managed ffmpeg {} -> {}",
+            info.version,
+            info.latest_version.as_deref().unwrap_or("?")
+        ),
+        Err(e) => println!("[FFmpeg] No working ffmpeg ({}), installing", e),
+    }
+
+    let release = match release {
+        Some(release) => release,
+        None => fetch_latest_release().await?,
+    };
+    let name = asset_name().ok_or_else(|| "No ffmpeg build is available for this platform".to_string())?;
+    let asset = release
+        .assets
+        .iter()
+        .find(|a| a.name == name)
+        .cloned()
+        .ok_or_else(|| format!("ffmpeg release {} has no {} build", release.tag_name, name))?;
+
+    let target_path = managed_ffmpeg_path(&app_handle)?;
+    println!("[FFmpeg] Installing {} ({}) to {:?}", release.tag_name, asset.name, target_path);
+    if let Err(e) = download_and_install(&app_handle, &asset, &target_path).await {
+        emit_progress(&app_handle, "failed", 0, None, Some(e.clone()));
+        return Err(e);
+    }
+    emit_progress(&app_handle, "completed", asset.size, Some(asset.size), None);
+
+    check_ffmpeg(&app_handle, Some(&release)).await
+}
+
+/// Report the ffmpeg in use without installing anything
+#[tauri::command]
+pub async fn check_ffmpeg_status(app_handle: AppHandle, include_latest: Option<bool>) -> Result<FfmpegInfo, String> {
+    let release = if include_latest.unwrap_or(false) && crate::outbound::allowed(crate::outbound::OutboundCall::FfmpegVersionCheck) {
+        match fetch_latest_release().await {
+            Ok(release) => Some(release),
+            Err(e) => {
+                println!("[FFmpeg] Failed to fetch latest version: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    check_ffmpeg(&app_handle, release.as_ref()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version_output() {
+        assert_eq!(
+            parse_version_output("ffmpeg version 6.0-static https://johnvansickle.com\nbuilt with gcc").as_deref(),
+            Some("6.0")
+        );
+        assert_eq!(parse_version_output("ffmpeg version n7.1.1 Copyright").as_deref(), Some("7.1.1"));
+        assert_eq!(parse_version_output("ffmpeg version N-112345-gabc").as_deref(), None);
+        assert_eq!(parse_version_output("not ffmpeg"), None);
+    }
+
+    #[test]
+    fn test_version_comparison() {
+        assert_eq!(normalize_tag("b6.0"), "6.0");
+        assert!(is_newer("5.1.2", "6.0"));
+        assert!(!is_newer("6.0", "6.0"));
+        assert!(!is_newer("unknown", "6.0"));
+    }
+
+    #[test]
+    fn test_asset_digest() {
+        let asset = |digest: Option<&str>| GithubAsset {
+            name: "ffmpeg-linux-x64.gz".to_string(),
+            size: 3,
+            browser_download_url: String::new(),
+            digest: digest.map(str::to_string),
+        };
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(asset(Some(&format!("sha256:{}", abc))).sha256(), Some(abc));
+        assert_eq!(sha256_hex(b"abc"), abc);
+        assert_eq!(asset(Some("md5:abc")).sha256(), None);
+        assert_eq!(asset(None).sha256(), None);
+    }
+}
@@ -0,0 +1,377 @@
+//! Download Folder Stats
+//!
+//! Size / file count / last change of a download folder without walking the whole tree
+//! on every call. Per-directory totals are cached and a filesystem watcher marks the
+//! directories that changed, so a refresh only rescans those.
+//!
+//! Key Features:
+//! - Incremental rescans driven by `notify` events
+//! - Falls back to a periodic full rescan when the watcher can't be started or overflows
+//! - Scans run on the blocking pool
+//! - `ScanControl` lets any long filesystem walk be cancelled with `cancel_scan` and
+//!   report "scan-progress" events
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+/// Without a working watcher, cached stats are trusted for this long
+const UNWATCHED_TTL: Duration = Duration::from_secs(30);
+
+/// Folders kept in the cache at once
+const MAX_CACHED_FOLDERS: usize = 8;
+
+/// Minimum gap between "scan-progress" events of one scan
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FolderStats {
+    pub size: u64,
+    pub file_count: u64,
+    /// Unix timestamp of the newest modification inside the folder
+    pub last_changed: Option<i64>,
+}
+
+/// Files directly inside one directory
+#[derive(Debug, Default)]
+struct DirStats {
+    size: u64,
+    files: u64,
+    last_changed: Option<i64>,
+    subdirs: Vec<PathBuf>,
+}
+
+#[derive(Default)]
+struct Dirty {
+    dirs: HashSet<PathBuf>,
+    full_rescan: bool,
+}
+
+struct FolderCache {
+    dirs: HashMap<PathBuf, DirStats>,
+    dirty: Arc<Mutex<Dirty>>,
+    /// Kept alive for as long as the cache entry; `None` if watching failed
+    watcher: Option<notify::RecommendedWatcher>,
+    scanned_at: Instant,
+    last_used: Instant,
+}
+
+lazy_static::lazy_static! {
+    static ref FOLDER_CACHES: Mutex<HashMap<PathBuf, FolderCache>> = Mutex::new(HashMap::new());
+    /// Cancellation flags of running scans started with a scan id
+    static ref ACTIVE_SCANS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+}
+
+const SCAN_CANCELLED_ERROR: &str = "Scan cancelled";
+
+#[derive(Debug, Clone, Serialize)]
+struct ScanProgress {
+    scan_id: String,
+    scanned: u64,
+}
+
+/// Cancellation and progress reporting for a blocking filesystem walk. The default
+/// value is never cancelled and reports nothing.
+#[derive(Default)]
+pub struct ScanControl {
+    scan_id: Option<String>,
+    app_handle: Option<AppHandle>,
+    cancelled: Arc<AtomicBool>,
+    scanned: AtomicU64,
+    last_report: Mutex<Option<Instant>>,
+}
+
+impl ScanControl {
+    /// A scan that `cancel_scan(scan_id)` can stop and that emits "scan-progress"
+    pub fn new(app_handle: &AppHandle, scan_id: Option<String>) -> Self {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        if let Some(scan_id) = &scan_id {
+            ACTIVE_SCANS.lock().unwrap().insert(scan_id.clone(), cancelled.clone());
+        }
+        Self {
+            scan_id,
+            app_handle: Some(app_handle.clone()),
+            cancelled,
+            scanned: AtomicU64::new(0),
+            last_report: Mutex::new(None),
+        }
+    }
+
+    /// Count `entries` more scanned entries; errors once the scan was cancelled
+    pub fn advance(&self, entries: u64) -> Result<(), String> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(SCAN_CANCELLED_ERROR.to_string());
+        }
+        let scanned = self.scanned.fetch_add(entries, Ordering::Relaxed) + entries;
+
+        if let (Some(scan_id), Some(app_handle)) = (&self.scan_id, &self.app_handle) {
+            let mut last_report = self.last_report.lock().unwrap();
+            if last_report.map_or(true, |t| t.elapsed() >= PROGRESS_INTERVAL) {
+                *last_report = Some(Instant::now());
+                let _ = app_handle.emit("scan-progress", ScanProgress {
+                    scan_id: scan_id.clone(),
+                    scanned,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ScanControl {
+    fn drop(&mut self) {
+        if let Some(scan_id) = &self.scan_id {
+            ACTIVE_SCANS.lock().unwrap().remove(scan_id);
+        }
+    }
+}
+
+/// Run a filesystem walk on the blocking pool so it can't stall the async runtime
+pub async fn run_blocking_scan<T, F>(control: ScanControl, scan: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&ScanControl) -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || scan(&control))
+        .await
+        .map_err(|e| format!("Scan task failed: {}", e))?
+}
+
+fn modified_secs(metadata: &std::fs::Metadata) -> Option<i64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+}
+
+/// Stats for the files directly in `dir`; `None` if it no longer exists
+fn scan_dir(dir: &Path) -> Option<DirStats> {
+    let entries = std::fs::read_dir(dir).ok()?;
+    let mut stats = DirStats {
+        last_changed: std::fs::metadata(dir).ok().as_ref().and_then(modified_secs),
+        ..Default::default()
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            stats.subdirs.push(entry.path());
+        } else if let Ok(metadata) = entry.metadata() {
+            stats.size += metadata.len();
+            stats.files += 1;
+            stats.last_changed = stats.last_changed.max(modified_secs(&metadata));
+        }
+    }
+    Some(stats)
+}
+
+/// Rescan `dir` and any subdirectories not seen before
+fn scan_tree(dirs: &mut HashMap<PathBuf, DirStats>, dir: &Path, control: &ScanControl) -> Result<(), String> {
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Some(stats) = scan_dir(&dir) else {
+            remove_tree(dirs, &dir);
+            continue;
+        };
+        control.advance(stats.files + stats.subdirs.len() as u64)?;
+
+        // Subdirectories that disappeared take their cached totals with them
+        let gone: Vec<PathBuf> = dirs
+            .get(&dir)
+            .map(|previous| previous.subdirs.iter().filter(|d| !stats.subdirs.contains(d)).cloned().collect())
+            .unwrap_or_default();
+        for subdir in gone {
+            remove_tree(dirs, &subdir);
+        }
+        pending.extend(stats.subdirs.iter().filter(|d| !dirs.contains_key(*d)).cloned());
+        dirs.insert(dir, stats);
+    }
+    Ok(())
+}
+
+fn remove_tree(dirs: &mut HashMap<PathBuf, DirStats>, dir: &Path) {
+    dirs.retain(|path, _| !path.starts_with(dir));
+}
+
+fn totals(dirs: &HashMap<PathBuf, DirStats>) -> FolderStats {
+    dirs.values().fold(FolderStats::default(), |mut acc, dir| {
+        acc.size += dir.size;
+        acc.file_count += dir.files;
+        acc.last_changed = acc.last_changed.max(dir.last_changed);
+        acc
+    })
+}
+
+/// Apply pending changes to a cache, or rescan everything when the changes are unknown
+fn refresh(cache: &mut FolderCache, root: &Path, control: &ScanControl) -> Result<(), String> {
+    let dirty = std::mem::take(&mut *cache.dirty.lock().unwrap());
+    let expired = cache.watcher.is_none() && cache.scanned_at.elapsed() > UNWATCHED_TTL;
+    if dirty.full_rescan || expired {
+        cache.dirs.clear();
+        scan_tree(&mut cache.dirs, root, control)?;
+        cache.scanned_at = Instant::now();
+        return Ok(());
+    }
+    for dir in dirty.dirs {
+        // Events outside the tree we know about (or for its parent) are ignored
+        if dir.starts_with(root) {
+            scan_tree(&mut cache.dirs, &dir, control)?;
+        }
+    }
+    Ok(())
+}
+
+fn start_watcher(root: &Path, dirty: Arc<Mutex<Dirty>>) -> Option<notify::RecommendedWatcher> {
+    use notify::Watcher;
+
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let mut dirty = dirty.lock().unwrap();
+        match result {
+            Ok(event) if !event.need_rescan() => {
+                for path in event.paths {
+                    // A change to an entry alters its parent's totals; a directory may also
+                    // have been removed or replaced itself
+                    if let Some(parent) = path.parent() {
+                        dirty.dirs.insert(parent.to_path_buf());
+                    }
+                    dirty.dirs.insert(path);
+                }
+            }
+            _ => dirty.full_rescan = true,
+        }
+    })
+    .ok()?;
+
+    match watcher.watch(root, notify::RecursiveMode::Recursive) {
+        Ok(()) => Some(watcher),
+        Err(e) => {
+            println!("[FolderStats] Can't watch {:?}, using periodic rescans: {}", root, e);
+            None
+        }
+    }
+}
+
+/// Current stats for `root`, rescanning only what changed since the last call
+fn folder_stats(root: &Path, control: &ScanControl) -> Result<FolderStats, String> {
+    let mut caches = FOLDER_CACHES.lock().unwrap();
+    if let Some(cache) = caches.get_mut(root) {
+        if let Err(e) = refresh(cache, root, control) {
+            // A half-applied refresh can't be trusted; start over next time
+            cache.dirty.lock().unwrap().full_rescan = true;
+            return Err(e);
+        }
+        cache.last_used = Instant::now();
+        return Ok(totals(&cache.dirs));
+    }
+
+    if caches.len() >= MAX_CACHED_FOLDERS {
+        if let Some(oldest) = caches.iter().min_by_key(|(_, c)| c.last_used).map(|(p, _)| p.clone()) {
+            caches.remove(&oldest);
+        }
+    }
+
+    let dirty = Arc::new(Mutex::new(Dirty::default()));
+    // Watch before scanning so nothing that changes mid-scan is missed
+    let watcher = start_watcher(root, dirty.clone());
+    let mut dirs = HashMap::new();
+    scan_tree(&mut dirs, root, control)?;
+    let stats = totals(&dirs);
+    caches.insert(
+        root.to_path_buf(),
+        FolderCache {
+            dirs,
+            dirty,
+            watcher,
+            scanned_at: Instant::now(),
+            last_used: Instant::now(),
+        },
+    );
+    Ok(stats)
+}
+
+/// Stats for a folder (or a single file), off the async runtime
+pub async fn get_folder_stats(path: &str, control: ScanControl) -> Result<FolderStats, String> {
+    let path = PathBuf::from(path);
+    run_blocking_scan(control, move |control| {
+        if path.is_file() {
+            let metadata = std::fs::metadata(&path).map_err(|e| e.to_string())?;
+            return Ok(FolderStats {
+                size: metadata.len(),
+                file_count: 1,
+                last_changed: modified_secs(&metadata),
+            });
+        }
+        if !path.is_dir() {
+            return Ok(FolderStats::default());
+        }
+        let root = path.canonicalize().unwrap_or(path);
+        folder_stats(&root, control)
+    })
+    .await
+}
+
+/// Size, file count and last change of a download folder, served from the cache.
+/// Pass a `scan_id` to get "scan-progress" events and allow `cancel_scan`.
+#[tauri::command]
+pub async fn get_download_folder_stats(
+    app_handle: AppHandle,
+    path: String,
+    scan_id: Option<String>,
+) -> Result<FolderStats, String> {
+    get_folder_stats(&path, ScanControl::new(&app_handle, scan_id)).await
+}
+
+/// Stop a running scan started with the same `scan_id`
+#[tauri::command]
+pub async fn cancel_scan(scan_id: String) -> Result<(), String> {
+    match ACTIVE_SCANS.lock().unwrap().get(&scan_id) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        None => Err("Scan not found or already finished".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incremental_rescan() {
+        let root = std::env::temp_dir().join(format!("ownstash-folder-stats-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("one.bin"), vec![0u8; 10]).unwrap();
+        std::fs::write(root.join("a/b/two.bin"), vec![0u8; 20]).unwrap();
+
+        let control = ScanControl::default();
+        let mut dirs = HashMap::new();
+        scan_tree(&mut dirs, &root, &control).unwrap();
+        let stats = totals(&dirs);
+        assert_eq!((stats.size, stats.file_count), (30, 2));
+
+        // Only the changed directory is rescanned
+        std::fs::write(root.join("a/three.bin"), vec![0u8; 5]).unwrap();
+        scan_tree(&mut dirs, &root.join("a"), &control).unwrap();
+        assert_eq!(totals(&dirs).size, 35);
+
+        // Removing a directory drops its subtree
+        std::fs::remove_dir_all(root.join("a")).unwrap();
+        scan_tree(&mut dirs, &root, &control).unwrap();
+        let stats = totals(&dirs);
+        assert_eq!((stats.size, stats.file_count), (10, 1));
+        assert_eq!(dirs.len(), 1);
+
+        // A cancelled scan stops with an error
+        control.cancelled.store(true, Ordering::Relaxed);
+        assert!(scan_tree(&mut HashMap::new(), &root, &control).is_err());
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
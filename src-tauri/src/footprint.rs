@@ -0,0 +1,235 @@
+//! Download Footprint Estimates
+//!
+//! Optional, rough energy and carbon estimates for finished downloads, for users who want
+//! to keep an eye on the footprint of what they fetch.
+//!
+//! Key Features:
+//! - Off by default; factors for network energy, device power and grid intensity are configurable
+//! - Estimated from bytes transferred and download duration, whatever engine ran it
+//! - Stored per download next to the history, summed for the stats dashboard
+
+use crate::commands::AppState;
+use crate::database::{Database, DownloadFootprint, FootprintTotals};
+use crate::download_lifecycle::{DownloadTracker, ProgressUpdate, Transition};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, RwLock};
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+
+/// Settings key holding the JSON encoded `FootprintSettings`
+pub const FOOTPRINT_SETTING: &str = "download_footprint";
+
+const BYTES_PER_GB: f64 = 1_000_000_000.0;
+
+/// Factors the estimate is built from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FootprintSettings {
+    pub enabled: bool,
+    /// Network and data-center energy per gigabyte transferred
+    pub network_kwh_per_gb: f64,
+    /// Power this machine draws while downloading
+    pub device_watts: f64,
+    /// Grid carbon intensity
+    pub grid_g_per_kwh: f64,
+}
+
+impl Default for FootprintSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            // Published estimates range from ~0.01 to ~0.1; a middle-of-the-road figure
+            network_kwh_per_gb: 0.06,
+            device_watts: 30.0,
+            // Roughly the global average
+            grid_g_per_kwh: 475.0,
+        }
+    }
+}
+
+impl FootprintSettings {
+    fn validate(&self) -> Result<(), String> {
+        let factors = [
+            ("Network energy", self.network_kwh_per_gb, 10.0),
+            ("Device power", self.device_watts, 5000.0),
+            ("Grid intensity", self.grid_g_per_kwh, 2000.0),
+        ];
+        for (name, value, max) in factors {
+            if !value.is_finite() || !(0.0..=max).contains(&value) {
+                return Err(format!("{} must be between 0 and {}", name, max));
+            }
+        }
+        Ok(())
+    }
+
+    /// Energy in Wh and carbon in grams for `bytes` over `duration_secs`
+    pub fn estimate(&self, bytes: u64, duration_secs: f64) -> (f64, f64) {
+        let network_wh = bytes as f64 / BYTES_PER_GB * self.network_kwh_per_gb * 1000.0;
+        let device_wh = self.device_watts * duration_secs.max(0.0) / 3600.0;
+        let energy_wh = network_wh + device_wh;
+        (energy_wh, energy_wh / 1000.0 * self.grid_g_per_kwh)
+    }
+}
+
+/// What's known about a download that hasn't finished yet
+struct Running {
+    started: Instant,
+    bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FootprintSummary {
+    pub enabled: bool,
+    pub all_time: FootprintTotals,
+    pub last_30_days: FootprintTotals,
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<FootprintSettings> = RwLock::new(FootprintSettings::default());
+    static ref RUNNING: Mutex<DownloadTracker<Running>> = Mutex::new(DownloadTracker::default());
+}
+
+/// Restore the estimator settings at startup
+pub fn load_from_settings(db: &Database) {
+    let settings = db
+        .get_setting(FOOTPRINT_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    *SETTINGS.write().unwrap() = settings;
+}
+
+/// Track one progress event; returns the footprint once the download completes
+fn record(update: &ProgressUpdate, settings: &FootprintSettings) -> Option<DownloadFootprint> {
+    let bytes = update.total_bytes.or(update.downloaded_bytes);
+    let mut running = RUNNING.lock().unwrap();
+
+    match running.track(update, || Running { started: Instant::now(), bytes: None }) {
+        Transition::Running { entry, .. } => {
+            entry.bytes = bytes.or(entry.bytes);
+            None
+        }
+        Transition::Finished(entry) => {
+            let entry = entry?;
+            if update.status != "completed" {
+                return None;
+            }
+            let bytes = bytes.or(entry.bytes)?;
+            let duration_secs = entry.started.elapsed().as_secs_f64();
+            let (energy_wh, carbon_g) = settings.estimate(bytes, duration_secs);
+            Some(DownloadFootprint {
+                download_id: update.id.clone(),
+                bytes: bytes as i64,
+                duration_secs,
+                energy_wh,
+                carbon_g,
+                created_at: chrono::Utc::now().timestamp_millis(),
+            })
+        }
+    }
+}
+
+/// Estimate and store the footprint of each finished download; call once at startup
+pub fn install(app_handle: &AppHandle) {
+    let handle = app_handle.clone();
+    crate::download_lifecycle::on_progress(app_handle, move |update| {
+        let settings = SETTINGS.read().unwrap().clone();
+        if !settings.enabled {
+            return;
+        }
+        let Some(footprint) = record(update, &settings) else {
+            return;
+        };
+        if let Some(state) = handle.try_state::<AppState>() {
+            if let Ok(db) = state.db.lock() {
+                if let Err(e) = db.save_footprint(&footprint) {
+                    println!("[Footprint] Failed to save estimate for {}: {}", footprint.download_id, e);
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_footprint_settings() -> Result<FootprintSettings, String> {
+    Ok(SETTINGS.read().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn set_footprint_settings(state: State<'_, AppState>, settings: FootprintSettings) -> Result<(), String> {
+    settings.validate()?;
+
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize footprint settings: {}", e))?;
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(FOOTPRINT_SETTING, &json).map_err(|e| e.to_string())?;
+    }
+    if !settings.enabled {
+        RUNNING.lock().unwrap().clear();
+    }
+    *SETTINGS.write().unwrap() = settings;
+    Ok(())
+}
+
+/// Estimate for one download, if it finished while the estimator was on
+#[tauri::command]
+pub async fn get_download_footprint(state: State<'_, AppState>, id: String) -> Result<Option<DownloadFootprint>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_footprint(&id).map_err(|e| e.to_string())
+}
+
+/// All-time and 30-day totals for the stats dashboard
+#[tauri::command]
+pub async fn get_footprint_summary(state: State<'_, AppState>) -> Result<FootprintSummary, String> {
+    let month_ago = chrono::Utc::now().timestamp_millis() - 30 * 24 * 60 * 60 * 1000;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    Ok(FootprintSummary {
+        enabled: SETTINGS.read().unwrap().enabled,
+        all_time: db.get_footprint_totals(0).map_err(|e| e.to_string())?,
+        last_30_days: db.get_footprint_totals(month_ago).map_err(|e| e.to_string())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        let settings = FootprintSettings {
+            enabled: true,
+            network_kwh_per_gb: 0.05,
+            device_watts: 36.0,
+            grid_g_per_kwh: 400.0,
+        };
+        // 2 GB over 100 s: 100 Wh network + 1 Wh device
+        let (energy_wh, carbon_g) = settings.estimate(2_000_000_000, 100.0);
+        assert!((energy_wh - 101.0).abs() < 1e-9);
+        assert!((carbon_g - 40.4).abs() < 1e-9);
+
+        assert!(FootprintSettings::default().validate().is_ok());
+        assert!(FootprintSettings { device_watts: -1.0, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_only_completed_downloads_are_recorded() {
+        let settings = FootprintSettings { enabled: true, ..Default::default() };
+        let event = |id: &str, status: &str| {
+            ProgressUpdate::from_download_progress(serde_json::json!({
+                "id": id, "status": status, "downloaded_bytes": 10, "total_bytes": 1_000_000
+            }))
+            .unwrap()
+        };
+
+        assert!(record(&event("fp-a", "downloading"), &settings).is_none());
+        let footprint = record(&event("fp-a", "completed"), &settings).unwrap();
+        assert_eq!(footprint.bytes, 1_000_000);
+        assert!(footprint.energy_wh > 0.0);
+
+        assert!(record(&event("fp-b", "downloading"), &settings).is_none());
+        assert!(record(&event("fp-b", "failed"), &settings).is_none());
+        // Never seen running: no duration to go on
+        assert!(record(&event("fp-c", "completed"), &settings).is_none());
+    }
+}
@@ -0,0 +1,377 @@
+//! Gallery Engine
+//!
+//! Image galleries (Instagram profiles, Reddit galleries, imgur albums) are a poor fit for
+//! yt-dlp, so the router sends them here and they're fetched with gallery-dl instead.
+//!
+//! Key Features:
+//! - App-managed gallery-dl binary, installed from its GitHub releases on request and
+//!   checked against the size and SHA-256 GitHub publishes for the asset
+//! - Falls back to a bundled or system gallery-dl (the only option on macOS)
+//! - Same proxy, cookie and bandwidth settings as the Media Engine
+//! - Per-file `gallery-progress` events alongside the usual `download-progress`
+//! - Post-download hooks run once per gallery, with the folder it landed in as `{file}`
+
+use crate::downloader::{cookie_args, Downloader, DownloadProgress};
+use crate::health_metrics::{DownloadPhase, HEALTH_REGISTRY};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::oneshot;
+
+pub const GALLERY_BADGE: &str = "GALLERY";
+
+const GALLERY_DL_REPOSITORY: &str = "mikf/gallery-dl";
+
+/// One downloaded or skipped file reported on gallery-dl's stdout, or an error from stderr
+#[derive(Debug, Clone, PartialEq)]
+enum GalleryLine {
+    Downloaded(PathBuf),
+    /// Already on disk; gallery-dl prefixes these with "# "
+    Skipped(PathBuf),
+    Error(String),
+}
+
+fn parse_line(line: &str) -> Option<GalleryLine> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    // Log lines look like "[instagram][error] message"
+    if line.starts_with('[') {
+        return line
+            .split_once("][error] ")
+            .map(|(_, message)| GalleryLine::Error(message.trim().to_string()));
+    }
+    match line.strip_prefix("# ") {
+        Some(path) => Some(GalleryLine::Skipped(PathBuf::from(path))),
+        None => Some(GalleryLine::Downloaded(PathBuf::from(line))),
+    }
+}
+
+/// Emitted for every file gallery-dl finishes
+#[derive(Debug, Clone, Serialize)]
+pub struct GalleryProgress {
+    pub id: String,
+    pub files_downloaded: u32,
+    pub files_skipped: u32,
+    pub current_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GalleryDlStatus {
+    pub path: Option<String>,
+    pub version: Option<String>,
+    /// Whether the binary is the app-managed one (and so can be updated in-app)
+    pub managed: bool,
+}
+
+fn binary_name() -> &'static str {
+    if cfg!(windows) {
+        "gallery-dl.exe"
+    } else {
+        "gallery-dl"
+    }
+}
+
+fn managed_gallery_dl_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join("binaries").join(binary_name()))
+        .map_err(|e| format!("Failed to access app data directory: {}", e))
+}
+
+/// Managed binary first, then a bundled one, then PATH
+pub fn find_gallery_dl(app_handle: &AppHandle) -> Option<PathBuf> {
+    if let Some(managed) = managed_gallery_dl_path(app_handle).ok().filter(|p| p.exists()) {
+        return Some(managed);
+    }
+    if let Some(bundled) = app_handle
+        .path()
+        .resource_dir()
+        .ok()
+        .map(|dir| dir.join("binaries").join(binary_name()))
+        .filter(|p| p.exists())
+    {
+        return Some(bundled);
+    }
+    which::which("gallery-dl").ok()
+}
+
+/// Release asset for this platform; gallery-dl only publishes Windows and Linux builds
+fn release_asset_name() -> Option<&'static str> {
+    if cfg!(windows) {
+        Some("gallery-dl.exe")
+    } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some("gallery-dl.bin")
+    } else {
+        None
+    }
+}
+
+fn hidden_command(program: &Path) -> Result<Command, String> {
+    #[allow(unused_mut)]
+    let mut cmd = crate::exec_guard::tokio_command(program)?;
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    Ok(cmd)
+}
+
+async fn gallery_dl_version(binary: &Path) -> Option<String> {
+    let output = hidden_command(binary).ok()?.arg("--version").output().await.ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// One gallery download
+pub struct GalleryJob {
+    pub id: String,
+    pub url: String,
+    pub output_dir: PathBuf,
+    pub cookies_source: Option<String>,
+    pub bandwidth_limit_kbps: Option<u64>,
+}
+
+/// What a finished gallery run produced
+pub struct GalleryOutcome {
+    pub files: Vec<PathBuf>,
+    pub skipped: u32,
+    /// Last error gallery-dl logged, even if the run as a whole succeeded
+    pub error: Option<String>,
+}
+
+fn gallery_args(job: &GalleryJob) -> Result<Vec<String>, String> {
+    let mut args = vec![
+        "--dest".to_string(),
+        job.output_dir.to_string_lossy().to_string(),
+        "--retries".to_string(),
+        "4".to_string(),
+    ];
+    if let Some(limit) = job.bandwidth_limit_kbps {
+        args.extend(["--limit-rate".to_string(), format!("{}k", limit)]);
+    }
+    if let Some(source) = &job.cookies_source {
+        args.extend(cookie_args(source)?);
+    }
+    args.extend(crate::proxy::proxy_args());
+    args.push(job.url.clone());
+    Ok(args)
+}
+
+fn progress_event(id: &str, status: &str, progress: f64) -> DownloadProgress {
+    DownloadProgress {
+        id: id.to_string(),
+        progress,
+        status: status.to_string(),
+        engine_badge: Some(GALLERY_BADGE.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Run gallery-dl for a gallery URL. gallery-dl doesn't know the item count up front,
+/// so progress is reported as a file count rather than a percentage.
+pub async fn download(
+    app_handle: &AppHandle,
+    binary: &Path,
+    job: GalleryJob,
+    mut cancel_rx: oneshot::Receiver<()>,
+) -> Result<GalleryOutcome, String> {
+    let args = gallery_args(&job)?;
+    println!("[Gallery] Downloading {} with gallery-dl", job.url);
+
+    let mut child = hidden_command(binary)?
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start gallery-dl: {}", e))?;
+    let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
+    let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
+    let mut stdout_reader = BufReader::new(stdout).lines();
+    let mut stderr_reader = BufReader::new(stderr).lines();
+
+    let mut outcome = GalleryOutcome { files: Vec::new(), skipped: 0, error: None };
+    let mut stdout_open = true;
+    let mut stderr_open = true;
+    while stdout_open || stderr_open {
+        let line = tokio::select! {
+            _ = &mut cancel_rx => {
+                let _ = child.kill().await;
+                let _ = app_handle.emit("download-progress", progress_event(&job.id, "cancelled", 0.0));
+                HEALTH_REGISTRY.set_phase(&job.id, DownloadPhase::Cancelled);
+                return Err("Download cancelled".to_string());
+            }
+            line = stdout_reader.next_line(), if stdout_open => match line {
+                Ok(Some(line)) => line,
+                _ => { stdout_open = false; continue; }
+            },
+            line = stderr_reader.next_line(), if stderr_open => match line {
+                Ok(Some(line)) => line,
+                _ => { stderr_open = false; continue; }
+            },
+        };
+
+        let current_file = match parse_line(&line) {
+            Some(GalleryLine::Downloaded(path)) => {
+                let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+                outcome.files.push(path);
+                name
+            }
+            Some(GalleryLine::Skipped(path)) => {
+                outcome.skipped += 1;
+                path.file_name().map(|n| n.to_string_lossy().to_string())
+            }
+            Some(GalleryLine::Error(message)) => {
+                println!("[Gallery] {}", message);
+                outcome.error = Some(message);
+                continue;
+            }
+            None => continue,
+        };
+
+        let downloaded = outcome.files.len() as u32;
+        let _ = app_handle.emit("gallery-progress", GalleryProgress {
+            id: job.id.clone(),
+            files_downloaded: downloaded,
+            files_skipped: outcome.skipped,
+            current_file: current_file.clone(),
+        });
+        let _ = app_handle.emit("download-progress", DownloadProgress {
+            speed: format!("{} files", downloaded + outcome.skipped),
+            filename: current_file,
+            ..progress_event(&job.id, "downloading", 0.0)
+        });
+    }
+
+    let status = child.wait().await.map_err(|e| format!("gallery-dl did not exit cleanly: {}", e))?;
+    let fetched_anything = !outcome.files.is_empty() || outcome.skipped > 0;
+    if !status.success() && !fetched_anything {
+        let error = outcome
+            .error
+            .take()
+            .unwrap_or_else(|| format!("gallery-dl exited with {}", status));
+        let _ = app_handle.emit("download-progress", DownloadProgress {
+            error: Some(error.clone()),
+            ..progress_event(&job.id, "failed", 0.0)
+        });
+        HEALTH_REGISTRY.set_phase(&job.id, DownloadPhase::Failed);
+        return Err(error);
+    }
+
+    // Some items failing doesn't fail the gallery; the error is passed on to the UI
+    let _ = app_handle.emit("download-progress", DownloadProgress {
+        speed: format!("{} files", outcome.files.len() as u32 + outcome.skipped),
+        error: outcome.error.clone(),
+        ..progress_event(&job.id, "completed", 100.0)
+    });
+    HEALTH_REGISTRY.set_phase(&job.id, DownloadPhase::Completed);
+    println!(
+        "[Gallery] Completed {}: {} downloaded, {} skipped",
+        job.url,
+        outcome.files.len(),
+        outcome.skipped
+    );
+    Ok(outcome)
+}
+
+#[tauri::command]
+pub async fn get_gallery_dl_status(app_handle: AppHandle) -> Result<GalleryDlStatus, String> {
+    let path = find_gallery_dl(&app_handle);
+    let version = match &path {
+        Some(path) => gallery_dl_version(path).await,
+        None => None,
+    };
+    let managed = managed_gallery_dl_path(&app_handle).ok();
+    Ok(GalleryDlStatus {
+        managed: path.is_some() && path == managed,
+        path: path.map(|p| p.to_string_lossy().to_string()),
+        version,
+    })
+}
+
+/// Install or update the app-managed gallery-dl from its latest GitHub release
+#[tauri::command]
+pub async fn install_gallery_dl(app_handle: AppHandle) -> Result<GalleryDlStatus, String> {
+    let asset_name = release_asset_name()
+        .ok_or("gallery-dl has no prebuilt binary for this platform; install it with pip instead")?;
+    let client = reqwest::Client::builder()
+        .user_agent("OwnstashDownloader/1.0")
+        .timeout(std::time::Duration::from_secs(20))
+        .build()
+        .map_err(|e| format!("Failed to initialize HTTP client: {}", e))?;
+    let release: serde_json::Value = client
+        .get(format!("https://api.github.com/repos/{}/releases/latest", GALLERY_DL_REPOSITORY))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to check latest gallery-dl release: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse gallery-dl release metadata: {}", e))?;
+    let tag = release["tag_name"]
+        .as_str()
+        .ok_or("gallery-dl release has no tag")?;
+    let expected = release["assets"]
+        .as_array()
+        .and_then(|assets| assets.iter().find(|asset| asset["name"].as_str() == Some(asset_name)))
+        .ok_or_else(|| format!("gallery-dl {} has no {} build", tag, asset_name))?;
+    let expected = crate::downloader::ExpectedBinary::from_release_asset(expected)
+        .ok_or_else(|| format!("gallery-dl {} has no published checksum for {}", tag, asset_name))?;
+    let download_url = format!(
+        "https://github.com/{}/releases/download/{}/{}",
+        GALLERY_DL_REPOSITORY, tag, asset_name
+    );
+
+    let target_path = managed_gallery_dl_path(&app_handle)?;
+    println!("[Gallery] Installing gallery-dl {} to {:?}", tag, target_path);
+    Downloader::download_binary(&download_url, &target_path, &expected).await?;
+    get_gallery_dl_status(app_handle).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("/d/instagram/someone/123_1.jpg"),
+            Some(GalleryLine::Downloaded(PathBuf::from("/d/instagram/someone/123_1.jpg")))
+        );
+        assert_eq!(
+            parse_line("# /d/imgur/abc/01.png"),
+            Some(GalleryLine::Skipped(PathBuf::from("/d/imgur/abc/01.png")))
+        );
+        assert_eq!(
+            parse_line("[instagram][error] HttpError: '401 Unauthorized'"),
+            Some(GalleryLine::Error("HttpError: '401 Unauthorized'".to_string()))
+        );
+        assert_eq!(parse_line("[urllib3.connectionpool][debug] Starting new HTTPS connection"), None);
+        assert_eq!(parse_line("   "), None);
+    }
+
+    #[test]
+    fn test_gallery_args() {
+        let job = GalleryJob {
+            id: "g1".to_string(),
+            url: "https://imgur.com/a/abc".to_string(),
+            output_dir: PathBuf::from("/d"),
+            cookies_source: None,
+            bandwidth_limit_kbps: Some(500),
+        };
+        let args = gallery_args(&job).unwrap();
+        assert_eq!(&args[..2], ["--dest", "/d"]);
+        assert!(args.windows(2).any(|w| w == ["--limit-rate", "500k"]));
+        assert_eq!(args.last().map(String::as_str), Some("https://imgur.com/a/abc"));
+    }
+}
@@ -0,0 +1,387 @@
+//! Header Profiles
+//!
+//! Browser-consistent request headers for direct HTTP downloads.
+//! Some hosts reject bare requests or an unexpected User-Agent, so SNDE, router probes
+//! and vault direct downloads send the full header set a real browser would send.
+//!
+//! Key Features:
+//! - Chrome, Edge, Firefox and Safari profiles with matching Accept / Sec-Fetch-* / client hints
+//! - A rotating profile that cycles browsers per download
+//! - Per-host rules (matching parent domains) layered over a default profile
+//! - Per-host User-Agent / extra header overrides, stored with host reputation, for CDNs
+//!   that throttle or block the browser User-Agents
+
+use crate::commands::AppState;
+use crate::database::Database;
+use crate::host_reputation::{host_and_parents, normalize_host, HostHeaderOverride};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use tauri::State;
+
+/// Settings key for the default profile
+pub const HEADER_PROFILE_SETTING: &str = "header_profile";
+
+/// Settings key for per-host rules (JSON map of domain -> profile)
+pub const HEADER_PROFILE_RULES_SETTING: &str = "header_profile_rules";
+
+/// Browser a request pretends to come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderProfile {
+    #[default]
+    Chrome,
+    Edge,
+    Firefox,
+    Safari,
+    /// Cycle through the browser profiles, one per download
+    Rotate,
+    /// Only a User-Agent, for hosts that dislike browser headers from non-browsers
+    Minimal,
+}
+
+/// Profiles used by `HeaderProfile::Rotate`
+const ROTATION: &[HeaderProfile] = &[
+    HeaderProfile::Chrome,
+    HeaderProfile::Firefox,
+    HeaderProfile::Edge,
+    HeaderProfile::Safari,
+];
+
+const CHROME_UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+const EDGE_UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0";
+const FIREFOX_UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:133.0) Gecko/20100101 Firefox/133.0";
+const SAFARI_UA: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/18.1 Safari/605.1.15";
+
+/// Default profile plus per-host rules
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HeaderProfileSettings {
+    pub default_profile: HeaderProfile,
+    pub host_rules: HashMap<String, HeaderProfile>,
+}
+
+/// Headers an override may not set, since every download manages them itself
+const RESERVED_HEADERS: &[&str] = &["host", "range", "content-length", "connection", "transfer-encoding"];
+
+lazy_static::lazy_static! {
+    static ref CURRENT_SETTINGS: RwLock<HeaderProfileSettings> = RwLock::new(HeaderProfileSettings::default());
+    /// Header overrides by domain, mirrored from host reputation
+    static ref HOST_OVERRIDES: RwLock<HashMap<String, HostHeaderOverride>> = RwLock::new(HashMap::new());
+}
+
+static ROTATION_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+impl HeaderProfile {
+    /// Resolve `Rotate` to a concrete browser
+    fn concrete(self) -> HeaderProfile {
+        match self {
+            HeaderProfile::Rotate => {
+                let index = ROTATION_INDEX.fetch_add(1, Ordering::Relaxed);
+                ROTATION[index % ROTATION.len()]
+            }
+            other => other,
+        }
+    }
+
+    /// The header set this browser sends when navigating to a file
+    fn headers(self) -> Vec<(&'static str, &'static str)> {
+        let navigation = [
+            ("sec-fetch-dest", "document"),
+            ("sec-fetch-mode", "navigate"),
+            ("sec-fetch-site", "none"),
+            ("sec-fetch-user", "?1"),
+            ("upgrade-insecure-requests", "1"),
+        ];
+        let chromium_accept = "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7";
+
+        let mut headers = match self {
+            HeaderProfile::Chrome => vec![
+                ("user-agent", CHROME_UA),
+                ("accept", chromium_accept),
+                ("accept-language", "en-US,en;q=0.9"),
+                ("sec-ch-ua", "\"Google Chrome\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\""),
+                ("sec-ch-ua-mobile", "?0"),
+                ("sec-ch-ua-platform", "\"Windows\""),
+            ],
+            HeaderProfile::Edge => vec![
+                ("user-agent", EDGE_UA),
+                ("accept", chromium_accept),
+                ("accept-language", "en-US,en;q=0.9"),
+                ("sec-ch-ua", "\"Microsoft Edge\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\""),
+                ("sec-ch-ua-mobile", "?0"),
+                ("sec-ch-ua-platform", "\"Windows\""),
+            ],
+            HeaderProfile::Firefox => vec![
+                ("user-agent", FIREFOX_UA),
+                ("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"),
+                ("accept-language", "en-US,en;q=0.5"),
+            ],
+            HeaderProfile::Safari => vec![
+                ("user-agent", SAFARI_UA),
+                ("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"),
+                ("accept-language", "en-US,en;q=0.9"),
+            ],
+            HeaderProfile::Minimal | HeaderProfile::Rotate => {
+                return vec![("user-agent", CHROME_UA)];
+            }
+        };
+        headers.extend(navigation);
+        headers
+    }
+
+    fn header_map(self) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in self.concrete().headers() {
+            map.insert(HeaderName::from_static(name), HeaderValue::from_static(value));
+        }
+        map
+    }
+}
+
+/// The entry for a host, matching parent domains (e.g. "cdn.example.com" -> "example.com")
+fn find_for_host<'a, T>(entries: &'a HashMap<String, T>, host: &str) -> Option<&'a T> {
+    host_and_parents(host).find_map(|candidate| entries.get(candidate))
+}
+
+/// The profile that applies to a host
+fn profile_for_host(settings: &HeaderProfileSettings, host: &str) -> HeaderProfile {
+    find_for_host(&settings.host_rules, host)
+        .copied()
+        .unwrap_or(settings.default_profile)
+}
+
+/// Put a host's User-Agent and extra headers over the profile's
+fn apply_override(headers: &mut HeaderMap, header_override: &HostHeaderOverride) {
+    let user_agent = header_override.user_agent.iter().map(|ua| ("user-agent", ua.as_str()));
+    let extra = header_override.headers.iter().map(|(name, value)| (name.as_str(), value.as_str()));
+    for (name, value) in user_agent.chain(extra) {
+        // Checked when saved
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            headers.insert(name, value);
+        }
+    }
+}
+
+/// Headers for a request to `url`. Call once per download so every request in it looks alike.
+pub fn headers_for_url(url: &str) -> HeaderMap {
+    let host = crate::host_reputation::extract_domain(url);
+    let profile = {
+        let settings = CURRENT_SETTINGS.read().unwrap();
+        match host.as_deref() {
+            Some(host) => profile_for_host(&settings, host),
+            None => settings.default_profile,
+        }
+    };
+    let mut headers = profile.header_map();
+    if let Some(host) = host {
+        if let Some(header_override) = find_for_host(&HOST_OVERRIDES.read().unwrap(), &host) {
+            apply_override(&mut headers, header_override);
+        }
+    }
+    headers
+}
+
+fn read_settings(db: &Database) -> HeaderProfileSettings {
+    let default_profile = db
+        .get_setting(HEADER_PROFILE_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_value(serde_json::Value::String(v)).ok())
+        .unwrap_or_default();
+    let host_rules = db
+        .get_setting(HEADER_PROFILE_RULES_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    HeaderProfileSettings {
+        default_profile,
+        host_rules,
+    }
+}
+
+/// Apply the persisted profiles at startup
+pub fn load_from_settings(db: &Database) {
+    let settings = read_settings(db);
+    *CURRENT_SETTINGS.write().unwrap() = settings;
+}
+
+/// Load the per-host header overrides; call once host reputation is set up
+pub fn load_host_overrides() {
+    let Some(manager) = crate::host_reputation::manager() else {
+        return;
+    };
+    match manager.get_header_overrides() {
+        Ok(overrides) => {
+            *HOST_OVERRIDES.write().unwrap() = overrides.into_iter().map(|o| (o.domain.clone(), o)).collect();
+        }
+        Err(e) => println!("[HeaderProfiles] Failed to load host header overrides: {}", e),
+    }
+}
+
+/// Error unless every header is valid HTTP and not one downloads manage themselves
+fn validate_override(user_agent: Option<&str>, headers: &BTreeMap<String, String>) -> Result<(), String> {
+    if let Some(user_agent) = user_agent {
+        HeaderValue::from_str(user_agent).map_err(|_| "Invalid User-Agent".to_string())?;
+    }
+    for (name, value) in headers {
+        HeaderName::from_bytes(name.as_bytes()).map_err(|_| format!("Invalid header name: {}", name))?;
+        HeaderValue::from_str(value).map_err(|_| format!("Invalid value for header {}", name))?;
+        if RESERVED_HEADERS.contains(&name.as_str()) {
+            return Err(format!("The {} header can't be overridden", name));
+        }
+        if name == "user-agent" {
+            return Err("Set the User-Agent with user_agent".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn profile_name(profile: HeaderProfile) -> String {
+    serde_json::to_value(profile)
+        .ok()
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_header_profiles() -> Result<HeaderProfileSettings, String> {
+    Ok(CURRENT_SETTINGS.read().unwrap().clone())
+}
+
+/// Set the profile used for hosts without a rule
+#[tauri::command]
+pub async fn set_header_profile(
+    state: State<'_, AppState>,
+    profile: HeaderProfile,
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(HEADER_PROFILE_SETTING, &profile_name(profile))
+        .map_err(|e| e.to_string())?;
+    CURRENT_SETTINGS.write().unwrap().default_profile = profile;
+    Ok(())
+}
+
+/// Set or remove (`profile: None`) the profile for a host
+#[tauri::command]
+pub async fn set_header_profile_rule(
+    state: State<'_, AppState>,
+    host: String,
+    profile: Option<HeaderProfile>,
+) -> Result<(), String> {
+    let host = normalize_host(&host)?;
+
+    let mut settings = CURRENT_SETTINGS.read().unwrap().clone();
+    match profile {
+        Some(profile) => {
+            settings.host_rules.insert(host, profile);
+        }
+        None => {
+            settings.host_rules.remove(&host);
+        }
+    }
+
+    let json = serde_json::to_string(&settings.host_rules).map_err(|e| e.to_string())?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(HEADER_PROFILE_RULES_SETTING, &json)
+        .map_err(|e| e.to_string())?;
+    *CURRENT_SETTINGS.write().unwrap() = settings;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_host_header_overrides() -> Result<Vec<HostHeaderOverride>, String> {
+    let mut overrides: Vec<_> = HOST_OVERRIDES.read().unwrap().values().cloned().collect();
+    overrides.sort_by(|a, b| a.domain.cmp(&b.domain));
+    Ok(overrides)
+}
+
+/// Set the User-Agent and extra headers sent to a host and its subdomains. Leaving both
+/// empty removes the override.
+#[tauri::command]
+pub async fn set_host_header_override(
+    host: String,
+    user_agent: Option<String>,
+    headers: Option<BTreeMap<String, String>>,
+) -> Result<(), String> {
+    let host = normalize_host(&host)?;
+    let user_agent = user_agent.map(|ua| ua.trim().to_string()).filter(|ua| !ua.is_empty());
+    let headers: BTreeMap<String, String> = headers
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    validate_override(user_agent.as_deref(), &headers)?;
+    let manager = crate::host_reputation::manager().ok_or("Host reputation is not available")?;
+
+    if user_agent.is_none() && headers.is_empty() {
+        manager.delete_header_override(&host)?;
+        HOST_OVERRIDES.write().unwrap().remove(&host);
+        return Ok(());
+    }
+    let header_override = HostHeaderOverride {
+        domain: host.clone(),
+        user_agent,
+        headers,
+        last_updated: chrono::Utc::now().timestamp(),
+    };
+    manager.upsert_header_override(&header_override)?;
+    HOST_OVERRIDES.write().unwrap().insert(host, header_override);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_headers_are_consistent() {
+        let chrome = HeaderProfile::Chrome.header_map();
+        assert!(chrome.get("user-agent").unwrap().to_str().unwrap().contains("Chrome/"));
+        assert!(chrome.contains_key("sec-ch-ua"));
+
+        // Firefox doesn't send client hints
+        let firefox = HeaderProfile::Firefox.header_map();
+        assert!(firefox.get("user-agent").unwrap().to_str().unwrap().contains("Firefox/"));
+        assert!(!firefox.contains_key("sec-ch-ua"));
+        assert_eq!(firefox.get("sec-fetch-mode").unwrap(), "navigate");
+    }
+
+    #[test]
+    fn test_host_rules() {
+        let mut settings = HeaderProfileSettings::default();
+        settings
+            .host_rules
+            .insert("example.com".to_string(), HeaderProfile::Firefox);
+
+        assert_eq!(profile_for_host(&settings, "dl.example.com"), HeaderProfile::Firefox);
+        assert_eq!(profile_for_host(&settings, "example.org"), HeaderProfile::Chrome);
+        assert_eq!(profile_name(HeaderProfile::Rotate), "rotate");
+    }
+
+    #[test]
+    fn test_host_override() {
+        let header_override = HostHeaderOverride {
+            domain: "example.com".to_string(),
+            user_agent: Some("Wget/1.21".to_string()),
+            headers: BTreeMap::from([("x-client".to_string(), "ownstash".to_string())]),
+            last_updated: 0,
+        };
+        let overrides = HashMap::from([("example.com".to_string(), header_override)]);
+        let found = find_for_host(&overrides, "cdn.example.com").unwrap();
+        assert!(find_for_host(&overrides, "example.org").is_none());
+
+        let mut headers = HeaderProfile::Chrome.header_map();
+        apply_override(&mut headers, found);
+        assert_eq!(headers.get("user-agent").unwrap(), "Wget/1.21");
+        assert_eq!(headers.get("x-client").unwrap(), "ownstash");
+        assert!(headers.contains_key("sec-fetch-mode"));
+
+        assert!(validate_override(None, &found.headers).is_ok());
+        assert!(validate_override(Some("bad\nagent"), &BTreeMap::new()).is_err());
+        let range = BTreeMap::from([("range".to_string(), "bytes=0-".to_string())]);
+        assert!(validate_override(None, &range).unwrap_err().contains("range"));
+    }
+}
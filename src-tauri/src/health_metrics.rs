@@ -98,6 +98,12 @@ pub enum DownloadEngine {
     SNDESafe,
     /// yt-dlp based Media Engine for streaming platforms
     MediaEngine,
+    /// aria2c over JSON-RPC for HLS streams
+    Aria2,
+    /// gallery-dl for image galleries and profiles
+    Gallery,
+    /// FTP/SFTP transfers
+    Ftp,
 }
 
 impl std::fmt::Display for DownloadEngine {
@@ -106,6 +112,9 @@ impl std::fmt::Display for DownloadEngine {
             DownloadEngine::SNDE => write!(f, "SNDE ACCELERATED"),
             DownloadEngine::SNDESafe => write!(f, "SNDE SAFE"),
             DownloadEngine::MediaEngine => write!(f, "MEDIA ENGINE"),
+            DownloadEngine::Aria2 => write!(f, "ARIA2"),
+            DownloadEngine::Gallery => write!(f, "GALLERY"),
+            DownloadEngine::Ftp => write!(f, "FTP"),
         }
     }
 }
@@ -258,6 +267,11 @@ impl HealthMetricsRegistry {
         }
     }
 
+    /// Whether a download is being tracked, i.e. an engine is running it
+    pub fn is_registered(&self, download_id: &str) -> bool {
+        self.downloads.read().map(|d| d.contains_key(download_id)).unwrap_or(false)
+    }
+
     /// Update download phase
     pub fn set_phase(&self, download_id: &str, phase: DownloadPhase) {
         if let Ok(mut downloads) = self.downloads.write() {
@@ -306,6 +320,16 @@ impl HealthMetricsRegistry {
         }
     }
 
+    /// Set how many connections the engine is running
+    pub fn set_active_connections(&self, download_id: &str, count: u8) {
+        if let Ok(mut downloads) = self.downloads.write() {
+            if let Some(health) = downloads.get_mut(download_id) {
+                health.active_connections = count;
+                health.peak_connections = health.peak_connections.max(count);
+            }
+        }
+    }
+
     /// Record an error
     pub fn record_error(&self, download_id: &str, error_message: &str, status_code: Option<u16>) {
         if let Ok(mut downloads) = self.downloads.write() {
@@ -466,5 +490,7 @@ mod tests {
         assert_eq!(format!("{}", DownloadEngine::SNDE), "SNDE ACCELERATED");
         assert_eq!(format!("{}", DownloadEngine::SNDESafe), "SNDE SAFE");
         assert_eq!(format!("{}", DownloadEngine::MediaEngine), "MEDIA ENGINE");
+        assert_eq!(format!("{}", DownloadEngine::Aria2), "ARIA2");
+        assert_eq!(format!("{}", DownloadEngine::Gallery), "GALLERY");
     }
 }
@@ -0,0 +1,320 @@
+//! Post-Processing Hooks
+//!
+//! User-configured commands that run after a download completes, e.g. custom tagging,
+//! moving files to a NAS or refreshing a Plex library.
+//!
+//! Key Features:
+//! - Commands are spawned directly (no shell), with placeholders substituted per argument
+//! - Download details are also passed as `OWNSTASH_*` environment variables
+//! - Each hook has a timeout; stdout/stderr are captured and reported via `post-download-hook`
+
+use crate::commands::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+/// Settings key holding the JSON encoded list of hooks
+pub const HOOKS_SETTING: &str = "post_download_hooks";
+
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 60;
+const MAX_HOOK_TIMEOUT_SECS: u64 = 3600;
+
+/// Captured output kept per stream
+const MAX_CAPTURED_OUTPUT: usize = 8 * 1024;
+
+/// Placeholders that can appear in hook arguments
+const PLACEHOLDERS: &[&str] = &["{file}", "{dir}", "{name}", "{url}", "{id}"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PostDownloadHook {
+    pub name: String,
+    /// Program or script to run
+    pub command: String,
+    /// Arguments; `{file}`, `{dir}`, `{name}`, `{url}` and `{id}` are substituted
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl PostDownloadHook {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Hook name is required".to_string());
+        }
+        if self.command.trim().is_empty() {
+            return Err(format!("Hook '{}' has no command", self.name));
+        }
+        if self.timeout_secs.is_some_and(|t| t == 0 || t > MAX_HOOK_TIMEOUT_SECS) {
+            return Err(format!(
+                "Hook '{}' timeout must be between 1 and {} seconds",
+                self.name, MAX_HOOK_TIMEOUT_SECS
+            ));
+        }
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS))
+    }
+}
+
+/// What a hook is run for
+#[derive(Debug, Clone)]
+pub struct HookContext {
+    pub download_id: String,
+    pub url: String,
+    pub file_path: String,
+}
+
+impl HookContext {
+    fn values(&self) -> [(&'static str, String); 5] {
+        let path = Path::new(&self.file_path);
+        let dir = path
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        [
+            ("{file}", self.file_path.clone()),
+            ("{dir}", dir),
+            ("{name}", name),
+            ("{url}", self.url.clone()),
+            ("{id}", self.download_id.clone()),
+        ]
+    }
+}
+
+/// Outcome of one hook run, emitted as `post-download-hook`
+#[derive(Debug, Clone, Serialize)]
+pub struct HookResult {
+    pub download_id: String,
+    pub hook_name: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+fn substitute(arg: &str, values: &[(&'static str, String)]) -> String {
+    let mut result = arg.to_string();
+    for (placeholder, value) in values {
+        result = result.replace(placeholder, value);
+    }
+    result
+}
+
+fn capture(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    if text.len() <= MAX_CAPTURED_OUTPUT {
+        return text.to_string();
+    }
+    let mut end = MAX_CAPTURED_OUTPUT;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n[output truncated]", &text[..end])
+}
+
+async fn run_hook(hook: &PostDownloadHook, context: &HookContext) -> HookResult {
+    let values = context.values();
+    let mut result = HookResult {
+        download_id: context.download_id.clone(),
+        hook_name: hook.name.clone(),
+        success: false,
+        exit_code: None,
+        timed_out: false,
+        stdout: String::new(),
+        stderr: String::new(),
+        duration_ms: 0,
+        error: None,
+    };
+
+    let mut cmd = match crate::exec_guard::user_tokio_command(hook.command.trim()) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            result.error = Some(format!("Failed to start hook: {}", e));
+            return result;
+        }
+    };
+    cmd.args(hook.args.iter().map(|arg| substitute(arg, &values)))
+        .env("OWNSTASH_FILE", &context.file_path)
+        .env("OWNSTASH_URL", &context.url)
+        .env("OWNSTASH_DOWNLOAD_ID", &context.download_id)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(dir) = Path::new(&context.file_path).parent() {
+        if dir.is_dir() {
+            cmd.current_dir(dir);
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let started = Instant::now();
+    let outcome = match cmd.spawn() {
+        Ok(child) => tokio::time::timeout(hook.timeout(), child.wait_with_output()).await,
+        Err(e) => {
+            result.error = Some(format!("Failed to start hook: {}", e));
+            return result;
+        }
+    };
+    result.duration_ms = started.elapsed().as_millis() as u64;
+
+    match outcome {
+        Ok(Ok(output)) => {
+            result.success = output.status.success();
+            result.exit_code = output.status.code();
+            result.stdout = capture(&output.stdout);
+            result.stderr = capture(&output.stderr);
+        }
+        Ok(Err(e)) => result.error = Some(format!("Hook failed: {}", e)),
+        Err(_) => {
+            // Dropping the future kills the child (kill_on_drop)
+            result.timed_out = true;
+            result.error = Some(format!("Hook timed out after {}s", hook.timeout().as_secs()));
+        }
+    }
+    result
+}
+
+fn load_hooks(app_handle: &AppHandle) -> Vec<PostDownloadHook> {
+    crate::commands::read_setting(app_handle, HOOKS_SETTING)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Run every enabled hook for a finished download, in order, in the background
+pub fn run_post_download_hooks(app_handle: &AppHandle, context: HookContext) {
+    let hooks: Vec<PostDownloadHook> = load_hooks(app_handle)
+        .into_iter()
+        .filter(|hook| hook.enabled)
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+
+    let app = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        crate::idle::wait_for_heavy_job(&app, "post-download hooks", Some(&context.download_id)).await;
+        for hook in &hooks {
+            println!("[Hooks] Running '{}' for {}", hook.name, context.file_path);
+            let result = run_hook(hook, &context).await;
+            if !result.success {
+                println!(
+                    "[Hooks] '{}' failed (exit {:?}): {}",
+                    hook.name,
+                    result.exit_code,
+                    result.error.as_deref().unwrap_or(result.stderr.trim())
+                );
+            }
+            let _ = app.emit("post-download-hook", &result);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_post_download_hooks(state: State<'_, AppState>) -> Result<Vec<PostDownloadHook>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let hooks = db.get_setting(HOOKS_SETTING).map_err(|e| e.to_string())?;
+    Ok(hooks.and_then(|json| serde_json::from_str(&json).ok()).unwrap_or_default())
+}
+
+/// Replace the configured hooks
+#[tauri::command]
+pub async fn set_post_download_hooks(
+    state: State<'_, AppState>,
+    hooks: Vec<PostDownloadHook>,
+) -> Result<(), String> {
+    for hook in &hooks {
+        hook.validate()?;
+    }
+
+    let json = serde_json::to_string(&hooks).map_err(|e| format!("Failed to serialize hooks: {}", e))?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(HOOKS_SETTING, &json).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Run a hook once against an existing file so users can check it before saving
+#[tauri::command]
+pub async fn test_post_download_hook(hook: PostDownloadHook, file_path: String) -> Result<HookResult, String> {
+    hook.validate()?;
+    let context = HookContext {
+        download_id: "test".to_string(),
+        url: String::new(),
+        file_path,
+    };
+    Ok(run_hook(&hook, &context).await)
+}
+
+/// Placeholders the UI can offer when editing hook arguments
+#[tauri::command]
+pub async fn get_hook_placeholders() -> Result<Vec<String>, String> {
+    Ok(PLACEHOLDERS.iter().map(|p| p.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(timeout_secs: Option<u64>) -> PostDownloadHook {
+        PostDownloadHook {
+            name: "tag".to_string(),
+            command: "tagger".to_string(),
+            args: vec!["--file={file}".to_string(), "{name}".to_string()],
+            enabled: true,
+            timeout_secs,
+        }
+    }
+
+    #[test]
+    fn test_substitute_placeholders() {
+        let context = HookContext {
+            download_id: "abc".to_string(),
+            url: "https://example.com/v".to_string(),
+            file_path: "/downloads/clip.mp4".to_string(),
+        };
+        let values = context.values();
+
+        assert_eq!(substitute("--file={file}", &values), "--file=/downloads/clip.mp4");
+        assert_eq!(substitute("{dir}|{name}|{id}", &values), "/downloads|clip.mp4|abc");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(hook(None).validate().is_ok());
+        assert!(hook(Some(0)).validate().is_err());
+        assert!(hook(Some(MAX_HOOK_TIMEOUT_SECS + 1)).validate().is_err());
+
+        let mut unnamed = hook(None);
+        unnamed.name = " ".to_string();
+        assert!(unnamed.validate().is_err());
+    }
+
+    #[test]
+    fn test_capture_truncates() {
+        let long = "a".repeat(MAX_CAPTURED_OUTPUT + 10);
+        assert!(capture(long.as_bytes()).ends_with("[output truncated]"));
+        assert_eq!(capture(b"ok"), "ok");
+    }
+}
@@ -8,12 +8,40 @@
 //! - Tracks stable connection counts for each domain
 //! - Remembers favored protocols (HTTP/1.1, HTTP/2, HTTP/3)
 //! - Stores health scores for intelligent preflight decisions
+//! - Remembers the connection count SNDE's ramp-up found fastest
+//! - Keeps per-host User-Agent / header overrides for CDNs that block the default browser
+//! - Host matching shared by every per-host setting: `normalize_host` for user input,
+//!   `host_and_parents` so a rule for "example.com" also covers "cdn.example.com"
 
 use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
 use chrono::Utc;
 
+lazy_static::lazy_static! {
+    /// Shared manager on its own connection to the app database, set up by `init`
+    static ref MANAGER: RwLock<Option<Arc<HostReputationManager>>> = RwLock::new(None);
+}
+
+/// Open the shared manager on the app database; call once at startup
+pub fn init(db_path: &Path) -> Result<(), String> {
+    let conn = Connection::open(db_path).map_err(|e| format!("Failed to open database: {}", e))?;
+    // The main connection writes to the same file
+    conn.busy_timeout(std::time::Duration::from_secs(5))
+        .map_err(|e| format!("Failed to configure database: {}", e))?;
+    let manager = HostReputationManager::new(Arc::new(Mutex::new(conn)));
+    manager.initialize_table()?;
+    *MANAGER.write().unwrap() = Some(Arc::new(manager));
+    Ok(())
+}
+
+/// The shared manager, once `init` has run
+pub fn manager() -> Option<Arc<HostReputationManager>> {
+    MANAGER.read().unwrap().clone()
+}
+
 /// Host reputation record stored in the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HostReputation {
@@ -53,6 +81,18 @@ impl Default for HostReputation {
     }
 }
 
+/// User-Agent and headers sent to a host (and its subdomains) on top of its header profile
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostHeaderOverride {
+    pub domain: String,
+    /// Replaces the profile's User-Agent
+    pub user_agent: Option<String>,
+    /// Added to the profile's headers, replacing any of the same name
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub last_updated: i64,
+}
+
 /// Host Reputation Manager handles all database operations for host reputation
 pub struct HostReputationManager {
     conn: Arc<Mutex<Connection>>,
@@ -83,6 +123,17 @@ impl HostReputationManager {
             [],
         ).map_err(|e| format!("Failed to create table: {}", e))?;
 
+        // Set by the user, so kept out of the stale record cleanup
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS host_header_overrides (
+                domain TEXT PRIMARY KEY,
+                user_agent TEXT,
+                headers TEXT NOT NULL DEFAULT '{}',
+                last_updated INTEGER NOT NULL
+            )",
+            [],
+        ).map_err(|e| format!("Failed to create table: {}", e))?;
+
         // Create index for faster domain lookups
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_host_reputation_domain ON host_reputation(domain)",
@@ -128,6 +179,22 @@ impl HostReputationManager {
         }
     }
 
+    /// Reputation for a domain only if there's a record of it
+    pub fn find_reputation(&self, domain: &str) -> Result<Option<HostReputation>, String> {
+        let known: i64 = {
+            let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+            conn.query_row(
+                "SELECT COUNT(*) FROM host_reputation WHERE domain = ?1",
+                params![domain],
+                |row| row.get(0),
+            ).map_err(|e| format!("Database error: {}", e))?
+        };
+        if known == 0 {
+            return Ok(None);
+        }
+        self.get_reputation(domain).map(Some)
+    }
+
     /// Update or insert host reputation
     pub fn upsert_reputation(&self, reputation: &HostReputation) -> Result<(), String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
@@ -224,6 +291,61 @@ impl HostReputationManager {
         self.upsert_reputation(&reputation)
     }
 
+    /// Record the connection count a ramp-up found fastest for this host
+    pub fn record_optimal_connections(&self, domain: &str, connections: u8) -> Result<(), String> {
+        let mut reputation = self.get_reputation(domain)?;
+        reputation.max_stable_conns = connections.max(1);
+        reputation.last_updated = Utc::now().timestamp();
+        self.upsert_reputation(&reputation)
+    }
+
+    /// Update or insert the header override for a domain
+    pub fn upsert_header_override(&self, header_override: &HostHeaderOverride) -> Result<(), String> {
+        let headers = serde_json::to_string(&header_override.headers)
+            .map_err(|e| format!("Failed to serialize headers: {}", e))?;
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        conn.execute(
+            "INSERT INTO host_header_overrides (domain, user_agent, headers, last_updated)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(domain) DO UPDATE SET
+                user_agent = excluded.user_agent,
+                headers = excluded.headers,
+                last_updated = excluded.last_updated",
+            params![header_override.domain, header_override.user_agent, headers, header_override.last_updated],
+        ).map_err(|e| format!("Failed to save header override: {}", e))?;
+        Ok(())
+    }
+
+    /// Remove the header override for a domain; false if there was none
+    pub fn delete_header_override(&self, domain: &str) -> Result<bool, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let deleted = conn.execute("DELETE FROM host_header_overrides WHERE domain = ?1", params![domain])
+            .map_err(|e| format!("Delete error: {}", e))?;
+        Ok(deleted > 0)
+    }
+
+    /// All header overrides
+    pub fn get_header_overrides(&self) -> Result<Vec<HostHeaderOverride>, String> {
+        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        let mut stmt = conn.prepare(
+            "SELECT domain, user_agent, headers, last_updated FROM host_header_overrides ORDER BY domain"
+        ).map_err(|e| format!("Prepare error: {}", e))?;
+
+        let overrides = stmt.query_map([], |row| {
+            let headers: String = row.get(2)?;
+            Ok(HostHeaderOverride {
+                domain: row.get(0)?,
+                user_agent: row.get(1)?,
+                headers: serde_json::from_str(&headers).unwrap_or_default(),
+                last_updated: row.get(3)?,
+            })
+        }).map_err(|e| format!("Query error: {}", e))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+        Ok(overrides)
+    }
+
     /// Get all host reputations (for debugging/diagnostics)
     pub fn get_all_reputations(&self) -> Result<Vec<HostReputation>, String> {
         let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
@@ -275,6 +397,20 @@ pub fn extract_domain(url: &str) -> Option<String> {
         .and_then(|u| u.host_str().map(|s| s.to_lowercase()))
 }
 
+/// Lower-cased host from user input, without a leading "*."
+pub fn normalize_host(host: &str) -> Result<String, String> {
+    let host = host.trim().trim_start_matches("*.").to_lowercase();
+    if host.is_empty() || host.contains(|c: char| c == '/' || c.is_whitespace()) {
+        return Err("Invalid host".to_string());
+    }
+    Ok(host)
+}
+
+/// The host, then each parent domain ("cdn.example.com", "example.com", "com")
+pub fn host_and_parents(host: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(host), |candidate| candidate.split_once('.').map(|(_, parent)| parent))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,7 +427,52 @@ mod tests {
         );
         assert_eq!(extract_domain("invalid-url"), None);
     }
+
+    #[test]
+    fn test_host_matching_helpers() {
+        assert_eq!(normalize_host(" *.CDN.Example.com ").unwrap(), "cdn.example.com");
+        assert!(normalize_host("").is_err());
+        assert!(normalize_host("example.com/path").is_err());
+        assert!(normalize_host("exa mple.com").is_err());
+
+        let candidates: Vec<&str> = host_and_parents("a.cdn.example.com").collect();
+        assert_eq!(candidates, ["a.cdn.example.com", "cdn.example.com", "example.com", "com"]);
+    }
     
+    #[test]
+    fn test_record_optimal_connections() {
+        let manager = HostReputationManager::new(Arc::new(Mutex::new(Connection::open_in_memory().unwrap())));
+        manager.initialize_table().unwrap();
+
+        assert!(manager.find_reputation("cdn.example.com").unwrap().is_none());
+        manager.record_optimal_connections("cdn.example.com", 6).unwrap();
+        assert_eq!(manager.find_reputation("cdn.example.com").unwrap().unwrap().max_stable_conns, 6);
+        manager.record_optimal_connections("cdn.example.com", 3).unwrap();
+        assert_eq!(manager.get_reputation("cdn.example.com").unwrap().max_stable_conns, 3);
+    }
+
+    #[test]
+    fn test_header_overrides() {
+        let manager = HostReputationManager::new(Arc::new(Mutex::new(Connection::open_in_memory().unwrap())));
+        manager.initialize_table().unwrap();
+
+        let mut header_override = HostHeaderOverride {
+            domain: "example.com".to_string(),
+            user_agent: Some("curl/8.5.0".to_string()),
+            headers: BTreeMap::from([("referer".to_string(), "https://example.com/".to_string())]),
+            last_updated: 0,
+        };
+        manager.upsert_header_override(&header_override).unwrap();
+        header_override.user_agent = None;
+        manager.upsert_header_override(&header_override).unwrap();
+        assert_eq!(manager.get_header_overrides().unwrap(), [header_override]);
+
+        // Not reputation data, so cleanup leaves it alone
+        manager.cleanup_stale_records().unwrap();
+        assert!(manager.delete_header_override("example.com").unwrap());
+        assert!(!manager.delete_header_override("example.com").unwrap());
+    }
+
     #[test]
     fn test_default_reputation() {
         let rep = HostReputation::default();
@@ -0,0 +1,328 @@
+//! Idle Policy
+//!
+//! Heavy post-processing (device transcodes, audio extraction, post-download hooks) competes
+//! with whatever the user is doing. The idle policy holds those jobs back until the machine
+//! is idle or the screen is locked.
+//!
+//! Key Features:
+//! - OS idle time: `GetLastInputInfo` on Windows, `HIDIdleTime` on macOS, xprintidle on Linux
+//! - Screen lock detection on Windows and on Linux (logind `LockedHint`)
+//! - Policies: run always, only when idle, only when the screen is locked
+//! - When idle state can't be determined, jobs run rather than wait forever
+//! - Waiting jobs are announced via `heavy-job-deferred`
+
+use crate::commands::AppState;
+use crate::database::Database;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+/// Settings key holding the JSON encoded `IdlePolicy`
+pub const IDLE_POLICY_SETTING: &str = "heavy_job_idle_policy";
+
+/// How often a deferred job re-checks the idle state
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+const MAX_IDLE_MINUTES: u32 = 240;
+
+/// When heavy jobs may run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeavyJobMode {
+    #[default]
+    Always,
+    WhenIdle,
+    WhenLocked,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdlePolicy {
+    #[serde(default)]
+    pub mode: HeavyJobMode,
+    /// Minutes without input that count as idle
+    #[serde(default = "default_idle_minutes")]
+    pub idle_minutes: u32,
+    /// Run anyway after waiting this long; None waits as long as it takes
+    #[serde(default)]
+    pub max_wait_minutes: Option<u32>,
+}
+
+fn default_idle_minutes() -> u32 {
+    5
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        Self {
+            mode: HeavyJobMode::Always,
+            idle_minutes: default_idle_minutes(),
+            max_wait_minutes: None,
+        }
+    }
+}
+
+impl IdlePolicy {
+    fn validate(&self) -> Result<(), String> {
+        if self.idle_minutes == 0 || self.idle_minutes > MAX_IDLE_MINUTES {
+            return Err(format!("Idle time must be between 1 and {} minutes", MAX_IDLE_MINUTES));
+        }
+        if self.max_wait_minutes == Some(0) {
+            return Err("Maximum wait must be at least 1 minute".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether a heavy job may start given the current idle state. Unknown idle time
+    /// doesn't hold jobs back; with an unknown lock state, `WhenLocked` falls back to idle time.
+    fn allows(&self, status: &IdleStatus) -> bool {
+        let idle_enough = status
+            .idle_secs
+            .map(|secs| secs >= u64::from(self.idle_minutes) * 60);
+        match self.mode {
+            HeavyJobMode::Always => true,
+            HeavyJobMode::WhenIdle => status.screen_locked == Some(true) || idle_enough.unwrap_or(true),
+            HeavyJobMode::WhenLocked => match status.screen_locked {
+                Some(locked) => locked,
+                None => idle_enough.unwrap_or(true),
+            },
+        }
+    }
+}
+
+/// What the OS reports; None where it can't tell
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IdleStatus {
+    pub idle_secs: Option<u64>,
+    pub screen_locked: Option<bool>,
+}
+
+/// Emitted while a heavy job waits for the idle policy
+#[derive(Debug, Clone, Serialize)]
+pub struct HeavyJobDeferred {
+    pub job: String,
+    pub download_id: Option<String>,
+    pub mode: HeavyJobMode,
+}
+
+lazy_static::lazy_static! {
+    static ref IDLE_POLICY: RwLock<IdlePolicy> = RwLock::new(IdlePolicy::default());
+}
+
+/// Apply the persisted idle policy at startup
+pub fn load_from_settings(db: &Database) {
+    let policy = db
+        .get_setting(IDLE_POLICY_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    *IDLE_POLICY.write().unwrap() = policy;
+}
+
+#[cfg(target_os = "windows")]
+fn idle_secs() -> Option<u64> {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    unsafe {
+        if !GetLastInputInfo(&mut info).as_bool() {
+            return None;
+        }
+        Some(u64::from(GetTickCount().wrapping_sub(info.dwTime)) / 1000)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn screen_locked() -> Option<bool> {
+    use windows::Win32::Foundation::BOOL;
+    use windows::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, SwitchDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_SWITCHDESKTOP,
+    };
+
+    // The secure desktop shown while locked can't be switched to
+    unsafe {
+        let Ok(desktop) = OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), BOOL::from(false), DESKTOP_SWITCHDESKTOP) else {
+            return Some(true);
+        };
+        let switchable = SwitchDesktop(desktop).is_ok();
+        let _ = CloseDesktop(desktop);
+        Some(!switchable)
+    }
+}
+
+/// Nanoseconds in `"HIDIdleTime" = 123` from `ioreg -c IOHIDSystem`
+#[cfg(any(target_os = "macos", test))]
+fn parse_hid_idle_time(output: &str) -> Option<u64> {
+    output
+        .lines()
+        .find(|line| line.contains("\"HIDIdleTime\""))
+        .and_then(|line| line.split('=').nth(1))
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(|nanos| nanos / 1_000_000_000)
+}
+
+#[cfg(target_os = "macos")]
+fn idle_secs() -> Option<u64> {
+    let output = crate::exec_guard::command("ioreg")
+        .ok()?
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    parse_hid_idle_time(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(target_os = "macos")]
+fn screen_locked() -> Option<bool> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn idle_secs() -> Option<u64> {
+    let xprintidle = which::which("xprintidle").ok()?;
+    let output = crate::exec_guard::command(xprintidle).ok()?.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(|millis| millis / 1000)
+}
+
+#[cfg(target_os = "linux")]
+fn screen_locked() -> Option<bool> {
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    let output = crate::exec_guard::command("loginctl")
+        .ok()?
+        .args(["show-session", &session, "-p", "LockedHint", "--value"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn idle_secs() -> Option<u64> {
+    None
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn screen_locked() -> Option<bool> {
+    None
+}
+
+/// Query the OS; spawns helper processes on some platforms, so run it off the async runtime
+async fn current_status() -> IdleStatus {
+    tokio::task::spawn_blocking(|| IdleStatus {
+        idle_secs: idle_secs(),
+        screen_locked: screen_locked(),
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Wait until the idle policy lets a heavy job run. Returns immediately under `Always`.
+pub async fn wait_for_heavy_job(app_handle: &AppHandle, job: &str, download_id: Option<&str>) {
+    let started = Instant::now();
+    let mut announced = false;
+    loop {
+        let policy = IDLE_POLICY.read().unwrap().clone();
+        if policy.mode == HeavyJobMode::Always || policy.allows(&current_status().await) {
+            break;
+        }
+        if policy
+            .max_wait_minutes
+            .is_some_and(|minutes| started.elapsed() >= Duration::from_secs(u64::from(minutes) * 60))
+        {
+            println!("[Idle] Waited too long, running {} anyway", job);
+            break;
+        }
+        if !announced {
+            println!("[Idle] Deferring {} until {:?}", job, policy.mode);
+            let _ = app_handle.emit("heavy-job-deferred", HeavyJobDeferred {
+                job: job.to_string(),
+                download_id: download_id.map(|id| id.to_string()),
+                mode: policy.mode,
+            });
+            announced = true;
+        }
+        tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+    }
+}
+
+#[tauri::command]
+pub async fn get_idle_policy() -> Result<IdlePolicy, String> {
+    Ok(IDLE_POLICY.read().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn set_idle_policy(state: State<'_, AppState>, policy: IdlePolicy) -> Result<(), String> {
+    policy.validate()?;
+
+    let json = serde_json::to_string(&policy).map_err(|e| format!("Failed to serialize idle policy: {}", e))?;
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(IDLE_POLICY_SETTING, &json).map_err(|e| e.to_string())?;
+    }
+    *IDLE_POLICY.write().unwrap() = policy;
+    Ok(())
+}
+
+/// Current idle time and lock state, for showing why jobs are waiting
+#[tauri::command]
+pub async fn get_idle_status() -> Result<IdleStatus, String> {
+    Ok(current_status().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(mode: HeavyJobMode) -> IdlePolicy {
+        IdlePolicy { mode, ..Default::default() }
+    }
+
+    fn status(idle_secs: Option<u64>, screen_locked: Option<bool>) -> IdleStatus {
+        IdleStatus { idle_secs, screen_locked }
+    }
+
+    #[test]
+    fn test_policy_allows() {
+        let idle = policy(HeavyJobMode::WhenIdle);
+        assert!(!idle.allows(&status(Some(60), Some(false))));
+        assert!(idle.allows(&status(Some(300), Some(false))));
+        assert!(idle.allows(&status(Some(10), Some(true))));
+        assert!(idle.allows(&status(None, None)));
+
+        let locked = policy(HeavyJobMode::WhenLocked);
+        assert!(!locked.allows(&status(Some(3600), Some(false))));
+        assert!(locked.allows(&status(Some(0), Some(true))));
+        assert!(!locked.allows(&status(Some(60), None)));
+        assert!(locked.allows(&status(Some(600), None)));
+
+        assert!(policy(HeavyJobMode::Always).allows(&status(Some(0), Some(false))));
+    }
+
+    #[test]
+    fn test_validate_and_parse() {
+        assert!(IdlePolicy { idle_minutes: 0, ..Default::default() }.validate().is_err());
+        assert!(IdlePolicy { max_wait_minutes: Some(0), ..Default::default() }.validate().is_err());
+        assert!(IdlePolicy::default().validate().is_ok());
+
+        let ioreg = "    | |   \"HIDIdleTime\" = 125000000000\n    | |   \"HIDKeyboardModifierFlags\" = 0";
+        assert_eq!(parse_hid_idle_time(ioreg), Some(125));
+        assert_eq!(parse_hid_idle_time("nothing"), None);
+    }
+}
@@ -1,23 +1,66 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod bandwidth;
 mod commands;
 mod database;
+mod download_card;
+mod download_summary;
 mod download_router;
 mod downloader;
+mod exec_guard;
 mod extension_server;
+mod extractor_index;
+mod extractor_options;
+mod ffmpeg;
+mod folder_stats;
+mod footprint;
+mod header_profiles;
 mod health_metrics;
+mod hooks;
 mod host_reputation;
+mod idle;
 mod scheduler;
 mod snde;
 mod spotify_downloader;
 mod updater;
 mod watchdog;
 mod media_server;
+mod proxy;
 mod vault;
 mod vault_download;
+mod vault_consent;
+mod warc;
+mod aria2;
+mod gallery;
+mod capabilities;
+mod relay;
+mod organizer;
+mod coalesce;
+mod saved;
+mod checksum;
+mod cover_art;
+mod mirrors;
+mod request_headers;
+mod split_archive;
+mod takeover;
+mod outbound;
 mod native_integration;
+mod presets;
+mod subscriptions;
 mod secure_storage;
+mod ftp;
+mod staging;
+mod crash_resume;
+mod stale_downloads;
+mod search;
+mod download_events;
+mod download_lifecycle;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench_support;
 
 use commands::AppState;
 use database::Database;
@@ -118,6 +161,7 @@ pub fn run() {
                 }
                 // Also handle download deep links
                 if let Some(download_url) = parse_deep_link(arg) {
+                    request_headers::stash(&download_url, deep_link_headers(arg));
                     let _ = app.emit("extension-download-request", &download_url);
                 }
             }
@@ -135,10 +179,27 @@ pub fn run() {
             let binaries_dir = app_data_dir.join("binaries");
             std::fs::create_dir_all(&binaries_dir).ok();
 
+            // Only hash-verified binaries may run from there
+            exec_guard::init(&binaries_dir, app.path().resource_dir().ok().as_deref());
+
             // Initialize database
+            let db_path = app_data_dir.join("db.sqlite");
             let db = Database::new(app_data_dir)
                 .expect("Failed to initialize database");
 
+            // Host reputation keeps its own connection to the same database
+            if let Err(e) = host_reputation::init(&db_path) {
+                println!("[HostReputation] {}", e);
+            }
+
+            // Restore per-host User-Agent / header overrides, kept with host reputation
+            header_profiles::load_host_overrides();
+
+            // Tell the UI if a corrupted database had to be rebuilt
+            if let Some(report) = db.recovery_report() {
+                let _ = app_handle.emit("database-recovered", &report);
+            }
+
             // Store in app state
             app.manage(AppState { db: Mutex::new(db) });
 
@@ -151,6 +212,57 @@ pub fn run() {
                     let _ = db.save_setting("autostart_initialized", "true");
                     let _ = db.save_setting("autostart_enabled", "true");
                 }
+
+                // Restore the global speed limit
+                bandwidth::load_from_settings(&db);
+
+                // Restore proxy settings
+                proxy::load_from_settings(&app_handle, &db);
+
+                // Restore geo-bypass / extractor workarounds
+                extractor_options::load_from_settings(&db);
+
+                // Restore request header profiles
+                header_profiles::load_from_settings(&db);
+
+                // Restore sites pinned to the Media Engine
+                download_router::load_from_settings(&db);
+
+                // Restore notification preferences and quiet hours
+                native_integration::load_from_settings(&db);
+
+                // Restore when heavy post-processing may run
+                idle::load_from_settings(&db);
+
+                // Restore whether HLS goes to aria2c
+                aria2::load_from_settings(&db);
+
+                // Restore configured download relays
+                relay::load_from_settings(&app_handle, &db);
+
+                // Restore auto-organization rules
+                organizer::load_from_settings(&db);
+
+                // Restore offline mode before anything calls out
+                outbound::load_from_settings(&db);
+
+                // Restore the screen reader summary interval
+                download_summary::load_from_settings(&db);
+
+                // Restore the energy / carbon estimator factors
+                footprint::load_from_settings(&db);
+
+                // Restore the browser download takeover threshold
+                takeover::load_from_settings(&db);
+
+                // Restore where temp files are staged
+                staging::load_from_settings(&db);
+
+                // Restore which search providers are enabled
+                search::load_from_settings(&db);
+
+                // Restore the media tool paths trusted outside the app bundle
+                exec_guard::load_from_settings(&db);
             }
 
             // Check if started with --minimized flag
@@ -169,9 +281,45 @@ pub fn run() {
             // Initialize native integration (taskbar progress, notifications)
             native_integration::init(&app_handle);
 
+            // Keep the dock badge / taskbar overlay in step with pending and failed downloads
+            native_integration::install_badge(&app_handle);
+
             // Start the media server for video playback
             media_server::start_media_server(app_handle.clone());
 
+            // Purge expired vault trash items in the background
+            vault::start_trash_purge_task(app_handle.clone());
+
+            // Sync subscribed channels and playlists on their intervals
+            subscriptions::start_subscription_sync_task(app_handle.clone());
+
+            // Forward progress of coalesced duplicate downloads
+            coalesce::install(&app_handle);
+
+            // Periodic plain-language progress summaries for screen readers
+            download_summary::install(&app_handle);
+
+            // Estimate energy / carbon for finished downloads when enabled
+            footprint::install(&app_handle);
+
+            // Report the outcome of downloads taken over from the browser
+            takeover::install(&app_handle);
+
+            // Watch running downloads and step in when they stall or get throttled
+            watchdog::install(&app_handle);
+
+            // Offer to resume SNDE downloads a crash left half-done
+            crash_resume::install(&app_handle);
+
+            // Fail downloads left on "downloading" that no engine is running
+            stale_downloads::install(&app_handle);
+
+            // Coarse download lifecycle log streamed at /events on the extension server
+            download_events::install(&app_handle);
+
+            // Index yt-dlp's supported sites (only re-runs after yt-dlp changes)
+            extractor_index::refresh_in_background(app_handle.clone());
+
             // Handle deep links from Chrome extension (for installed app)
             #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
             {
@@ -201,6 +349,7 @@ pub fn run() {
                 // Parse the URL and extract the download URL
                 if let Some(download_url) = parse_deep_link(payload) {
                     println!("[DeepLink] Parsed download URL: {}", download_url);
+                    request_headers::stash(&download_url, deep_link_headers(payload));
 
                     background_mode_for_deep_link.store(false, Ordering::SeqCst);
                     show_main_window(&handle);
@@ -276,6 +425,14 @@ pub fn run() {
             commands::update_download_status,
             commands::delete_download,
             commands::clear_downloads,
+            commands::get_database_recovery_report,
+            commands::get_intervention_history,
+            download_card::export_download_card,
+            // Download archive commands
+            commands::get_download_archive,
+            commands::get_download_archive_count,
+            commands::remove_download_archive_entry,
+            commands::clear_download_archive,
             // Search history commands
             commands::add_search,
             commands::get_search_history,
@@ -292,46 +449,138 @@ pub fn run() {
             // Use media_server's robust matching instead of commands' basic one
             media_server::find_best_media_match,
             media_server::get_media_stream_url,
+            media_server::get_media_chapters,
+            media_server::restart_media_server,
+            media_server::get_media_server_status,
+            media_server::get_prebuffer_stream_url,
+            extension_server::set_extension_server_enabled,
+            extension_server::get_extension_server_status,
             commands::transcode_for_playback,
+            commands::remux_media,
+            commands::extract_audio,
             // Downloader commands
             downloader::check_yt_dlp,
             downloader::update_yt_dlp,
             downloader::get_media_info,
+            downloader::get_playlist_entries,
+            downloader::cancel_media_info,
             downloader::probe_direct_file,
             downloader::start_download,
             downloader::cancel_download,
+            downloader::snde_pause,
+            downloader::snde_resume,
             downloader::get_supported_platforms,
+            downloader::detect_installed_browsers,
+            downloader::check_filename_template,
+            downloader::preview_output_path,
+            downloader::list_available_subtitles,
             downloader::get_default_download_path,
             downloader::get_download_folder_size,
+            downloader::get_free_disk_space,
+            folder_stats::get_download_folder_stats,
+            folder_stats::cancel_scan,
+            // Bandwidth commands
+            bandwidth::set_bandwidth_limit,
+            bandwidth::get_bandwidth_limit,
+            // Header profile commands
+            header_profiles::get_header_profiles,
+            header_profiles::set_header_profile,
+            header_profiles::set_header_profile_rule,
+            header_profiles::get_host_header_overrides,
+            header_profiles::set_host_header_override,
+            // Post-download hook commands
+            hooks::get_post_download_hooks,
+            hooks::set_post_download_hooks,
+            hooks::test_post_download_hook,
+            hooks::get_hook_placeholders,
+            // Router commands
+            download_router::get_media_engine_rules,
+            download_router::set_media_engine_rule,
+            // SNDE commands
+            snde::get_snde_limits,
+            snde::set_snde_limits,
+            snde::get_snde_host_overrides,
+            snde::set_snde_host_override,
+            snde::snde_speed_test,
+            // FFmpeg commands
+            ffmpeg::ensure_ffmpeg,
+            ffmpeg::check_ffmpeg_status,
+            // Preset commands
+            presets::save_preset,
+            presets::list_presets,
+            presets::apply_preset,
+            presets::delete_preset,
+            presets::set_default_preset,
+            presets::list_device_profiles,
+            presets::save_device_profile,
+            presets::delete_device_profile,
+            presets::set_default_device_profile,
+            // Subscription commands
+            subscriptions::save_subscription,
+            subscriptions::list_subscriptions,
+            subscriptions::delete_subscription,
+            subscriptions::sync_subscription_now,
+            // Proxy commands
+            proxy::get_proxy_settings,
+            proxy::set_proxy_settings,
+            // Extractor workaround commands
+            extractor_options::get_extractor_settings,
+            extractor_options::set_extractor_settings,
+            // Supported site commands
+            extractor_index::is_url_supported,
+            extractor_index::get_supported_sites,
             // SpotDL (Spotify) commands
             spotify_downloader::check_spotdl,
             spotify_downloader::update_spotdl,
             spotify_downloader::get_spotify_info,
             spotify_downloader::start_spotify_download,
             spotify_downloader::cancel_spotify_download,
+            spotify_downloader::resume_spotify_download,
+            spotify_downloader::get_resumable_spotify_jobs,
+            spotify_downloader::discard_spotify_job,
             // Updater commands
             updater::check_for_updates,
             updater::download_and_install_update,
             updater::get_current_version,
             // Vault commands
             vault::vault_get_status,
+            vault::vault_get_stats,
+            vault::vault_sync_stats,
             vault::vault_setup,
             vault::vault_unlock,
             vault::vault_lock,
             vault::vault_add_file,
             vault::vault_list_files,
             vault::vault_export_file,
+            vault::vault_export_portable,
             vault::vault_get_temp_playback_path,
             vault::vault_cleanup_temp,
             vault::vault_delete_file,
+            vault::vault_list_trash,
+            vault::vault_restore_deleted,
+            vault::vault_purge_trash,
             vault::vault_change_pin,
             vault::vault_reset,
             vault::vault_get_config,
             vault::vault_import_config,
             vault::vault_wipe_local_config,
+            // Vault compression commands
+            vault::vault_set_compression,
+            vault::vault_get_compression,
+            // Vault legacy format migration commands
+            vault::vault_get_legacy_status,
+            vault::vault_migrate_legacy_files,
+            // Vault capture protection commands
+            vault::vault_set_playback_protection,
+            vault::vault_set_paranoid_mode,
+            vault::vault_get_capture_protection_status,
             // Vault direct download commands
             vault_download::vault_direct_download,
             vault_download::vault_cancel_download,
+            // Vault write consent commands
+            vault_consent::respond_vault_write,
+            vault_consent::get_vault_external_policy,
+            vault_consent::set_vault_external_policy,
             // Vault cloud sync commands
             vault::vault_check_local_file,
             vault::vault_get_file_base64,
@@ -351,12 +600,85 @@ pub fn run() {
             native_integration::send_notification,
             native_integration::notify_download_complete,
             native_integration::notify_download_failed,
+            native_integration::notify_queue_finished,
+            native_integration::get_notification_preferences,
+            native_integration::set_notification_preferences,
             native_integration::check_notification_permission,
             native_integration::request_notification_permission,
+            native_integration::get_notifications,
+            native_integration::mark_read,
+            native_integration::get_badge_counts,
+            native_integration::acknowledge_failed_downloads,
+            // Idle policy commands
+            idle::get_idle_policy,
+            idle::set_idle_policy,
+            idle::get_idle_status,
+            // aria2 engine commands
+            aria2::get_aria2_status,
+            aria2::set_aria2_enabled,
+            // Gallery engine commands
+            gallery::get_gallery_dl_status,
+            gallery::install_gallery_dl,
+            // Engine capability commands
+            capabilities::get_engine_capabilities,
+            // Relay commands
+            relay::get_relays,
+            relay::save_relay,
+            relay::delete_relay,
+            relay::check_relay_health,
+            // Organization rule commands
+            organizer::get_organize_rules,
+            organizer::save_organize_rule,
+            organizer::delete_organize_rule,
+            organizer::preview_organized_path,
+            // Download later commands
+            saved::save_for_later,
+            saved::list_saved,
+            saved::delete_saved,
+            saved::download_saved,
+            // Split archive commands
+            split_archive::detect_split_archive,
+            split_archive::download_split_archive,
+            // Outbound call policy commands
+            outbound::get_outbound_call_policy,
+            outbound::set_offline_mode,
+            // Download summary commands
+            download_summary::get_download_summary,
+            download_summary::get_download_summary_interval,
+            download_summary::set_download_summary_interval,
+            // Download footprint commands
+            footprint::get_footprint_settings,
+            footprint::set_footprint_settings,
+            footprint::get_download_footprint,
+            footprint::get_footprint_summary,
+            // Browser takeover commands
+            takeover::get_takeover_settings,
+            takeover::set_takeover_settings,
+            takeover::get_takeover_outcomes,
+            // External process commands
+            exec_guard::get_process_invocations,
+            exec_guard::get_trusted_tool_paths,
+            exec_guard::set_trusted_tool_paths,
             // Secure storage commands
             secure_storage::secure_save_setting,
             secure_storage::secure_get_setting,
             secure_storage::secure_delete_setting,
+            // FTP/SFTP commands
+            ftp::set_remote_credentials,
+            ftp::delete_remote_credentials,
+            // Staging folder commands
+            staging::get_staging_directory,
+            staging::set_staging_directory,
+            // Crash resume commands
+            crash_resume::get_incomplete_downloads,
+            crash_resume::resume_incomplete_downloads,
+            // Stale download commands
+            stale_downloads::get_recovery_list,
+            stale_downloads::dismiss_stale_download,
+            // Search provider commands
+            search::list_search_providers,
+            search::set_search_provider_enabled,
+            search::search_media,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
@@ -396,3 +718,25 @@ fn parse_deep_link(deep_link: &str) -> Option<String> {
     
     None
 }
+
+/// Headers a download deep link carries: `referer=...` and any number of `header=Name: value`
+fn deep_link_headers(deep_link: &str) -> std::collections::HashMap<String, String> {
+    let clean = deep_link.trim().trim_matches('"').trim_matches('[').trim_matches(']');
+    let mut headers = std::collections::HashMap::new();
+    if let Ok(url) = url::Url::parse(clean) {
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "referer" | "referrer" => {
+                    headers.insert("Referer".to_string(), value.into_owned());
+                }
+                "header" => {
+                    if let Some((name, value)) = value.split_once(':') {
+                        headers.insert(name.trim().to_string(), value.trim().to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    headers
+}
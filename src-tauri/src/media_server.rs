@@ -1,15 +1,44 @@
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU16, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter};
 use warp::Filter;
 use std::fs;
 
-// Global port for the media server
+// Default port for the media server
 pub const MEDIA_SERVER_PORT: u16 = 18456;
 
+/// Largest slice served per request from a download that's still running
+const PREBUFFER_MAX_SLICE: u64 = 8 * 1024 * 1024;
+
+/// How long a request past the downloaded prefix waits for it to catch up
+const PREBUFFER_WAIT: std::time::Duration = std::time::Duration::from_secs(20);
+
+/// Settings key overriding the media server port
+pub const MEDIA_SERVER_PORT_SETTING: &str = "media_server_port";
+
 lazy_static::lazy_static! {
     static ref SERVER_SECRET: String = uuid::Uuid::new_v4().to_string();
+    static ref SERVER_HANDLE: tokio::sync::Mutex<Option<ServerHandle>> = tokio::sync::Mutex::new(None);
+}
+
+/// Port the running server listens on (used when building stream URLs)
+static CURRENT_PORT: AtomicU16 = AtomicU16::new(MEDIA_SERVER_PORT);
+
+/// A running local server that can be shut down to release its port
+struct ServerHandle {
+    port: u16,
+    shutdown: tokio::sync::oneshot::Sender<()>,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+/// Running state of a local server, emitted on every start/stop
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ServerStatus {
+    pub running: bool,
+    pub port: u16,
+    pub error: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -18,27 +47,105 @@ pub struct MediaFileInfo {
     pub is_audio: bool,
 }
 
+fn configured_port(app: &AppHandle) -> u16 {
+    crate::commands::read_setting(app, MEDIA_SERVER_PORT_SETTING)
+        .and_then(|v| v.trim().parse::<u16>().ok())
+        .filter(|port| *port != 0)
+        .unwrap_or(MEDIA_SERVER_PORT)
+}
+
 pub fn start_media_server(app: AppHandle) {
     tauri::async_runtime::spawn(async move {
-        println!("[MediaServer] Starting on port {}", MEDIA_SERVER_PORT);
-        
-        let stream_route = warp::path("stream")
-            .and(warp::query::<std::collections::HashMap<String, String>>())
-            .and(warp::header::optional::<String>("range"))
-            .and_then(handle_stream_request);
-            
-        let cors = warp::cors()
-            .allow_any_origin()
-            .allow_methods(vec!["GET", "HEAD", "OPTIONS"])
-            .allow_headers(vec!["Content-Type", "Range", "Accept-Ranges"]);
-
-        let routes = stream_route.with(cors);
-        
-        let addr = SocketAddr::from(([127, 0, 0, 1], MEDIA_SERVER_PORT));
-        warp::serve(routes).run(addr).await;
+        launch(&app).await;
     });
 }
 
+/// Bind the server on the configured port and remember its handle
+async fn launch(app: &AppHandle) -> ServerStatus {
+    let mut handle = SERVER_HANDLE.lock().await;
+    if let Some(running) = handle.as_ref() {
+        return ServerStatus { running: true, port: running.port, error: None };
+    }
+
+    let port = configured_port(app);
+    println!("[MediaServer] Starting on port {}", port);
+
+    let stream_route = warp::path("stream")
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and(warp::header::optional::<String>("range"))
+        .and_then(handle_stream_request);
+
+    // Plays a running SNDE download from the part already on disk
+    let prebuffer_route = warp::path("prebuffer")
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and(warp::header::optional::<String>("range"))
+        .and_then(handle_prebuffer_request);
+
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_methods(vec!["GET", "HEAD", "OPTIONS"])
+        .allow_headers(vec!["Content-Type", "Range", "Accept-Ranges"]);
+
+    let routes = stream_route.or(prebuffer_route).with(cors);
+
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let status = match warp::serve(routes).try_bind_with_graceful_shutdown(addr, async {
+        let _ = shutdown_rx.await;
+    }) {
+        Ok((_, server)) => {
+            CURRENT_PORT.store(port, Ordering::SeqCst);
+            *handle = Some(ServerHandle {
+                port,
+                shutdown: shutdown_tx,
+                task: tauri::async_runtime::spawn(server),
+            });
+            ServerStatus { running: true, port, error: None }
+        }
+        Err(e) => {
+            println!("[MediaServer] Failed to bind port {}: {}", port, e);
+            ServerStatus { running: false, port, error: Some(format!("Failed to bind port {}: {}", port, e)) }
+        }
+    };
+
+    let _ = app.emit("media-server-status", &status);
+    status
+}
+
+/// Shut the server down and wait until its port is released
+async fn stop() {
+    let handle = SERVER_HANDLE.lock().await.take();
+    if let Some(handle) = handle {
+        println!("[MediaServer] Stopping server on port {}", handle.port);
+        let _ = handle.shutdown.send(());
+        let _ = handle.task.await;
+    }
+}
+
+/// Restart the media server, picking up a changed port setting
+#[tauri::command]
+pub async fn restart_media_server(app: AppHandle) -> Result<ServerStatus, String> {
+    stop().await;
+    let status = launch(&app).await;
+    match status.error {
+        Some(ref e) => Err(e.clone()),
+        None => Ok(status),
+    }
+}
+
+#[tauri::command]
+pub async fn get_media_server_status() -> Result<ServerStatus, String> {
+    let handle = SERVER_HANDLE.lock().await;
+    Ok(ServerStatus {
+        running: handle.is_some(),
+        port: handle
+            .as_ref()
+            .map(|h| h.port)
+            .unwrap_or_else(|| CURRENT_PORT.load(Ordering::SeqCst)),
+        error: None,
+    })
+}
+
 async fn handle_stream_request(
     params: std::collections::HashMap<String, String>,
     range_header: Option<String>
@@ -134,10 +241,123 @@ async fn handle_stream_request(
         .unwrap())
 }
 
+/// Serve a running download from its finished prefix. Responses are capped at the bytes
+/// already on disk, while `Content-Range` reports the full size so the player can seek.
+/// Once the download is done the request is answered from the finished file instead.
+async fn handle_prebuffer_request(
+    params: std::collections::HashMap<String, String>,
+    range_header: Option<String>,
+) -> Result<warp::http::Response<warp::hyper::Body>, warp::Rejection> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+    use warp::http::{Response, StatusCode};
+
+    let token = params.get("token").ok_or_else(warp::reject::not_found)?;
+    if token != &*SERVER_SECRET {
+        println!("[MediaServer] Invalid token provided for prebuffer request");
+        return Err(warp::reject::not_found());
+    }
+    let id = params.get("id").ok_or_else(warp::reject::not_found)?;
+
+    let Some(mut prefix) = crate::snde::playable_prefix(id).await else {
+        // Finished (or never started): play the file where it ended up
+        let reply = handle_stream_request(params, range_header).await?;
+        return Ok(warp::Reply::into_response(reply));
+    };
+
+    let start = range_header
+        .as_deref()
+        .and_then(|r| r.strip_prefix("bytes="))
+        .and_then(|r| r.split('-').next())
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0);
+    let requested_end = range_header
+        .as_deref()
+        .and_then(|r| r.strip_prefix("bytes="))
+        .and_then(|r| r.split('-').nth(1))
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(u64::MAX);
+    if start >= prefix.total_size {
+        return Ok(Response::builder()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header("Content-Range", format!("bytes */{}", prefix.total_size))
+            .body(warp::hyper::Body::empty())
+            .unwrap());
+    }
+
+    // Past what's on disk: give the download a moment to get there
+    let deadline = tokio::time::Instant::now() + PREBUFFER_WAIT;
+    while prefix.available <= start {
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header("Retry-After", "1")
+                .body(warp::hyper::Body::empty())
+                .unwrap());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        match crate::snde::playable_prefix(id).await {
+            Some(latest) => prefix = latest,
+            None => {
+                let reply = handle_stream_request(params, range_header).await?;
+                return Ok(warp::Reply::into_response(reply));
+            }
+        }
+    }
+
+    let end = requested_end
+        .min(prefix.available - 1)
+        .min(start + PREBUFFER_MAX_SLICE - 1);
+    let length = end - start + 1;
+    let mut file = tokio::fs::File::open(&prefix.temp_path).await.map_err(|_| warp::reject::not_found())?;
+    file.seek(std::io::SeekFrom::Start(start)).await.map_err(|_| warp::reject::not_found())?;
+    let mut buffer = vec![0; length as usize];
+    file.read_exact(&mut buffer).await.map_err(|_| warp::reject::not_found())?;
+
+    let content_type = mime_guess::from_path(&prefix.final_path).first_or_octet_stream();
+    Ok(Response::builder()
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header("Content-Type", content_type.as_ref())
+        .header("Accept-Ranges", "bytes")
+        .header("Content-Range", format!("bytes {}-{}/{}", start, end, prefix.total_size))
+        .header("Content-Length", length)
+        .body(warp::hyper::Body::from(buffer))
+        .unwrap())
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct PrebufferInfo {
+    pub url: String,
+    /// Leading bytes that can be played now
+    pub playable_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Stream URL for watching a running SNDE download before it finishes. Formats that play
+/// from the front (MKV, WebM, MP4 with the index up front) start right away.
+#[tauri::command]
+pub async fn get_prebuffer_stream_url(download_id: String) -> Result<PrebufferInfo, String> {
+    let prefix = crate::snde::playable_prefix(&download_id)
+        .await
+        .ok_or_else(|| "This download isn't running in SNDE".to_string())?;
+    let url = format!(
+        "http://127.0.0.1:{}/prebuffer?id={}&path={}&token={}",
+        CURRENT_PORT.load(Ordering::SeqCst),
+        urlencoding::encode(&download_id),
+        urlencoding::encode(&prefix.final_path.to_string_lossy()),
+        *SERVER_SECRET
+    );
+    Ok(PrebufferInfo { url, playable_bytes: prefix.available, total_bytes: prefix.total_size })
+}
+
 /// Get the streaming URL for a given file path
 pub fn get_stream_url(file_path: &str) -> String {
     let encoded_path = urlencoding::encode(file_path);
-    format!("http://127.0.0.1:{}/stream?path={}&token={}", MEDIA_SERVER_PORT, encoded_path, *SERVER_SECRET)
+    format!(
+        "http://127.0.0.1:{}/stream?path={}&token={}",
+        CURRENT_PORT.load(Ordering::SeqCst),
+        encoded_path,
+        *SERVER_SECRET
+    )
 }
 
 #[tauri::command]
@@ -145,6 +365,18 @@ pub fn get_media_stream_url(file_path: String) -> String {
     get_stream_url(&file_path)
 }
 
+/// Chapter markers saved next to a media file by a `chapters_sidecar: "json"` download;
+/// empty when there is no sidecar
+#[tauri::command]
+pub fn get_media_chapters(file_path: String) -> Result<Vec<crate::downloader::Chapter>, String> {
+    let sidecar = crate::downloader::chapter_sidecar_path(Path::new(&file_path), "json");
+    match fs::read_to_string(&sidecar) {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid chapter file {:?}: {}", sidecar, e)),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
 /// Renamed to avoid macro collision in commands.rs
 #[tauri::command]
 pub fn find_best_media_match(path: String, title: String) -> Result<MediaFileInfo, String> {
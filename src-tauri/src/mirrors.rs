@@ -0,0 +1,185 @@
+//! SNDE Mirror Pool
+//!
+//! Spreads a download's chunks over several URLs serving the same file, preferring the
+//! faster mirrors and dropping ones that keep failing or fall far behind.
+//!
+//! Key Features:
+//! - Each chunk goes to the mirror with the least load relative to its measured speed
+//! - Untested mirrors are tried first so every mirror gets measured
+//! - Mirrors are dropped after repeated failures or when much slower than the best one
+//! - The last working mirror is never dropped
+
+use reqwest::header::HeaderMap;
+use std::sync::Mutex;
+
+/// Consecutive failed chunks before a mirror is dropped
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// A mirror slower than this fraction of the fastest one is dropped...
+const SLOW_MIRROR_RATIO: f64 = 0.2;
+
+/// ...once both have finished at least this many chunks
+const MIN_CHUNKS_FOR_SPEED: u32 = 2;
+
+struct Mirror {
+    url: String,
+    headers: HeaderMap,
+    /// Chunks currently being fetched from this mirror
+    active: u32,
+    completed_chunks: u32,
+    bytes: u64,
+    busy_secs: f64,
+    consecutive_failures: u32,
+    dropped: bool,
+}
+
+impl Mirror {
+    /// Bytes per second over finished chunks, once there are enough to judge
+    fn speed(&self) -> Option<f64> {
+        (self.completed_chunks >= MIN_CHUNKS_FOR_SPEED && self.busy_secs > 0.0).then(|| self.bytes as f64 / self.busy_secs)
+    }
+}
+
+/// A claimed mirror; hand it back with `MirrorPool::report`
+#[derive(Debug, Clone)]
+pub struct MirrorLease {
+    index: usize,
+    pub url: String,
+    pub headers: HeaderMap,
+}
+
+pub struct MirrorPool {
+    mirrors: Mutex<Vec<Mirror>>,
+}
+
+impl MirrorPool {
+    /// A pool over `(url, request headers)` pairs; the first is the primary URL
+    pub fn new(mirrors: Vec<(String, HeaderMap)>) -> Self {
+        let mirrors = mirrors
+            .into_iter()
+            .map(|(url, headers)| Mirror {
+                url,
+                headers,
+                active: 0,
+                completed_chunks: 0,
+                bytes: 0,
+                busy_secs: 0.0,
+                consecutive_failures: 0,
+                dropped: false,
+            })
+            .collect();
+        Self { mirrors: Mutex::new(mirrors) }
+    }
+
+    /// A pool with just one URL
+    pub fn single(url: String, headers: HeaderMap) -> Self {
+        Self::new(vec![(url, headers)])
+    }
+
+    /// Mirrors still in use
+    pub fn live_count(&self) -> usize {
+        self.mirrors.lock().unwrap().iter().filter(|m| !m.dropped).count()
+    }
+
+    /// Claim the mirror that should fetch the next chunk
+    pub fn pick(&self) -> MirrorLease {
+        let mut mirrors = self.mirrors.lock().unwrap();
+        let (index, mirror) = mirrors
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, m)| !m.dropped)
+            .min_by(|(_, a), (_, b)| {
+                // Load over speed; an idle unmeasured mirror goes first so it gets measured,
+                // busy unmeasured ones last, spread by load
+                let score = |m: &Mirror| match m.speed() {
+                    Some(speed) => (m.active + 1) as f64 / speed,
+                    None if m.active == 0 => 0.0,
+                    None => f64::INFINITY,
+                };
+                score(a).total_cmp(&score(b)).then(a.active.cmp(&b.active))
+            })
+            .expect("the last mirror is never dropped");
+        mirror.active += 1;
+        MirrorLease { index, url: mirror.url.clone(), headers: mirror.headers.clone() }
+    }
+
+    /// Record how a chunk from `lease` went, dropping the mirror if it has become a liability
+    pub fn report(&self, lease: &MirrorLease, bytes: u64, busy_secs: f64, success: bool) {
+        let mut mirrors = self.mirrors.lock().unwrap();
+        let live = mirrors.iter().filter(|m| !m.dropped).count();
+        let best_speed = mirrors.iter().filter(|m| !m.dropped).filter_map(Mirror::speed).fold(0.0, f64::max);
+
+        let Some(mirror) = mirrors.get_mut(lease.index) else {
+            return;
+        };
+        mirror.active = mirror.active.saturating_sub(1);
+        if success {
+            mirror.completed_chunks += 1;
+            mirror.bytes += bytes;
+            mirror.busy_secs += busy_secs;
+            mirror.consecutive_failures = 0;
+        } else {
+            mirror.consecutive_failures += 1;
+        }
+
+        if mirror.dropped || live <= 1 {
+            return;
+        }
+        let failing = mirror.consecutive_failures >= MAX_CONSECUTIVE_FAILURES;
+        let slow = mirror.speed().is_some_and(|speed| speed < best_speed * SLOW_MIRROR_RATIO);
+        if failing || slow {
+            mirror.dropped = true;
+            println!(
+                "[SNDE] Dropping mirror {} ({})",
+                mirror.url,
+                if failing { "keeps failing" } else { "too slow" }
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(urls: &[&str]) -> MirrorPool {
+        MirrorPool::new(urls.iter().map(|u| (u.to_string(), HeaderMap::new())).collect())
+    }
+
+    #[test]
+    fn test_untested_mirrors_are_tried_first() {
+        let pool = pool(&["a", "b", "c"]);
+        let picked: Vec<String> = (0..3).map(|_| pool.pick().url).collect();
+        assert_eq!(picked, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_failing_mirror_is_dropped_but_not_the_last() {
+        let pool = pool(&["a", "b"]);
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            let lease = MirrorLease { index: 1, url: "b".to_string(), headers: HeaderMap::new() };
+            pool.report(&lease, 0, 1.0, false);
+        }
+        assert_eq!(pool.live_count(), 1);
+        assert!((0..5).all(|_| pool.pick().url == "a"));
+
+        for _ in 0..10 {
+            let lease = pool.pick();
+            pool.report(&lease, 0, 1.0, false);
+        }
+        assert_eq!(pool.live_count(), 1);
+    }
+
+    #[test]
+    fn test_slow_mirror_is_dropped_and_fast_one_preferred() {
+        let pool = pool(&["fast", "slow"]);
+        for _ in 0..MIN_CHUNKS_FOR_SPEED {
+            let fast = pool.pick();
+            let slow = pool.pick();
+            pool.report(&fast, 10_000_000, 1.0, true);
+            pool.report(&slow, 1_000_000, 1.0, true);
+        }
+        assert_eq!(pool.live_count(), 1);
+        assert_eq!(pool.pick().url, "fast");
+    }
+}
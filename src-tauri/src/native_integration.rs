@@ -1,8 +1,17 @@
 // Native OS Integration module
 // - Windows Taskbar Progress
 // - Native Notifications with actions
-
-use tauri::{AppHandle, Emitter, Manager};
+// - Per-event notification preferences and quiet hours
+// - Dock badge / taskbar overlay for pending and failed downloads
+
+use crate::commands::AppState;
+use crate::database::{Database, NotificationRecord};
+use crate::download_lifecycle::{DownloadTracker, ProgressUpdate, Transition};
+use chrono::{NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[cfg(target_os = "windows")]
 use std::ptr;
@@ -143,6 +152,193 @@ pub fn clear_taskbar_progress(app_handle: AppHandle) -> Result<(), String> {
 
 // ============ Native Notifications ============
 
+/// Settings key holding the JSON encoded `NotificationPreferences`
+pub const NOTIFICATION_PREFERENCES_SETTING: &str = "notification_preferences";
+
+/// What a notification is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationEvent {
+    DownloadComplete,
+    DownloadFailed,
+    QueueFinished,
+    WatchdogWarning,
+    /// App or yt-dlp update results; only quiet hours apply
+    Update,
+    /// Anything else; only quiet hours apply
+    Other,
+}
+
+impl NotificationEvent {
+    fn from_type(notification_type: &str) -> Self {
+        match notification_type {
+            "download_complete" | "complete" | "success" => NotificationEvent::DownloadComplete,
+            "download_failed" | "failed" | "error" => NotificationEvent::DownloadFailed,
+            "queue_finished" => NotificationEvent::QueueFinished,
+            "watchdog" | "watchdog_warning" => NotificationEvent::WatchdogWarning,
+            "update" => NotificationEvent::Update,
+            _ => NotificationEvent::Other,
+        }
+    }
+
+    /// Kind stored in the notification center
+    fn kind(self) -> &'static str {
+        match self {
+            NotificationEvent::DownloadComplete => "download_complete",
+            NotificationEvent::DownloadFailed => "download_failed",
+            NotificationEvent::QueueFinished => "queue_finished",
+            NotificationEvent::WatchdogWarning => "watchdog",
+            NotificationEvent::Update => "update",
+            NotificationEvent::Other => "other",
+        }
+    }
+}
+
+/// Daily window ("HH:MM" local time) during which no notifications are shown.
+/// `end` before `start` means the window spans midnight.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuietHours {
+    pub enabled: bool,
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NotificationPreferences {
+    #[serde(default = "default_true")]
+    pub download_complete: bool,
+    #[serde(default = "default_true")]
+    pub download_failed: bool,
+    #[serde(default = "default_true")]
+    pub queue_finished: bool,
+    #[serde(default = "default_true")]
+    pub watchdog_warnings: bool,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            download_complete: true,
+            download_failed: true,
+            queue_finished: true,
+            watchdog_warnings: true,
+            quiet_hours: None,
+        }
+    }
+}
+
+fn parse_clock(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time '{}', expected HH:MM", value))
+}
+
+impl QuietHours {
+    fn contains(&self, now: NaiveTime) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let (Ok(start), Ok(end)) = (parse_clock(&self.start), parse_clock(&self.end)) else {
+            return false;
+        };
+        let now = NaiveTime::from_hms_opt(now.hour(), now.minute(), 0).unwrap_or(now);
+        if start <= end {
+            start <= now && now < end
+        } else {
+            now >= start || now < end
+        }
+    }
+}
+
+impl NotificationPreferences {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(quiet) = &self.quiet_hours {
+            parse_clock(&quiet.start)?;
+            parse_clock(&quiet.end)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `event` may be shown at local time `now`
+    fn allows(&self, event: NotificationEvent, now: NaiveTime) -> bool {
+        let enabled = match event {
+            NotificationEvent::DownloadComplete => self.download_complete,
+            NotificationEvent::DownloadFailed => self.download_failed,
+            NotificationEvent::QueueFinished => self.queue_finished,
+            NotificationEvent::WatchdogWarning => self.watchdog_warnings,
+            NotificationEvent::Update | NotificationEvent::Other => true,
+        };
+        enabled && !self.quiet_hours.as_ref().is_some_and(|q| q.contains(now))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref NOTIFICATION_PREFERENCES: RwLock<NotificationPreferences> = RwLock::new(NotificationPreferences::default());
+}
+
+/// Apply the persisted notification preferences at startup
+pub fn load_from_settings(db: &Database) {
+    let prefs = db
+        .get_setting(NOTIFICATION_PREFERENCES_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    *NOTIFICATION_PREFERENCES.write().unwrap() = prefs;
+}
+
+/// Show a notification unless the user's preferences suppress it. Returns whether it was shown.
+pub fn show_notification(
+    app_handle: &AppHandle,
+    event: NotificationEvent,
+    title: &str,
+    body: &str,
+) -> Result<bool, String> {
+    use tauri_plugin_notification::NotificationExt;
+
+    let now = chrono::Local::now().time();
+    if !NOTIFICATION_PREFERENCES.read().unwrap().allows(event, now) {
+        println!("[NativeIntegration] Suppressed {:?} notification: {}", event, title);
+        return Ok(false);
+    }
+
+    app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+        .map_err(|e| format!("Failed to send notification: {}", e))?;
+    Ok(true)
+}
+
+#[tauri::command]
+pub async fn get_notification_preferences() -> Result<NotificationPreferences, String> {
+    Ok(NOTIFICATION_PREFERENCES.read().unwrap().clone())
+}
+
+#[tauri::command]
+pub async fn set_notification_preferences(
+    state: State<'_, AppState>,
+    preferences: NotificationPreferences,
+) -> Result<(), String> {
+    preferences.validate()?;
+
+    let json = serde_json::to_string(&preferences)
+        .map_err(|e| format!("Failed to serialize notification preferences: {}", e))?;
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(NOTIFICATION_PREFERENCES_SETTING, &json)
+            .map_err(|e| e.to_string())?;
+    }
+    *NOTIFICATION_PREFERENCES.write().unwrap() = preferences;
+    Ok(())
+}
+
 /// Send a native notification
 #[tauri::command]
 pub async fn send_notification(
@@ -151,19 +347,7 @@ pub async fn send_notification(
     body: String,
     notification_type: String,
 ) -> Result<(), String> {
-    use tauri_plugin_notification::NotificationExt;
-
-    let notification = app_handle.notification()
-        .builder()
-        .title(&title)
-        .body(&body);
-
-    // Add icon based on type
-    // Note: Custom actions require additional platform-specific setup
-    
-    notification.show()
-        .map_err(|e| format!("Failed to send notification: {}", e))?;
-
+    show_notification(&app_handle, NotificationEvent::from_type(&notification_type), &title, &body)?;
     Ok(())
 }
 
@@ -174,22 +358,21 @@ pub async fn notify_download_complete(
     title: String,
     file_path: String,
 ) -> Result<(), String> {
-    use tauri_plugin_notification::NotificationExt;
-
-    let notification = app_handle.notification()
-        .builder()
-        .title("Download Complete")
-        .body(&format!("{} has finished downloading", title));
-
-    notification.show()
-        .map_err(|e| format!("Failed to send notification: {}", e))?;
+    let shown = show_notification(
+        &app_handle,
+        NotificationEvent::DownloadComplete,
+        "Download Complete",
+        &format!("{} has finished downloading", title),
+    )?;
 
     // Emit event to frontend for handling click actions
-    let _ = app_handle.emit("notification-click", serde_json::json!({
-        "type": "download_complete",
-        "title": title,
-        "file_path": file_path,
-    }));
+    if shown {
+        let _ = app_handle.emit("notification-click", serde_json::json!({
+            "type": "download_complete",
+            "title": title,
+            "file_path": file_path,
+        }));
+    }
 
     Ok(())
 }
@@ -201,16 +384,28 @@ pub async fn notify_download_failed(
     title: String,
     error: String,
 ) -> Result<(), String> {
-    use tauri_plugin_notification::NotificationExt;
-
-    let notification = app_handle.notification()
-        .builder()
-        .title("Download Failed")
-        .body(&format!("{}: {}", title, error));
-
-    notification.show()
-        .map_err(|e| format!("Failed to send notification: {}", e))?;
+    show_notification(
+        &app_handle,
+        NotificationEvent::DownloadFailed,
+        "Download Failed",
+        &format!("{}: {}", title, error),
+    )?;
+    Ok(())
+}
 
+/// Send a notification when the download queue has drained
+#[tauri::command]
+pub async fn notify_queue_finished(
+    app_handle: AppHandle,
+    completed: u32,
+    failed: u32,
+) -> Result<(), String> {
+    let body = if failed == 0 {
+        format!("{} downloads finished", completed)
+    } else {
+        format!("{} downloads finished, {} failed", completed, failed)
+    };
+    show_notification(&app_handle, NotificationEvent::QueueFinished, "Queue Finished", &body)?;
     Ok(())
 }
 
@@ -241,3 +436,257 @@ pub async fn request_notification_permission(
 
     Ok(matches!(permission, tauri_plugin_notification::PermissionState::Granted))
 }
+
+// ============ Notification Center ============
+
+/// Keep a notification in the notification center and let the UI know
+pub fn record_notification(
+    app_handle: &AppHandle,
+    event: NotificationEvent,
+    title: &str,
+    message: &str,
+    download_id: Option<&str>,
+) {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let result = match state.db.lock() {
+        Ok(db) => db
+            .add_notification(event.kind(), title, message, download_id)
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match result {
+        Ok(id) => {
+            let _ = app_handle.emit("notification-recorded", id);
+        }
+        Err(e) => println!("[NativeIntegration] Failed to record notification: {}", e),
+    }
+}
+
+/// Notification center entries, newest first
+#[tauri::command]
+pub async fn get_notifications(
+    state: State<'_, AppState>,
+    unread_only: Option<bool>,
+) -> Result<Vec<NotificationRecord>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_notifications(unread_only.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// Mark notifications as read (all of them when `ids` is omitted); returns how many changed
+#[tauri::command]
+pub async fn mark_read(state: State<'_, AppState>, ids: Option<Vec<i64>>) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.mark_notifications_read(ids.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+// ============ Badge Counts ============
+
+/// How often counts are re-read, to catch history changes made without a progress event
+const BADGE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Pending and failed downloads, for the dock badge / taskbar overlay and the UI
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BadgeCounts {
+    pub active: i64,
+    pub queued: i64,
+    /// Failed downloads the user hasn't acknowledged yet
+    pub failed: i64,
+}
+
+impl BadgeCounts {
+    /// The number on the dock badge
+    pub fn total(self) -> i64 {
+        self.active + self.queued + self.failed
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Counts last shown, so the badge and event only change when they do
+    static ref BADGE_COUNTS: Mutex<Option<BadgeCounts>> = Mutex::new(None);
+    /// Last status seen per download, so only status changes trigger a recount
+    static ref BADGE_STATUSES: Mutex<DownloadTracker<String>> = Mutex::new(DownloadTracker::default());
+}
+
+fn read_badge_counts(db: &Database) -> Result<BadgeCounts, String> {
+    let (active, queued, failed) = db.get_badge_counts().map_err(|e| e.to_string())?;
+    Ok(BadgeCounts { active, queued, failed })
+}
+
+/// A 16x16 dot for the taskbar overlay: red when something failed, blue otherwise
+#[cfg(target_os = "windows")]
+fn badge_overlay(counts: BadgeCounts) -> Option<tauri::image::Image<'static>> {
+    if counts.total() == 0 {
+        return None;
+    }
+    let color: [u8; 3] = if counts.failed > 0 { [0xE5, 0x39, 0x35] } else { [0x1E, 0x88, 0xE5] };
+    let mut rgba = Vec::with_capacity(16 * 16 * 4);
+    for y in 0..16 {
+        for x in 0..16 {
+            let (dx, dy) = (x as f64 - 7.5, y as f64 - 7.5);
+            let alpha = if dx * dx + dy * dy <= 56.25 { 0xFF } else { 0x00 };
+            rgba.extend_from_slice(&[color[0], color[1], color[2], alpha]);
+        }
+    }
+    Some(tauri::image::Image::new(&rgba, 16, 16).to_owned())
+}
+
+fn apply_badge(app_handle: &AppHandle, counts: BadgeCounts) {
+    let Some(window) = app_handle.get_webview_window("main") else {
+        return;
+    };
+    #[cfg(target_os = "windows")]
+    let result = window.set_overlay_icon(badge_overlay(counts));
+    #[cfg(not(target_os = "windows"))]
+    let result = window.set_badge_count(Some(counts.total()).filter(|total| *total > 0));
+    if let Err(e) = result {
+        println!("[NativeIntegration] Failed to set badge: {}", e);
+    }
+}
+
+/// Re-read the counts; when they changed, update the badge and emit `badge-counts`
+fn refresh_badge(app_handle: &AppHandle) {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let counts = match state.db.lock().map_err(|e| e.to_string()).and_then(|db| read_badge_counts(&db)) {
+        Ok(counts) => counts,
+        Err(e) => {
+            println!("[NativeIntegration] Failed to count downloads for the badge: {}", e);
+            return;
+        }
+    };
+    {
+        let mut shown = BADGE_COUNTS.lock().unwrap();
+        if *shown == Some(counts) {
+            return;
+        }
+        *shown = Some(counts);
+    }
+    apply_badge(app_handle, counts);
+    let _ = app_handle.emit("badge-counts", counts);
+}
+
+/// Whether a progress event moves a download to a new status, remembering it
+fn status_changed(statuses: &mut DownloadTracker<String>, update: &ProgressUpdate) -> bool {
+    match statuses.track(update, String::new) {
+        Transition::Finished(_) => true,
+        Transition::Running { entry, .. } => {
+            if *entry == update.status {
+                return false;
+            }
+            *entry = update.status.clone();
+            true
+        }
+    }
+}
+
+/// Keep the badge and `badge-counts` in step with the downloads; call once at startup
+pub fn install_badge(app_handle: &AppHandle) {
+    let app = app_handle.clone();
+    crate::download_lifecycle::on_progress(app_handle, move |update| {
+        if status_changed(&mut BADGE_STATUSES.lock().unwrap(), update) {
+            // The history row is written around the event; count once the emitter is done
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(250)).await;
+                refresh_badge(&app);
+            });
+        }
+    });
+
+    let app = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            refresh_badge(&app);
+            tokio::time::sleep(BADGE_POLL_INTERVAL).await;
+        }
+    });
+}
+
+/// Active, queued and unacknowledged failed downloads
+#[tauri::command]
+pub async fn get_badge_counts(state: State<'_, AppState>) -> Result<BadgeCounts, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    read_badge_counts(&db)
+}
+
+/// Mark failed downloads as seen (all of them when `ids` is omitted), taking them off the
+/// badge; returns how many changed
+#[tauri::command]
+pub async fn acknowledge_failed_downloads(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    ids: Option<Vec<String>>,
+) -> Result<usize, String> {
+    let changed = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.acknowledge_failed_downloads(ids.as_deref()).map_err(|e| e.to_string())?
+    };
+    refresh_badge(&app_handle);
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn quiet(start: &str, end: &str) -> NotificationPreferences {
+        NotificationPreferences {
+            quiet_hours: Some(QuietHours {
+                enabled: true,
+                start: start.to_string(),
+                end: end.to_string(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_quiet_hours_across_midnight() {
+        let prefs = quiet("22:00", "07:30");
+        assert!(!prefs.allows(NotificationEvent::DownloadComplete, at(23, 15)));
+        assert!(!prefs.allows(NotificationEvent::Other, at(7, 29)));
+        assert!(prefs.allows(NotificationEvent::DownloadComplete, at(7, 30)));
+        assert!(prefs.allows(NotificationEvent::DownloadComplete, at(12, 0)));
+
+        let daytime = quiet("09:00", "17:00");
+        assert!(!daytime.allows(NotificationEvent::QueueFinished, at(9, 0)));
+        assert!(daytime.allows(NotificationEvent::QueueFinished, at(17, 0)));
+    }
+
+    #[test]
+    fn test_badge_recounts_on_status_changes_only() {
+        let update = |status: &str| {
+            ProgressUpdate::from_download_progress(serde_json::json!({ "id": "a", "status": status })).unwrap()
+        };
+        let mut statuses = DownloadTracker::default();
+        assert!(status_changed(&mut statuses, &update("downloading")));
+        assert!(!status_changed(&mut statuses, &update("downloading")));
+        assert!(status_changed(&mut statuses, &update("paused")));
+        assert!(status_changed(&mut statuses, &update("failed")));
+        assert!(statuses.is_empty());
+        assert_eq!(BadgeCounts { active: 1, queued: 2, failed: 3 }.total(), 6);
+    }
+
+    #[test]
+    fn test_per_event_preferences() {
+        let prefs = NotificationPreferences {
+            download_complete: false,
+            ..Default::default()
+        };
+        assert!(!prefs.allows(NotificationEvent::DownloadComplete, at(12, 0)));
+        assert!(prefs.allows(NotificationEvent::DownloadFailed, at(12, 0)));
+        assert_eq!(NotificationEvent::from_type("watchdog"), NotificationEvent::WatchdogWarning);
+        assert_eq!(NotificationEvent::from_type("update").kind(), "update");
+
+        assert!(quiet("25:00", "07:00").validate().is_err());
+    }
+}
@@ -0,0 +1,454 @@
+//! Auto-Organization
+//!
+//! Moves finished downloads into sub folders of their output directory based on user rules,
+//! e.g. "audio -> Music/{artist}" or "platform = TikTok -> TikTok/{date}".
+//!
+//! Key Features:
+//! - Rules match on media kind, platform and extension; the first enabled match wins
+//! - Target templates with {artist}, {uploader}, {platform}, {kind}, {ext}, {date}, {year}, {month}
+//! - `preview_organized_path` shows where a file would land before downloading
+//! - Rules persisted in settings; a download can opt out with `organize: false`
+
+use crate::commands::AppState;
+use crate::database::Database;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::State;
+
+/// Settings key holding the JSON encoded rule list
+pub const ORGANIZE_RULES_SETTING: &str = "organize_rules";
+
+/// Used for template fields the download has no value for
+const UNKNOWN_VALUE: &str = "Unknown";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Audio,
+    Video,
+    Image,
+    Document,
+    Archive,
+    Other,
+}
+
+impl MediaKind {
+    pub fn from_extension(ext: &str) -> MediaKind {
+        match ext.to_lowercase().as_str() {
+            "mp3" | "m4a" | "aac" | "opus" | "ogg" | "oga" | "flac" | "wav" | "alac" | "aiff" | "wma" => MediaKind::Audio,
+            "mp4" | "mkv" | "webm" | "mov" | "avi" | "flv" | "m4v" | "ts" | "wmv" | "3gp" => MediaKind::Video,
+            "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "heic" | "avif" | "tiff" => MediaKind::Image,
+            "pdf" | "epub" | "doc" | "docx" | "txt" | "odt" | "xls" | "xlsx" | "ppt" | "pptx" => MediaKind::Document,
+            "zip" | "rar" | "7z" | "tar" | "gz" | "xz" | "bz2" | "zst" => MediaKind::Archive,
+            _ => MediaKind::Other,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            MediaKind::Audio => "Audio",
+            MediaKind::Video => "Video",
+            MediaKind::Image => "Images",
+            MediaKind::Document => "Documents",
+            MediaKind::Archive => "Archives",
+            MediaKind::Other => "Other",
+        }
+    }
+}
+
+/// What a rule matches; empty fields match anything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleCondition {
+    #[serde(default)]
+    pub kind: Option<MediaKind>,
+    /// Platform name as shown by `platform_for_url`, compared case-insensitively
+    #[serde(default)]
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub extension: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrganizeRule {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub condition: RuleCondition,
+    /// Folder template relative to the download's output directory, e.g. "Music/{artist}"
+    pub target: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// The facts about a finished download that rules look at
+#[derive(Debug, Clone)]
+pub struct OrganizeContext {
+    pub kind: MediaKind,
+    pub platform: String,
+    pub extension: String,
+    pub artist: Option<String>,
+    pub uploader: Option<String>,
+    pub date: chrono::NaiveDate,
+}
+
+impl OrganizeContext {
+    pub fn new(url: &str, file: &Path) -> OrganizeContext {
+        let extension = file
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        OrganizeContext {
+            kind: MediaKind::from_extension(&extension),
+            platform: platform_for_url(url),
+            extension,
+            artist: None,
+            uploader: None,
+            date: chrono::Local::now().date_naive(),
+        }
+    }
+
+    /// Fill artist, uploader and (for unknown hosts) platform from a yt-dlp info dict
+    pub fn with_info(mut self, info: &serde_json::Value) -> OrganizeContext {
+        let text = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|k| info[*k].as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        self.artist = text(&["artist", "creator", "album_artist", "uploader"]);
+        self.uploader = text(&["uploader", "channel"]);
+        if self.platform == UNKNOWN_VALUE {
+            if let Some(extractor) = text(&["extractor_key"]) {
+                self.platform = extractor;
+            }
+        }
+        self
+    }
+}
+
+const KNOWN_PLATFORMS: &[(&str, &str)] = &[
+    ("youtube.com", "YouTube"),
+    ("youtu.be", "YouTube"),
+    ("tiktok.com", "TikTok"),
+    ("instagram.com", "Instagram"),
+    ("twitter.com", "Twitter"),
+    ("x.com", "Twitter"),
+    ("facebook.com", "Facebook"),
+    ("vimeo.com", "Vimeo"),
+    ("soundcloud.com", "SoundCloud"),
+    ("bandcamp.com", "Bandcamp"),
+    ("spotify.com", "Spotify"),
+    ("reddit.com", "Reddit"),
+    ("twitch.tv", "Twitch"),
+    ("dailymotion.com", "Dailymotion"),
+    ("pixiv.net", "Pixiv"),
+];
+
+/// Platform name for a URL: a known site name, else the domain without its suffix
+pub fn platform_for_url(url: &str) -> String {
+    let Some(host) = crate::host_reputation::extract_domain(url) else {
+        return UNKNOWN_VALUE.to_string();
+    };
+    let host = host.trim_start_matches("www.").to_lowercase();
+    for (domain, name) in KNOWN_PLATFORMS {
+        if host == *domain || host.ends_with(&format!(".{}", domain)) {
+            return name.to_string();
+        }
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    match labels.len() {
+        0 | 1 => UNKNOWN_VALUE.to_string(),
+        n => labels[n - 2].to_string(),
+    }
+}
+
+impl RuleCondition {
+    fn matches(&self, ctx: &OrganizeContext) -> bool {
+        self.kind.is_none_or(|kind| kind == ctx.kind)
+            && self
+                .platform
+                .as_deref()
+                .is_none_or(|p| p.trim().eq_ignore_ascii_case(&ctx.platform))
+            && self
+                .extension
+                .as_deref()
+                .is_none_or(|e| e.trim().trim_start_matches('.').eq_ignore_ascii_case(&ctx.extension))
+    }
+}
+
+/// Characters that aren't allowed in a folder name on some platform
+pub(crate) fn sanitize_component(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| if matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') || c.is_control() { '_' } else { c })
+        .collect();
+    match cleaned.trim().trim_end_matches('.') {
+        "" => UNKNOWN_VALUE.to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Render a target template into a relative folder path
+pub fn render_target(template: &str, ctx: &OrganizeContext) -> Result<PathBuf, String> {
+    let fields = [
+        ("{artist}", ctx.artist.clone()),
+        ("{uploader}", ctx.uploader.clone()),
+        ("{platform}", Some(ctx.platform.clone())),
+        ("{kind}", Some(ctx.kind.label().to_string())),
+        ("{ext}", Some(ctx.extension.clone()).filter(|e| !e.is_empty())),
+        ("{date}", Some(ctx.date.format("%Y-%m-%d").to_string())),
+        ("{year}", Some(ctx.date.format("%Y").to_string())),
+        ("{month}", Some(ctx.date.format("%m").to_string())),
+    ];
+
+    let mut path = PathBuf::new();
+    for part in template.split(['/', '\\']).map(str::trim).filter(|p| !p.is_empty()) {
+        if part == "." || part == ".." {
+            return Err(format!("Target '{}' can't leave the output folder", template));
+        }
+        let mut rendered = part.to_string();
+        for (token, value) in &fields {
+            if rendered.contains(token) {
+                let value = value.as_deref().unwrap_or(UNKNOWN_VALUE);
+                rendered = rendered.replace(token, &sanitize_component(value));
+            }
+        }
+        if let Some(unknown) = rendered.find('{').and_then(|start| {
+            rendered[start..].find('}').map(|end| &rendered[start..=start + end])
+        }) {
+            return Err(format!("Unknown field {} in target '{}'", unknown, template));
+        }
+        path.push(sanitize_component(&rendered));
+    }
+    Ok(path)
+}
+
+/// The first enabled rule that matches
+pub fn matching_rule<'a>(rules: &'a [OrganizeRule], ctx: &OrganizeContext) -> Option<&'a OrganizeRule> {
+    rules.iter().find(|r| r.enabled && r.condition.matches(ctx))
+}
+
+lazy_static::lazy_static! {
+    static ref RULES: RwLock<Vec<OrganizeRule>> = RwLock::new(Vec::new());
+}
+
+/// Apply the persisted rules at startup
+pub fn load_from_settings(db: &Database) {
+    let rules = db
+        .get_setting(ORGANIZE_RULES_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    *RULES.write().unwrap() = rules;
+}
+
+/// Whether any rule could move a file, so callers can skip gathering metadata
+pub fn has_rules() -> bool {
+    RULES.read().unwrap().iter().any(|r| r.enabled)
+}
+
+/// Where a file would land: `<output_dir>/<rendered target>/<file name>`, or None if no rule matches
+pub fn organized_path(output_dir: &Path, file_name: &str, ctx: &OrganizeContext) -> Result<Option<PathBuf>, String> {
+    let rules = RULES.read().unwrap();
+    let Some(rule) = matching_rule(&rules, ctx) else {
+        return Ok(None);
+    };
+    let folder = render_target(&rule.target, ctx)?;
+    Ok(Some(output_dir.join(folder).join(file_name)))
+}
+
+/// `path`, or `name (n).ext` next to it when that's already taken
+fn available_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+async fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if tokio::fs::rename(from, to).await.is_ok() {
+        return Ok(());
+    }
+    // Rename fails across filesystems
+    tokio::fs::copy(from, to)
+        .await
+        .map_err(|e| format!("Failed to move {:?} to {:?}: {}", from, to, e))?;
+    tokio::fs::remove_file(from)
+        .await
+        .map_err(|e| format!("Failed to remove {:?} after moving it: {}", from, e))
+}
+
+/// Move a finished file (and its companions, e.g. a chapter sidecar) to where the rules say.
+/// Returns the file's new path, or None when no rule applies.
+pub async fn organize(
+    file: &Path,
+    output_dir: &Path,
+    ctx: &OrganizeContext,
+    companions: &[PathBuf],
+) -> Result<Option<PathBuf>, String> {
+    let Some(file_name) = file.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return Ok(None);
+    };
+    let Some(target) = organized_path(output_dir, &file_name, ctx)? else {
+        return Ok(None);
+    };
+    if target.parent() == file.parent() {
+        return Ok(None);
+    }
+    let target_dir = target.parent().unwrap_or(output_dir).to_path_buf();
+    tokio::fs::create_dir_all(&target_dir)
+        .await
+        .map_err(|e| format!("Failed to create {:?}: {}", target_dir, e))?;
+
+    let target = available_path(&target);
+    move_file(file, &target).await?;
+    for companion in companions.iter().filter(|c| c.exists()) {
+        if let Some(name) = companion.file_name() {
+            if let Err(e) = move_file(companion, &available_path(&target_dir.join(name))).await {
+                println!("[Organizer] {}", e);
+            }
+        }
+    }
+    println!("[Organizer] Moved {:?} to {:?}", file, target);
+    Ok(Some(target))
+}
+
+fn save_rules(db: &Database, rules: Vec<OrganizeRule>) -> Result<(), String> {
+    let json = serde_json::to_string(&rules).map_err(|e| e.to_string())?;
+    db.save_setting(ORGANIZE_RULES_SETTING, &json)
+        .map_err(|e| e.to_string())?;
+    *RULES.write().unwrap() = rules;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_organize_rules() -> Result<Vec<OrganizeRule>, String> {
+    Ok(RULES.read().unwrap().clone())
+}
+
+/// Add a rule, or replace the one with the same id
+#[tauri::command]
+pub async fn save_organize_rule(state: State<'_, AppState>, mut rule: OrganizeRule) -> Result<OrganizeRule, String> {
+    if rule.name.trim().is_empty() {
+        return Err("Rule name cannot be empty".to_string());
+    }
+    if rule.target.trim().is_empty() {
+        return Err("Rule target cannot be empty".to_string());
+    }
+    // Catch template mistakes now rather than after a download finishes
+    render_target(&rule.target, &OrganizeContext::new("", Path::new("file")))?;
+    if rule.id.is_empty() {
+        rule.id = uuid::Uuid::new_v4().to_string();
+    }
+
+    let mut rules = RULES.read().unwrap().clone();
+    match rules.iter_mut().find(|r| r.id == rule.id) {
+        Some(existing) => *existing = rule.clone(),
+        None => rules.push(rule.clone()),
+    }
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    save_rules(&db, rules)?;
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_organize_rule(state: State<'_, AppState>, rule_id: String) -> Result<(), String> {
+    let mut rules = RULES.read().unwrap().clone();
+    rules.retain(|r| r.id != rule_id);
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    save_rules(&db, rules)
+}
+
+/// Where a download of `url` saved as `file_name` would end up after organization
+#[tauri::command]
+pub async fn preview_organized_path(
+    url: String,
+    file_name: String,
+    output_path: String,
+    artist: Option<String>,
+    uploader: Option<String>,
+) -> Result<String, String> {
+    let output_dir = PathBuf::from(&output_path);
+    let mut ctx = OrganizeContext::new(&url, Path::new(&file_name));
+    ctx.artist = artist.filter(|a| !a.trim().is_empty());
+    ctx.uploader = uploader.filter(|u| !u.trim().is_empty());
+    let path = organized_path(&output_dir, &file_name, &ctx)?.unwrap_or_else(|| output_dir.join(&file_name));
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(condition: RuleCondition, target: &str) -> OrganizeRule {
+        OrganizeRule {
+            id: target.to_string(),
+            name: target.to_string(),
+            enabled: true,
+            condition,
+            target: target.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_platform_for_url() {
+        assert_eq!(platform_for_url("https://www.youtube.com/watch?v=x"), "YouTube");
+        assert_eq!(platform_for_url("https://vm.tiktok.com/abc"), "TikTok");
+        assert_eq!(platform_for_url("https://files.example.com/a.zip"), "example");
+    }
+
+    #[test]
+    fn test_rules_and_templates() {
+        let rules = vec![
+            rule(RuleCondition { platform: Some("tiktok".to_string()), ..Default::default() }, "TikTok/{date}"),
+            rule(RuleCondition { kind: Some(MediaKind::Audio), ..Default::default() }, "Music/{artist}"),
+        ];
+        let mut ctx = OrganizeContext::new("https://soundcloud.com/a/b", Path::new("Song.mp3"));
+        ctx.date = chrono::NaiveDate::from_ymd_opt(2025, 3, 9).unwrap();
+
+        let matched = matching_rule(&rules, &ctx).unwrap();
+        assert_eq!(render_target(&matched.target, &ctx).unwrap(), PathBuf::from("Music").join(UNKNOWN_VALUE));
+        ctx.artist = Some("AC/DC".to_string());
+        assert_eq!(render_target(&matched.target, &ctx).unwrap(), PathBuf::from("Music").join("AC_DC"));
+
+        let tiktok = OrganizeContext { platform: "TikTok".to_string(), ..ctx.clone() };
+        assert_eq!(matching_rule(&rules, &tiktok).unwrap().target, "TikTok/{date}");
+        assert_eq!(render_target("TikTok/{date}", &tiktok).unwrap(), PathBuf::from("TikTok").join("2025-03-09"));
+
+        let video = OrganizeContext { kind: MediaKind::Video, ..ctx.clone() };
+        assert!(matching_rule(&rules, &video).is_none());
+
+        assert!(render_target("../Escape", &ctx).is_err());
+        assert!(render_target("Music/{album}", &ctx).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_organize_moves_file_and_companions() {
+        let dir = std::env::temp_dir().join(format!("ownstash-organize-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("Clip.mp4");
+        let sidecar = dir.join("Clip.chapters.json");
+        std::fs::write(&file, b"video").unwrap();
+        std::fs::write(&sidecar, b"[]").unwrap();
+
+        *RULES.write().unwrap() = vec![rule(RuleCondition { extension: Some("mp4".to_string()), ..Default::default() }, "{kind}")];
+        let ctx = OrganizeContext::new("https://example.com/Clip.mp4", &file);
+        let moved = organize(&file, &dir, &ctx, std::slice::from_ref(&sidecar)).await.unwrap().unwrap();
+        *RULES.write().unwrap() = Vec::new();
+
+        assert_eq!(moved, dir.join("Video").join("Clip.mp4"));
+        assert!(moved.exists() && !file.exists());
+        assert!(dir.join("Video").join("Clip.chapters.json").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
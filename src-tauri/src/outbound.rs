@@ -0,0 +1,140 @@
+//! Outbound Call Policy
+//!
+//! An "offline" switch for the requests the app makes on its own: update and version
+//! checks and background syncs. Anything the user starts (downloads, installs, manual
+//! syncs) still goes out.
+//!
+//! Key Features:
+//! - Single atomic flag, persisted in settings and restored at startup
+//! - Every automatic outbound call asks `allowed` first and is skipped when offline
+//! - `get_outbound_call_policy` lists those calls and whether they'd be blocked
+
+use crate::commands::AppState;
+use crate::database::Database;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::State;
+
+/// Settings key: "true" blocks automatic outbound calls
+pub const OFFLINE_MODE_SETTING: &str = "offline_mode";
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Outbound calls the app makes without the user asking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboundCall {
+    AppUpdateCheck,
+    YtDlpVersionCheck,
+    SpotDlVersionCheck,
+    FfmpegVersionCheck,
+    SubscriptionSync,
+}
+
+impl OutboundCall {
+    const ALL: [OutboundCall; 5] = [
+        OutboundCall::AppUpdateCheck,
+        OutboundCall::YtDlpVersionCheck,
+        OutboundCall::SpotDlVersionCheck,
+        OutboundCall::FfmpegVersionCheck,
+        OutboundCall::SubscriptionSync,
+    ];
+
+    fn description(self) -> &'static str {
+        match self {
+            OutboundCall::AppUpdateCheck => "Checking for new versions of the app",
+            OutboundCall::YtDlpVersionCheck => "Looking up the latest yt-dlp release",
+            OutboundCall::SpotDlVersionCheck => "Looking up the latest SpotDL release",
+            OutboundCall::FfmpegVersionCheck => "Looking up the latest FFmpeg build",
+            OutboundCall::SubscriptionSync => "Scheduled syncs of subscribed channels and playlists",
+        }
+    }
+
+    fn destination(self) -> &'static str {
+        match self {
+            OutboundCall::AppUpdateCheck => "App update feed",
+            OutboundCall::YtDlpVersionCheck
+            | OutboundCall::SpotDlVersionCheck
+            | OutboundCall::FfmpegVersionCheck => "api.github.com",
+            OutboundCall::SubscriptionSync => "Subscribed sites",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboundCallInfo {
+    pub call: OutboundCall,
+    pub description: String,
+    pub destination: String,
+    pub blocked: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboundCallPolicy {
+    pub offline_mode: bool,
+    pub calls: Vec<OutboundCallInfo>,
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Whether `call` may go out now; logs when it's skipped
+pub fn allowed(call: OutboundCall) -> bool {
+    if is_offline() {
+        println!("[Outbound] Skipped {:?}: offline mode is on", call);
+        return false;
+    }
+    true
+}
+
+/// Restore the offline switch; call once at startup
+pub fn load_from_settings(db: &Database) {
+    let offline = db.get_setting(OFFLINE_MODE_SETTING).ok().flatten().as_deref() == Some("true");
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+fn policy() -> OutboundCallPolicy {
+    let offline_mode = is_offline();
+    OutboundCallPolicy {
+        offline_mode,
+        calls: OutboundCall::ALL
+            .iter()
+            .map(|&call| OutboundCallInfo {
+                call,
+                description: call.description().to_string(),
+                destination: call.destination().to_string(),
+                blocked: offline_mode,
+            })
+            .collect(),
+    }
+}
+
+/// What the app calls out to on its own, and what offline mode currently blocks
+#[tauri::command]
+pub async fn get_outbound_call_policy() -> Result<OutboundCallPolicy, String> {
+    Ok(policy())
+}
+
+#[tauri::command]
+pub async fn set_offline_mode(state: State<'_, AppState>, enabled: bool) -> Result<OutboundCallPolicy, String> {
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(OFFLINE_MODE_SETTING, if enabled { "true" } else { "false" })
+            .map_err(|e| e.to_string())?;
+    }
+    OFFLINE.store(enabled, Ordering::Relaxed);
+    Ok(policy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_lists_every_call() {
+        let report = policy();
+        assert_eq!(report.calls.len(), OutboundCall::ALL.len());
+        assert!(report.calls.iter().all(|c| c.blocked == report.offline_mode));
+    }
+}
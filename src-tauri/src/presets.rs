@@ -0,0 +1,613 @@
+//! Download Presets
+//!
+//! Named bundles of download options ("Music 320k", "Archive 4K mkv") stored in the
+//! presets table, so a download can be configured in one click.
+//!
+//! Key Features:
+//! - Every option is optional; a preset only overrides what it sets
+//! - `apply_preset` overlays a preset onto a `DownloadRequest` for the UI to start or tweak
+//! - Device profiles ("TV 4K60", "Old laptop H.264 only") cap resolution, frame rate and
+//!   codec in format selection, and re-encode downloads the device still can't play
+
+use crate::commands::AppState;
+use crate::database::{Database, PresetRecord};
+use crate::downloader::DownloadRequest;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const MAX_PRESET_NAME_LEN: usize = 64;
+
+/// `presets.kind` values
+const DOWNLOAD_PRESET_KIND: &str = "download";
+const DEVICE_PROFILE_KIND: &str = "device";
+
+/// Settings key for the preset used by downloads started outside the download dialog
+pub const DEFAULT_PRESET_SETTING: &str = "default_download_preset";
+
+/// Settings key for the device profile used when a download doesn't pick one
+pub const DEFAULT_DEVICE_PROFILE_SETTING: &str = "default_device_profile";
+
+/// Codecs a device profile can allow
+const DEVICE_CODECS: &[&str] = &["h264", "vp9", "av1"];
+
+/// Options a preset can set; `None` leaves the request's value alone
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresetSettings {
+    pub quality: Option<String>,
+    pub format: Option<String>,
+    pub audio_only: Option<bool>,
+    pub audio_format: Option<String>,
+    pub audio_quality: Option<String>,
+    pub video_format: Option<String>,
+    pub video_codec: Option<String>,
+    pub dynamic_range: Option<String>,
+    pub remux_container: Option<String>,
+    pub download_subtitles: Option<bool>,
+    pub subtitle_languages: Option<Vec<String>>,
+    pub allow_auto_subs: Option<bool>,
+    pub embed_thumbnail: Option<bool>,
+    pub embed_metadata: Option<bool>,
+    pub use_sponsorblock: Option<bool>,
+    pub output_path: Option<String>,
+    pub filename_template: Option<String>,
+    pub device_profile_id: Option<String>,
+}
+
+impl PresetSettings {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(template) = &self.filename_template {
+            crate::downloader::validate_filename_template(template)?;
+        }
+        if self.output_path.as_ref().is_some_and(|p| p.trim().is_empty()) {
+            return Err("Preset output folder is empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// Overlay the preset's options onto a request
+    pub fn apply(&self, request: &mut DownloadRequest) {
+        fn set<T: Clone>(target: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *target = value.clone();
+            }
+        }
+        fn set_opt<T: Clone>(target: &mut Option<T>, value: &Option<T>) {
+            if value.is_some() {
+                *target = value.clone();
+            }
+        }
+
+        set_opt(&mut request.quality, &self.quality);
+        set_opt(&mut request.format, &self.format);
+        set(&mut request.audio_only, &self.audio_only);
+        set(&mut request.audio_format, &self.audio_format);
+        set(&mut request.audio_quality, &self.audio_quality);
+        set(&mut request.video_format, &self.video_format);
+        set_opt(&mut request.video_codec, &self.video_codec);
+        set_opt(&mut request.dynamic_range, &self.dynamic_range);
+        set_opt(&mut request.remux_container, &self.remux_container);
+        set(&mut request.download_subtitles, &self.download_subtitles);
+        set(&mut request.subtitle_languages, &self.subtitle_languages);
+        set(&mut request.allow_auto_subs, &self.allow_auto_subs);
+        set(&mut request.embed_thumbnail, &self.embed_thumbnail);
+        set(&mut request.embed_metadata, &self.embed_metadata);
+        set(&mut request.use_sponsorblock, &self.use_sponsorblock);
+        set(&mut request.output_path, &self.output_path);
+        set_opt(&mut request.filename_template, &self.filename_template);
+        set_opt(&mut request.device_profile_id, &self.device_profile_id);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadPreset {
+    pub id: String,
+    pub name: String,
+    pub settings: PresetSettings,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl From<PresetRecord> for DownloadPreset {
+    fn from(record: PresetRecord) -> Self {
+        Self {
+            settings: serde_json::from_str(&record.settings_json).unwrap_or_default(),
+            id: record.id,
+            name: record.name,
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+        }
+    }
+}
+
+/// Store a preset or device profile under a unique name; returns its id
+fn save_record(db: &Database, id: Option<String>, name: &str, kind: &str, json: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_PRESET_NAME_LEN {
+        return Err(format!("Preset name must be 1-{} characters", MAX_PRESET_NAME_LEN));
+    }
+
+    let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    if let Some(existing) = db.get_preset(&id).map_err(|e| e.to_string())? {
+        if existing.kind != kind {
+            return Err(format!("{} is not a {} preset", id, kind));
+        }
+    }
+    let taken = [DOWNLOAD_PRESET_KIND, DEVICE_PROFILE_KIND].iter().any(|kind| {
+        db.get_presets(kind)
+            .map(|presets| presets.iter().any(|p| p.id != id && p.name.eq_ignore_ascii_case(name)))
+            .unwrap_or(false)
+    });
+    if taken {
+        return Err(format!("A preset named '{}' already exists", name));
+    }
+    db.save_preset(&id, name, kind, json).map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Look up a download preset (not a device profile) by id
+pub(crate) fn get_download_preset(db: &Database, id: &str) -> Result<DownloadPreset, String> {
+    db.get_preset(id)
+        .map_err(|e| e.to_string())?
+        .filter(|p| p.kind == DOWNLOAD_PRESET_KIND)
+        .map(DownloadPreset::from)
+        .ok_or_else(|| format!("Preset {} not found", id))
+}
+
+/// The default download preset, if one is set and still exists
+pub(crate) fn default_download_preset(db: &Database) -> Option<DownloadPreset> {
+    let id = db.get_setting(DEFAULT_PRESET_SETTING).ok().flatten()?;
+    get_download_preset(db, &id).ok()
+}
+
+/// Set or clear (`preset_id: None`) the default download preset
+#[tauri::command]
+pub async fn set_default_preset(state: State<'_, AppState>, preset_id: Option<String>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    match preset_id {
+        Some(id) => {
+            get_download_preset(&db, &id)?;
+            db.save_setting(DEFAULT_PRESET_SETTING, &id)
+        }
+        None => db.delete_setting(DEFAULT_PRESET_SETTING),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Create a preset, or update it when `id` is given
+#[tauri::command]
+pub async fn save_preset(
+    state: State<'_, AppState>,
+    id: Option<String>,
+    name: String,
+    settings: PresetSettings,
+) -> Result<DownloadPreset, String> {
+    settings.validate()?;
+
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize preset: {}", e))?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let id = save_record(&db, id, &name, DOWNLOAD_PRESET_KIND, &json)?;
+    db.get_preset(&id)
+        .map_err(|e| e.to_string())?
+        .map(DownloadPreset::from)
+        .ok_or_else(|| "Preset was not saved".to_string())
+}
+
+#[tauri::command]
+pub async fn list_presets(state: State<'_, AppState>) -> Result<Vec<DownloadPreset>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let presets = db.get_presets(DOWNLOAD_PRESET_KIND).map_err(|e| e.to_string())?;
+    Ok(presets.into_iter().map(DownloadPreset::from).collect())
+}
+
+/// Return `request` with the preset's options applied
+#[tauri::command]
+pub async fn apply_preset(
+    state: State<'_, AppState>,
+    preset_id: String,
+    request: DownloadRequest,
+) -> Result<DownloadRequest, String> {
+    let preset = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        get_download_preset(&db, &preset_id)?
+    };
+
+    let mut request = request;
+    preset.settings.apply(&mut request);
+    Ok(request)
+}
+
+#[tauri::command]
+pub async fn delete_preset(state: State<'_, AppState>, preset_id: String) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.delete_preset(&preset_id).map_err(|e| e.to_string())
+}
+
+// ============ DEVICE PROFILES ============
+
+/// Playback limits of a target device
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceProfile {
+    pub max_height: Option<u32>,
+    pub max_fps: Option<u32>,
+    /// Codecs the device plays, most preferred first ("h264", "vp9", "av1"); empty means any
+    pub video_codecs: Vec<String>,
+    /// Re-encode downloads that still exceed the limits (e.g. only a VP9 stream existed)
+    pub transcode_incompatible: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedDeviceProfile {
+    pub id: String,
+    pub name: String,
+    pub profile: DeviceProfile,
+    /// Built-in profiles can't be edited or deleted
+    pub builtin: bool,
+}
+
+fn builtin_device_profiles() -> Vec<NamedDeviceProfile> {
+    let builtin = |id: &str, name: &str, max_height, max_fps, codecs: &[&str], transcode| NamedDeviceProfile {
+        id: id.to_string(),
+        name: name.to_string(),
+        profile: DeviceProfile {
+            max_height,
+            max_fps,
+            video_codecs: codecs.iter().map(|c| c.to_string()).collect(),
+            transcode_incompatible: transcode,
+        },
+        builtin: true,
+    };
+    vec![
+        builtin("builtin-tv-4k60", "TV 4K60", Some(2160), Some(60), &[], false),
+        builtin("builtin-phone-1080p30", "Phone 1080p30", Some(1080), Some(30), &["h264", "vp9"], true),
+        builtin("builtin-old-laptop-h264", "Old laptop H.264 only", Some(1080), Some(30), &["h264"], true),
+    ]
+}
+
+impl DeviceProfile {
+    fn validate(&self) -> Result<(), String> {
+        if self.max_height.is_some_and(|h| !(144..=4320).contains(&h)) {
+            return Err("Maximum height must be between 144 and 4320".to_string());
+        }
+        if self.max_fps.is_some_and(|f| !(1..=240).contains(&f)) {
+            return Err("Maximum frame rate must be between 1 and 240".to_string());
+        }
+        if let Some(codec) = self.video_codecs.iter().find(|c| !DEVICE_CODECS.contains(&c.as_str())) {
+            return Err(format!(
+                "Unsupported codec '{}'. Use one of: {}",
+                codec,
+                DEVICE_CODECS.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// ffprobe codec names for the allowed codecs
+    fn allows_codec(&self, probed: &str) -> bool {
+        self.video_codecs.is_empty()
+            || self.video_codecs.iter().any(|codec| match codec.as_str() {
+                "h264" => probed == "h264",
+                "vp9" => probed == "vp9",
+                "av1" => probed == "av1",
+                _ => false,
+            })
+    }
+
+    /// Why a file with this stream doesn't suit the device, if it doesn't
+    fn incompatibility(&self, stream: &crate::commands::VideoStreamInfo) -> Option<String> {
+        if !self.allows_codec(&stream.codec) {
+            return Some(format!("codec {} not supported", stream.codec));
+        }
+        if let (Some(max), Some(height)) = (self.max_height, stream.height) {
+            if height > max {
+                return Some(format!("{}p exceeds {}p", height, max));
+            }
+        }
+        if let (Some(max), Some(fps)) = (self.max_fps, stream.fps) {
+            // 59.94 counts as 60
+            if fps > max as f64 + 0.5 {
+                return Some(format!("{:.0} fps exceeds {} fps", fps, max));
+            }
+        }
+        None
+    }
+
+    /// ffmpeg arguments re-encoding to the device's first codec within its limits
+    fn transcode_args(&self, input: &str, output: &str, container: &str) -> Vec<String> {
+        let mut args: Vec<String> = ["-y", "-i", input, "-map", "0:v:0", "-map", "0:a?"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let video: &[&str] = match self.video_codecs.first().map(|c| c.as_str()) {
+            Some("vp9") => &["-c:v", "libvpx-vp9", "-crf", "32", "-b:v", "0"],
+            Some("av1") => &["-c:v", "libsvtav1", "-crf", "35"],
+            _ => &["-c:v", "libx264", "-preset", "medium", "-crf", "20", "-pix_fmt", "yuv420p"],
+        };
+        args.extend(video.iter().map(|s| s.to_string()));
+        if let Some(height) = self.max_height {
+            args.extend(["-vf".to_string(), format!("scale=-2:'min({},ih)'", height)]);
+        }
+        if let Some(fps) = self.max_fps {
+            args.extend(["-fpsmax".to_string(), fps.to_string()]);
+        }
+        let audio: &[&str] = match container {
+            "webm" => &["-c:a", "libopus", "-b:a", "160k"],
+            "mkv" => &["-c:a", "copy"],
+            _ => &["-c:a", "aac", "-b:a", "192k", "-movflags", "+faststart"],
+        };
+        args.extend(audio.iter().map(|s| s.to_string()));
+        args.push(output.to_string());
+        args
+    }
+}
+
+/// Find a device profile by id, built-in or user-defined
+fn get_device_profile(db: &Database, id: &str) -> Option<DeviceProfile> {
+    if let Some(builtin) = builtin_device_profiles().into_iter().find(|p| p.id == id) {
+        return Some(builtin.profile);
+    }
+    db.get_preset(id)
+        .ok()
+        .flatten()
+        .filter(|record| record.kind == DEVICE_PROFILE_KIND)
+        .and_then(|record| serde_json::from_str(&record.settings_json).ok())
+}
+
+/// The device profile for a download: its own choice, else the default. An empty id opts out.
+pub fn resolve_device_profile(app_handle: &AppHandle, requested: Option<&str>) -> Option<DeviceProfile> {
+    let state = app_handle.try_state::<AppState>()?;
+    let db = state.db.lock().ok()?;
+    let id = match requested {
+        Some(id) => id.to_string(),
+        None => db.get_setting(DEFAULT_DEVICE_PROFILE_SETTING).ok().flatten()?,
+    };
+    if id.trim().is_empty() {
+        return None;
+    }
+    get_device_profile(&db, id.trim())
+}
+
+/// Re-encode a finished download that the device can't play. Returns the new path
+/// when the file was replaced.
+async fn transcode_for_device(app_handle: &AppHandle, file: &Path, profile: &DeviceProfile) -> Result<Option<PathBuf>, String> {
+    if !profile.transcode_incompatible {
+        return Ok(None);
+    }
+
+    let input = file.to_string_lossy().to_string();
+    let probe_app = app_handle.clone();
+    let probe_input = input.clone();
+    let stream = tokio::task::spawn_blocking(move || crate::commands::probe_video_stream(&probe_app, &probe_input))
+        .await
+        .map_err(|e| format!("Failed to inspect download: {}", e))?;
+    let Some(stream) = stream else {
+        // Audio-only file, or no ffprobe to tell
+        return Ok(None);
+    };
+    let Some(reason) = profile.incompatibility(&stream) else {
+        return Ok(None);
+    };
+
+    let ffmpeg = crate::commands::find_ffmpeg(app_handle)
+        .ok_or_else(|| "FFmpeg not found. Cannot transcode for device.".to_string())?;
+
+    // H.264 can't go in WebM; everything else keeps its container
+    let extension = file.extension().and_then(|e| e.to_str()).unwrap_or("mp4").to_lowercase();
+    let container = match (extension.as_str(), profile.video_codecs.first().map(|c| c.as_str())) {
+        ("webm", Some("h264") | None) => "mp4".to_string(),
+        _ => extension,
+    };
+    let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("video");
+    let temp = file.with_file_name(format!("{}.device.{}", stem, container));
+    let output = file.with_extension(&container);
+
+    println!("[Presets] Transcoding {:?} for device ({})", file, reason);
+    let mut cmd = crate::exec_guard::tokio_command(&ffmpeg)?;
+    cmd.args(profile.transcode_args(&input, &temp.to_string_lossy(), &container))
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    {
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let result = cmd.output().await.map_err(|e| format!("Failed to run FFmpeg: {}", e))?;
+    if !result.status.success() {
+        let _ = std::fs::remove_file(&temp);
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let last_line = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("");
+        return Err(format!("Device transcode failed: {}", last_line));
+    }
+
+    // The original stays until the transcode is in place; rename replaces it when the
+    // container is unchanged
+    if let Err(e) = std::fs::rename(&temp, &output) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(format!("Failed to replace download: {}", e));
+    }
+    if output != file {
+        let _ = std::fs::remove_file(file);
+    }
+    Ok(Some(output))
+}
+
+/// Outcome of a queued device transcode, sent as `device-transcode-finished`
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceTranscodeFinished {
+    pub download_id: String,
+    /// The re-encoded file, when the download was replaced
+    pub file_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Transcode a completed download in the background, once the idle policy allows heavy jobs.
+/// The download is reported complete right away; the history entry follows the new file.
+pub fn queue_device_transcode(app_handle: &AppHandle, file: PathBuf, profile: DeviceProfile, download_id: String) {
+    let app = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        crate::idle::wait_for_heavy_job(&app, "device transcode", Some(&download_id)).await;
+        let result = transcode_for_device(&app, &file, &profile).await;
+        if let Ok(Some(transcoded)) = &result {
+            crate::downloader::record_final_file(&app, &download_id, transcoded);
+        }
+        if let Err(e) = &result {
+            println!("[Presets] {}", e);
+        }
+        let _ = app.emit("device-transcode-finished", DeviceTranscodeFinished {
+            download_id,
+            file_path: result.as_ref().ok().flatten().map(|p| p.to_string_lossy().to_string()),
+            error: result.err(),
+        });
+    });
+}
+
+#[tauri::command]
+pub async fn list_device_profiles(state: State<'_, AppState>) -> Result<Vec<NamedDeviceProfile>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let mut profiles = builtin_device_profiles();
+    for record in db.get_presets(DEVICE_PROFILE_KIND).map_err(|e| e.to_string())? {
+        profiles.push(NamedDeviceProfile {
+            profile: serde_json::from_str(&record.settings_json).unwrap_or_default(),
+            id: record.id,
+            name: record.name,
+            builtin: false,
+        });
+    }
+    Ok(profiles)
+}
+
+/// Create a device profile, or update it when `id` is given
+#[tauri::command]
+pub async fn save_device_profile(
+    state: State<'_, AppState>,
+    id: Option<String>,
+    name: String,
+    profile: DeviceProfile,
+) -> Result<NamedDeviceProfile, String> {
+    if id.as_deref().is_some_and(|id| id.starts_with("builtin-")) {
+        return Err("Built-in device profiles can't be changed".to_string());
+    }
+    profile.validate()?;
+
+    let json = serde_json::to_string(&profile).map_err(|e| format!("Failed to serialize device profile: {}", e))?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let id = save_record(&db, id, &name, DEVICE_PROFILE_KIND, &json)?;
+    Ok(NamedDeviceProfile {
+        id,
+        name: name.trim().to_string(),
+        profile,
+        builtin: false,
+    })
+}
+
+#[tauri::command]
+pub async fn delete_device_profile(state: State<'_, AppState>, profile_id: String) -> Result<(), String> {
+    if profile_id.starts_with("builtin-") {
+        return Err("Built-in device profiles can't be deleted".to_string());
+    }
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.delete_preset(&profile_id).map_err(|e| e.to_string())?;
+    if db.get_setting(DEFAULT_DEVICE_PROFILE_SETTING).ok().flatten().as_deref() == Some(profile_id.as_str()) {
+        db.delete_setting(DEFAULT_DEVICE_PROFILE_SETTING).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Set (or clear with `None`) the device profile downloads use by default
+#[tauri::command]
+pub async fn set_default_device_profile(
+    state: State<'_, AppState>,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    match profile_id {
+        Some(id) => {
+            if get_device_profile(&db, &id).is_none() {
+                return Err(format!("Device profile {} not found", id));
+            }
+            db.save_setting(DEFAULT_DEVICE_PROFILE_SETTING, &id)
+        }
+        None => db.delete_setting(DEFAULT_DEVICE_PROFILE_SETTING),
+    }
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> DownloadRequest {
+        serde_json::from_value(serde_json::json!({
+            "id": "test",
+            "url": "https://example.com/watch",
+            "output_path": "/downloads",
+            "format": null,
+            "audio_only": false,
+            "quality": "1080p",
+            "embed_thumbnail": false,
+            "embed_metadata": false,
+            "download_subtitles": true,
+            "audio_quality": "0",
+            "audio_format": "mp3",
+            "video_format": "mp4",
+            "use_sponsorblock": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_apply_only_overrides_set_options() {
+        let music = PresetSettings {
+            audio_only: Some(true),
+            audio_quality: Some("320k".to_string()),
+            output_path: Some("/music".to_string()),
+            ..Default::default()
+        };
+        let mut request = request();
+        music.apply(&mut request);
+
+        assert!(request.audio_only);
+        assert_eq!(request.audio_quality, "320k");
+        assert_eq!(request.output_path, "/music");
+        // Untouched options keep the request's values
+        assert_eq!(request.quality.as_deref(), Some("1080p"));
+        assert!(request.download_subtitles);
+    }
+
+    #[test]
+    fn test_device_profile_compatibility() {
+        let laptop = builtin_device_profiles().remove(2).profile;
+        let stream = |codec: &str, height, fps| crate::commands::VideoStreamInfo {
+            codec: codec.to_string(),
+            height: Some(height),
+            fps: Some(fps),
+        };
+
+        assert_eq!(laptop.incompatibility(&stream("h264", 1080, 29.97)), None);
+        assert!(laptop.incompatibility(&stream("vp9", 720, 30.0)).is_some());
+        assert!(laptop.incompatibility(&stream("h264", 1440, 30.0)).is_some());
+        assert!(laptop.incompatibility(&stream("h264", 1080, 60.0)).is_some());
+
+        let args = laptop.transcode_args("in.webm", "out.mp4", "mp4");
+        assert!(args.windows(2).any(|w| w == ["-c:v", "libx264"]));
+        assert!(args.windows(2).any(|w| w == ["-fpsmax", "30"]));
+
+        let bad = DeviceProfile {
+            video_codecs: vec!["mpeg2".to_string()],
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let bad_template = PresetSettings {
+            filename_template: Some("../%(title)s".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_template.validate().is_err());
+        assert!(PresetSettings::default().validate().is_ok());
+    }
+}
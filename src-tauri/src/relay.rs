@@ -0,0 +1,450 @@
+//! Download Relays
+//!
+//! For users with a seedbox or VPS: the relay fetches the URL server-side on its fast
+//! connection, then SNDE pulls the finished file from the relay with parallel ranges.
+//!
+//! Relay contract (JSON over HTTP, control calls send `Authorization: Bearer <token>`):
+//! - `GET /health` -> `{"version": "..."}`
+//! - `POST /jobs` with `{"url": "..."}` -> `{"id": "..."}`
+//! - `GET /jobs/{id}` -> `{"status": "queued|downloading|completed|failed", "downloaded_bytes",
+//!   "total_bytes", "filename", "download_url", "error"}`. `download_url` (absolute or relative
+//!   to the relay) must serve the file without further auth and support Range requests.
+//! - `DELETE /jobs/{id}` frees the relay's copy once it's been pulled
+//!
+//! Key Features:
+//! - Relay config CRUD, persisted in settings; tokens live in secure storage and are never
+//!   returned to the UI
+//! - Relays must use https, except on this machine
+//! - Health check with round-trip latency
+//! - Remote progress reported through the usual `download-progress` events
+
+use crate::commands::AppState;
+use crate::database::Database;
+use crate::downloader::DownloadProgress;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::oneshot;
+use url::Url;
+
+/// Settings key holding the JSON encoded relay list
+pub const RELAYS_SETTING: &str = "download_relays";
+
+pub const RELAY_BADGE: &str = "RELAY";
+
+const RELAY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Give up on a relay that stops answering for this long
+const RELAY_UNREACHABLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Hosts a relay may be reached on over plain http
+const LOOPBACK_HOSTS: &[&str] = &["localhost", "127.0.0.1", "[::1]"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayConfig {
+    pub id: String,
+    pub name: String,
+    pub base_url: String,
+    /// Only sent when setting or changing it; empty when returned
+    #[serde(default)]
+    pub token: String,
+    /// Whether a token is saved
+    #[serde(default)]
+    pub has_token: bool,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl RelayConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Relay name cannot be empty".to_string());
+        }
+        let url = Url::parse(self.base_url.trim()).map_err(|e| format!("Invalid relay URL: {}", e))?;
+        let loopback = url.host_str().is_some_and(|host| LOOPBACK_HOSTS.contains(&host));
+        match url.scheme() {
+            "https" => {}
+            "http" if loopback => {}
+            "http" => return Err("Relay URL must use https unless the relay runs on this machine".to_string()),
+            _ => return Err("Relay URL must be http or https".to_string()),
+        }
+        if self.token.trim().is_empty() {
+            return Err("Relay token cannot be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// Copy safe to store or hand to the UI
+    fn redacted(&self) -> Self {
+        Self { token: String::new(), has_token: !self.token.is_empty(), ..self.clone() }
+    }
+
+    /// `<base_url>/<path>`, keeping any path prefix the relay is mounted under
+    fn endpoint(&self, path: &str) -> Result<Url, String> {
+        let base = format!("{}/", self.base_url.trim().trim_end_matches('/'));
+        Url::parse(&base)
+            .and_then(|base| base.join(path.trim_start_matches('/')))
+            .map_err(|e| format!("Invalid relay URL: {}", e))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayHealth {
+    pub ok: bool,
+    pub version: Option<String>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JobCreated {
+    id: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct JobStatus {
+    status: String,
+    #[serde(default)]
+    downloaded_bytes: Option<u64>,
+    #[serde(default)]
+    total_bytes: Option<u64>,
+    #[serde(default)]
+    filename: Option<String>,
+    #[serde(default)]
+    download_url: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// A file the relay has finished fetching
+#[derive(Debug, Clone)]
+pub struct RelayedFile {
+    pub job_id: String,
+    pub download_url: String,
+    pub filename: Option<String>,
+}
+
+lazy_static::lazy_static! {
+    static ref RELAYS: RwLock<Vec<RelayConfig>> = RwLock::new(Vec::new());
+}
+
+fn token_key(relay_id: &str) -> String {
+    format!("relay_token:{}", relay_id)
+}
+
+fn save_relays_in(db: &Database, relays: &[RelayConfig]) -> Result<(), String> {
+    let redacted: Vec<RelayConfig> = relays.iter().map(RelayConfig::redacted).collect();
+    let json = serde_json::to_string(&redacted).map_err(|e| format!("Failed to serialize relays: {}", e))?;
+    db.save_setting(RELAYS_SETTING, &json).map_err(|e| e.to_string())
+}
+
+/// Apply the persisted relays at startup. Tokens saved in plain text by an older version
+/// are moved into secure storage.
+pub fn load_from_settings(app_handle: &AppHandle, db: &Database) {
+    let mut relays: Vec<RelayConfig> = db
+        .get_setting(RELAYS_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+
+    let mut migrated = false;
+    for relay in &mut relays {
+        if relay.token.is_empty() {
+            match crate::secure_storage::load_secret_in(app_handle, db, &token_key(&relay.id)) {
+                Ok(token) => relay.token = token.unwrap_or_default(),
+                Err(e) => println!("[Relay] Failed to read the token of {}: {}", relay.name, e),
+            }
+        } else if let Err(e) = crate::secure_storage::save_secret_in(app_handle, db, &token_key(&relay.id), &relay.token) {
+            println!("[Relay] Failed to move the token of {} to secure storage: {}", relay.name, e);
+        } else {
+            migrated = true;
+        }
+    }
+    if migrated {
+        if let Err(e) = save_relays_in(db, &relays) {
+            println!("[Relay] {}", e);
+        }
+    }
+    *RELAYS.write().unwrap() = relays;
+}
+
+/// An enabled relay by id
+pub fn find_relay(id: &str) -> Option<RelayConfig> {
+    RELAYS.read().unwrap().iter().find(|r| r.id == id && r.enabled).cloned()
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    crate::proxy::apply_to_client(reqwest::Client::builder())
+        .timeout(Duration::from_secs(20))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+async fn job_status(client: &reqwest::Client, relay: &RelayConfig, job_id: &str) -> Result<JobStatus, String> {
+    client
+        .get(relay.endpoint(&format!("jobs/{}", job_id))?)
+        .bearer_auth(&relay.token)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Relay status check failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid relay response: {}", e))
+}
+
+/// Free the relay's copy of a job; failures only leave a file on the relay
+pub async fn release_job(relay: &RelayConfig, job_id: &str) {
+    let Ok(client) = client() else { return };
+    let Ok(endpoint) = relay.endpoint(&format!("jobs/{}", job_id)) else { return };
+    if let Err(e) = client.delete(endpoint).bearer_auth(&relay.token).send().await {
+        println!("[Relay] Failed to release job {} on {}: {}", job_id, relay.name, e);
+    }
+}
+
+fn relay_progress(id: &str, status: &JobStatus) -> DownloadProgress {
+    let progress = match (status.downloaded_bytes, status.total_bytes) {
+        (Some(done), Some(total)) if total > 0 => done as f64 / total as f64 * 100.0,
+        _ => 0.0,
+    };
+    DownloadProgress {
+        id: id.to_string(),
+        progress,
+        status: "relaying".to_string(),
+        downloaded_bytes: status.downloaded_bytes.map(|b| b as i64),
+        total_bytes: status.total_bytes.map(|b| b as i64),
+        filename: status.filename.clone(),
+        engine_badge: Some(RELAY_BADGE.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Have the relay fetch `url` and wait until it's done. Returns where to pull the file from.
+pub async fn fetch_remote(
+    app_handle: &AppHandle,
+    relay: &RelayConfig,
+    download_id: &str,
+    url: &str,
+    cancel_rx: &mut oneshot::Receiver<()>,
+) -> Result<RelayedFile, String> {
+    let client = client()?;
+    let created: JobCreated = client
+        .post(relay.endpoint("jobs")?)
+        .bearer_auth(&relay.token)
+        .json(&serde_json::json!({ "url": url }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Relay {} rejected the job: {}", relay.name, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid relay response: {}", e))?;
+    println!("[Relay] {} fetching {} as job {}", relay.name, url, created.id);
+
+    let mut last_reachable = Instant::now();
+    loop {
+        tokio::select! {
+            _ = &mut *cancel_rx => {
+                release_job(relay, &created.id).await;
+                return Err("Download cancelled".to_string());
+            }
+            _ = tokio::time::sleep(RELAY_POLL_INTERVAL) => {}
+        }
+
+        let status = match job_status(&client, relay, &created.id).await {
+            Ok(status) => status,
+            Err(e) if last_reachable.elapsed() < RELAY_UNREACHABLE_TIMEOUT => {
+                println!("[Relay] {}", e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        last_reachable = Instant::now();
+
+        match status.status.as_str() {
+            "completed" => {
+                let download_url = status
+                    .download_url
+                    .as_deref()
+                    .ok_or("Relay finished without a download URL")?;
+                let download_url = relay.endpoint(download_url)?.to_string();
+                return Ok(RelayedFile {
+                    job_id: created.id,
+                    download_url,
+                    filename: status.filename,
+                });
+            }
+            "failed" => {
+                release_job(relay, &created.id).await;
+                return Err(format!(
+                    "Relay {} failed: {}",
+                    relay.name,
+                    status.error.as_deref().unwrap_or("unknown error")
+                ));
+            }
+            _ => {
+                let _ = app_handle.emit("download-progress", relay_progress(download_id, &status));
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_relays() -> Result<Vec<RelayConfig>, String> {
+    Ok(RELAYS.read().unwrap().iter().map(RelayConfig::redacted).collect())
+}
+
+/// Add a relay, or replace the one with the same id. An empty id gets a new one, and an
+/// empty token keeps the saved one.
+#[tauri::command]
+pub async fn save_relay(app_handle: AppHandle, mut relay: RelayConfig) -> Result<RelayConfig, String> {
+    relay.base_url = relay.base_url.trim().trim_end_matches('/').to_string();
+    relay.token = relay.token.trim().to_string();
+    if relay.id.trim().is_empty() {
+        relay.id = uuid::Uuid::new_v4().to_string();
+    }
+
+    let mut relays = RELAYS.read().unwrap().clone();
+    let new_token = !relay.token.is_empty();
+    if !new_token {
+        if let Some(existing) = relays.iter().find(|r| r.id == relay.id) {
+            relay.token = existing.token.clone();
+        }
+    }
+    relay.validate()?;
+
+    match relays.iter_mut().find(|r| r.id == relay.id) {
+        Some(existing) => *existing = relay.clone(),
+        None => relays.push(relay.clone()),
+    }
+    {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        if new_token {
+            crate::secure_storage::save_secret_in(&app_handle, &db, &token_key(&relay.id), &relay.token)?;
+        }
+        save_relays_in(&db, &relays)?;
+    }
+    *RELAYS.write().unwrap() = relays;
+    Ok(relay.redacted())
+}
+
+#[tauri::command]
+pub async fn delete_relay(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let mut relays = RELAYS.read().unwrap().clone();
+    let before = relays.len();
+    relays.retain(|r| r.id != id);
+    if relays.len() == before {
+        return Err(format!("Relay {} not found", id));
+    }
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        save_relays_in(&db, &relays)?;
+        db.delete_setting(&token_key(&id)).map_err(|e| e.to_string())?;
+    }
+    *RELAYS.write().unwrap() = relays;
+    Ok(())
+}
+
+/// Check that a relay answers and accepts its token
+#[tauri::command]
+pub async fn check_relay_health(id: String) -> Result<RelayHealth, String> {
+    let relay = RELAYS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|r| r.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Relay {} not found", id))?;
+
+    let started = Instant::now();
+    let result = client()?
+        .get(relay.endpoint("health")?)
+        .bearer_auth(&relay.token)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    Ok(match result {
+        Ok(response) => {
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            RelayHealth {
+                ok: true,
+                version: body["version"].as_str().map(|v| v.to_string()),
+                latency_ms,
+                error: None,
+            }
+        }
+        Err(e) => RelayHealth {
+            ok: false,
+            version: None,
+            latency_ms,
+            error: Some(e.to_string()),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relay(base_url: &str) -> RelayConfig {
+        RelayConfig {
+            id: "r1".to_string(),
+            name: "seedbox".to_string(),
+            base_url: base_url.to_string(),
+            token: "secret".to_string(),
+            has_token: false,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(relay("https://box.example.com").validate().is_ok());
+        assert!(relay("ftp://box.example.com").validate().is_err());
+        assert!(relay("not a url").validate().is_err());
+        assert!(RelayConfig { token: " ".to_string(), ..relay("https://box.example.com") }.validate().is_err());
+
+        // The token would travel in clear text
+        assert!(relay("http://box.example.com").validate().unwrap_err().contains("https"));
+        assert!(relay("http://127.0.0.1:8080").validate().is_ok());
+        assert!(relay("http://localhost:8080/relay").validate().is_ok());
+        assert!(relay("http://[::1]:8080").validate().is_ok());
+    }
+
+    #[test]
+    fn test_redacted_drops_the_token() {
+        let redacted = relay("https://box.example.com").redacted();
+        assert!(redacted.token.is_empty());
+        assert!(redacted.has_token);
+        assert!(!serde_json::to_string(&redacted).unwrap().contains("secret"));
+    }
+
+    #[test]
+    fn test_endpoint_keeps_prefix() {
+        let r = relay("https://box.example.com/relay/");
+        assert_eq!(r.endpoint("jobs/42").unwrap().as_str(), "https://box.example.com/relay/jobs/42");
+        assert_eq!(r.endpoint("/health").unwrap().as_str(), "https://box.example.com/relay/health");
+        assert_eq!(
+            r.endpoint("https://cdn.example.com/f/abc?key=1").unwrap().as_str(),
+            "https://cdn.example.com/f/abc?key=1"
+        );
+    }
+
+    #[test]
+    fn test_relay_progress() {
+        let status: JobStatus =
+            serde_json::from_str(r#"{"status":"downloading","downloaded_bytes":25,"total_bytes":100}"#).unwrap();
+        let progress = relay_progress("d1", &status);
+        assert_eq!(progress.progress, 25.0);
+        assert_eq!(progress.status, "relaying");
+    }
+}
@@ -0,0 +1,147 @@
+//! Custom Request Headers
+//!
+//! Per-download headers (Referer, Authorization, cookies) for direct downloads from hosts
+//! that want a referring page or a session, sent on top of the header profile.
+//!
+//! Key Features:
+//! - Checked when the download starts; malformed names or values are rejected
+//! - Headers the engine manages itself (Range, Host, Content-Length, ...) can't be overridden
+//! - The browser extension and deep links hand headers over with the URL; they're held
+//!   briefly and attached when that URL is downloaded
+//! - Only sent to the download's own URL, never to mirrors or relays
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Headers set by the download engines that a request can't replace
+const RESERVED_HEADERS: &[&str] = &[
+    "host",
+    "range",
+    "if-range",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+    "upgrade",
+];
+
+/// How long headers from the extension wait for their download to start
+const PENDING_TTL: Duration = Duration::from_secs(10 * 60);
+
+lazy_static::lazy_static! {
+    static ref PENDING: Mutex<HashMap<String, (Instant, HashMap<String, String>)>> = Mutex::new(HashMap::new());
+}
+
+/// Validate `headers` and turn them into a `HeaderMap`
+pub fn to_header_map(headers: &HashMap<String, String>) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = name.trim();
+        if RESERVED_HEADERS.iter().any(|reserved| name.eq_ignore_ascii_case(reserved)) {
+            return Err(format!("The {} header can't be set on a download", name));
+        }
+        let header_name =
+            HeaderName::from_bytes(name.as_bytes()).map_err(|_| format!("Invalid header name: {:?}", name))?;
+        let header_value =
+            HeaderValue::from_str(value.trim()).map_err(|_| format!("Invalid value for header {}", name))?;
+        map.insert(header_name, header_value);
+    }
+    Ok(map)
+}
+
+/// Headers from an extension request body: a `headers` object plus the `referrer` and
+/// `cookies` shortcuts
+pub fn from_extension_body(body: &serde_json::Value) -> HashMap<String, String> {
+    let mut headers: HashMap<String, String> = body
+        .get("headers")
+        .and_then(|h| h.as_object())
+        .map(|h| {
+            h.iter()
+                .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let referrer = body.get("referrer").or_else(|| body.get("referer")).and_then(|v| v.as_str());
+    if let Some(referrer) = referrer.filter(|r| !r.is_empty()) {
+        headers.insert("Referer".to_string(), referrer.to_string());
+    }
+    if let Some(cookies) = body.get("cookies").and_then(|v| v.as_str()).filter(|c| !c.is_empty()) {
+        headers.insert("Cookie".to_string(), cookies.to_string());
+    }
+    headers
+}
+
+/// Hold headers for `url` until a download of it starts
+pub fn stash(url: &str, headers: HashMap<String, String>) {
+    if headers.is_empty() {
+        return;
+    }
+    // Only the names; values are often secrets
+    let names: Vec<&str> = headers.keys().map(String::as_str).collect();
+    println!("[RequestHeaders] Holding {} for {}", names.join(", "), url);
+
+    let mut pending = PENDING.lock().unwrap();
+    pending.retain(|_, (stashed, _)| stashed.elapsed() < PENDING_TTL);
+    pending.insert(url.to_string(), (Instant::now(), headers));
+}
+
+/// Headers stashed for `url` without using them up, e.g. to probe before downloading
+pub fn peek_pending(url: &str) -> HashMap<String, String> {
+    PENDING
+        .lock()
+        .unwrap()
+        .get(url)
+        .filter(|(stashed, _)| stashed.elapsed() < PENDING_TTL)
+        .map(|(_, headers)| headers.clone())
+        .unwrap_or_default()
+}
+
+/// Headers stashed for `url`, if they haven't expired; each stash is used once
+pub fn take_pending(url: &str) -> HashMap<String, String> {
+    PENDING
+        .lock()
+        .unwrap()
+        .remove(url)
+        .filter(|(stashed, _)| stashed.elapsed() < PENDING_TTL)
+        .map(|(_, headers)| headers)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_header_map() {
+        let headers = HashMap::from([
+            ("Referer".to_string(), "https://example.com/page".to_string()),
+            ("Authorization".to_string(), "Bearer abc".to_string()),
+        ]);
+        let map = to_header_map(&headers).unwrap();
+        assert_eq!(map["referer"], "https://example.com/page");
+        assert_eq!(map["authorization"], "Bearer abc");
+
+        assert!(to_header_map(&HashMap::from([("Range".to_string(), "bytes=0-".to_string())])).is_err());
+        assert!(to_header_map(&HashMap::from([("Bad Name".to_string(), "x".to_string())])).is_err());
+        assert!(to_header_map(&HashMap::from([("X-Token".to_string(), "a\nb".to_string())])).is_err());
+    }
+
+    #[test]
+    fn test_extension_headers_are_stashed_once() {
+        let body = serde_json::json!({
+            "url": "https://files.example.com/a.zip",
+            "referrer": "https://example.com/",
+            "cookies": "session=1",
+            "headers": { "X-Token": "t" }
+        });
+        let headers = from_extension_body(&body);
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers["Cookie"], "session=1");
+
+        stash("https://files.example.com/a.zip", headers);
+        assert_eq!(take_pending("https://files.example.com/a.zip").len(), 3);
+        assert!(take_pending("https://files.example.com/a.zip").is_empty());
+    }
+}
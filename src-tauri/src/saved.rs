@@ -0,0 +1,210 @@
+//! Download Later
+//!
+//! A "read later" list for media: URLs are saved with their metadata now and downloaded
+//! in one go when the user is ready.
+//!
+//! Key Features:
+//! - `save_for_later` probes title, thumbnail and duration without downloading
+//! - `download_saved` starts the chosen items one after another with the default preset
+//! - Items already in the download history are skipped instead of fetched twice
+
+use crate::commands::AppState;
+use crate::database::SavedItem;
+use crate::downloader::{DownloadRequest, Downloader};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How long the metadata probe may take before the item is saved without it
+const SAVE_PROBE_TIMEOUT_SECS: u64 = 30;
+
+/// History statuses that mean the URL doesn't need downloading again
+const DOWNLOADED_STATUSES: &[&str] = &["completed", "downloading"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadSavedResult {
+    /// Download ids of the items that were queued, in order
+    pub queued: Vec<String>,
+    /// Items skipped because the history already has them
+    pub skipped: Vec<SavedItem>,
+}
+
+/// Saved items that aren't in `history_urls` (canonical URLs), and those that are
+fn split_downloaded(items: Vec<SavedItem>, history_urls: &HashSet<String>) -> (Vec<SavedItem>, Vec<SavedItem>) {
+    items
+        .into_iter()
+        .partition(|item| !history_urls.contains(&crate::coalesce::canonical_url(&item.url)))
+}
+
+/// Save a URL with its metadata to download later
+#[tauri::command]
+pub async fn save_for_later(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    url: String,
+    note: Option<String>,
+) -> Result<SavedItem, String> {
+    let url = url.trim().to_string();
+    url::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+
+    // Metadata is nice to have; a failed probe still saves the URL
+    let info = match Downloader::new(&app_handle)
+        .get_media_info(&url, false, None, None, std::time::Duration::from_secs(SAVE_PROBE_TIMEOUT_SECS))
+        .await
+    {
+        Ok(info) => Some(info),
+        Err(e) => {
+            println!("[Saved] No metadata for {}: {}", url, e);
+            None
+        }
+    };
+
+    let item = SavedItem {
+        id: uuid::Uuid::new_v4().to_string(),
+        url: url.clone(),
+        note: note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+        title: info.as_ref().map(|i| i.title.clone()),
+        thumbnail: info.as_ref().and_then(|i| i.thumbnail.clone()),
+        platform: info.as_ref().map(|i| i.platform.clone()),
+        duration: info.as_ref().and_then(|i| i.duration),
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_item(&item).map_err(|e| e.to_string())?;
+    db.get_saved_item_by_url(&url)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Item was not saved".to_string())
+}
+
+#[tauri::command]
+pub async fn list_saved(state: State<'_, AppState>) -> Result<Vec<SavedItem>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_saved_items().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_saved(state: State<'_, AppState>, ids: Vec<String>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    for id in ids {
+        db.delete_saved_item(&id).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Queue saved items for download with the default preset. `output_path` overrides the
+/// preset's folder; without either, the default download folder is used.
+#[tauri::command]
+pub async fn download_saved(
+    app_handle: AppHandle,
+    ids: Vec<String>,
+    output_path: Option<String>,
+) -> Result<DownloadSavedResult, String> {
+    let default_path = crate::downloader::get_default_download_path(app_handle.clone()).await?;
+
+    let (items, preset, history_urls) = {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let wanted: HashSet<&String> = ids.iter().collect();
+        let items: Vec<SavedItem> = db
+            .get_saved_items()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|item| wanted.contains(&item.id))
+            .collect();
+        let history_urls: HashSet<String> = db
+            .get_downloads()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|d| DOWNLOADED_STATUSES.contains(&d.status.as_str()))
+            .map(|d| crate::coalesce::canonical_url(&d.url))
+            .collect();
+        (items, crate::presets::default_download_preset(&db), history_urls)
+    };
+    let (to_download, skipped) = split_downloaded(items, &history_urls);
+
+    let mut queue: Vec<(SavedItem, DownloadRequest)> = Vec::new();
+    for item in to_download {
+        let mut request = DownloadRequest::with_defaults(&item.url, &default_path)?;
+        if let Some(preset) = &preset {
+            preset.settings.apply(&mut request);
+        }
+        if let Some(path) = output_path.as_ref().filter(|p| !p.trim().is_empty()) {
+            request.output_path = path.clone();
+        }
+        queue.push((item, request));
+    }
+
+    // Record everything up front so the history shows the whole batch
+    {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        for (item, request) in &queue {
+            db.add_download(&crate::database::Download {
+                id: request.id.clone(),
+                title: item.title.clone().unwrap_or_else(|| item.url.clone()),
+                url: request.url.clone(),
+                format: if request.audio_only { request.audio_format.clone() } else { request.video_format.clone() },
+                path: request.output_path.clone(),
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                status: "queued".to_string(),
+                size_bytes: None,
+                platform: item.platform.clone(),
+                thumbnail: item.thumbnail.clone(),
+                on_complete: None,
+            })
+            .map_err(|e| e.to_string())?;
+            db.delete_saved_item(&item.id).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let result = DownloadSavedResult {
+        queued: queue.iter().map(|(_, r)| r.id.clone()).collect(),
+        skipped,
+    };
+    let _ = app_handle.emit("saved-downloads-queued", &result);
+
+    let app = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        for (item, request) in queue {
+            if let Err(e) = crate::downloader::run_download(&app, request, None).await {
+                println!("[Saved] Failed to download {}: {}", item.url, e);
+            }
+        }
+    });
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, url: &str) -> SavedItem {
+        SavedItem {
+            id: id.to_string(),
+            url: url.to_string(),
+            note: None,
+            title: None,
+            thumbnail: None,
+            platform: None,
+            duration: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_history_dedupe() {
+        let history: HashSet<String> = [crate::coalesce::canonical_url("https://www.youtube.com/watch?v=a")]
+            .into_iter()
+            .collect();
+        let (fresh, skipped) = split_downloaded(
+            vec![
+                item("1", "https://youtube.com/watch?v=a&utm_source=share"),
+                item("2", "https://youtube.com/watch?v=b"),
+            ],
+            &history,
+        );
+        assert_eq!(fresh.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(), vec!["2"]);
+        assert_eq!(skipped.iter().map(|i| i.id.as_str()).collect::<Vec<_>>(), vec!["1"]);
+    }
+}
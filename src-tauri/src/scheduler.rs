@@ -8,6 +8,7 @@
 //! - Bandwidth allocation based on download priority
 //! - Rate limiting to prevent network saturation
 //! - Concurrent download limits
+//! - Periodic background jobs (`every`) for housekeeping like subscription syncs
 
 use crate::health_metrics::{DownloadEngine, DownloadPhase, HEALTH_REGISTRY};
 use serde::{Deserialize, Serialize};
@@ -185,7 +186,10 @@ impl GlobalScheduler {
                     // Check SNDE semaphore
                     self.snde_semaphore.available_permits() > 0
                 }
-                DownloadEngine::MediaEngine => true,
+                DownloadEngine::MediaEngine
+                | DownloadEngine::Aria2
+                | DownloadEngine::Gallery
+                | DownloadEngine::Ftp => true,
             }
         });
 
@@ -395,6 +399,23 @@ pub struct SchedulerStatus {
     pub available_slots: usize,
 }
 
+/// Run `job` every `period`, starting now, for the lifetime of the app. A run that
+/// overruns the period delays the next one instead of bunching them up.
+pub fn every<F, Fut>(period: Duration, mut job: F)
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = ()> + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            job().await;
+        }
+    });
+}
+
 /// Global scheduler instance
 lazy_static::lazy_static! {
     pub static ref GLOBAL_SCHEDULER: GlobalScheduler = GlobalScheduler::new();
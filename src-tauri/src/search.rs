@@ -0,0 +1,329 @@
+//! In-App Search Providers
+//!
+//! Finds things to download without leaving the app. Each source is a `SearchProvider`
+//! turning a query into results; the search fans out to the enabled ones.
+//!
+//! Key Features:
+//! - YouTube and SoundCloud through yt-dlp's `ytsearch` / `scsearch`, so no API keys
+//! - Archive.org through its public advanced search API
+//! - Providers can be turned off one by one; the choice is saved in settings
+//! - Providers run side by side; one failing doesn't hide the others' results
+
+use crate::commands::AppState;
+use crate::database::Database;
+use crate::downloader::{Downloader, PlaylistEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+
+/// Settings key holding the JSON map of provider id to enabled
+pub const SEARCH_PROVIDERS_SETTING: &str = "search_providers";
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 50;
+
+/// One thing a provider found
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// Id of the provider that found it
+    pub provider: String,
+    pub id: String,
+    pub title: String,
+    /// What to hand to a download
+    pub url: String,
+    pub uploader: Option<String>,
+    /// Seconds
+    pub duration: Option<i64>,
+    pub thumbnail: Option<String>,
+}
+
+pub type SearchFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<SearchResult>, String>> + Send + 'a>>;
+
+/// A source of search results
+pub trait SearchProvider: Send + Sync {
+    /// Stable identifier, used for the enable toggles
+    fn id(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    /// Up to `limit` results for `query`
+    fn search<'a>(&'a self, app_handle: &'a AppHandle, query: &'a str, limit: usize) -> SearchFuture<'a>;
+}
+
+/// Sites yt-dlp can search with a `<prefix><limit>:<query>` pseudo-URL
+struct YtDlpSearch {
+    id: &'static str,
+    name: &'static str,
+    prefix: &'static str,
+}
+
+impl YtDlpSearch {
+    fn result(&self, entry: PlaylistEntry) -> Option<SearchResult> {
+        let id = entry.id?;
+        let url = entry.url?;
+        let thumbnail = (self.id == "youtube").then(|| format!("https://i.ytimg.com/vi/{}/hqdefault.jpg", id));
+        Some(SearchResult {
+            provider: self.id.to_string(),
+            id,
+            title: entry.title,
+            url,
+            uploader: None,
+            duration: entry.duration,
+            thumbnail,
+        })
+    }
+}
+
+impl SearchProvider for YtDlpSearch {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn search<'a>(&'a self, app_handle: &'a AppHandle, query: &'a str, limit: usize) -> SearchFuture<'a> {
+        Box::pin(async move {
+            let search_url = format!("{}{}:{}", self.prefix, limit, query);
+            let listing = Downloader::new(app_handle).get_playlist_entries(&search_url, None).await?;
+            Ok(listing.entries.into_iter().filter_map(|entry| self.result(entry)).collect())
+        })
+    }
+}
+
+struct ArchiveOrgSearch;
+
+/// Results of an advanced search response (`response.docs`)
+fn parse_archive_results(json: &serde_json::Value) -> Vec<SearchResult> {
+    let Some(docs) = json["response"]["docs"].as_array() else {
+        return Vec::new();
+    };
+    docs.iter()
+        .filter_map(|doc| {
+            let identifier = doc["identifier"].as_str()?;
+            // `creator` is a string or a list of them
+            let creator = match &doc["creator"] {
+                serde_json::Value::String(creator) => Some(creator.clone()),
+                serde_json::Value::Array(creators) => creators.first().and_then(|c| c.as_str()).map(str::to_string),
+                _ => None,
+            };
+            Some(SearchResult {
+                provider: "archive_org".to_string(),
+                id: identifier.to_string(),
+                title: doc["title"].as_str().unwrap_or(identifier).to_string(),
+                url: format!("https://archive.org/details/{}", identifier),
+                uploader: creator,
+                duration: None,
+                thumbnail: Some(format!("https://archive.org/services/img/{}", identifier)),
+            })
+        })
+        .collect()
+}
+
+impl SearchProvider for ArchiveOrgSearch {
+    fn id(&self) -> &'static str {
+        "archive_org"
+    }
+
+    fn name(&self) -> &'static str {
+        "Archive.org"
+    }
+
+    fn search<'a>(&'a self, _app_handle: &'a AppHandle, query: &'a str, limit: usize) -> SearchFuture<'a> {
+        Box::pin(async move {
+            let client = crate::proxy::apply_to_client(reqwest::Client::builder())
+                .timeout(Duration::from_secs(20))
+                .build()
+                .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+            let rows = limit.to_string();
+            let response = client
+                .get("https://archive.org/advancedsearch.php")
+                .query(&[
+                    ("q", query),
+                    ("fl[]", "identifier"),
+                    ("fl[]", "title"),
+                    ("fl[]", "creator"),
+                    ("rows", rows.as_str()),
+                    ("output", "json"),
+                ])
+                .send()
+                .await
+                .map_err(|e| format!("Archive.org search failed: {}", e))?;
+            if !response.status().is_success() {
+                return Err(format!("Archive.org search returned {}", response.status()));
+            }
+            let json: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse Archive.org results: {}", e))?;
+            Ok(parse_archive_results(&json))
+        })
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref PROVIDERS: Vec<Box<dyn SearchProvider>> = vec![
+        Box::new(YtDlpSearch { id: "youtube", name: "YouTube", prefix: "ytsearch" }),
+        Box::new(YtDlpSearch { id: "soundcloud", name: "SoundCloud", prefix: "scsearch" }),
+        Box::new(ArchiveOrgSearch),
+    ];
+
+    /// Enabled flag per provider id; providers missing from it are enabled
+    static ref ENABLED: RwLock<HashMap<String, bool>> = RwLock::new(HashMap::new());
+}
+
+/// Restore the provider toggles at startup
+pub fn load_from_settings(db: &Database) {
+    let enabled = db
+        .get_setting(SEARCH_PROVIDERS_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    *ENABLED.write().unwrap() = enabled;
+}
+
+fn is_enabled(toggles: &HashMap<String, bool>, id: &str) -> bool {
+    toggles.get(id).copied().unwrap_or(true)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchProviderInfo {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderError {
+    pub provider: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResponse {
+    /// Grouped by provider, in provider order
+    pub results: Vec<SearchResult>,
+    pub errors: Vec<ProviderError>,
+}
+
+#[tauri::command]
+pub async fn list_search_providers() -> Result<Vec<SearchProviderInfo>, String> {
+    let toggles = ENABLED.read().unwrap();
+    Ok(PROVIDERS
+        .iter()
+        .map(|provider| SearchProviderInfo {
+            id: provider.id().to_string(),
+            name: provider.name().to_string(),
+            enabled: is_enabled(&toggles, provider.id()),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn set_search_provider_enabled(
+    state: State<'_, AppState>,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    if !PROVIDERS.iter().any(|provider| provider.id() == id) {
+        return Err(format!("Unknown search provider: {}", id));
+    }
+    let mut toggles = ENABLED.read().unwrap().clone();
+    toggles.insert(id, enabled);
+    let json = serde_json::to_string(&toggles).map_err(|e| format!("Failed to serialize search providers: {}", e))?;
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(SEARCH_PROVIDERS_SETTING, &json).map_err(|e| e.to_string())?;
+    }
+    *ENABLED.write().unwrap() = toggles;
+    Ok(())
+}
+
+/// Search the enabled providers, or only `providers` among them, for `query`
+#[tauri::command]
+pub async fn search_media(
+    app_handle: AppHandle,
+    query: String,
+    providers: Option<Vec<String>>,
+    limit: Option<usize>,
+) -> Result<SearchResponse, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Enter something to search for".to_string());
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let selected: Vec<&dyn SearchProvider> = {
+        let toggles = ENABLED.read().unwrap();
+        PROVIDERS
+            .iter()
+            .map(|provider| provider.as_ref())
+            .filter(|provider| is_enabled(&toggles, provider.id()))
+            .filter(|provider| providers.as_ref().is_none_or(|ids| ids.iter().any(|id| id == provider.id())))
+            .collect()
+    };
+    if selected.is_empty() {
+        return Err("No search provider is enabled".to_string());
+    }
+
+    let searches = selected.iter().map(|provider| provider.search(&app_handle, query, limit));
+    let outcomes = futures_util::future::join_all(searches).await;
+
+    let mut response = SearchResponse { results: Vec::new(), errors: Vec::new() };
+    for (provider, outcome) in selected.iter().zip(outcomes) {
+        match outcome {
+            Ok(results) => response.results.extend(results),
+            Err(error) => {
+                println!("[Search] {} failed: {}", provider.name(), error);
+                response.errors.push(ProviderError { provider: provider.id().to_string(), error });
+            }
+        }
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_archive_results() {
+        let json = serde_json::json!({
+            "response": { "docs": [
+                { "identifier": "night_of_the_living_dead", "title": "Night of the Living Dead", "creator": ["George A. Romero"] },
+                { "identifier": "untitled-item" },
+                { "title": "no identifier" }
+            ] }
+        });
+        let results = parse_archive_results(&json);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].url, "https://archive.org/details/night_of_the_living_dead");
+        assert_eq!(results[0].uploader.as_deref(), Some("George A. Romero"));
+        assert_eq!(results[1].title, "untitled-item");
+        assert!(parse_archive_results(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_youtube_results_from_flat_entries() {
+        let youtube = YtDlpSearch { id: "youtube", name: "YouTube", prefix: "ytsearch" };
+        let entry = PlaylistEntry {
+            index: 1,
+            id: Some("dQw4w9WgXcQ".to_string()),
+            extractor: Some("youtube".to_string()),
+            title: "Video".to_string(),
+            url: Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string()),
+            duration: Some(212),
+        };
+        let result = youtube.result(entry.clone()).unwrap();
+        assert_eq!(result.provider, "youtube");
+        assert_eq!(result.thumbnail.as_deref(), Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg"));
+        assert!(youtube.result(PlaylistEntry { url: None, ..entry }).is_none());
+
+        let toggles = HashMap::from([("youtube".to_string(), false)]);
+        assert!(!is_enabled(&toggles, "youtube"));
+        assert!(is_enabled(&toggles, "soundcloud"));
+    }
+}
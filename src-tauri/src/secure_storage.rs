@@ -9,6 +9,7 @@ use argon2::Argon2;
 use rand::RngCore;
 use tauri::{AppHandle, Manager, State};
 use crate::commands::AppState;
+use crate::database::Database;
 use std::fs;
 
 const NONCE_SIZE: usize = 12;
@@ -91,14 +92,9 @@ fn get_system_key(app_handle: &AppHandle) -> Result<[u8; KEY_SIZE], String> {
     Ok(key)
 }
 
-#[tauri::command]
-pub async fn secure_save_setting(
-    app_handle: AppHandle,
-    state: State<'_, AppState>,
-    key: String,
-    value: String,
-) -> Result<(), String> {
-    let system_key = get_system_key(&app_handle)?;
+/// Encrypt `value` with the machine-bound key, as hex of nonce + ciphertext
+fn encrypt_value(app_handle: &AppHandle, value: &str) -> Result<String, String> {
+    let system_key = get_system_key(app_handle)?;
     let cipher = Aes256Gcm::new_from_slice(&system_key)
         .map_err(|e| format!("Failed to create cipher: {}", e))?;
 
@@ -117,30 +113,13 @@ pub async fn secure_save_setting(
     combined.extend_from_slice(&ciphertext);
 
     // Encode as hex for database storage
-    let encoded = to_hex(&combined);
-
-    // Save to database
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.save_setting(&key, &encoded).map_err(|e| e.to_string())
+    Ok(to_hex(&combined))
 }
 
-#[tauri::command]
-pub async fn secure_get_setting(
-    app_handle: AppHandle,
-    state: State<'_, AppState>,
-    key: String,
-) -> Result<Option<String>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let encoded = db.get_setting(&key).map_err(|e| e.to_string())?;
-    drop(db);
-
-    let hex_str = match encoded {
-        Some(s) => s,
-        None => return Ok(None),
-    };
-
+/// Reverse of `encrypt_value`
+fn decrypt_value(app_handle: &AppHandle, hex_str: &str) -> Result<String, String> {
     // Decode hex
-    let combined = from_hex(&hex_str)?;
+    let combined = from_hex(hex_str)?;
 
     if combined.len() < NONCE_SIZE {
         return Err("Stored data is corrupted".to_string());
@@ -149,7 +128,7 @@ pub async fn secure_get_setting(
     let (nonce_bytes, ciphertext) = combined.split_at(NONCE_SIZE);
     let nonce = Nonce::from_slice(nonce_bytes);
 
-    let system_key = get_system_key(&app_handle)?;
+    let system_key = get_system_key(app_handle)?;
     let cipher = Aes256Gcm::new_from_slice(&system_key)
         .map_err(|e| format!("Failed to create cipher: {}", e))?;
 
@@ -157,10 +136,72 @@ pub async fn secure_get_setting(
     let plaintext = cipher.decrypt(nonce, ciphertext)
         .map_err(|_| "Decryption failed - possibly the database was moved from another machine or corrupted".to_string())?;
 
-    let value = String::from_utf8(plaintext)
-        .map_err(|e| format!("Invalid UTF-8 in decrypted data: {}", e))?;
+    String::from_utf8(plaintext)
+        .map_err(|e| format!("Invalid UTF-8 in decrypted data: {}", e))
+}
+
+/// Encrypt and store a secret for the backend's own use
+pub(crate) fn save_secret(app_handle: &AppHandle, key: &str, value: &str) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    save_secret_in(app_handle, &db, key, value)
+}
+
+/// `save_secret` for callers already holding the database
+pub(crate) fn save_secret_in(app_handle: &AppHandle, db: &Database, key: &str, value: &str) -> Result<(), String> {
+    let encoded = encrypt_value(app_handle, value)?;
+    db.save_setting(key, &encoded).map_err(|e| e.to_string())
+}
+
+/// A secret stored with `save_secret` or `secure_save_setting`
+pub(crate) fn load_secret(app_handle: &AppHandle, key: &str) -> Result<Option<String>, String> {
+    let encoded = {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.get_setting(key).map_err(|e| e.to_string())?
+    };
+    encoded.map(|hex_str| decrypt_value(app_handle, &hex_str)).transpose()
+}
+
+/// `load_secret` for callers already holding the database
+pub(crate) fn load_secret_in(app_handle: &AppHandle, db: &Database, key: &str) -> Result<Option<String>, String> {
+    let encoded = db.get_setting(key).map_err(|e| e.to_string())?;
+    encoded.map(|hex_str| decrypt_value(app_handle, &hex_str)).transpose()
+}
+
+#[tauri::command]
+pub async fn secure_save_setting(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    key: String,
+    value: String,
+) -> Result<(), String> {
+    let encoded = encrypt_value(&app_handle, &value)?;
+
+    // Save to database
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(&key, &encoded).map_err(|e| e.to_string())
+}
 
-    Ok(Some(value))
+#[tauri::command]
+pub async fn secure_get_setting(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    key: String,
+) -> Result<Option<String>, String> {
+    // The proxy password is only handed to the engines, never back to the UI
+    if key == crate::proxy::PROXY_PASSWORD_SECRET {
+        return Err(format!("'{}' can't be read back", key));
+    }
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let encoded = db.get_setting(&key).map_err(|e| e.to_string())?;
+    drop(db);
+
+    match encoded {
+        Some(hex_str) => decrypt_value(&app_handle, &hex_str).map(Some),
+        None => Ok(None),
+    }
 }
 
 #[tauri::command]
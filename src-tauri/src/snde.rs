@@ -9,45 +9,579 @@
 //! - Safe range management (no mid-stream splitting)
 //! - Automatic throttling detection and connection collapse
 //! - Integration with Host Reputation for optimal starting configuration
-
+//! - Pause/resume from a `.snde-state` sidecar of completed chunk ranges, also after a restart;
+//!   the file's ETag / Last-Modified must match, and ranges are sent with If-Range
+//! - Optional MD5/SHA-1/SHA-256 verification of the finished file
+//! - Chunks spread over mirror URLs of the same file, dropping slow or failing mirrors
+//! - Connection ramp-up from 2, remembering each host's fastest count in Host Reputation
+//! - Watchdog collapses shed workers mid-download; Safe Mode leaves one HTTP/2 connection
+//! - Speed test measuring 1, 4 and 8 connections against a configurable URL
+//! - HTTP/3 for hosts that advertise it (built with the `http3` feature), back to TCP when
+//!   the QUIC handshake fails
+
+use crate::checksum::ExpectedChecksum;
+use crate::bandwidth::{self, BandwidthLimiter, GLOBAL_BANDWIDTH_LIMITER};
 use crate::download_router::RoutingDecision;
+use crate::mirrors::MirrorPool;
 use crate::health_metrics::{
-    ConnectionHealth, DownloadEngine, DownloadHealth, DownloadPhase, 
+    DownloadEngine, DownloadHealth, DownloadPhase, 
     HEALTH_REGISTRY, WatchdogAction,
 };
+use crate::watchdog::WatchdogCommand;
 use crate::host_reputation::extract_domain;
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_RANGES, CONTENT_LENGTH, RANGE, USER_AGENT};
-use reqwest::{Client, Response, Version};
+use crate::warc::HttpExchange;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, IF_RANGE, RANGE, RETRY_AFTER};
+use reqwest::{Client, Response, StatusCode, Version};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::SeekFrom;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
+use crate::commands::AppState;
+use tauri::{AppHandle, Emitter, State};
 use tokio::fs::{File, OpenOptions};
-use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt, BufWriter};
 use tokio::sync::{mpsc, Mutex, RwLock, Semaphore};
 
-/// Maximum number of concurrent connections per download
+/// Default maximum number of concurrent connections per download
 const MAX_CONNECTIONS: u8 = 16;
 
+/// Upper bound users can raise the connection limit to
+const MAX_CONNECTIONS_LIMIT: u8 = 32;
+
 /// Minimum chunk size (1MB) - don't split below this
 const MIN_CHUNK_SIZE: u64 = 1024 * 1024;
 
 /// Default chunk size for work distribution (8MB)
 const DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
 
-/// Buffer size for reading response body (256KB)
+/// Largest configurable chunk size (256MB)
+const MAX_CHUNK_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Write buffer per worker (256KB), so small body frames don't each cost a syscall
 const BUFFER_SIZE: usize = 256 * 1024;
 
 /// Stall detection timeout (10 seconds with no progress)
 const STALL_TIMEOUT_SECS: u64 = 10;
 
+/// Bounds for the configurable stall timeout
+const MIN_STALL_TIMEOUT_SECS: u64 = 3;
+const MAX_STALL_TIMEOUT_SECS: u64 = 300;
+
+/// Settings key for the global engine limits (JSON `SNDELimits`)
+pub const SNDE_LIMITS_SETTING: &str = "snde_limits";
+
+/// Settings key for per-host overrides (JSON map of domain -> `SNDELimitOverride`)
+pub const SNDE_HOST_OVERRIDES_SETTING: &str = "snde_host_overrides";
+
 /// Throttling detection - if speed drops below this % of peak, consider throttled
 const THROTTLE_THRESHOLD_PERCENT: f64 = 0.3;
 
+/// User-tunable engine limits, read when a download starts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SNDELimits {
+    pub max_connections: u8,
+    pub chunk_size_mb: u64,
+    pub stall_timeout_secs: u64,
+}
+
+impl Default for SNDELimits {
+    fn default() -> Self {
+        Self {
+            max_connections: MAX_CONNECTIONS,
+            chunk_size_mb: DEFAULT_CHUNK_SIZE / (1024 * 1024),
+            stall_timeout_secs: STALL_TIMEOUT_SECS,
+        }
+    }
+}
+
+impl SNDELimits {
+    /// Clamp every value into its safe range
+    pub fn clamped(self) -> Self {
+        Self {
+            max_connections: self.max_connections.clamp(1, MAX_CONNECTIONS_LIMIT),
+            chunk_size_mb: self
+                .chunk_size_mb
+                .clamp(MIN_CHUNK_SIZE / (1024 * 1024), MAX_CHUNK_SIZE / (1024 * 1024)),
+            stall_timeout_secs: self
+                .stall_timeout_secs
+                .clamp(MIN_STALL_TIMEOUT_SECS, MAX_STALL_TIMEOUT_SECS),
+        }
+    }
+
+    fn chunk_size_bytes(&self) -> u64 {
+        self.chunk_size_mb * 1024 * 1024
+    }
+
+    fn with_override(self, host: &SNDELimitOverride) -> Self {
+        Self {
+            max_connections: host.max_connections.unwrap_or(self.max_connections),
+            chunk_size_mb: host.chunk_size_mb.unwrap_or(self.chunk_size_mb),
+            stall_timeout_secs: host.stall_timeout_secs.unwrap_or(self.stall_timeout_secs),
+        }
+        .clamped()
+    }
+}
+
+/// Per-host replacement for some or all of the global limits
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SNDELimitOverride {
+    #[serde(default)]
+    pub max_connections: Option<u8>,
+    #[serde(default)]
+    pub chunk_size_mb: Option<u64>,
+    #[serde(default)]
+    pub stall_timeout_secs: Option<u64>,
+}
+
+fn load_limits(app_handle: &AppHandle) -> SNDELimits {
+    crate::commands::read_setting(app_handle, SNDE_LIMITS_SETTING)
+        .and_then(|json| serde_json::from_str::<SNDELimits>(&json).ok())
+        .unwrap_or_default()
+        .clamped()
+}
+
+fn load_host_overrides(app_handle: &AppHandle) -> HashMap<String, SNDELimitOverride> {
+    crate::commands::read_setting(app_handle, SNDE_HOST_OVERRIDES_SETTING)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Find the override for a host, also matching parent domains (e.g. "cdn.example.com" -> "example.com")
+fn find_host_override<'a>(
+    overrides: &'a HashMap<String, SNDELimitOverride>,
+    domain: &str,
+) -> Option<&'a SNDELimitOverride> {
+    crate::host_reputation::host_and_parents(domain).find_map(|candidate| overrides.get(candidate))
+}
+
+/// Effective limits for a URL: global settings with any per-host override applied
+pub fn limits_for_url(app_handle: &AppHandle, url: &str) -> SNDELimits {
+    let limits = load_limits(app_handle);
+    let Some(domain) = extract_domain(url) else {
+        return limits;
+    };
+    match find_host_override(&load_host_overrides(app_handle), &domain) {
+        Some(host) => limits.with_override(host),
+        None => limits,
+    }
+}
+
+/// Suffix of the sidecar recording which ranges of a partial download are on disk
+const STATE_SUFFIX: &str = ".snde-state";
+
+/// How often the chunk state sidecar is rewritten while downloading
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Connections a download starts with on a host without a recorded optimum
+const RAMP_START_CONNECTIONS: u8 = 2;
+
+/// How long each connection count runs before its throughput is judged
+const RAMP_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Aggregate speed has to rise by this fraction for another connection to be worth it
+const RAMP_MIN_GAIN: f64 = 0.1;
+
+/// Attempts per chunk before the download gives up on it
+const MAX_CHUNK_RETRIES: u8 = 5;
+
+/// First retry delay; doubles with each attempt up to `RETRY_MAX_DELAY`
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Chunk retries one host gets per window, across all downloads from it
+const RETRY_BUDGET: u32 = 30;
+const RETRY_BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+/// Longest wait for a QUIC handshake before a download falls back to TCP
+const HTTP3_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+lazy_static::lazy_static! {
+    /// Downloads whose cancellation is a pause: their partial file and state are kept
+    static ref PAUSE_REQUESTS: std::sync::Mutex<std::collections::HashSet<String>> =
+        std::sync::Mutex::new(std::collections::HashSet::new());
+
+    /// Per host: when the current retry window started and retries spent in it
+    static ref HOST_RETRY_BUDGETS: std::sync::Mutex<HashMap<String, (Instant, u32)>> =
+        std::sync::Mutex::new(HashMap::new());
+
+    /// Running downloads by id, for playing what's already on disk
+    static ref LIVE_FILES: std::sync::Mutex<HashMap<String, Arc<LiveFile>>> =
+        std::sync::Mutex::new(HashMap::new());
+
+    /// Connection controls of running downloads by id, for the watchdog
+    static ref CONNECTION_CONTROLS: std::sync::Mutex<HashMap<String, Arc<ConnectionControl>>> =
+        std::sync::Mutex::new(HashMap::new());
+}
+
+/// How many connections a running download may use, lowered by the watchdog
+#[derive(Debug)]
+/// The first GET response of a download, shared by its workers
+type ExchangeSlot = std::sync::Mutex<Option<HttpExchange>>;
+
+struct ConnectionControl {
+    /// Workers numbered at or above this put their chunk back and stop
+    limit: AtomicU8,
+    /// A single connection, moved to HTTP/2 if it was on HTTP/1.1
+    safe_mode: AtomicBool,
+    /// HTTP/3 broke down mid-download; every worker moves to TCP
+    quic_failed: AtomicBool,
+}
+
+impl ConnectionControl {
+    fn new(limit: u8) -> Self {
+        Self { limit: AtomicU8::new(limit.max(1)), safe_mode: AtomicBool::new(false), quic_failed: AtomicBool::new(false) }
+    }
+
+    fn limit(&self) -> u8 {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    fn sheds(&self, conn_id: u8) -> bool {
+        conn_id >= self.limit()
+    }
+
+    fn safe_mode(&self) -> bool {
+        self.safe_mode.load(Ordering::Relaxed)
+    }
+
+    /// Never raises the limit, and always leaves one connection
+    fn collapse_to(&self, count: u8) {
+        self.limit.fetch_min(count.max(1), Ordering::Relaxed);
+    }
+
+    fn enter_safe_mode(&self) {
+        self.collapse_to(1);
+        self.safe_mode.store(true, Ordering::Relaxed);
+    }
+
+    fn quic_failed(&self) -> bool {
+        self.quic_failed.load(Ordering::Relaxed)
+    }
+
+    /// True for the first caller only
+    fn fail_quic(&self) -> bool {
+        !self.quic_failed.swap(true, Ordering::Relaxed)
+    }
+}
+
+/// Cut running download `id` to `count` connections; the workers over it finish their
+/// current frame and put the rest of their chunk back. False if SNDE isn't running `id`.
+pub fn collapse_connections(id: &str, count: u8) -> bool {
+    let Some(control) = CONNECTION_CONTROLS.lock().unwrap().get(id).cloned() else {
+        return false;
+    };
+    control.collapse_to(count);
+    println!("[SNDE] {} limited to {} connections", id, control.limit());
+    true
+}
+
+/// Put running download `id` in Safe Mode: one connection, over HTTP/2. False if SNDE
+/// isn't running `id`.
+pub fn enable_safe_mode(id: &str) -> bool {
+    let Some(control) = CONNECTION_CONTROLS.lock().unwrap().get(id).cloned() else {
+        return false;
+    };
+    control.enter_safe_mode();
+    println!("[SNDE] {} switched to Safe Mode", id);
+    true
+}
+
+/// A running download's chunk map, shared with the media server
+struct LiveFile {
+    url: String,
+    temp_path: PathBuf,
+    final_path: PathBuf,
+    total_size: u64,
+    previously_completed: Vec<(u64, u64)>,
+    chunks: Arc<Mutex<Vec<ChunkWork>>>,
+}
+
+/// How much of a running download can be played from the front
+#[derive(Debug, Clone)]
+pub struct PlayablePrefix {
+    /// The file being written
+    pub temp_path: PathBuf,
+    /// Where it goes once finished
+    pub final_path: PathBuf,
+    /// Leading bytes already on disk with no gaps
+    pub available: u64,
+    pub total_size: u64,
+}
+
+/// The unbroken run of finished chunks from byte 0 of download `id`, while SNDE is
+/// still fetching it
+pub async fn playable_prefix(id: &str) -> Option<PlayablePrefix> {
+    let live = LIVE_FILES.lock().unwrap().get(id).cloned()?;
+    let state = ChunkState::new(&live.url, live.total_size, FileVersion::default(), None)
+        .snapshot(&live.previously_completed, &live.chunks.lock().await);
+    let available = missing_ranges(live.total_size, &state.completed)
+        .first()
+        .map_or(live.total_size, |(start, _)| *start);
+    Some(PlayablePrefix {
+        temp_path: live.temp_path.clone(),
+        final_path: live.final_path.clone(),
+        available,
+        total_size: live.total_size,
+    })
+}
+
+/// Delay before retry number `attempt`: exponential with jitter, so workers that failed
+/// together don't come back together. A longer Retry-After from the server wins.
+fn retry_delay(attempt: u8, retry_after: Option<Duration>) -> Duration {
+    use rand::Rng;
+
+    let doublings = u32::from(attempt.saturating_sub(1)).min(16);
+    let backoff = RETRY_BASE_DELAY.saturating_mul(1 << doublings).min(RETRY_MAX_DELAY);
+    let jittered = backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0));
+    jittered.max(retry_after.unwrap_or_default().min(RETRY_MAX_DELAY))
+}
+
+/// Spend one of `host`'s retries; once its budget is used up, how long until it refills
+fn spend_retry_budget(host: &str) -> Duration {
+    let mut budgets = HOST_RETRY_BUDGETS.lock().unwrap();
+    let now = Instant::now();
+    let (window_start, used) = budgets.entry(host.to_string()).or_insert((now, 0));
+    if now.duration_since(*window_start) >= RETRY_BUDGET_WINDOW {
+        *window_start = now;
+        *used = 0;
+    }
+    *used += 1;
+    if *used <= RETRY_BUDGET {
+        Duration::ZERO
+    } else {
+        RETRY_BUDGET_WINDOW.saturating_sub(now.duration_since(*window_start))
+    }
+}
+
+/// Why a chunk attempt failed
+#[derive(Debug)]
+struct ChunkError {
+    message: String,
+    /// Status of an error response
+    status: Option<u16>,
+    /// The server's Retry-After, if it sent one
+    retry_after: Option<Duration>,
+    /// Stopped by the watchdog rather than failed; what arrived is flushed to disk
+    shed: bool,
+}
+
+impl ChunkError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into(), status: None, retry_after: None, shed: false }
+    }
+}
+
+/// Mark the next cancellation of `id` as a pause
+pub fn request_pause(id: &str) {
+    PAUSE_REQUESTS.lock().unwrap().insert(id.to_string());
+}
+
+/// `<file>.snde-state`, next to the final file name
+fn state_path(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(STATE_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// A download SNDE left unfinished: its `.downloading` file plus the `.snde-state` sidecar
+#[derive(Debug, Clone, Serialize)]
+pub struct PartialDownload {
+    pub url: String,
+    /// The name the file gets once it's finished
+    pub file_path: PathBuf,
+    pub downloaded_bytes: u64,
+    pub total_bytes: u64,
+    /// The download request that started it, if it was saved
+    #[serde(skip)]
+    pub request: Option<serde_json::Value>,
+}
+
+/// Unfinished SNDE downloads in `dir`. Sidecars whose partial file is gone are skipped;
+/// starting the same URL into `dir` again picks up from the saved ranges.
+pub fn partial_downloads(dir: &Path) -> Vec<PartialDownload> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let file_path = PathBuf::from(path.to_str()?.strip_suffix(STATE_SUFFIX)?);
+            if !crate::downloader::downloading_path(&file_path).exists() {
+                return None;
+            }
+            let state: ChunkState = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+            Some(PartialDownload {
+                downloaded_bytes: state.bytes(),
+                total_bytes: state.total_size,
+                url: state.url,
+                request: state.request,
+                file_path,
+            })
+        })
+        .collect()
+}
+
+/// Sort and merge inclusive byte ranges, joining adjacent ones
+fn merge_ranges(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// The inclusive ranges of `0..total_size` not covered by `completed` (sorted and merged)
+fn missing_ranges(total_size: u64, completed: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    let mut missing = Vec::new();
+    let mut next = 0u64;
+    for &(start, end) in completed {
+        if start > next {
+            missing.push((next, (start - 1).min(total_size.saturating_sub(1))));
+        }
+        next = next.max(end.saturating_add(1));
+        if next >= total_size {
+            break;
+        }
+    }
+    if next < total_size {
+        missing.push((next, total_size - 1));
+    }
+    missing.retain(|(start, end)| start <= end && *start < total_size);
+    missing
+}
+
+/// What identifies the version of a file the server sent: its ETag and Last-Modified
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct FileVersion {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl FileVersion {
+    fn from_headers(headers: &[(String, String)]) -> Self {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self { etag: header("etag"), last_modified: header("last-modified") }
+    }
+
+    /// `If-Range` value, so a server whose file changed sends it whole instead of a range.
+    /// Weak ETags aren't allowed there.
+    fn if_range(&self) -> Option<HeaderValue> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+            .and_then(|value| HeaderValue::from_str(value).ok())
+    }
+}
+
+/// Completed ranges of a partial download, saved in its `.snde-state` sidecar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ChunkState {
+    url: String,
+    total_size: u64,
+    /// Version of the file the ranges came from
+    #[serde(default)]
+    version: FileVersion,
+    /// The download request that started it, for resuming after a crash
+    #[serde(default)]
+    request: Option<serde_json::Value>,
+    /// Inclusive byte ranges already written, sorted and merged
+    completed: Vec<(u64, u64)>,
+}
+
+impl ChunkState {
+    /// The state of a download with nothing written yet
+    fn new(url: &str, total_size: u64, version: FileVersion, request: Option<serde_json::Value>) -> ChunkState {
+        ChunkState { url: url.to_string(), total_size, version, request, completed: Vec::new() }
+    }
+
+    /// The saved state, if it's for the same URL, size and version of the file
+    fn load(path: &Path, url: &str, total_size: u64, version: &FileVersion) -> Option<ChunkState> {
+        let json = std::fs::read_to_string(path).ok()?;
+        let state: ChunkState = serde_json::from_str(&json).ok()?;
+        (state.url == url && state.total_size == total_size && state.version == *version).then_some(state)
+    }
+
+    /// This download with `previous` plus the chunks finished in this run completed
+    fn snapshot(&self, previous: &[(u64, u64)], chunks: &[ChunkWork]) -> ChunkState {
+        let mut completed = previous.to_vec();
+        completed.extend(chunks.iter().filter(|c| c.completed).map(|c| (c.start, c.end)));
+        ChunkState { completed: merge_ranges(completed), ..self.clone() }
+    }
+
+    fn bytes(&self) -> u64 {
+        self.completed.iter().map(|(start, end)| end - start + 1).sum()
+    }
+
+    async fn save(&self, path: &Path) {
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                if let Err(e) = tokio::fs::write(path, json).await {
+                    println!("[SNDE] Failed to save chunk state {:?}: {}", path, e);
+                }
+            }
+            Err(e) => println!("[SNDE] Failed to serialize chunk state: {}", e),
+        }
+    }
+}
+
+/// Adds connections one at a time while each one still raises aggregate speed
+#[derive(Debug)]
+struct ConnectionRamp {
+    connections: u8,
+    max: u8,
+    last_speed: Option<f64>,
+    done: bool,
+    /// The count that was fastest, once the ramp has finished by measuring
+    optimum: Option<u8>,
+}
+
+impl ConnectionRamp {
+    fn new(start: u8, max: u8) -> Self {
+        let connections = start.clamp(1, max.max(1));
+        Self { connections, max, last_speed: None, done: connections >= max, optimum: None }
+    }
+
+    /// Take a throughput sample at the current count; true means add a connection
+    fn observe(&mut self, speed_bps: f64) -> bool {
+        if self.done {
+            return false;
+        }
+        match self.last_speed {
+            Some(last) if speed_bps < last * (1.0 + RAMP_MIN_GAIN) => {
+                // The last connection added didn't pay off
+                self.done = true;
+                self.optimum = Some(self.connections - 1);
+                false
+            }
+            _ if self.connections >= self.max => {
+                self.done = true;
+                self.optimum = Some(self.connections);
+                false
+            }
+            _ => {
+                self.last_speed = Some(speed_bps);
+                self.connections += 1;
+                true
+            }
+        }
+    }
+}
+
 /// SNDE Download Progress event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SNDEProgress {
@@ -62,6 +596,9 @@ pub struct SNDEProgress {
     pub engine_badge: String,
 }
 
+/// Where a download's progress goes; the app emits it as `download-progress`
+type ProgressSink = Arc<dyn Fn(SNDEProgress) + Send + Sync>;
+
 /// A byte range work unit
 #[derive(Debug, Clone)]
 struct ChunkWork {
@@ -75,6 +612,68 @@ struct ChunkWork {
     completed: bool,
     /// Retry count for this chunk
     retries: u8,
+    /// Not claimed again before this, after a failed attempt
+    retry_at: Option<Instant>,
+    /// Live position of the worker fetching this chunk
+    cursor: Arc<ChunkCursor>,
+}
+
+impl ChunkWork {
+    fn new(start: u64, end: u64) -> Self {
+        Self {
+            start,
+            end,
+            in_progress: false,
+            completed: false,
+            retries: 0,
+            retry_at: None,
+            cursor: Arc::new(ChunkCursor {
+                position: AtomicU64::new(start),
+                limit: AtomicU64::new(end),
+            }),
+        }
+    }
+
+    /// Bytes an in-progress chunk has yet to fetch
+    fn remaining(&self) -> u64 {
+        (self.end + 1).saturating_sub(self.cursor.position.load(Ordering::Relaxed))
+    }
+}
+
+/// Where a chunk's worker has got to, and where it must stop. Work stealing lowers `limit`
+/// under a running worker.
+#[derive(Debug)]
+struct ChunkCursor {
+    /// Next byte to write
+    position: AtomicU64,
+    /// Last byte to write (inclusive)
+    limit: AtomicU64,
+}
+
+/// Split off the untouched second half of the in-progress chunk with the most left to
+/// fetch, for an idle worker. Both halves stay at least `MIN_CHUNK_SIZE`. Returns the index
+/// of the new, already claimed, chunk.
+fn steal_tail(chunks: &mut Vec<ChunkWork>) -> Option<usize> {
+    let (victim, remaining) = chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.in_progress && !c.completed)
+        .map(|(idx, c)| (idx, c.remaining()))
+        .max_by_key(|(_, remaining)| *remaining)?;
+    if remaining < 2 * MIN_CHUNK_SIZE {
+        return None;
+    }
+
+    let chunk = &mut chunks[victim];
+    let split = chunk.end + 1 - remaining / 2;
+    let tail_end = chunk.end;
+    chunk.end = split - 1;
+    chunk.cursor.limit.store(split - 1, Ordering::Relaxed);
+
+    let mut tail = ChunkWork::new(split, tail_end);
+    tail.in_progress = true;
+    chunks.push(tail);
+    Some(chunks.len() - 1)
 }
 
 /// Statistics for a single connection
@@ -102,6 +701,15 @@ pub struct SNDERequest {
     pub url: String,
     pub output_path: PathBuf,
     pub routing_decision: RoutingDecision,
+    /// Hash the finished file must match
+    pub checksum: Option<ExpectedChecksum>,
+    /// Other URLs serving the same file; chunks are spread over them and `url`
+    pub mirrors: Vec<String>,
+    /// Headers sent to `url` on top of the header profile (Referer, Authorization, cookies);
+    /// mirrors don't get them
+    pub extra_headers: HeaderMap,
+    /// The download request as `crash_resume` saves it, kept in the chunk state
+    pub origin: Option<serde_json::Value>,
 }
 
 /// SNDE Download Result
@@ -112,54 +720,151 @@ pub struct SNDEResult {
     pub bytes_downloaded: u64,
     pub duration_secs: f64,
     pub avg_speed_kbps: u32,
+    /// Headers of the first GET that delivered bytes, for WARC capture
+    pub exchange: Option<HttpExchange>,
+    /// The finished file, after it was renamed into place
+    pub output_path: Option<PathBuf>,
+    /// Stopped by `request_pause`; the partial file and its chunk state were kept
+    pub paused: bool,
+    /// Every byte arrived but the file didn't match the expected checksum
+    pub checksum_mismatch: bool,
+    /// Stopped because the user cancelled it
+    pub cancelled: bool,
 }
 
 /// The SNDE Download Engine
 pub struct SNDEEngine {
+    /// HTTP clients, rebuilt when proxy settings change
+    clients: std::sync::RwLock<SNDEClients>,
+}
+
+/// HTTP clients used by the engine
+#[derive(Clone)]
+struct SNDEClients {
     /// HTTP client configured for parallel downloads
     client: Client,
     /// HTTP/1.1 only client for forced parallelism
     http1_client: Client,
+    /// HTTP/3 client; None without the `http3` feature or behind a proxy
+    http3_client: Option<Client>,
 }
 
 impl SNDEEngine {
     /// Create a new SNDE engine
     pub fn new() -> Self {
+        Self {
+            clients: std::sync::RwLock::new(Self::build_clients()),
+        }
+    }
+
+    fn build_clients() -> SNDEClients {
         // Standard client with HTTP/2 support
-        let client = Client::builder()
+        let client = crate::proxy::apply_to_client(Client::builder())
             .timeout(Duration::from_secs(300))
             .connect_timeout(Duration::from_secs(30))
-            .pool_max_idle_per_host(MAX_CONNECTIONS as usize)
+            .pool_max_idle_per_host(MAX_CONNECTIONS_LIMIT as usize)
             .build()
             .unwrap_or_default();
 
         // HTTP/1.1 only client for guaranteed parallel TCP connections
-        let http1_client = Client::builder()
+        let http1_client = crate::proxy::apply_to_client(Client::builder())
             .timeout(Duration::from_secs(300))
             .connect_timeout(Duration::from_secs(30))
-            .pool_max_idle_per_host(MAX_CONNECTIONS as usize)
+            .pool_max_idle_per_host(MAX_CONNECTIONS_LIMIT as usize)
             .http1_only()  // Force HTTP/1.1
             .build()
             .unwrap_or_default();
 
-        Self { client, http1_client }
+        SNDEClients { client, http1_client, http3_client: Self::build_http3_client() }
+    }
+
+    /// QUIC runs over UDP, which the HTTP and SOCKS proxies don't carry
+    #[cfg(feature = "http3")]
+    fn build_http3_client() -> Option<Client> {
+        if crate::proxy::current_proxy_url().is_some() {
+            return None;
+        }
+        Client::builder()
+            .timeout(Duration::from_secs(300))
+            .connect_timeout(Duration::from_secs(30))
+            .use_rustls_tls()
+            .http3_prior_knowledge()
+            .build()
+            .ok()
+    }
+
+    #[cfg(not(feature = "http3"))]
+    fn build_http3_client() -> Option<Client> {
+        None
+    }
+
+    /// Rebuild the HTTP clients (e.g. after proxy settings change).
+    /// Downloads already running keep their existing clients.
+    pub fn rebuild_clients(&self) {
+        let clients = Self::build_clients();
+        if let Ok(mut current) = self.clients.write() {
+            *current = clients;
+        }
     }
 
     /// Get the appropriate client based on routing decision
-    fn get_client(&self, force_http1: bool) -> &Client {
+    fn get_client(&self, force_http1: bool) -> Client {
+        let clients = self.clients.read().unwrap();
         if force_http1 {
-            &self.http1_client
+            clients.http1_client.clone()
         } else {
-            &self.client
+            clients.client.clone()
         }
     }
 
+    /// The HTTP/3 client, if the router flagged the host and a QUIC request to it goes
+    /// through. Otherwise the host is forgotten and the download stays on TCP. Mirrors
+    /// aren't known to speak QUIC, so downloads with mirrors skip it.
+    async fn negotiate_http3(&self, request: &SNDERequest, request_headers: &HeaderMap) -> Option<Client> {
+        if !request.routing_decision.prefer_http3 || !request.mirrors.is_empty() {
+            return None;
+        }
+        let client = self.clients.read().unwrap().http3_client.clone()?;
+        let handshake = client
+            .get(&request.url)
+            .headers(request_headers.clone())
+            .header(RANGE, "bytes=0-0")
+            .send();
+        let failure = match tokio::time::timeout(HTTP3_HANDSHAKE_TIMEOUT, handshake).await {
+            Ok(Ok(response)) if response.status().is_success() => {
+                println!("[SNDE] {} going over HTTP/3", request.id);
+                return Some(client);
+            }
+            Ok(Ok(response)) => format!("status {}", response.status()),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "handshake timed out".to_string(),
+        };
+        println!("[SNDE] HTTP/3 failed for {} ({}), falling back to TCP", request.id, failure);
+        crate::download_router::forget_quic_host(&request.url);
+        None
+    }
+
     /// Perform the parallel download
     pub async fn download(
         &self,
         request: SNDERequest,
         app_handle: AppHandle,
         cancel_rx: mpsc::Receiver<()>,
+    ) -> SNDEResult {
+        let limits = limits_for_url(&app_handle, &request.url);
+        let report: ProgressSink = Arc::new(move |progress: SNDEProgress| {
+            let _ = app_handle.emit("download-progress", progress);
+        });
+        self.download_with(request, limits, report, cancel_rx).await
+    }
+
+    /// `download` with the limits already looked up, sending progress to `report`
+    async fn download_with(
+        &self,
+        request: SNDERequest,
+        limits: SNDELimits,
+        report: ProgressSink,
+        cancel_rx: mpsc::Receiver<()>,
     ) -> SNDEResult {
         let start_time = Instant::now();
         let id = request.id.clone();
@@ -169,8 +874,13 @@ impl SNDEEngine {
         // Update health registry
         HEALTH_REGISTRY.set_phase(&id, DownloadPhase::Preflight);
 
+        // Every request in this download presents the same browser, plus any headers the
+        // download brought along
+        let mut request_headers = crate::header_profiles::headers_for_url(&request.url);
+        request_headers.extend(request.extra_headers.clone());
+
         // Determine file size and verify range support
-        let probe_result = match self.probe_file(&request).await {
+        let probe_result = match self.probe_file(&request, &request_headers).await {
             Ok(r) => r,
             Err(e) => {
                 return SNDEResult {
@@ -179,13 +889,19 @@ impl SNDEEngine {
                     bytes_downloaded: 0,
                     duration_secs: start_time.elapsed().as_secs_f64(),
                     avg_speed_kbps: 0,
+                    exchange: None,
+                    output_path: None,
+                    paused: false,
+                    checksum_mismatch: false,
+                    cancelled: false,
                 };
             }
         };
 
-        let total_size = probe_result.0;
+        let probed_size = probe_result.0;
         let supports_range = probe_result.1;
         let probed_filename = probe_result.2;
+        let probe_exchange = probe_result.3;
 
         // Determine the actual output path
         // If we got a filename from the server and the current path looks like a directory or generic name
@@ -211,51 +927,126 @@ impl SNDEEngine {
             request.output_path.clone()
         };
 
+        // Written under a temporary name so nothing picks up a half-finished file
+        let temp_output_path = crate::downloader::downloading_path(&actual_output_path);
+
+        // Without a size there's nothing to split or resume: stream it over one connection
+        let Some(total_size) = probed_size else {
+            return self
+                .download_streaming(request, request_headers, limits, report, cancel_rx, actual_output_path, start_time)
+                .await;
+        };
+
         println!("[SNDE] File size: {} bytes, Range support: {}", total_size, supports_range);
         println!("[SNDE] Output path: {:?}", actual_output_path);
 
-        // If no range support, fall back to single connection
+        let version = FileVersion::from_headers(&probe_exchange.response_headers);
+
+        // Mirrors have to serve the same bytes with ranges; the others are left out. Range
+        // requests to the main URL carry If-Range so a file replaced mid-download isn't spliced.
+        let mut primary_headers = request_headers.clone();
+        if let Some(if_range) = version.if_range().filter(|_| supports_range) {
+            primary_headers.insert(IF_RANGE, if_range);
+        }
+        let mut sources = vec![(request.url.clone(), primary_headers)];
+        if supports_range && !request.mirrors.is_empty() {
+            let probes = request.mirrors.iter().map(|mirror| async move {
+                let headers = crate::header_profiles::headers_for_url(mirror);
+                let checked = self.probe_mirror(mirror, &headers, total_size).await;
+                (mirror.clone(), headers, checked)
+            });
+            for (mirror, headers, checked) in futures_util::future::join_all(probes).await {
+                match checked {
+                    Ok(()) => sources.push((mirror, headers)),
+                    Err(e) => println!("[SNDE] Skipping mirror {}: {}", mirror, e),
+                }
+            }
+        }
+        let mirror_count = sources.len();
+        let mirror_pool = Arc::new(MirrorPool::new(sources));
+
+        // If no range support, fall back to single connection; each mirror gets its own share
         let num_connections = if supports_range {
-            request.routing_decision.recommended_connections.min(MAX_CONNECTIONS)
+            let per_mirror = request.routing_decision.recommended_connections.min(limits.max_connections) as usize;
+            (per_mirror * mirror_count).min(MAX_CONNECTIONS_LIMIT as usize) as u8
         } else {
             1
         };
 
-        println!("[SNDE] Using {} connections", num_connections);
+        println!("[SNDE] Using {} connections ({:?})", num_connections, limits);
 
         // Update health registry with file info
         HEALTH_REGISTRY.set_phase(&id, DownloadPhase::Allocating);
 
-        // Pre-allocate the file
-        if let Err(e) = self.preallocate_file(&actual_output_path, total_size).await {
+        // Pick up where a paused or interrupted run left off
+        let state_file = state_path(&actual_output_path);
+        let base_state = ChunkState::new(&request.url, total_size, version.clone(), request.origin.clone());
+        let resumed = if supports_range && temp_output_path.exists() {
+            ChunkState::load(&state_file, &request.url, total_size, &version)
+        } else {
+            None
+        };
+        let previously_completed = resumed.as_ref().map(|s| s.completed.clone()).unwrap_or_default();
+        if let Some(state) = &resumed {
+            println!("[SNDE] Resuming with {} of {} bytes already downloaded", state.bytes(), total_size);
+        } else if let Err(e) = self.preallocate_file(&temp_output_path, total_size).await {
             return SNDEResult {
                 success: false,
                 error: Some(format!("Failed to allocate file: {}", e)),
                 bytes_downloaded: 0,
                 duration_secs: start_time.elapsed().as_secs_f64(),
                 avg_speed_kbps: 0,
+                exchange: None,
+                output_path: None,
+                paused: false,
+                checksum_mismatch: false,
+                cancelled: false,
             };
         }
 
         HEALTH_REGISTRY.set_phase(&id, DownloadPhase::Downloading);
 
         // Create work chunks
-        let chunks = self.create_chunks(total_size, num_connections);
+        let chunks = self.create_chunks(total_size, num_connections, limits.chunk_size_bytes(), &previously_completed);
         let chunks = Arc::new(Mutex::new(chunks));
+        LIVE_FILES.lock().unwrap().insert(id.clone(), Arc::new(LiveFile {
+            url: request.url.clone(),
+            temp_path: temp_output_path.clone(),
+            final_path: actual_output_path.clone(),
+            total_size,
+            previously_completed: previously_completed.clone(),
+            chunks: Arc::clone(&chunks),
+        }));
+        let control = Arc::new(ConnectionControl::new(num_connections));
+        CONNECTION_CONTROLS.lock().unwrap().insert(id.clone(), Arc::clone(&control));
+        crate::watchdog::send(WatchdogCommand::StartMonitoring(id.clone()));
 
         // Shared state
-        let total_downloaded = Arc::new(AtomicU64::new(0));
+        let total_downloaded = Arc::new(AtomicU64::new(resumed.as_ref().map_or(0, ChunkState::bytes)));
         let is_cancelled = Arc::new(AtomicBool::new(false));
+        let was_cancelled = Arc::new(AtomicBool::new(false));
+        let exchange: Arc<ExchangeSlot> = Arc::default();
         let connection_stats: Arc<Vec<ConnectionStats>> = Arc::new(
             (0..num_connections).map(|_| ConnectionStats::default()).collect()
         );
 
+        // Start with a few connections, or the count that was fastest on this host last time
+        let reputation_domain = extract_domain(&request.url).filter(|_| mirror_count == 1);
+        let known_optimum = reputation_domain.as_deref().and_then(|domain| {
+            crate::host_reputation::manager()?.find_reputation(domain).ok()?.map(|r| r.max_stable_conns)
+        });
+        let mut ramp = ConnectionRamp::new(known_optimum.unwrap_or(RAMP_START_CONNECTIONS), num_connections);
+        let active_connections = Arc::new(AtomicU8::new(ramp.connections));
+        HEALTH_REGISTRY.set_active_connections(&id, ramp.connections);
+
         // Progress reporting task
         let progress_handle = {
             let id = id.clone();
-            let app = app_handle.clone();
+            let report = Arc::clone(&report);
             let total_downloaded = Arc::clone(&total_downloaded);
             let is_cancelled = Arc::clone(&is_cancelled);
+            let active_connections = Arc::clone(&active_connections);
+            let control = Arc::clone(&control);
             let badge = request.routing_decision.badge.clone();
             
             tokio::spawn(async move {
@@ -281,7 +1072,7 @@ impl SNDEEngine {
                         let speed_str = format_speed(speed_bps);
                         let eta_str = format_eta(eta_secs);
 
-                        let _ = app.emit("download-progress", SNDEProgress {
+                        report(SNDEProgress {
                             id: id.clone(),
                             progress,
                             speed: speed_str,
@@ -289,7 +1080,7 @@ impl SNDEEngine {
                             status: "downloading".to_string(),
                             downloaded_bytes: current_bytes as i64,
                             total_bytes: total_size as i64,
-                            active_connections: num_connections,
+                            active_connections: active_connections.load(Ordering::Relaxed).min(control.limit()),
                             engine_badge: badge.clone(),
                         });
 
@@ -306,55 +1097,109 @@ impl SNDEEngine {
         // Cancellation listener
         let cancel_handle = {
             let is_cancelled = Arc::clone(&is_cancelled);
+            let was_cancelled = Arc::clone(&was_cancelled);
             let mut cancel_rx = cancel_rx;
             
             tokio::spawn(async move {
                 let _ = cancel_rx.recv().await;
+                was_cancelled.store(true, Ordering::Relaxed);
                 is_cancelled.store(true, Ordering::Relaxed);
             })
         };
 
-        // Spawn download workers
-        let mut worker_handles = Vec::new();
-        let file = Arc::new(Mutex::new(
-            OpenOptions::new()
-                .write(true)
-                .open(&actual_output_path)
-                .await
-                .expect("Failed to open output file")
-        ));
+        // Periodically record finished chunks so a crash loses at most a few seconds
+        let checkpoint_handle = supports_range.then(|| {
+            let chunks = Arc::clone(&chunks);
+            let is_cancelled = Arc::clone(&is_cancelled);
+            let base_state = base_state.clone();
+            let previous = previously_completed.clone();
+            let state_file = state_file.clone();
 
-        let client = if request.routing_decision.force_http1 {
-            self.http1_client.clone()
-        } else {
-            self.client.clone()
-        };
+            tokio::spawn(async move {
+                while !is_cancelled.load(Ordering::Relaxed) {
+                    tokio::time::sleep(STATE_SAVE_INTERVAL).await;
+                    let state = base_state.snapshot(&previous, &chunks.lock().await);
+                    state.save(&state_file).await;
+                }
+            })
+        });
 
-        for conn_id in 0..num_connections {
+        // Spawn download workers; each opens its own handle on the output file
+        let http3_client = self.negotiate_http3(&request, &request_headers).await;
+        let on_http3 = http3_client.is_some();
+        // What an HTTP/3 download moves to if QUIC stops working
+        let tcp_fallback = on_http3.then(|| self.get_client(request.routing_decision.force_http1));
+        let client = http3_client.unwrap_or_else(|| self.get_client(request.routing_decision.force_http1));
+        // What an HTTP/1.1 download moves to in Safe Mode
+        let safe_client = (request.routing_decision.force_http1 && !on_http3).then(|| self.get_client(false));
+
+        let spawn_worker = |conn_id: u8| {
             let client = client.clone();
-            let url = request.url.clone();
+            let tcp_fallback = tcp_fallback.clone();
+            let safe_client = safe_client.clone();
+            let control = Arc::clone(&control);
+            let mirror_pool = Arc::clone(&mirror_pool);
             let chunks = Arc::clone(&chunks);
-            let file = Arc::clone(&file);
+            let output_path = temp_output_path.clone();
             let total_downloaded = Arc::clone(&total_downloaded);
             let is_cancelled = Arc::clone(&is_cancelled);
             let connection_stats = Arc::clone(&connection_stats);
+            let exchange = Arc::clone(&exchange);
             let id = id.clone();
+            let stall_timeout = Duration::from_secs(limits.stall_timeout_secs);
 
-            let handle = tokio::spawn(async move {
+            tokio::spawn(async move {
                 Self::worker_loop(
                     conn_id,
                     client,
-                    url,
+                    tcp_fallback,
+                    safe_client,
+                    control,
+                    mirror_pool,
                     chunks,
-                    file,
+                    output_path,
                     total_downloaded,
                     is_cancelled,
                     connection_stats,
+                    exchange,
                     id,
+                    stall_timeout,
                 ).await
-            });
-
-            worker_handles.push(handle);
+            })
+        };
+        let mut worker_handles: Vec<_> = (0..ramp.connections).map(&spawn_worker).collect();
+
+        // Add connections while they keep raising throughput
+        let mut ramp_bytes = total_downloaded.load(Ordering::Relaxed);
+        while !ramp.done && ramp.connections < control.limit() && !is_cancelled.load(Ordering::Relaxed) {
+            let sample_end = Instant::now() + RAMP_INTERVAL;
+            while Instant::now() < sample_end && !worker_handles.iter().all(|h| h.is_finished()) {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            if worker_handles.iter().all(|h| h.is_finished()) {
+                break;
+            }
+            let bytes = total_downloaded.load(Ordering::Relaxed);
+            let speed_bps = bytes.saturating_sub(ramp_bytes) as f64 / RAMP_INTERVAL.as_secs_f64();
+            ramp_bytes = bytes;
+            if ramp.observe(speed_bps) {
+                println!(
+                    "[SNDE] {} per connection with {}, adding one",
+                    format_speed((speed_bps / (ramp.connections - 1) as f64) as u64),
+                    ramp.connections - 1
+                );
+                worker_handles.push(spawn_worker(ramp.connections - 1));
+                active_connections.store(ramp.connections, Ordering::Relaxed);
+                HEALTH_REGISTRY.set_active_connections(&id, ramp.connections);
+            }
+        }
+        if let (Some(optimum), Some(domain)) = (ramp.optimum, &reputation_domain) {
+            println!("[SNDE] {} connections were fastest for {}", optimum, domain);
+            if let Some(manager) = crate::host_reputation::manager() {
+                if let Err(e) = manager.record_optimal_connections(domain, optimum) {
+                    println!("[SNDE] Failed to record optimal connections: {}", e);
+                }
+            }
         }
 
         // Wait for all workers to complete
@@ -384,6 +1229,10 @@ impl SNDEEngine {
         
         // Wait for progress task to finish (it checks is_cancelled and will exit)
         let _ = progress_handle.await;
+        if let Some(handle) = checkpoint_handle {
+            handle.abort();
+        }
+        let paused = PAUSE_REQUESTS.lock().unwrap().remove(&id);
 
         let duration = start_time.elapsed().as_secs_f64();
         let final_bytes = total_downloaded.load(Ordering::Relaxed);
@@ -393,20 +1242,67 @@ impl SNDEEngine {
             0
         };
 
-        // Update health registry
+        let mut error = (!all_success).then(|| "Download incomplete".to_string());
+        let mut checksum_mismatch = false;
+        if let (true, Some(expected)) = (all_success && final_bytes == total_size, request.checksum.clone()) {
+            HEALTH_REGISTRY.set_phase(&id, DownloadPhase::PostProcessing);
+            if let Err(e) = crate::checksum::verify(temp_output_path.clone(), expected).await {
+                println!("[SNDE] {}", e);
+                // Resuming would only reassemble the same bad bytes
+                let _ = tokio::fs::remove_file(&temp_output_path).await;
+                checksum_mismatch = true;
+                all_success = false;
+                error = Some(e);
+            }
+        }
         if all_success && final_bytes == total_size {
+            if let Err(e) = crate::downloader::finalize_file(&temp_output_path, &actual_output_path).await {
+                all_success = false;
+                error = Some(e);
+            }
+        }
+        let finished = all_success && final_bytes == total_size;
+        // Players following the download switch to the finished file from here on
+        LIVE_FILES.lock().unwrap().remove(&id);
+        CONNECTION_CONTROLS.lock().unwrap().remove(&id);
+        crate::watchdog::send(WatchdogCommand::StopMonitoring(id.clone()));
+        if finished || checksum_mismatch {
+            let _ = tokio::fs::remove_file(&state_file).await;
+        } else if supports_range {
+            // Keep what's on disk resumable, whether paused, cancelled or failed
+            base_state.snapshot(&previously_completed, &chunks.lock().await)
+                .save(&state_file)
+                .await;
+        }
+        if paused && !finished {
+            error = Some("Download paused".to_string());
+        }
+        let status = if finished {
+            "completed"
+        } else if checksum_mismatch {
+            crate::checksum::CHECKSUM_MISMATCH_STATUS
+        } else if paused {
+            "paused"
+        } else if was_cancelled.load(Ordering::Relaxed) {
+            "cancelled"
+        } else {
+            "failed"
+        };
+
+        // Update health registry
+        if finished {
             HEALTH_REGISTRY.set_phase(&id, DownloadPhase::Completed);
         } else {
             HEALTH_REGISTRY.set_phase(&id, DownloadPhase::Failed);
         }
 
         // Emit final progress
-        let _ = app_handle.emit("download-progress", SNDEProgress {
+        report(SNDEProgress {
             id: id.clone(),
-            progress: if all_success { 100.0 } else { (final_bytes as f64 / total_size as f64) * 100.0 },
+            progress: if finished { 100.0 } else { (final_bytes as f64 / total_size as f64) * 100.0 },
             speed: String::new(),
             eta: String::new(),
-            status: if all_success { "completed".to_string() } else { "failed".to_string() },
+            status: status.to_string(),
             downloaded_bytes: final_bytes as i64,
             total_bytes: total_size as i64,
             active_connections: 0,
@@ -414,79 +1310,320 @@ impl SNDEEngine {
         });
 
         println!("[SNDE] Download finished: success={}, bytes={}/{}, duration={:.1}s, speed={} KB/s", 
-            all_success && final_bytes == total_size, final_bytes, total_size, duration, avg_speed_kbps);
+            finished, final_bytes, total_size, duration, avg_speed_kbps);
 
         SNDEResult {
-            success: all_success && final_bytes == total_size,
-            error: if all_success { None } else { Some("Download incomplete".to_string()) },
+            success: finished,
+            error,
             bytes_downloaded: final_bytes,
             duration_secs: duration,
             avg_speed_kbps,
+            exchange: exchange.lock().unwrap().take(),
+            output_path: finished.then_some(actual_output_path),
+            paused: paused && !finished,
+            checksum_mismatch,
+            cancelled: status == "cancelled",
         }
     }
 
-    /// Probe the file to get size, range support, and filename
-    async fn probe_file(&self, request: &SNDERequest) -> Result<(u64, bool, Option<String>), String> {
-        let response = self.client
-            .head(&request.url)
-            .header(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .send()
-            .await
-            .map_err(|e| format!("HEAD request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("HEAD request returned {}", response.status()));
-        }
+    /// Fetch a file whose size the server won't tell over a single connection, reporting
+    /// progress by bytes only. Nothing is kept for resuming; a paused download starts over.
+    async fn download_streaming(
+        &self,
+        request: SNDERequest,
+        request_headers: HeaderMap,
+        limits: SNDELimits,
+        report: ProgressSink,
+        mut cancel_rx: mpsc::Receiver<()>,
+        output_path: PathBuf,
+        start_time: Instant,
+    ) -> SNDEResult {
+        let id = request.id.clone();
+        let temp_output_path = crate::downloader::downloading_path(&output_path);
+        println!("[SNDE] File size unknown, streaming over one connection to {:?}", output_path);
 
-        let headers = response.headers();
-        
-        // Get content length
-        let content_length = headers
-            .get(CONTENT_LENGTH)
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse::<u64>().ok())
-            .ok_or_else(|| "No Content-Length header".to_string())?;
-
-        // Check for range support
-        let supports_range = headers
-            .get(ACCEPT_RANGES)
-            .map(|v| v.to_str().unwrap_or("") == "bytes")
-            .unwrap_or(false);
-
-        // Try to extract filename from Content-Disposition header
-        let filename = headers
-            .get("content-disposition")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| {
-                // Parse Content-Disposition: attachment; filename="something.zip"
-                // or Content-Disposition: attachment; filename*=UTF-8''something.zip
-                let extracted = if let Some(pos) = s.find("filename=") {
-                    let rest = &s[pos + 9..];
-                    rest.trim_start_matches('"')
-                        .split('"').next()
-                        .or_else(|| rest.split(';').next())
-                        .map(|s| s.trim().to_string())
-                } else if let Some(pos) = s.find("filename*=") {
-                    let rest = &s[pos + 10..];
-                    // Handle UTF-8 encoded filenames like: UTF-8''filename.ext
-                    rest.split("''").nth(1)
-                        .map(|s| urlencoding::decode(s).unwrap_or_else(|_| s.into()).to_string())
-                } else {
-                    None
+        HEALTH_REGISTRY.set_phase(&id, DownloadPhase::Allocating);
+        let writer = match self.preallocate_file(&temp_output_path, 0).await {
+            Ok(()) => ChunkWriter::open(&temp_output_path).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        let mut writer = match writer {
+            Ok(writer) => writer,
+            Err(e) => {
+                return SNDEResult {
+                    success: false,
+                    error: Some(format!("Failed to allocate file: {}", e)),
+                    bytes_downloaded: 0,
+                    duration_secs: start_time.elapsed().as_secs_f64(),
+                    avg_speed_kbps: 0,
+                    exchange: None,
+                    output_path: None,
+                    paused: false,
+                    checksum_mismatch: false,
+                    cancelled: false,
                 };
+            }
+        };
+        HEALTH_REGISTRY.set_phase(&id, DownloadPhase::Downloading);
 
-                // Prevent path traversal by extracting only the final file component.
-                extracted.map(|name| {
-                    std::path::Path::new(&name)
-                        .file_name()
-                        .map(|f| f.to_string_lossy().into_owned())
-                        .unwrap_or_else(|| "download".to_string())
-                })
-            });
+        let total_downloaded = Arc::new(AtomicU64::new(0));
+        let is_cancelled = Arc::new(AtomicBool::new(false));
+
+        // Progress by bytes and speed; there's no percentage or ETA without a total
+        let progress_handle = {
+            let id = id.clone();
+            let report = Arc::clone(&report);
+            let total_downloaded = Arc::clone(&total_downloaded);
+            let is_cancelled = Arc::clone(&is_cancelled);
+            let badge = request.routing_decision.badge.clone();
+
+            tokio::spawn(async move {
+                let mut last_bytes = 0u64;
+                let mut last_time = Instant::now();
+
+                while !is_cancelled.load(Ordering::Relaxed) {
+                    tokio::time::sleep(Duration::from_millis(250)).await;
+
+                    let current_bytes = total_downloaded.load(Ordering::Relaxed);
+                    let elapsed = last_time.elapsed().as_secs_f64();
+                    if elapsed > 0.0 {
+                        let speed_bps = ((current_bytes - last_bytes) as f64 / elapsed) as u64;
+                        report(SNDEProgress {
+                            id: id.clone(),
+                            progress: 0.0,
+                            speed: format_speed(speed_bps),
+                            eta: String::new(),
+                            status: "downloading".to_string(),
+                            downloaded_bytes: current_bytes as i64,
+                            total_bytes: 0,
+                            active_connections: 1,
+                            engine_badge: badge.clone(),
+                        });
+                        HEALTH_REGISTRY.update_progress(&id, current_bytes, speed_bps);
+
+                        last_bytes = current_bytes;
+                        last_time = Instant::now();
+                    }
+                }
+            })
+        };
+
+        let client = match self.negotiate_http3(&request, &request_headers).await {
+            Some(http3) => http3,
+            None => self.get_client(request.routing_decision.force_http1),
+        };
+        let fetch = Self::stream_to_file(
+            &client,
+            &request.url,
+            &request_headers,
+            &mut writer,
+            Arc::clone(&total_downloaded),
+            Arc::clone(&is_cancelled),
+            bandwidth::download_limiter(&id),
+            Duration::from_secs(limits.stall_timeout_secs),
+        );
+        let (fetched, was_cancelled) = tokio::select! {
+            result = fetch => (result, false),
+            _ = cancel_rx.recv() => (Err("Download cancelled".to_string()), true),
+        };
+        drop(writer);
+
+        is_cancelled.store(true, Ordering::Relaxed);
+        let _ = progress_handle.await;
+        let paused = PAUSE_REQUESTS.lock().unwrap().remove(&id);
+
+        let duration = start_time.elapsed().as_secs_f64();
+        let final_bytes = total_downloaded.load(Ordering::Relaxed);
+        let avg_speed_kbps = if duration > 0.0 {
+            ((final_bytes as f64 / 1024.0) / duration) as u32
+        } else {
+            0
+        };
+
+        let exchange = fetched.as_ref().ok().cloned();
+        let mut error = fetched.err();
+        let mut checksum_mismatch = false;
+        if let (None, Some(expected)) = (&error, request.checksum.clone()) {
+            HEALTH_REGISTRY.set_phase(&id, DownloadPhase::PostProcessing);
+            if let Err(e) = crate::checksum::verify(temp_output_path.clone(), expected).await {
+                println!("[SNDE] {}", e);
+                checksum_mismatch = true;
+                error = Some(e);
+            }
+        }
+        if error.is_none() {
+            if let Err(e) = crate::downloader::finalize_file(&temp_output_path, &output_path).await {
+                error = Some(e);
+            }
+        }
+        let finished = error.is_none();
+        if !finished {
+            let _ = tokio::fs::remove_file(&temp_output_path).await;
+        }
+        if paused && !finished {
+            error = Some("Download paused".to_string());
+        }
+        let status = if finished {
+            "completed"
+        } else if checksum_mismatch {
+            crate::checksum::CHECKSUM_MISMATCH_STATUS
+        } else if paused {
+            "paused"
+        } else if was_cancelled {
+            "cancelled"
+        } else {
+            "failed"
+        };
+        HEALTH_REGISTRY.set_phase(&id, if finished { DownloadPhase::Completed } else { DownloadPhase::Failed });
+
+        report(SNDEProgress {
+            id: id.clone(),
+            progress: if finished { 100.0 } else { 0.0 },
+            speed: String::new(),
+            eta: String::new(),
+            status: status.to_string(),
+            downloaded_bytes: final_bytes as i64,
+            total_bytes: if finished { final_bytes as i64 } else { 0 },
+            active_connections: 0,
+            engine_badge: request.routing_decision.badge.clone(),
+        });
+
+        println!("[SNDE] Streamed download finished: success={}, bytes={}, duration={:.1}s, speed={} KB/s",
+            finished, final_bytes, duration, avg_speed_kbps);
+
+        SNDEResult {
+            success: finished,
+            error,
+            bytes_downloaded: final_bytes,
+            duration_secs: duration,
+            avg_speed_kbps,
+            exchange,
+            output_path: finished.then_some(output_path),
+            paused: paused && !finished,
+            checksum_mismatch,
+            cancelled: status == "cancelled",
+        }
+    }
+
+    /// GET the whole body and write it out in order until the server closes the stream;
+    /// returns the exchange for WARC capture
+    async fn stream_to_file(
+        client: &Client,
+        url: &str,
+        request_headers: &HeaderMap,
+        writer: &mut ChunkWriter,
+        total_downloaded: Arc<AtomicU64>,
+        is_cancelled: Arc<AtomicBool>,
+        download_limiter: Arc<BandwidthLimiter>,
+        stall_timeout: Duration,
+    ) -> Result<HttpExchange, String> {
+        use futures_util::StreamExt;
+
+        let request = client.get(url).headers(request_headers.clone()).send();
+        let response = match tokio::time::timeout(stall_timeout, request).await {
+            Ok(Ok(r)) => r,
+            Ok(Err(e)) => return Err(format!("Request failed: {}", e)),
+            Err(_) => return Err(format!("No response for {}s", stall_timeout.as_secs())),
+        };
+        if !response.status().is_success() {
+            return Err(format!("Server returned {}", response.status()));
+        }
+        let exchange = HttpExchange::capture(request_headers, &response);
+
+        let mut stream = response.bytes_stream();
+        loop {
+            let bytes = match tokio::time::timeout(stall_timeout, stream.next()).await {
+                Ok(Some(Ok(bytes))) => bytes,
+                Ok(Some(Err(e))) => return Err(format!("Stream error: {}", e)),
+                Ok(None) => break,
+                Err(_) => return Err(format!("Download stalled for {}s", stall_timeout.as_secs())),
+            };
+            if is_cancelled.load(Ordering::Relaxed) {
+                return Err("Download cancelled".to_string());
+            }
 
-        println!("[SNDE] Probe result: size={}, range={}, filename={:?}", content_length, supports_range, filename);
+            writer.write(&bytes).await.map_err(|e| format!("Write failed: {}", e))?;
+            total_downloaded.fetch_add(bytes.len() as u64, Ordering::Relaxed);
 
-        Ok((content_length, supports_range, filename))
+            // Honour global and per-download speed limits
+            GLOBAL_BANDWIDTH_LIMITER.acquire(bytes.len() as u64).await;
+            download_limiter.acquire(bytes.len() as u64).await;
+        }
+
+        writer.flush().await.map_err(|e| format!("Write failed: {}", e))?;
+        Ok(exchange)
+    }
+
+    /// Probe the file to get size (if the server reveals it), range support, and filename
+    async fn probe_file(
+        &self,
+        request: &SNDERequest,
+        request_headers: &HeaderMap,
+    ) -> Result<(Option<u64>, bool, Option<String>, HttpExchange), String> {
+        let head = self.get_client(false)
+            .head(&request.url)
+            .headers(request_headers.clone())
+            .send()
+            .await;
+
+        // Many hosts reject HEAD or leave out Content-Length; ask for the first byte instead
+        let response = match head {
+            Ok(r) if r.status().is_success() && content_length(r.headers()).is_some() => r,
+            Ok(r) => {
+                println!("[SNDE] HEAD returned {} without a size, probing with a ranged GET", r.status());
+                self.probe_with_range_get(&request.url, request_headers).await?
+            }
+            Err(e) => {
+                println!("[SNDE] HEAD request failed ({}), probing with a ranged GET", e);
+                self.probe_with_range_get(&request.url, request_headers).await?
+            }
+        };
+
+        let (content_length, supports_range) = size_and_range_support(&response);
+        let filename = content_disposition_filename(response.headers());
+
+        println!("[SNDE] Probe result: size={:?}, range={}, filename={:?}", content_length, supports_range, filename);
+
+        Ok((content_length, supports_range, filename, HttpExchange::capture(request_headers, &response)))
+    }
+
+    /// GET just the first byte; a 206 gives range support and (usually) the size, a 200
+    /// means the server ignored the range
+    async fn probe_with_range_get(&self, url: &str, request_headers: &HeaderMap) -> Result<Response, String> {
+        let response = self.get_client(false)
+            .get(url)
+            .headers(request_headers.clone())
+            .header(RANGE, "bytes=0-0")
+            .send()
+            .await
+            .map_err(|e| format!("Probe request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Probe request returned {}", response.status()));
+        }
+        Ok(response)
+    }
+
+    /// Check that a mirror serves a file of the same size with range support
+    async fn probe_mirror(&self, url: &str, request_headers: &HeaderMap, total_size: u64) -> Result<(), String> {
+        let head = self.get_client(false)
+            .head(url)
+            .headers(request_headers.clone())
+            .send()
+            .await;
+        let response = match head {
+            Ok(r) if r.status().is_success() && content_length(r.headers()).is_some() => r,
+            _ => self.probe_with_range_get(url, request_headers).await?,
+        };
+
+        let (size, supports_range) = size_and_range_support(&response);
+        if size != Some(total_size) {
+            return Err(format!("size {:?} doesn't match {}", size, total_size));
+        }
+        if !supports_range {
+            return Err("no range support".to_string());
+        }
+        Ok(())
     }
 
     /// Pre-allocate the output file (Windows-optimized)
@@ -521,22 +1658,26 @@ impl SNDEEngine {
         Ok(())
     }
 
-    /// Create work chunks for parallel download
-    fn create_chunks(&self, total_size: u64, num_connections: u8) -> Vec<ChunkWork> {
-        let chunk_size = (total_size / num_connections as u64).max(MIN_CHUNK_SIZE);
+    /// Create work chunks for parallel download, skipping ranges a previous run completed
+    fn create_chunks(
+        &self,
+        total_size: u64,
+        num_connections: u8,
+        max_chunk_size: u64,
+        completed: &[(u64, u64)],
+    ) -> Vec<ChunkWork> {
+        let chunk_size = (total_size / num_connections as u64)
+            .min(max_chunk_size)
+            .max(MIN_CHUNK_SIZE);
         let mut chunks = Vec::new();
-        let mut start = 0u64;
 
-        while start < total_size {
-            let end = (start + chunk_size - 1).min(total_size - 1);
-            chunks.push(ChunkWork {
-                start,
-                end,
-                in_progress: false,
-                completed: false,
-                retries: 0,
-            });
-            start = end + 1;
+        for (gap_start, gap_end) in missing_ranges(total_size, completed) {
+            let mut start = gap_start;
+            while start <= gap_end {
+                let end = (start + chunk_size - 1).min(gap_end);
+                chunks.push(ChunkWork::new(start, end));
+                start = end + 1;
+            }
         }
 
         chunks
@@ -546,34 +1687,67 @@ impl SNDEEngine {
     async fn worker_loop(
         conn_id: u8,
         client: Client,
-        url: String,
+        tcp_fallback: Option<Client>,
+        safe_client: Option<Client>,
+        control: Arc<ConnectionControl>,
+        mirrors: Arc<MirrorPool>,
         chunks: Arc<Mutex<Vec<ChunkWork>>>,
-        file: Arc<Mutex<File>>,
+        output_path: PathBuf,
         total_downloaded: Arc<AtomicU64>,
         is_cancelled: Arc<AtomicBool>,
         _connection_stats: Arc<Vec<ConnectionStats>>,
-        _download_id: String,
+        exchange: Arc<ExchangeSlot>,
+        download_id: String,
+        stall_timeout: Duration,
     ) -> bool {
+        let download_limiter = bandwidth::download_limiter(&download_id);
+        let mut writer = match ChunkWriter::open(&output_path).await {
+            Ok(writer) => writer,
+            Err(e) => {
+                println!("[SNDE] Worker {} could not open {:?}: {}", conn_id, output_path, e);
+                return false;
+            }
+        };
+
         loop {
             if is_cancelled.load(Ordering::Relaxed) {
                 return true;
             }
+            if control.sheds(conn_id) {
+                println!("[SNDE] Worker {} stopped by the watchdog", conn_id);
+                return true;
+            }
 
             // Try to claim a chunk
             let chunk_opt = {
                 let mut chunks_guard = chunks.lock().await;
-                let mut found = None;
-                for (idx, c) in chunks_guard.iter_mut().enumerate() {
-                    if !c.completed && !c.in_progress && c.retries < 5 {
-                        c.in_progress = true;
-                        found = Some((c.start, c.end, idx));
-                        break;
+                let now = Instant::now();
+                let mut found = chunks_guard.iter_mut().position(|c| {
+                    !c.completed
+                        && !c.in_progress
+                        && c.retries < MAX_CHUNK_RETRIES
+                        && c.retry_at.map_or(true, |at| at <= now)
+                });
+                if let Some(idx) = found {
+                    chunks_guard[idx].in_progress = true;
+                } else {
+                    // Nothing queued: take the tail of the biggest chunk still being fetched
+                    found = steal_tail(&mut chunks_guard);
+                    if let Some(idx) = found {
+                        let tail = &chunks_guard[idx];
+                        println!("[SNDE] Worker {} took over bytes {}-{}", conn_id, tail.start, tail.end);
                     }
                 }
-                found
+                found.map(|idx| {
+                    let c = &chunks_guard[idx];
+                    // A retried chunk starts over
+                    c.cursor.position.store(c.start, Ordering::Relaxed);
+                    c.cursor.limit.store(c.end, Ordering::Relaxed);
+                    (c.start, c.end, idx, Arc::clone(&c.cursor))
+                })
             };
 
-            let (start, end, chunk_idx) = match chunk_opt {
+            let (start, end, chunk_idx, cursor) = match chunk_opt {
                 Some(c) => c,
                 None => {
                     // Check if all done
@@ -582,113 +1756,326 @@ impl SNDEEngine {
                     if all_complete {
                         return true;
                     }
+                    // Chunks out of retries can't finish; give up once nothing else is running
+                    let stuck = chunks_guard
+                        .iter()
+                        .all(|c| c.completed || (!c.in_progress && c.retries >= MAX_CHUNK_RETRIES));
+                    if stuck {
+                        return false;
+                    }
                     // Wait and retry
                     tokio::time::sleep(Duration::from_millis(100)).await;
                     continue;
                 }
             };
 
-            println!("[SNDE] Worker {} downloading bytes {}-{}", conn_id, start, end);
+            let mirror = mirrors.pick();
+            println!("[SNDE] Worker {} downloading bytes {}-{} from {}", conn_id, start, end, mirror.url);
+            let chunk_started = Instant::now();
+
+            // Safe Mode moves the remaining connection off HTTP/1.1, mid-chunk if need be; a
+            // QUIC failure moves every connection to TCP
+            let safe_mode = control.safe_mode();
+            let on_quic = tcp_fallback.is_some() && !control.quic_failed();
+            let chunk_client = match (&tcp_fallback, &safe_client, safe_mode) {
+                (Some(tcp), _, _) if !on_quic => tcp,
+                (_, Some(http2), true) => http2,
+                _ => &client,
+            };
+            let on_http1 = safe_client.is_some() && !safe_mode;
+            let shed = || control.sheds(conn_id) || (on_http1 && control.safe_mode());
 
             // Download this chunk
             let result = Self::download_chunk(
-                &client,
-                &url,
+                chunk_client,
+                &mirror.url,
                 start,
                 end,
-                Arc::clone(&file),
+                &cursor,
+                &mut writer,
                 Arc::clone(&total_downloaded),
                 Arc::clone(&is_cancelled),
+                Arc::clone(&download_limiter),
+                stall_timeout,
+                &mirror.headers,
+                &exchange,
+                &shed,
             ).await;
+            let position = cursor.position.load(Ordering::Relaxed);
+            let was_shed = matches!(&result, Err(e) if e.shed);
+            mirrors.report(&mirror, position - start, chunk_started.elapsed().as_secs_f64(), result.is_ok() || was_shed);
+            let cancelled = is_cancelled.load(Ordering::Relaxed);
+            if let (Err(e), false, false) = (&result, cancelled, was_shed) {
+                HEALTH_REGISTRY.record_error(&download_id, &e.message, e.status);
+            }
+            // A connection-level failure over QUIC: the chunk is tried again over TCP
+            let quic_broke = on_quic && !cancelled && !was_shed && matches!(&result, Err(e) if e.status.is_none());
+            if quic_broke && control.fail_quic() {
+                println!("[SNDE] HTTP/3 failed mid-download for {}, moving to TCP", download_id);
+                crate::download_router::forget_quic_host(&mirror.url);
+            }
 
             // Update chunk status
             {
                 let mut chunks_guard = chunks.lock().await;
+                let mut requeue = None;
                 if let Some(chunk) = chunks_guard.get_mut(chunk_idx) {
                     chunk.in_progress = false;
-                    if result {
-                        chunk.completed = true;
-                        println!("[SNDE] Worker {} completed chunk {}-{}", conn_id, start, end);
-                    } else {
-                        chunk.retries += 1;
-                        println!("[SNDE] Worker {} failed chunk {}-{}, retry {}", conn_id, start, end, chunk.retries);
+                    match &result {
+                        Ok(()) => {
+                            chunk.completed = true;
+                            println!("[SNDE] Worker {} completed chunk {}-{}", conn_id, start, chunk.end);
+                        }
+                        Err(_) if cancelled => {}
+                        Err(e) if quic_broke => {
+                            // The retry starts over from the chunk's start
+                            total_downloaded.fetch_sub(position - start, Ordering::Relaxed);
+                            println!("[SNDE] Worker {} retrying bytes {}-{} over TCP ({})", conn_id, start, end, e.message);
+                        }
+                        Err(_) if was_shed => {
+                            // Keep what arrived; the rest goes back in the queue without costing a retry
+                            println!("[SNDE] Worker {} put back bytes {}-{}", conn_id, position, chunk.end);
+                            if position > start && position <= chunk.end {
+                                requeue = Some(ChunkWork::new(position, chunk.end));
+                                chunk.end = position - 1;
+                                chunk.completed = true;
+                            }
+                        }
+                        Err(e) => {
+                            // The retry starts over from the chunk's start
+                            total_downloaded.fetch_sub(position - start, Ordering::Relaxed);
+                            chunk.retries += 1;
+                            // Back off, and further still once the host has had its share of retries
+                            let host = extract_domain(&mirror.url).unwrap_or_default();
+                            let delay = retry_delay(chunk.retries, e.retry_after).max(spend_retry_budget(&host));
+                            chunk.retry_at = Some(Instant::now() + delay);
+                            println!(
+                                "[SNDE] Worker {} failed chunk {}-{} ({}), retry {} in {:.1}s",
+                                conn_id, start, end, e.message, chunk.retries, delay.as_secs_f64()
+                            );
+                        }
                     }
                 }
+                chunks_guard.extend(requeue);
             }
         }
     }
 
-    /// Download a single chunk
+    /// Download a single chunk, stopping early if `cursor.limit` is lowered by work stealing
+    /// or `shed` says the watchdog wants the connection back
     async fn download_chunk(
         client: &Client,
         url: &str,
         start: u64,
         end: u64,
-        file: Arc<Mutex<File>>,
+        cursor: &ChunkCursor,
+        writer: &mut ChunkWriter,
         total_downloaded: Arc<AtomicU64>,
         is_cancelled: Arc<AtomicBool>,
-    ) -> bool {
+        download_limiter: Arc<BandwidthLimiter>,
+        stall_timeout: Duration,
+        request_headers: &HeaderMap,
+        exchange: &ExchangeSlot,
+        shed: &(dyn Fn() -> bool + Sync),
+    ) -> Result<(), ChunkError> {
         let range_header = format!("bytes={}-{}", start, end);
         
-        let response = match client
+        let request = client
             .get(url)
+            .headers(request_headers.clone())
             .header(RANGE, &range_header)
-            .header(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
-                println!("[SNDE] Request failed: {}", e);
-                return false;
-            }
+            .send();
+        let response = match tokio::time::timeout(stall_timeout, request).await {
+            Ok(Ok(r)) => r,
+            Ok(Err(e)) => return Err(ChunkError::new(format!("Request failed: {}", e))),
+            Err(_) => return Err(ChunkError::new(format!("No response for {}s", stall_timeout.as_secs()))),
         };
 
+        // A 200 to a range past the start means If-Range failed: the file changed
+        if start > 0 && response.status() == StatusCode::OK && request_headers.contains_key(IF_RANGE) {
+            return Err(ChunkError {
+                message: "The file changed on the server since the download started".to_string(),
+                status: Some(200),
+                retry_after: None,
+                shed: false,
+            });
+        }
+
         if !response.status().is_success() && response.status().as_u16() != 206 {
-            println!("[SNDE] Bad status: {}", response.status());
-            return false;
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            return Err(ChunkError {
+                message: format!("Bad status: {}", response.status()),
+                status: Some(response.status().as_u16()),
+                retry_after,
+                shed: false,
+            });
+        }
+
+        // The first response to arrive stands for the download in a WARC capture
+        {
+            let mut first = exchange.lock().unwrap();
+            if first.is_none() {
+                *first = Some(HttpExchange::capture(request_headers, &response));
+            }
         }
 
         let mut stream = response.bytes_stream();
         let mut position = start;
-        let mut _buffer: Vec<u8> = Vec::with_capacity(BUFFER_SIZE);
+        if let Err(e) = writer.seek_to(start).await {
+            return Err(ChunkError::new(format!("Seek failed at byte {}: {}", start, e)));
+        }
 
         use futures_util::StreamExt;
 
-        while let Some(chunk_result) = stream.next().await {
+        loop {
+            // A connection that delivers nothing for the stall timeout is dropped and the chunk retried
+            let chunk_result = match tokio::time::timeout(stall_timeout, stream.next()).await {
+                Ok(Some(chunk_result)) => chunk_result,
+                Ok(None) => break,
+                Err(_) => {
+                    return Err(ChunkError::new(format!(
+                        "Stalled for {}s at byte {}",
+                        stall_timeout.as_secs(),
+                        position
+                    )));
+                }
+            };
+
             if is_cancelled.load(Ordering::Relaxed) {
-                return false;
+                return Err(ChunkError::new("Cancelled"));
             }
 
             match chunk_result {
                 Ok(bytes) => {
                     let bytes: bytes::Bytes = bytes;
-                    let len = bytes.len();
+                    // Another worker may have taken over the rest of this range
+                    let limit = cursor.limit.load(Ordering::Relaxed);
+                    let len = (bytes.len() as u64).min((limit + 1).saturating_sub(position)) as usize;
                     
-                    // Write to file at correct position
-                    {
-                        let mut file_guard = file.lock().await;
-                        if let Err(e) = file_guard.seek(SeekFrom::Start(position)).await {
-                            println!("[SNDE] Seek failed: {}", e);
-                            return false;
-                        }
-                        if let Err(e) = file_guard.write_all(&bytes).await {
-                            println!("[SNDE] Write failed: {}", e);
-                            return false;
-                        }
+                    if let Err(e) = writer.write(&bytes[..len]).await {
+                        return Err(ChunkError::new(format!("Write failed at byte {}: {}", position, e)));
                     }
 
                     position += len as u64;
+                    cursor.position.store(position, Ordering::Relaxed);
                     total_downloaded.fetch_add(len as u64, Ordering::Relaxed);
+
+                    // Honour global and per-download speed limits
+                    GLOBAL_BANDWIDTH_LIMITER.acquire(len as u64).await;
+                    download_limiter.acquire(len as u64).await;
+
+                    if position > limit {
+                        break;
+                    }
+                    if shed() {
+                        if let Err(e) = writer.flush().await {
+                            return Err(ChunkError::new(format!("Write failed before byte {}: {}", position, e)));
+                        }
+                        return Err(ChunkError { shed: true, ..ChunkError::new("Connection shed") });
+                    }
                 }
-                Err(e) => {
-                    println!("[SNDE] Stream error: {}", e);
-                    return false;
-                }
+                Err(e) => return Err(ChunkError::new(format!("Stream error: {}", e))),
             }
         }
 
-        true
+        // The chunk only counts once its bytes are on disk
+        if let Err(e) = writer.flush().await {
+            return Err(ChunkError::new(format!("Write failed before byte {}: {}", position, e)));
+        }
+        Ok(())
+    }
+}
+
+/// File size and range support from a HEAD response or a `bytes=0-0` probe
+fn size_and_range_support(response: &Response) -> (Option<u64>, bool) {
+    let headers = response.headers();
+    if response.status() == StatusCode::PARTIAL_CONTENT {
+        return (content_range_total(headers), true);
+    }
+    let supports_range = headers
+        .get(ACCEPT_RANGES)
+        .is_some_and(|v| v.to_str().unwrap_or("") == "bytes");
+    (content_length(headers), supports_range)
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok())
+}
+
+/// Full size from `Content-Range: bytes 0-0/12345`; `None` for `*` or a malformed header
+fn content_range_total(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.rsplit_once('/'))
+        .and_then(|(_, total)| total.trim().parse::<u64>().ok())
+}
+
+/// Filename from Content-Disposition, reduced to its final path component
+fn content_disposition_filename(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("content-disposition")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| {
+            // Parse Content-Disposition: attachment; filename="something.zip"
+            // or Content-Disposition: attachment; filename*=UTF-8''something.zip
+            let extracted = if let Some(pos) = s.find("filename=") {
+                let rest = &s[pos + 9..];
+                rest.trim_start_matches('"')
+                    .split('"').next()
+                    .or_else(|| rest.split(';').next())
+                    .map(|s| s.trim().to_string())
+            } else if let Some(pos) = s.find("filename*=") {
+                let rest = &s[pos + 10..];
+                // Handle UTF-8 encoded filenames like: UTF-8''filename.ext
+                rest.split("''").nth(1)
+                    .map(|s| urlencoding::decode(s).unwrap_or_else(|_| s.into()).to_string())
+            } else {
+                None
+            };
+
+            // Prevent path traversal by extracting only the final file component.
+            extracted.map(|name| {
+                std::path::Path::new(&name)
+                    .file_name()
+                    .map(|f| f.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "download".to_string())
+            })
+        })
+}
+
+/// A worker's own buffered handle on the output file. Workers write disjoint ranges, so
+/// nothing is shared between them.
+pub struct ChunkWriter {
+    inner: BufWriter<File>,
+}
+
+impl ChunkWriter {
+    pub async fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().write(true).open(path).await?;
+        Ok(Self { inner: BufWriter::with_capacity(BUFFER_SIZE, file) })
+    }
+
+    /// Move to `position`, writing out anything buffered for the previous range first
+    pub async fn seek_to(&mut self, position: u64) -> std::io::Result<()> {
+        self.inner.seek(SeekFrom::Start(position)).await.map(|_| ())
+    }
+
+    pub async fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.inner.write_all(bytes).await
+    }
+
+    /// Write out the buffer and wait for the file to take it
+    pub async fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush().await
     }
 }
 
@@ -704,7 +2091,7 @@ lazy_static::lazy_static! {
 }
 
 /// Format bytes per second to human readable speed
-fn format_speed(bps: u64) -> String {
+pub(crate) fn format_speed(bps: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
     const GB: u64 = MB * 1024;
@@ -721,7 +2108,7 @@ fn format_speed(bps: u64) -> String {
 }
 
 /// Format seconds to human readable ETA
-fn format_eta(seconds: u64) -> String {
+pub(crate) fn format_eta(seconds: u64) -> String {
     if seconds >= 3600 {
         let hours = seconds / 3600;
         let mins = (seconds % 3600) / 60;
@@ -735,10 +2122,243 @@ fn format_eta(seconds: u64) -> String {
     }
 }
 
+/// Get the global SNDE limits
+#[tauri::command]
+pub async fn get_snde_limits(app_handle: AppHandle) -> Result<SNDELimits, String> {
+    Ok(load_limits(&app_handle))
+}
+
+/// Save the global SNDE limits (values are clamped to safe bounds). Applies to new downloads.
+#[tauri::command]
+pub async fn set_snde_limits(
+    state: State<'_, AppState>,
+    limits: SNDELimits,
+) -> Result<SNDELimits, String> {
+    let limits = limits.clamped();
+    let json = serde_json::to_string(&limits).map_err(|e| e.to_string())?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(SNDE_LIMITS_SETTING, &json).map_err(|e| e.to_string())?;
+    Ok(limits)
+}
+
+/// Get the per-host limit overrides
+#[tauri::command]
+pub async fn get_snde_host_overrides(
+    app_handle: AppHandle,
+) -> Result<HashMap<String, SNDELimitOverride>, String> {
+    Ok(load_host_overrides(&app_handle))
+}
+
+/// Set or remove (`limits: None`) the override for a host
+#[tauri::command]
+pub async fn set_snde_host_override(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    host: String,
+    limits: Option<SNDELimitOverride>,
+) -> Result<(), String> {
+    let host = crate::host_reputation::normalize_host(&host)?;
+
+    let mut overrides = load_host_overrides(&app_handle);
+    match limits {
+        Some(limits) => {
+            overrides.insert(host, limits);
+        }
+        None => {
+            overrides.remove(&host);
+        }
+    }
+
+    let json = serde_json::to_string(&overrides).map_err(|e| e.to_string())?;
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(SNDE_HOST_OVERRIDES_SETTING, &json).map_err(|e| e.to_string())
+}
+
+/// Settings key for the URL `snde_speed_test` downloads from
+pub const SPEED_TEST_URL_SETTING: &str = "snde_speed_test_url";
+
+/// A large file on a host that serves byte ranges
+const DEFAULT_SPEED_TEST_URL: &str = "https://proof.ovh.net/files/100Mb.dat";
+
+/// Bytes fetched per configuration, split evenly over its connections
+const SPEED_TEST_BYTES: u64 = 8 * 1024 * 1024;
+
+const SPEED_TEST_CONNECTIONS: &[u8] = &[1, 4, 8];
+
+/// Longest a single configuration may take
+const SPEED_TEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Throughput of one connection count
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeedTestRun {
+    pub connections: u8,
+    pub bytes: u64,
+    pub duration_ms: u64,
+    pub speed_bps: u64,
+    pub speed: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeedTestReport {
+    pub url: String,
+    pub runs: Vec<SpeedTestRun>,
+    /// The fewest connections that got close to the best speed
+    pub recommended_connections: Option<u8>,
+}
+
+/// Fetch the first `sample` bytes of `url` over `connections` parallel ranges, discarding them
+async fn measure_connections(client: &Client, url: &str, sample: u64, connections: u8) -> SpeedTestRun {
+    use futures_util::StreamExt;
+
+    let headers = crate::header_profiles::headers_for_url(url);
+    let share = sample.div_ceil(connections as u64);
+    let started = Instant::now();
+    let fetches = (0..connections as u64).map(|n| n * share).filter(|start| *start < sample).map(|start| {
+        let end = (start + share).min(sample) - 1;
+        let request = client.get(url).headers(headers.clone()).header(RANGE, format!("bytes={}-{}", start, end));
+        async move {
+            let response = request.send().await.map_err(|e| format!("Request failed: {}", e))?;
+            if response.status() != StatusCode::PARTIAL_CONTENT && connections > 1 {
+                return Err(format!("Server doesn't serve ranges ({})", response.status()));
+            }
+            let wanted = end - start + 1;
+            let mut received = 0;
+            let mut stream = response.bytes_stream();
+            while received < wanted {
+                match stream.next().await {
+                    Some(Ok(bytes)) => received += bytes.len() as u64,
+                    Some(Err(e)) => return Err(format!("Stream error: {}", e)),
+                    None => break,
+                }
+            }
+            Ok(received.min(wanted))
+        }
+    });
+
+    let outcome = tokio::time::timeout(SPEED_TEST_TIMEOUT, futures_util::future::join_all(fetches)).await;
+    let elapsed = started.elapsed();
+    let (bytes, error) = match outcome {
+        Ok(results) => results.into_iter().fold((0, None), |(bytes, error), result| match result {
+            Ok(received) => (bytes + received, error),
+            Err(e) => (bytes, error.or(Some(e))),
+        }),
+        Err(_) => (0, Some(format!("Took longer than {}s", SPEED_TEST_TIMEOUT.as_secs()))),
+    };
+    let speed_bps = match error {
+        None => (bytes as f64 / elapsed.as_secs_f64().max(0.001)) as u64,
+        Some(_) => 0,
+    };
+    SpeedTestRun {
+        connections,
+        bytes,
+        duration_ms: elapsed.as_millis() as u64,
+        speed_bps,
+        speed: format_speed(speed_bps),
+        error,
+    }
+}
+
+/// Fewest connections within `RAMP_MIN_GAIN` of the fastest run, so extra connections are
+/// only recommended when they pay off
+fn recommend_connections(runs: &[SpeedTestRun]) -> Option<u8> {
+    let best = runs.iter().filter(|r| r.error.is_none()).map(|r| r.speed_bps).max()?;
+    runs.iter()
+        .filter(|r| r.error.is_none() && r.speed_bps as f64 * (1.0 + RAMP_MIN_GAIN) >= best as f64)
+        .map(|r| r.connections)
+        .min()
+}
+
+/// Download a few MB from the test URL (`url`, else the saved one, else a default) with 1, 4
+/// and 8 connections. The recommended count is recorded in the test host's reputation.
+#[tauri::command]
+pub async fn snde_speed_test(app_handle: AppHandle, url: Option<String>) -> Result<SpeedTestReport, String> {
+    let url = url
+        .filter(|u| !u.trim().is_empty())
+        .or_else(|| crate::commands::read_setting(&app_handle, SPEED_TEST_URL_SETTING))
+        .unwrap_or_else(|| DEFAULT_SPEED_TEST_URL.to_string());
+    let parsed = url::Url::parse(url.trim()).map_err(|e| format!("Invalid speed test URL: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("The speed test URL must be http or https".to_string());
+    }
+    let url = parsed.to_string();
+
+    // HTTP/1.1 so every connection is its own TCP stream, as in a download
+    let client = SNDE_ENGINE.get_client(true);
+    let mut runs = Vec::new();
+    for &connections in SPEED_TEST_CONNECTIONS {
+        let run = measure_connections(&client, &url, SPEED_TEST_BYTES, connections).await;
+        println!("[SNDE] Speed test with {} connections: {} ({:?})", connections, run.speed, run.error);
+        runs.push(run);
+    }
+
+    let recommended_connections = recommend_connections(&runs);
+    if let (Some(connections), Some(domain), Some(manager)) =
+        (recommended_connections, extract_domain(&url), crate::host_reputation::manager())
+    {
+        if let Err(e) = manager.record_optimal_connections(&domain, connections) {
+            println!("[SNDE] Failed to record speed test result for {}: {}", domain, e);
+        }
+    }
+    Ok(SpeedTestReport { url, runs, recommended_connections })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_limits_clamped() {
+        let limits = SNDELimits {
+            max_connections: 200,
+            chunk_size_mb: 0,
+            stall_timeout_secs: 1,
+        }
+        .clamped();
+        assert_eq!(limits.max_connections, MAX_CONNECTIONS_LIMIT);
+        assert_eq!(limits.chunk_size_bytes(), MIN_CHUNK_SIZE);
+        assert_eq!(limits.stall_timeout_secs, MIN_STALL_TIMEOUT_SECS);
+    }
+
+    #[test]
+    fn test_host_override() {
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "example.com".to_string(),
+            SNDELimitOverride {
+                max_connections: Some(4),
+                ..Default::default()
+            },
+        );
+
+        let host = find_host_override(&overrides, "cdn.example.com").unwrap();
+        let limits = SNDELimits::default().with_override(host);
+        assert_eq!(limits.max_connections, 4);
+        assert_eq!(limits.stall_timeout_secs, STALL_TIMEOUT_SECS);
+        assert!(find_host_override(&overrides, "example.org").is_none());
+    }
+
+    #[test]
+    fn test_connection_ramp() {
+        let mut ramp = ConnectionRamp::new(RAMP_START_CONNECTIONS, 8);
+        assert!(ramp.observe(10.0));
+        assert!(ramp.observe(20.0));
+        // The fourth connection added less than the required gain
+        assert!(!ramp.observe(21.0));
+        assert_eq!((ramp.connections, ramp.optimum), (4, Some(3)));
+        assert!(!ramp.observe(100.0));
+
+        // Capped by the engine limit
+        let mut ramp = ConnectionRamp::new(2, 3);
+        assert!(ramp.observe(10.0));
+        assert!(!ramp.observe(20.0));
+        assert_eq!(ramp.optimum, Some(3));
+
+        // Nothing to ramp without range support
+        let ramp = ConnectionRamp::new(RAMP_START_CONNECTIONS, 1);
+        assert!(ramp.done && ramp.connections == 1 && ramp.optimum.is_none());
+    }
+
     #[test]
     fn test_format_speed() {
         assert_eq!(format_speed(500), "500 B/s");
@@ -753,4 +2373,542 @@ mod tests {
         assert_eq!(format_eta(90), "1m 30s");
         assert_eq!(format_eta(3700), "1h 1m");
     }
+
+    #[test]
+    fn test_resume_chunks_skip_completed_ranges() {
+        assert_eq!(merge_ranges(vec![(10, 19), (0, 9), (30, 39), (35, 50)]), vec![(0, 19), (30, 50)]);
+        assert_eq!(missing_ranges(60, &[(0, 19), (30, 50)]), vec![(20, 29), (51, 59)]);
+        assert_eq!(missing_ranges(60, &[(0, 59)]), Vec::<(u64, u64)>::new());
+
+        let engine = SNDEEngine::new();
+        let total = 4 * MIN_CHUNK_SIZE;
+        let done = [(0, MIN_CHUNK_SIZE - 1), (2 * MIN_CHUNK_SIZE, 3 * MIN_CHUNK_SIZE - 1)];
+        let chunks = engine.create_chunks(total, 4, MIN_CHUNK_SIZE, &done);
+        let ranges: Vec<(u64, u64)> = chunks.iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(
+            ranges,
+            vec![(MIN_CHUNK_SIZE, 2 * MIN_CHUNK_SIZE - 1), (3 * MIN_CHUNK_SIZE, total - 1)]
+        );
+
+        let state = ChunkState::new("https://example.com/a.bin", total, FileVersion::default(), None).snapshot(&done, &chunks);
+        assert_eq!(state.bytes(), 2 * MIN_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_steal_tail_splits_largest_in_progress_chunk() {
+        let mut chunks = vec![ChunkWork::new(0, 3 * MIN_CHUNK_SIZE - 1), ChunkWork::new(3 * MIN_CHUNK_SIZE, 8 * MIN_CHUNK_SIZE - 1)];
+        // Nothing is being fetched yet
+        assert_eq!(steal_tail(&mut chunks), None);
+
+        chunks[0].in_progress = true;
+        chunks[1].in_progress = true;
+        chunks[1].cursor.position.store(4 * MIN_CHUNK_SIZE, Ordering::Relaxed);
+        let tail = steal_tail(&mut chunks).unwrap();
+        assert_eq!((chunks[1].start, chunks[1].end), (3 * MIN_CHUNK_SIZE, 6 * MIN_CHUNK_SIZE - 1));
+        assert_eq!(chunks[1].cursor.limit.load(Ordering::Relaxed), 6 * MIN_CHUNK_SIZE - 1);
+        assert_eq!((chunks[tail].start, chunks[tail].end), (6 * MIN_CHUNK_SIZE, 8 * MIN_CHUNK_SIZE - 1));
+        assert!(chunks[tail].in_progress);
+
+        // Halves may not drop below the minimum chunk size
+        chunks[0].cursor.position.store(MIN_CHUNK_SIZE + 1, Ordering::Relaxed);
+        chunks[1].cursor.position.store(5 * MIN_CHUNK_SIZE, Ordering::Relaxed);
+        chunks[tail].cursor.position.store(7 * MIN_CHUNK_SIZE, Ordering::Relaxed);
+        assert_eq!(steal_tail(&mut chunks), None);
+    }
+
+    #[tokio::test]
+    async fn test_playable_prefix_stops_at_first_gap() {
+        let engine = SNDEEngine::new();
+        let mut chunks = engine.create_chunks(4 * MIN_CHUNK_SIZE, 4, MIN_CHUNK_SIZE, &[]);
+        chunks[0].completed = true;
+        chunks[2].completed = true;
+        let id = format!("snde-live-{}", uuid::Uuid::new_v4());
+        LIVE_FILES.lock().unwrap().insert(id.clone(), Arc::new(LiveFile {
+            url: "https://example.com/movie.mkv".to_string(),
+            temp_path: PathBuf::from("movie.mkv.downloading"),
+            final_path: PathBuf::from("movie.mkv"),
+            total_size: 4 * MIN_CHUNK_SIZE,
+            previously_completed: vec![(MIN_CHUNK_SIZE, MIN_CHUNK_SIZE + 99)],
+            chunks: Arc::new(Mutex::new(chunks)),
+        }));
+
+        let prefix = playable_prefix(&id).await.unwrap();
+        assert_eq!(prefix.available, MIN_CHUNK_SIZE + 100);
+        assert_eq!(prefix.total_size, 4 * MIN_CHUNK_SIZE);
+
+        LIVE_FILES.lock().unwrap().remove(&id);
+        assert!(playable_prefix(&id).await.is_none());
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_with_jitter() {
+        for attempt in 1..=4u8 {
+            let full = RETRY_BASE_DELAY * (1 << (attempt - 1));
+            let delay = retry_delay(attempt, None);
+            assert!(delay >= full / 2 && delay <= full, "attempt {}: {:?}", attempt, delay);
+        }
+        assert!(retry_delay(40, None) <= RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(1, Some(Duration::from_secs(7))), Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_retry_budget_is_per_host() {
+        let host = format!("budget-{}.example", uuid::Uuid::new_v4());
+        for _ in 0..RETRY_BUDGET {
+            assert_eq!(spend_retry_budget(&host), Duration::ZERO);
+        }
+        assert!(spend_retry_budget(&host) > Duration::ZERO);
+        assert_eq!(spend_retry_budget("other.example"), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_chunk_state_only_matches_same_download() {
+        let path = std::env::temp_dir().join(format!("ownstash-snde-state-{}{}", uuid::Uuid::new_v4(), STATE_SUFFIX));
+        let version = FileVersion::from_headers(&[
+            ("ETag".to_string(), "\"v1\"".to_string()),
+            ("Last-Modified".to_string(), "Tue, 15 Nov 1994 12:45:26 GMT".to_string()),
+        ]);
+        let state = ChunkState {
+            url: "https://example.com/a.bin".to_string(),
+            total_size: 100,
+            version: version.clone(),
+            request: None,
+            completed: vec![(0, 49)],
+        };
+        state.save(&path).await;
+
+        assert_eq!(ChunkState::load(&path, "https://example.com/a.bin", 100, &version), Some(state));
+        assert_eq!(ChunkState::load(&path, "https://example.com/a.bin", 200, &version), None);
+        assert_eq!(ChunkState::load(&path, "https://example.com/b.bin", 100, &version), None);
+        // Replaced on the server since
+        let replaced = FileVersion { etag: Some("\"v2\"".to_string()), ..version.clone() };
+        assert_eq!(ChunkState::load(&path, "https://example.com/a.bin", 100, &replaced), None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_if_range_prefers_a_strong_etag() {
+        let version = |etag: &str| FileVersion {
+            etag: Some(etag.to_string()),
+            last_modified: Some("Tue, 15 Nov 1994 12:45:26 GMT".to_string()),
+        };
+        assert_eq!(version("\"v1\"").if_range().unwrap(), "\"v1\"");
+        assert_eq!(version("W/\"v1\"").if_range().unwrap(), "Tue, 15 Nov 1994 12:45:26 GMT");
+        assert_eq!(FileVersion::default().if_range(), None);
+    }
+
+    #[tokio::test]
+    async fn test_partial_downloads_need_their_file() {
+        let dir = std::env::temp_dir().join(format!("ownstash-snde-partials-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["kept.iso", "orphan.iso"] {
+            let state = ChunkState {
+                url: format!("https://example.com/{}", name),
+                total_size: 100,
+                version: FileVersion::default(),
+                request: None,
+                completed: vec![(0, 9), (50, 59)],
+            };
+            state.save(&state_path(&dir.join(name))).await;
+        }
+        std::fs::write(crate::downloader::downloading_path(&dir.join("kept.iso")), [0u8; 100]).unwrap();
+
+        let partials = partial_downloads(&dir);
+        assert_eq!(partials.len(), 1);
+        assert_eq!(partials[0].url, "https://example.com/kept.iso");
+        assert_eq!(partials[0].file_path, dir.join("kept.iso"));
+        assert_eq!((partials[0].downloaded_bytes, partials[0].total_bytes), (20, 100));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // ---- Integration tests against the local mock server ----
+
+    use crate::test_support::{scratch_dir, test_body, MockBehavior, MockServer};
+
+    /// A request for `url` saved as `file.bin` in `dir`, split `connections` ways
+    async fn engine_request(url: String, dir: &Path, connections: u8) -> SNDERequest {
+        let mut request = probe_request(url).await;
+        request.id = format!("snde-test-{}", uuid::Uuid::new_v4());
+        request.output_path = dir.join("file.bin");
+        request.routing_decision.recommended_connections = connections;
+        request.routing_decision.prefer_http3 = false;
+        request
+    }
+
+    /// Up to `connections` connections on 1 MiB chunks
+    fn engine_limits(connections: u8, stall_timeout_secs: u64) -> SNDELimits {
+        SNDELimits { max_connections: connections, chunk_size_mb: 1, stall_timeout_secs }
+    }
+
+    /// Run `request` through the engine, keeping the last reported byte count in `downloaded`
+    async fn run_download(
+        request: SNDERequest,
+        limits: SNDELimits,
+        downloaded: Arc<AtomicU64>,
+        cancel_rx: mpsc::Receiver<()>,
+    ) -> SNDEResult {
+        let report: ProgressSink = Arc::new(move |progress: SNDEProgress| {
+            downloaded.store(progress.downloaded_bytes as u64, Ordering::Relaxed);
+        });
+        SNDEEngine::new().download_with(request, limits, report, cancel_rx).await
+    }
+
+    /// Download `url` into a scratch directory; the result and the finished file
+    async fn download_once(url: String, connections: u8) -> (SNDEResult, Vec<u8>) {
+        let dir = scratch_dir();
+        let request = engine_request(url, &dir, connections).await;
+        let (_cancel_tx, cancel_rx) = mpsc::channel(1);
+        let result = run_download(request, engine_limits(connections, 5), Arc::default(), cancel_rx).await;
+        let file = std::fs::read(dir.join("file.bin")).unwrap_or_default();
+        let _ = std::fs::remove_dir_all(&dir);
+        (result, file)
+    }
+
+    struct WorkerRun {
+        success: bool,
+        file: Vec<u8>,
+        chunks: usize,
+    }
+
+    /// Download `len` bytes as one chunk shared by `connections` workers, so all but the
+    /// first have to steal. `download` never starts more workers than chunks, so this
+    /// drives the workers directly.
+    async fn run_workers_on_one_chunk(url: String, len: u64, connections: u8) -> WorkerRun {
+        let engine = SNDEEngine::new();
+        let path = std::env::temp_dir().join(format!("ownstash-snde-test-{}", uuid::Uuid::new_v4()));
+        engine.preallocate_file(&path, len).await.unwrap();
+
+        let chunks = engine.create_chunks(len, 1, len, &[]);
+        let chunk_count = chunks.len();
+        let chunks = Arc::new(Mutex::new(chunks));
+        let control = Arc::new(ConnectionControl::new(connections));
+        let is_cancelled = Arc::new(AtomicBool::new(false));
+        let stats: Arc<Vec<ConnectionStats>> =
+            Arc::new((0..connections).map(|_| ConnectionStats::default()).collect());
+
+        let workers: Vec<_> = (0..connections)
+            .map(|conn_id| {
+                tokio::spawn(SNDEEngine::worker_loop(
+                    conn_id,
+                    engine.get_client(true),
+                    None,
+                    Some(engine.get_client(false)),
+                    control.clone(),
+                    Arc::new(MirrorPool::single(url.clone(), HeaderMap::new())),
+                    chunks.clone(),
+                    path.clone(),
+                    Arc::default(),
+                    is_cancelled.clone(),
+                    stats.clone(),
+                    Arc::default(),
+                    "snde-test".to_string(),
+                    Duration::from_secs(5),
+                ))
+            })
+            .collect();
+
+        let mut success = true;
+        for worker in workers {
+            success &= worker.await.unwrap_or(false);
+        }
+        let all_complete = chunks.lock().await.iter().all(|c| c.completed);
+        let contents = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        WorkerRun {
+            success: success && all_complete,
+            file: contents,
+            chunks: chunk_count,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parallel_chunks_reassemble_file() {
+        let body = test_body(3 * MIN_CHUNK_SIZE as usize + 12_345);
+        let server = MockServer::start(body.clone(), MockBehavior::default()).await;
+
+        // Four 1 MiB chunks, the last one short
+        let (result, file) = download_once(server.url("/file.bin"), 4).await;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.bytes_downloaded, body.len() as u64);
+        assert_eq!(server.get_requests(), 4);
+        assert!(file == body, "file content differs from the served body");
+    }
+
+    #[tokio::test]
+    async fn test_idle_worker_steals_tail_of_large_chunk() {
+        let body = test_body(4 * MIN_CHUNK_SIZE as usize);
+        let server = MockServer::start(body.clone(), MockBehavior {
+            throttle: Some(Duration::from_millis(2)),
+            ..Default::default()
+        })
+        .await;
+
+        let len = body.len() as u64;
+        let run = run_workers_on_one_chunk(server.url("/file.bin"), len, 2).await;
+        assert!(run.success);
+        assert_eq!(run.chunks, 1);
+        // The second worker fetched part of the single chunk
+        assert!(server.get_requests() >= 2);
+        assert!(run.file == body, "file content differs from the served body");
+    }
+
+    #[tokio::test]
+    async fn test_dropped_connection_chunk_is_retried() {
+        let body = test_body(2 * MIN_CHUNK_SIZE as usize);
+        let server = MockServer::start(body.clone(), MockBehavior {
+            drop_after_bytes: Some(100 * 1024),
+            drop_first: 2,
+            ..Default::default()
+        })
+        .await;
+
+        let (result, file) = download_once(server.url("/file.bin"), 1).await;
+        assert!(result.success, "{:?}", result.error);
+        // Bytes from the dropped attempts aren't counted twice
+        assert_eq!(result.bytes_downloaded, body.len() as u64);
+        // Two chunks, plus the two dropped requests
+        assert_eq!(server.get_requests(), 4);
+        assert!(file == body, "file content differs from the served body");
+    }
+
+    #[tokio::test]
+    async fn test_paused_download_resumes_from_saved_chunks() {
+        let body = test_body(4 * MIN_CHUNK_SIZE as usize);
+        let server = MockServer::start(body.clone(), MockBehavior {
+            throttle: Some(Duration::from_millis(10)),
+            ..Default::default()
+        })
+        .await;
+        let dir = scratch_dir();
+        let request = engine_request(server.url("/file.bin"), &dir, 2).await;
+        let limits = engine_limits(2, 5);
+
+        // With two connections on 1 MiB chunks, past 2 MiB at least one chunk is finished
+        let downloaded = Arc::new(AtomicU64::new(0));
+        let (cancel_tx, cancel_rx) = mpsc::channel(1);
+        let first = tokio::spawn(run_download(request.clone(), limits, downloaded.clone(), cancel_rx));
+        while downloaded.load(Ordering::Relaxed) <= 2 * MIN_CHUNK_SIZE {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        request_pause(&request.id);
+        cancel_tx.send(()).await.unwrap();
+        let paused = first.await.unwrap();
+        assert!(paused.paused);
+        assert!(!request.output_path.exists());
+        assert!(state_path(&request.output_path).exists());
+        let fetched_before_resume = server.get_requests();
+
+        let (_cancel_tx, cancel_rx) = mpsc::channel(1);
+        let resumed = run_download(request.clone(), limits, Arc::default(), cancel_rx).await;
+        assert!(resumed.success, "{:?}", resumed.error);
+        // Only the chunks that weren't finished were fetched again
+        assert!(server.get_requests() - fetched_before_resume < 4);
+        assert!(!state_path(&request.output_path).exists());
+        let file = std::fs::read(&request.output_path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(file == body, "file content differs from the served body");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_chunks_are_retried() {
+        let body = test_body(MIN_CHUNK_SIZE as usize);
+        let server = MockServer::start(body.clone(), MockBehavior {
+            rate_limit_first: 3,
+            ..Default::default()
+        })
+        .await;
+
+        let (result, file) = download_once(server.url("/file.bin"), 1).await;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(server.get_requests(), 4);
+        assert!(file == body);
+    }
+
+    #[tokio::test]
+    async fn test_probe_file_reads_size_and_range_support() {
+        let server = MockServer::start(test_body(5000), MockBehavior::default()).await;
+        let request = SNDERequest {
+            id: "snde-probe-test".to_string(),
+            url: server.url("/file.bin"),
+            output_path: PathBuf::from("download"),
+            routing_decision: crate::download_router::DownloadRouter::new().route(&server.url("/file.bin"), None).await,
+            checksum: None,
+            mirrors: Vec::new(),
+            extra_headers: HeaderMap::new(),
+            origin: None,
+        };
+
+        let (size, supports_range, _, _) = SNDEEngine::new().probe_file(&request, &HeaderMap::new()).await.unwrap();
+        assert_eq!(size, Some(5000));
+        assert!(supports_range);
+    }
+
+    async fn probe_request(url: String) -> SNDERequest {
+        SNDERequest {
+            id: "snde-probe-test".to_string(),
+            routing_decision: crate::download_router::DownloadRouter::new().route(&url, None).await,
+            url,
+            output_path: PathBuf::from("download"),
+            checksum: None,
+            mirrors: Vec::new(),
+            extra_headers: HeaderMap::new(),
+            origin: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_falls_back_to_ranged_get_when_head_is_rejected() {
+        let server = MockServer::start(test_body(5000), MockBehavior {
+            reject_head: true,
+            ..Default::default()
+        })
+        .await;
+
+        let request = probe_request(server.url("/file.bin")).await;
+        let (size, supports_range, _, _) = SNDEEngine::new().probe_file(&request, &HeaderMap::new()).await.unwrap();
+        assert_eq!(size, Some(5000));
+        assert!(supports_range);
+        assert_eq!(server.get_requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_size_is_streamed_over_one_connection() {
+        let body = test_body(MIN_CHUNK_SIZE as usize + 777);
+        let server = MockServer::start(body.clone(), MockBehavior {
+            supports_range: false,
+            reject_head: true,
+            omit_content_length: true,
+            ..Default::default()
+        })
+        .await;
+
+        let engine = SNDEEngine::new();
+        let request = probe_request(server.url("/file.bin")).await;
+        let (size, supports_range, _, _) = engine.probe_file(&request, &HeaderMap::new()).await.unwrap();
+        assert_eq!(size, None);
+        assert!(!supports_range);
+
+        let path = std::env::temp_dir().join(format!("ownstash-snde-stream-{}", uuid::Uuid::new_v4()));
+        engine.preallocate_file(&path, 0).await.unwrap();
+        let mut writer = ChunkWriter::open(&path).await.unwrap();
+        let total_downloaded = Arc::new(AtomicU64::new(0));
+        let result = SNDEEngine::stream_to_file(
+            &engine.get_client(true),
+            &request.url,
+            &HeaderMap::new(),
+            &mut writer,
+            total_downloaded.clone(),
+            Arc::default(),
+            bandwidth::download_limiter("snde-stream-test"),
+            Duration::from_secs(5),
+        )
+        .await;
+        drop(writer);
+        let contents = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(result.unwrap().status, 200);
+        assert_eq!(total_downloaded.load(Ordering::Relaxed), body.len() as u64);
+        assert!(contents == body, "file content differs from the served body");
+    }
+
+    #[tokio::test]
+    async fn test_safe_mode_sheds_workers_mid_download() {
+        let body = test_body(4 * MIN_CHUNK_SIZE as usize);
+        let server = MockServer::start(body.clone(), MockBehavior {
+            throttle: Some(Duration::from_millis(10)),
+            ..Default::default()
+        })
+        .await;
+        let dir = scratch_dir();
+        let request = engine_request(server.url("/file.bin"), &dir, 4).await;
+
+        let downloaded = Arc::new(AtomicU64::new(0));
+        let (_cancel_tx, cancel_rx) = mpsc::channel(1);
+        let download = tokio::spawn(run_download(request.clone(), engine_limits(4, 30), downloaded.clone(), cancel_rx));
+        while downloaded.load(Ordering::Relaxed) < MIN_CHUNK_SIZE {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(enable_safe_mode(&request.id));
+        let result = download.await.unwrap();
+        let file = std::fs::read(&request.output_path).unwrap_or_default();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(result.success, "{:?}", result.error);
+        assert!(file == body, "file content differs from the served body");
+        // Put-back ranges were fetched once, not restarted
+        assert_eq!(result.bytes_downloaded, body.len() as u64);
+        assert!(!collapse_connections(&request.id, 1));
+    }
+
+    #[tokio::test]
+    async fn test_speed_test_measures_each_connection_count() {
+        let body = test_body(256 * 1024);
+        let server = MockServer::start(body, MockBehavior::default()).await;
+        let client = Client::new();
+
+        let run = measure_connections(&client, &server.url("/file.bin"), 100_000, 8).await;
+        assert_eq!(run.error, None);
+        assert_eq!(run.bytes, 100_000);
+        assert!(run.speed_bps > 0);
+
+        let no_ranges = MockServer::start(test_body(1024), MockBehavior { supports_range: false, ..Default::default() }).await;
+        assert!(measure_connections(&client, &no_ranges.url("/file.bin"), 1024, 4).await.error.is_some());
+        assert_eq!(measure_connections(&client, &no_ranges.url("/file.bin"), 1024, 1).await.bytes, 1024);
+    }
+
+    #[test]
+    fn test_speed_test_recommends_fewest_connections_near_best() {
+        let run = |connections: u8, speed_bps: u64| SpeedTestRun {
+            connections,
+            bytes: 0,
+            duration_ms: 0,
+            speed_bps,
+            speed: String::new(),
+            error: None,
+        };
+        assert_eq!(recommend_connections(&[run(1, 100), run(4, 380), run(8, 400)]), Some(4));
+        assert_eq!(recommend_connections(&[run(1, 100), run(4, 105), run(8, 90)]), Some(1));
+        assert_eq!(recommend_connections(&[]), None);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_download_triggers_watchdog_collapse() {
+        use crate::watchdog::Watchdog;
+
+        let body = test_body(MIN_CHUNK_SIZE as usize);
+        let server = MockServer::start(body.clone(), MockBehavior {
+            hang_after_bytes: Some(64 * 1024),
+            ..Default::default()
+        })
+        .await;
+
+        let dir = scratch_dir();
+        let request = engine_request(server.url("/file.bin"), &dir, 2).await;
+        let id = request.id.clone();
+        HEALTH_REGISTRY.register_download(&id, DownloadEngine::SNDE, Some(body.len() as u64));
+        let watchdog = Watchdog::new();
+        watchdog.start_monitoring(&id);
+
+        let (_cancel_tx, cancel_rx) = mpsc::channel(1);
+        let download = tokio::spawn(run_download(request, engine_limits(2, 30), Arc::default(), cancel_rx));
+
+        // The engine's progress task feeds the registry until the watchdog steps in
+        let mut action = None;
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            if let Some(a) = watchdog.check_health(&id) {
+                action = Some(a);
+                break;
+            }
+        }
+        download.abort();
+        HEALTH_REGISTRY.unregister_download(&id);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // Half of the two connections the download started with
+        assert_eq!(action, Some(WatchdogAction::CollapseConnections(1)));
+    }
 }
@@ -0,0 +1,563 @@
+//! Split Archives
+//!
+//! Multi-part archives (`name.part1.rar`, `name.7z.001`, ...) are useless one piece at a
+//! time. When a link to one part is pasted, the sibling parts are found by probing the
+//! numbered URLs, offered as one group, downloaded together and optionally extracted.
+//!
+//! Key Features:
+//! - Detects `.partN.rar` volumes and byte-split `.7z/.zip/.rar.NNN` files, keeping the padding
+//! - Probes numbered siblings (with the download's headers) until the first one the server
+//!   doesn't have, or that doesn't look like the same archive (type, part size)
+//! - Parts download one after another into the same folder, recorded in the history
+//! - Optional extraction once every part is on disk: RAR volumes through unrar, byte-split
+//!   files joined first in a scratch file outside the download folder
+//! - Entries that would land outside the extraction folder are refused
+
+use crate::commands::AppState;
+use crate::download_router::DownloadRouter;
+use crate::download_router::ProbeResult;
+use crate::downloader::DownloadRequest;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+/// Parts probed at once while looking for siblings
+const PROBE_BATCH: usize = 8;
+
+/// Upper bound on parts, so a server answering every URL can't keep us probing
+const MAX_PARTS: u32 = 999;
+
+/// How long a finished part may take to report its final path
+const FINAL_PATH_WAIT: Duration = Duration::from_secs(30);
+
+/// How the parts of an archive are named
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum SplitScheme {
+    /// `name.part1.rar`, `name.part2.rar`, ... (RAR volumes)
+    RarVolumes { width: usize },
+    /// `name.7z.001`, `name.7z.002`, ... (one file cut into pieces)
+    Numbered { extension: String, width: usize },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitPart {
+    pub number: u32,
+    pub url: String,
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitArchive {
+    /// Archive name without the part suffix, e.g. "backup" for `backup.part1.rar`
+    pub base_name: String,
+    pub scheme: SplitScheme,
+    pub parts: Vec<SplitPart>,
+    /// Sum of the part sizes, when the server reported all of them
+    pub total_size: Option<u64>,
+    /// Headers the parts were probed with, sent again when they're downloaded
+    #[serde(default)]
+    pub request_headers: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SplitArchiveDownload {
+    pub group_id: String,
+    /// Download ids of the parts, in order
+    pub queued: Vec<String>,
+}
+
+/// The pattern a URL matched: encoded name before the number, the number, and the scheme
+struct Match {
+    prefix: String,
+    number: u32,
+    suffix: String,
+    scheme: SplitScheme,
+}
+
+fn rar_volume_regex() -> regex::Regex {
+    regex::Regex::new(r"(?i)^(.+\.part)(\d+)(\.rar)$").unwrap()
+}
+
+fn numbered_regex() -> regex::Regex {
+    regex::Regex::new(r"(?i)^(.+\.(7z|zip|rar)\.)(\d{3,})$").unwrap()
+}
+
+/// Last path segment of `url`, still percent-encoded
+fn last_segment(url: &url::Url) -> Option<&str> {
+    url.path().rsplit('/').next().filter(|s| !s.is_empty())
+}
+
+fn match_name(name: &str) -> Option<Match> {
+    if let Some(caps) = rar_volume_regex().captures(name) {
+        return Some(Match {
+            prefix: caps[1].to_string(),
+            number: caps[2].parse().ok()?,
+            suffix: caps[3].to_string(),
+            scheme: SplitScheme::RarVolumes { width: caps[2].len() },
+        });
+    }
+    let caps = numbered_regex().captures(name)?;
+    Some(Match {
+        prefix: caps[1].to_string(),
+        number: caps[3].parse().ok()?,
+        suffix: String::new(),
+        scheme: SplitScheme::Numbered { extension: caps[2].to_lowercase(), width: caps[3].len() },
+    })
+}
+
+impl Match {
+    fn width(&self) -> usize {
+        match &self.scheme {
+            SplitScheme::RarVolumes { width } | SplitScheme::Numbered { width, .. } => *width,
+        }
+    }
+
+    /// `url` with its file name switched to part `number`; the query string is kept
+    fn part_url(&self, url: &url::Url, number: u32) -> String {
+        let name = format!("{}{:0width$}{}", self.prefix, number, self.suffix, width = self.width());
+        let mut part = url.clone();
+        let path = url.path();
+        let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+        part.set_path(&format!("{}{}", dir, name));
+        part.to_string()
+    }
+
+    fn base_name(&self) -> String {
+        let encoded = match &self.scheme {
+            // "backup.part" -> "backup"
+            SplitScheme::RarVolumes { .. } => &self.prefix[..self.prefix.len() - ".part".len()],
+            // "backup.7z." -> "backup"
+            SplitScheme::Numbered { extension, .. } => &self.prefix[..self.prefix.len() - extension.len() - 2],
+        };
+        urlencoding::decode(encoded).map(|s| s.into_owned()).unwrap_or_else(|_| encoded.to_string())
+    }
+}
+
+/// Content type without parameters, for comparing probes
+fn mime(probe: &ProbeResult) -> Option<String> {
+    let content_type = probe.content_type.as_deref()?;
+    Some(content_type.split(';').next()?.trim().to_ascii_lowercase())
+}
+
+/// Whether a probe of the next number is another part of the same archive: the server has
+/// it with the pasted part's content type, and the parts before it were all full size.
+/// Hosts that answer every URL with a page would otherwise yield hundreds of bogus parts.
+fn is_next_part(parts: &[SplitPart], reference_mime: &Option<String>, probe: &ProbeResult) -> bool {
+    if !probe.success || mime(probe) != *reference_mime {
+        return false;
+    }
+    let Some(first) = parts.first() else {
+        return true;
+    };
+    // Only the last part may be shorter than the first, and none longer
+    let previous_full = parts.last().map(|p| p.size) == Some(first.size);
+    match (first.size, probe.content_length) {
+        (Some(first_size), Some(size)) => previous_full && size <= first_size,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Find the sibling parts of a split archive link, probing with `request_headers`; `None`
+/// when the URL isn't one
+pub async fn detect(url: &str, request_headers: HashMap<String, String>) -> Result<Option<SplitArchive>, String> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    let Some(found) = last_segment(&parsed).and_then(match_name) else {
+        return Ok(None);
+    };
+
+    let headers = crate::request_headers::to_header_map(&request_headers)?;
+    let router = DownloadRouter::with_timeout(Duration::from_secs(10));
+    let pasted = router.probe_url(url, &headers).await;
+    if !pasted.success {
+        return Err(format!("Part {} of {} isn't available", found.number, found.base_name()));
+    }
+    let reference_mime = mime(&pasted);
+
+    let mut parts: Vec<SplitPart> = Vec::new();
+    let mut next = 1;
+    'probe: while next <= MAX_PARTS {
+        let batch: Vec<u32> = (next..(next + PROBE_BATCH as u32).min(MAX_PARTS + 1)).collect();
+        let probes = batch.iter().map(|&number| {
+            let part_url = found.part_url(&parsed, number);
+            let router = &router;
+            let headers = &headers;
+            async move {
+                let probe = router.probe_url(&part_url, headers).await;
+                (number, part_url, probe)
+            }
+        });
+        for (number, part_url, probe) in futures_util::future::join_all(probes).await {
+            if !is_next_part(&parts, &reference_mime, &probe) {
+                if number <= found.number {
+                    return Err(format!("Part {} of {} isn't available", number, found.base_name()));
+                }
+                break 'probe;
+            }
+            parts.push(SplitPart { number, url: part_url, size: probe.content_length });
+        }
+        next += batch.len() as u32;
+    }
+
+    let total_size = parts.iter().map(|p| p.size).sum();
+    println!("[SplitArchive] Found {} parts of {}", parts.len(), found.base_name());
+    Ok(Some(SplitArchive {
+        base_name: found.base_name(),
+        scheme: found.scheme,
+        parts,
+        total_size,
+        request_headers,
+    }))
+}
+
+/// Where each part's download ended up, from the follow-up "completed" events
+fn watch_final_paths(app_handle: &AppHandle) -> (tauri::EventId, Arc<Mutex<HashMap<String, PathBuf>>>) {
+    let paths = Arc::new(Mutex::new(HashMap::new()));
+    let seen = Arc::clone(&paths);
+    let listener = app_handle.listen("download-progress", move |event| {
+        let Ok(progress) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+            return;
+        };
+        if let (Some(id), Some(path)) = (progress["id"].as_str(), progress["file_path"].as_str()) {
+            seen.lock().unwrap().insert(id.to_string(), PathBuf::from(path));
+        }
+    });
+    (listener, paths)
+}
+
+fn unrar_extract(first_volume: &Path, dest: &Path) -> Result<(), String> {
+    let mut archive = unrar::Archive::new(first_volume)
+        .open_for_processing()
+        .map_err(|e| format!("Failed to open RAR archive: {:?}", e))?;
+    while let Some(header) = archive.read_header().map_err(|e| format!("Failed to read RAR archive: {:?}", e))? {
+        archive = header
+            .extract_with_base(dest)
+            .map_err(|e| format!("Failed to extract from RAR archive: {:?}", e))?;
+    }
+    Ok(())
+}
+
+/// `name` as a path inside the extraction folder; None for absolute paths or any `..`
+fn contained_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    // Archives made on Windows separate with backslashes
+    for component in Path::new(&name.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+fn extract_7z(archive: &Path, dest: &Path) -> Result<(), String> {
+    sevenz_rust::decompress_file_with_extract_fn(archive, dest, |entry, reader, _| {
+        let Some(relative) = contained_path(entry.name()) else {
+            return Err(sevenz_rust::Error::other(format!("Refusing to extract {:?} outside the folder", entry.name())));
+        };
+        sevenz_rust::default_entry_extract_fn(entry, reader, &dest.join(relative))
+    })
+    .map_err(|e| format!("Failed to extract 7z archive: {}", e))
+}
+
+/// Scratch folder byte-split parts are joined in, outside the download folder
+fn join_dir() -> PathBuf {
+    crate::staging::dir(crate::staging::SPLIT_ARCHIVES).unwrap_or_else(|| std::env::temp_dir().join("ownstash-split-archives"))
+}
+
+/// Extract the archive from its downloaded `parts` (in order) into `dest`
+fn extract(scheme: &SplitScheme, parts: &[PathBuf], dest: &Path) -> Result<(), String> {
+    let first = parts.first().ok_or_else(|| "No parts to extract".to_string())?;
+    std::fs::create_dir_all(dest).map_err(|e| format!("Failed to create {:?}: {}", dest, e))?;
+
+    let extension = match scheme {
+        // unrar follows the volumes on its own as long as they sit together
+        SplitScheme::RarVolumes { .. } => return unrar_extract(first, dest),
+        SplitScheme::Numbered { extension, .. } => extension,
+    };
+
+    // Byte-split files are one archive once the pieces are put back together. The scratch
+    // file has a name of its own so nothing of the user's is overwritten or removed.
+    let scratch = join_dir();
+    std::fs::create_dir_all(&scratch).map_err(|e| format!("Failed to create {:?}: {}", scratch, e))?;
+    let joined = scratch.join(format!("{}.{}", uuid::Uuid::new_v4(), extension));
+    {
+        let mut out = File::create(&joined).map_err(|e| format!("Failed to create {:?}: {}", joined, e))?;
+        for part in parts {
+            let mut input = File::open(part).map_err(|e| format!("Failed to open {:?}: {}", part, e))?;
+            std::io::copy(&mut input, &mut out).map_err(|e| format!("Failed to join {:?}: {}", part, e))?;
+        }
+    }
+    let result = match extension.as_str() {
+        "7z" => extract_7z(&joined, dest),
+        "zip" => File::open(&joined)
+            .map_err(|e| e.to_string())
+            .and_then(|file| zip::ZipArchive::new(file).map_err(|e| e.to_string()))
+            .and_then(|mut archive| archive.extract(dest).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to extract zip archive: {}", e)),
+        _ => unrar_extract(&joined, dest),
+    };
+    let _ = std::fs::remove_file(&joined);
+    result
+}
+
+fn emit_status(app_handle: &AppHandle, group_id: &str, status: &str, message: Option<String>) {
+    let _ = app_handle.emit("split-archive-status", serde_json::json!({
+        "group_id": group_id,
+        "status": status,
+        "message": message,
+    }));
+}
+
+/// Download every part, then extract if asked; runs in the background
+async fn download_parts(
+    app: AppHandle,
+    group_id: String,
+    archive: SplitArchive,
+    requests: Vec<DownloadRequest>,
+    output_dir: PathBuf,
+    auto_extract: bool,
+) {
+    let (paths_listener, final_paths) = watch_final_paths(&app);
+    let mut failed = None;
+    for request in &requests {
+        let status = match crate::downloader::run_download(&app, request.clone(), None).await {
+            Ok(status) => status,
+            Err(e) => {
+                println!("[SplitArchive] Failed to download {}: {}", request.url, e);
+                "failed".to_string()
+            }
+        };
+        if status != "completed" {
+            failed = Some(format!("Part {} {}", request.url, status));
+            break;
+        }
+    }
+
+    // The path arrives just after "completed", once the file has its final name
+    let mut part_paths = Vec::new();
+    if failed.is_none() {
+        let deadline = tokio::time::Instant::now() + FINAL_PATH_WAIT;
+        for request in &requests {
+            let path = loop {
+                if let Some(path) = final_paths.lock().unwrap().get(&request.id).cloned() {
+                    break Some(path);
+                }
+                if tokio::time::Instant::now() >= deadline {
+                    break None;
+                }
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            };
+            match path {
+                Some(path) => part_paths.push(path),
+                None => {
+                    failed = Some(format!("Couldn't find the downloaded file for {}", request.url));
+                    break;
+                }
+            }
+        }
+    }
+    app.unlisten(paths_listener);
+
+    if let Some(error) = failed {
+        println!("[SplitArchive] {}: {}", archive.base_name, error);
+        emit_status(&app, &group_id, "failed", Some(error));
+        return;
+    }
+    if !auto_extract {
+        emit_status(&app, &group_id, "completed", None);
+        return;
+    }
+
+    emit_status(&app, &group_id, "extracting", None);
+    let dest = output_dir.join(&archive.base_name);
+    let scheme = archive.scheme.clone();
+    let extract_dest = dest.clone();
+    let result = tokio::task::spawn_blocking(move || extract(&scheme, &part_paths, &extract_dest))
+        .await
+        .unwrap_or_else(|e| Err(format!("Extraction stopped: {}", e)));
+    match result {
+        Ok(()) => {
+            println!("[SplitArchive] Extracted {} to {:?}", archive.base_name, dest);
+            emit_status(&app, &group_id, "extracted", Some(dest.to_string_lossy().to_string()));
+        }
+        Err(e) => {
+            println!("[SplitArchive] {}", e);
+            emit_status(&app, &group_id, "failed", Some(e));
+        }
+    }
+}
+
+/// Whether `url` is one part of a split archive, and if so every part the server has
+#[tauri::command]
+pub async fn detect_split_archive(
+    url: String,
+    request_headers: Option<HashMap<String, String>>,
+) -> Result<Option<SplitArchive>, String> {
+    // Headers the extension handed over with the link, left for the download itself
+    let request_headers = request_headers.unwrap_or_else(|| crate::request_headers::peek_pending(&url));
+    detect(&url, request_headers).await
+}
+
+/// Download all parts of a detected split archive as one group, extracting them afterwards
+/// when `auto_extract` is set. Progress of the group arrives as `split-archive-status` events.
+#[tauri::command]
+pub async fn download_split_archive(
+    app_handle: AppHandle,
+    archive: SplitArchive,
+    output_path: Option<String>,
+    auto_extract: bool,
+) -> Result<SplitArchiveDownload, String> {
+    if archive.parts.is_empty() {
+        return Err("The archive has no parts".to_string());
+    }
+    let output_path = match output_path.filter(|p| !p.trim().is_empty()) {
+        Some(path) => path,
+        None => crate::downloader::get_default_download_path(app_handle.clone()).await?,
+    };
+
+    let mut requests = Vec::new();
+    for part in &archive.parts {
+        let mut request = DownloadRequest::with_defaults(&part.url, &output_path)?;
+        // The parts have to stay side by side to be extracted
+        request.organize = Some(false);
+        request.request_headers = archive.request_headers.clone();
+        requests.push(request);
+    }
+
+    // Record everything up front so the history shows the whole group
+    {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        for (part, request) in archive.parts.iter().zip(&requests) {
+            db.add_download(&crate::database::Download {
+                id: request.id.clone(),
+                title: format!("{} (part {} of {})", archive.base_name, part.number, archive.parts.len()),
+                url: request.url.clone(),
+                format: String::new(),
+                path: request.output_path.clone(),
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                status: "queued".to_string(),
+                size_bytes: part.size.map(|s| s as i64),
+                platform: None,
+                thumbnail: None,
+                on_complete: None,
+            })
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let result = SplitArchiveDownload {
+        group_id: uuid::Uuid::new_v4().to_string(),
+        queued: requests.iter().map(|r| r.id.clone()).collect(),
+    };
+    emit_status(&app_handle, &result.group_id, "downloading", None);
+    tauri::async_runtime::spawn(download_parts(
+        app_handle.clone(),
+        result.group_id.clone(),
+        archive,
+        requests,
+        PathBuf::from(output_path),
+        auto_extract,
+    ));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn matched(url: &str) -> Option<(Match, url::Url)> {
+        let parsed = url::Url::parse(url).unwrap();
+        let found = match_name(last_segment(&parsed)?)?;
+        Some((found, parsed))
+    }
+
+    #[test]
+    fn test_part_urls_keep_padding_and_query() {
+        let (found, url) = matched("https://files.example.com/dl/My%20Backup.part01.rar?token=abc").unwrap();
+        assert_eq!(found.number, 1);
+        assert_eq!(found.base_name(), "My Backup");
+        assert_eq!(found.part_url(&url, 12), "https://files.example.com/dl/My%20Backup.part12.rar?token=abc");
+
+        let (found, url) = matched("https://example.com/video.7z.003").unwrap();
+        assert_eq!(found.number, 3);
+        assert_eq!(found.scheme, SplitScheme::Numbered { extension: "7z".to_string(), width: 3 });
+        assert_eq!(found.base_name(), "video");
+        assert_eq!(found.part_url(&url, 1), "https://example.com/video.7z.001");
+
+        assert!(matched("https://example.com/archive.rar").is_none());
+        assert!(matched("https://example.com/notes.txt.001").is_none());
+    }
+
+    #[test]
+    fn test_byte_split_zip_is_joined_and_extracted() {
+        let dir = std::env::temp_dir().join(format!("ownstash-split-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut zipped = Vec::new();
+        {
+            let mut writer = zip::ZipWriter::new(std::io::Cursor::new(&mut zipped));
+            writer.start_file("hello.txt", zip::write::FileOptions::default()).unwrap();
+            writer.write_all(&[b'x'; 5000]).unwrap();
+            writer.finish().unwrap();
+        }
+        let middle = zipped.len() / 2;
+        let parts = vec![dir.join("bundle.zip.001"), dir.join("bundle.zip.002")];
+        std::fs::write(&parts[0], &zipped[..middle]).unwrap();
+        std::fs::write(&parts[1], &zipped[middle..]).unwrap();
+
+        let scheme = SplitScheme::Numbered { extension: "zip".to_string(), width: 3 };
+        let dest = dir.join("bundle");
+        extract(&scheme, &parts, &dest).unwrap();
+        assert_eq!(std::fs::read(dest.join("hello.txt")).unwrap(), vec![b'x'; 5000]);
+        // The joined copy is cleaned up
+        assert!(!dir.join("bundle.zip").exists());
+
+        // A file named like the joined archive is left alone
+        std::fs::write(dir.join("bundle.zip"), b"mine").unwrap();
+        extract(&scheme, &parts, &dir.join("again")).unwrap();
+        assert_eq!(std::fs::read(dir.join("bundle.zip")).unwrap(), b"mine");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_contained_path() {
+        assert_eq!(contained_path("docs/./a.txt"), Some(PathBuf::from("docs").join("a.txt")));
+        assert_eq!(contained_path("docs\\a.txt"), Some(PathBuf::from("docs").join("a.txt")));
+        assert!(contained_path("../evil.txt").is_none());
+        assert!(contained_path("docs/../../evil.txt").is_none());
+        assert!(contained_path("..\\evil.txt").is_none());
+        assert!(contained_path("/etc/passwd").is_none());
+        assert!(contained_path("").is_none());
+    }
+
+    #[test]
+    fn test_next_part_must_match_the_archive() {
+        let probe = |size: Option<u64>, content_type: &str| ProbeResult {
+            success: true,
+            content_length: size,
+            content_type: Some(content_type.to_string()),
+            ..ProbeResult::default()
+        };
+        let part = |number: u32, size: u64| SplitPart { number, url: String::new(), size: Some(size) };
+        let zip = Some("application/zip".to_string());
+
+        assert!(is_next_part(&[], &zip, &probe(Some(100), "application/zip")));
+        assert!(is_next_part(&[part(1, 100)], &zip, &probe(Some(40), "application/zip; x=1")));
+        // A soft 404 page
+        assert!(!is_next_part(&[part(1, 100)], &zip, &probe(Some(100), "text/html")));
+        // Nothing follows a short last part, and no part is bigger than the first
+        assert!(!is_next_part(&[part(1, 100), part(2, 40)], &zip, &probe(Some(40), "application/zip")));
+        assert!(!is_next_part(&[part(1, 100)], &zip, &probe(Some(101), "application/zip")));
+        assert!(!is_next_part(&[part(1, 100)], &zip, &ProbeResult::default()));
+    }
+}
@@ -2,7 +2,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter, Manager};
+use crate::commands::AppState;
+use crate::database::SpotifyJob;
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::process::Command;
 
 // Track active Spotify download processes for cancellation
@@ -65,6 +67,9 @@ struct GithubRelease {
 struct GithubAsset {
     name: String,
     browser_download_url: String,
+    /// "sha256:<hex>", published by GitHub for release assets
+    #[serde(default)]
+    digest: Option<String>,
 }
 
 pub struct SpotifyDownloader {
@@ -73,19 +78,20 @@ pub struct SpotifyDownloader {
 }
 
 impl SpotifyDownloader {
-    /// Creates a new Command that won't show a console window on Windows
+    /// Creates a new Command that won't show a console window on Windows, once the
+    /// program has passed the execution guard
     #[cfg(windows)]
-    fn create_hidden_command(program: &str) -> Command {
+    fn create_hidden_command(program: &str) -> Result<Command, String> {
         use std::os::windows::process::CommandExt;
-        let mut cmd = Command::new(program);
+        let mut cmd = crate::exec_guard::tokio_command(program)?;
         // CREATE_NO_WINDOW = 0x08000000
         cmd.creation_flags(0x08000000);
-        cmd
+        Ok(cmd)
     }
     
     #[cfg(not(windows))]
-    fn create_hidden_command(program: &str) -> Command {
-        Command::new(program)
+    fn create_hidden_command(program: &str) -> Result<Command, String> {
+        crate::exec_guard::tokio_command(program)
     }
     
     pub fn new(app_handle: &AppHandle) -> Self {
@@ -107,7 +113,7 @@ impl SpotifyDownloader {
         Ok(Self::binaries_dir(app_handle)?.join(binary_name))
     }
 
-    async fn download_binary(url: &str, target_path: &Path) -> Result<(), String> {
+    async fn download_binary(url: &str, target_path: &Path, sha256: &str) -> Result<(), String> {
         let client = reqwest::Client::builder()
             .user_agent("OwnstashDownloader/1.0")
             .timeout(std::time::Duration::from_secs(180))
@@ -132,6 +138,14 @@ impl SpotifyDownloader {
             .await
             .map_err(|e| format!("Failed to read SpotDL binary: {}", e))?;
 
+        let actual = {
+            use sha2::{Digest, Sha256};
+            format!("{:x}", Sha256::digest(&bytes))
+        };
+        if !actual.eq_ignore_ascii_case(sha256) {
+            return Err(format!("SpotDL checksum mismatch: expected {}, got {}", sha256, actual));
+        }
+
         let parent = target_path
             .parent()
             .ok_or_else(|| "Invalid SpotDL destination path".to_string())?;
@@ -162,7 +176,7 @@ impl SpotifyDownloader {
             .await
             .map_err(|e| format!("Failed to finalize SpotDL update: {}", e))?;
 
-        Ok(())
+        crate::exec_guard::record_managed_binary(target_path)
     }
 
     fn pick_spotdl_asset(release: &GithubRelease) -> Option<GithubAsset> {
@@ -340,7 +354,12 @@ impl SpotifyDownloader {
         );
         println!("[SpotifyDownloader] Target path: {:?}", target_path);
 
-        Self::download_binary(&asset.browser_download_url, &target_path).await?;
+        let sha256 = asset
+            .digest
+            .as_deref()
+            .and_then(|digest| digest.strip_prefix("sha256:"))
+            .ok_or_else(|| format!("SpotDL release asset {} has no published checksum", asset.name))?;
+        Self::download_binary(&asset.browser_download_url, &target_path, sha256).await?;
 
         let downloader = SpotifyDownloader::new(app_handle);
         downloader.check_spotdl(true).await
@@ -391,6 +410,10 @@ impl SpotifyDownloader {
     }
 
     fn find_ffmpeg(app_handle: &AppHandle) -> Option<String> {
+        if let Some(managed_path) = crate::ffmpeg::find_managed_ffmpeg(app_handle) {
+            return Some(managed_path);
+        }
+
         // Try multiple possible locations for bundled ffmpeg
         if let Ok(resource_dir) = app_handle.path().resource_dir() {
             let possible_paths = if cfg!(windows) {
@@ -415,15 +438,6 @@ impl SpotifyDownloader {
             }
         }
 
-        if let Ok(app_data_dir) = app_handle.path().app_data_dir() {
-            let binary_name = if cfg!(windows) { "ffmpeg.exe" } else { "ffmpeg" };
-            let data_path = app_data_dir.join("binaries").join(binary_name);
-            
-            if data_path.exists() {
-                return Some(data_path.to_string_lossy().to_string());
-            }
-        }
-
         None
     }
 
@@ -433,7 +447,7 @@ impl SpotifyDownloader {
             .replace("\\\\?\\", "")
             .replace("\\", "/");
         
-        let output = Self::create_hidden_command(&spotdl_path_clean)
+        let output = Self::create_hidden_command(&spotdl_path_clean)?
             .arg("--version")
             .output()
             .await
@@ -450,7 +464,7 @@ impl SpotifyDownloader {
         } else {
             normalized_version
         };
-        let latest_version = if include_latest {
+        let latest_version = if include_latest && crate::outbound::allowed(crate::outbound::OutboundCall::SpotDlVersionCheck) {
             match Self::fetch_latest_spotdl_version().await {
                 Ok(version) => Some(version),
                 Err(err) => {
@@ -495,13 +509,14 @@ impl SpotifyDownloader {
         let current_path = std::env::var("PATH").unwrap_or_default();
         let new_path = format!("{};{}", binaries_dir.replace("/", "\\"), current_path);
         
-        let output = Self::create_hidden_command(&spotdl_path_clean)
+        let output = Self::create_hidden_command(&spotdl_path_clean)?
             .args([
                 "save",
                 url,
                 "--save-file",
                 temp_file.to_str().unwrap(),
             ])
+            .args(crate::proxy::proxy_args())
             .current_dir(&binaries_dir)
             .env("PATH", &new_path)
             .output()
@@ -597,7 +612,7 @@ impl SpotifyDownloader {
         request: SpotifyDownloadRequest,
         app_handle: AppHandle,
     ) -> Result<(), String> {
-        let (cancel_tx, mut cancel_rx) = tokio::sync::oneshot::channel::<()>();
+        let (cancel_tx, cancel_rx) = tokio::sync::oneshot::channel::<()>();
         
         // Store the cancellation sender
         {
@@ -610,14 +625,8 @@ impl SpotifyDownloader {
             .map_err(|e| format!("Failed to create output directory: {}", e))?;
 
         // Get the binaries directory (where spotdl, yt-dlp, ffmpeg are located)
-        let spotdl_path_clean = self.spotdl_path
-            .replace("\\\\?\\", "")  // Remove Windows extended path prefix
-            .replace("\\", "/");     // Normalize to forward slashes
-        
-        let binaries_dir = std::path::Path::new(&spotdl_path_clean)
-            .parent()
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| ".".to_string());
+        // and a PATH that includes it
+        let (spotdl_path_clean, binaries_dir, new_path) = self.command_paths();
         
         println!("[SpotDL] Binaries directory: {}", binaries_dir);
 
@@ -635,19 +644,16 @@ impl SpotifyDownloader {
         // Step 1: Use SpotDL to get track metadata including YouTube URL
         let temp_file = std::env::temp_dir().join(format!("spotdl_download_{}.spotdl", request.id));
         
-        // Clean paths and set PATH for SpotDL
-        let current_path = std::env::var("PATH").unwrap_or_default();
-        let new_path = format!("{};{}", binaries_dir.replace("/", "\\"), current_path);
-        
         println!("[SpotDL] Getting track info for: {}", request.url);
         
-        let save_output = Self::create_hidden_command(&spotdl_path_clean)
+        let save_output = Self::create_hidden_command(&spotdl_path_clean)?
             .args([
                 "save",
                 &request.url,
                 "--save-file",
                 temp_file.to_str().unwrap(),
             ])
+            .args(crate::proxy::proxy_args())
             .current_dir(&binaries_dir)
             .env("PATH", &new_path)
             .output()
@@ -685,208 +691,412 @@ impl SpotifyDownloader {
             speed: "Starting download...".to_string(),
         });
 
+        // Persist the job so it can resume after a restart
+        let job_tracks: Vec<JobTrack> = tracks
+            .into_iter()
+            .enumerate()
+            .map(|(index, track)| JobTrack {
+                index: index as i64,
+                display_name: track_display_name(&track),
+                track,
+                status: "pending".to_string(),
+            })
+            .collect();
+        save_job(&app_handle, &request, &job_tracks);
+
         // Step 2: Download each track using yt-dlp
-        let yt_dlp_path = binaries_dir.clone() + "/yt-dlp.exe";
-        let ffmpeg_path = binaries_dir.clone() + "/ffmpeg.exe";
-        let spotdl_for_url = spotdl_path_clean.clone();
-        let binaries_for_spawn = binaries_dir.clone();
-        let path_for_spawn = new_path.clone();
-        
-        println!("[SpotDL] Using yt-dlp at: {}", yt_dlp_path);
-
-        let id = request.id.clone();
-        let app = app_handle.clone();
-        let output_path = request.output_path.clone();
-        let audio_format = request.audio_format.clone();
-        let concurrent_fragments = request.threads.unwrap_or(4).clamp(2, 8).to_string();
-
-        tokio::spawn(async move {
-            let mut completed = 0;
-            let mut last_error: Option<String> = None;
-
-            for (index, track) in tracks.iter().enumerate() {
-                // Check for cancellation
-                if cancel_rx.try_recv().is_ok() {
-                    let _ = app.emit("spotify-download-progress", SpotifyDownloadProgress {
-                        id: id.clone(),
-                        progress: (completed as f64 / total_tracks as f64) * 100.0,
-                        status: "cancelled".to_string(),
-                        current_track: None,
-                        total_tracks: Some(total_tracks as i32),
-                        completed_tracks: Some(completed),
-                        speed: String::new(),
-                    });
-                    return;
-                }
+        let runner = TrackRunner::new(&spotdl_path_clean, &binaries_dir, &new_path, &request);
+        println!("[SpotDL] Using yt-dlp at: {}", runner.yt_dlp_path);
+        tokio::spawn(runner.run(app_handle.clone(), job_tracks, cancel_rx));
 
-                let track_name = track["name"].as_str().unwrap_or("Unknown").to_string();
-                let artist = track["artist"].as_str()
-                    .or(track["artists"].as_array().and_then(|a| a.first()).and_then(|a| a.as_str()))
-                    .unwrap_or("Unknown");
-                
-                // Get the Spotify URL for this track
-                let spotify_url = track["url"].as_str()
-                    .or(track["song_id"].as_str().map(|id| {
-                        // Construct URL from song ID if needed
-                        format!("https://open.spotify.com/track/{}", id).leak() as &str
-                    }))
-                    .unwrap_or("");
-                
-                let display_name = format!("{} - {}", artist, track_name);
-                println!("[SpotDL] Processing track {}/{}: {}", index + 1, total_tracks, display_name);
+        Ok(())
+    }
+
+    /// Continue a persisted job, skipping tracks that already finished
+    pub async fn resume_download(&self, job_id: &str, app_handle: AppHandle) -> Result<(), String> {
+        let job = {
+            let state = app_handle.state::<AppState>();
+            let db = state.db.lock().map_err(|e| e.to_string())?;
+            db.get_spotify_job(job_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Spotify job not found: {}", job_id))?
+        };
+        if job.status == "completed" {
+            return Err("Spotify job already completed".to_string());
+        }
+        let request: SpotifyDownloadRequest = serde_json::from_str(&job.request_json)
+            .map_err(|e| format!("Failed to read saved job: {}", e))?;
+
+        std::fs::create_dir_all(&request.output_path)
+            .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+        let (cancel_tx, cancel_rx) = tokio::sync::oneshot::channel::<()>();
+        {
+            let mut downloads = ACTIVE_SPOTIFY_DOWNLOADS.lock().unwrap();
+            if downloads.contains_key(job_id) {
+                return Err("Spotify job is already running".to_string());
+            }
+            downloads.insert(job_id.to_string(), cancel_tx);
+        }
+
+        let tracks: Vec<JobTrack> = job
+            .tracks
+            .into_iter()
+            .map(|t| JobTrack {
+                index: t.track_index,
+                track: serde_json::from_str(&t.track_json).unwrap_or_default(),
+                display_name: t.display_name,
+                status: t.status,
+            })
+            .collect();
+        let remaining = tracks.iter().filter(|t| !t.is_done()).count();
+        println!("[SpotDL] Resuming job {}: {} of {} tracks remaining", job_id, remaining, tracks.len());
+
+        with_db(&app_handle, |db| db.set_spotify_job_status(job_id, "running"));
+
+        let (spotdl_path_clean, binaries_dir, new_path) = self.command_paths();
+        let runner = TrackRunner::new(&spotdl_path_clean, &binaries_dir, &new_path, &request);
+        tokio::spawn(runner.run(app_handle.clone(), tracks, cancel_rx));
+        Ok(())
+    }
+
+    /// SpotDL path, its directory and a PATH that includes it
+    fn command_paths(&self) -> (String, String, String) {
+        let spotdl_path_clean = self.spotdl_path
+            .replace("\\\\?\\", "")  // Remove Windows extended path prefix
+            .replace("\\", "/");     // Normalize to forward slashes
+        let binaries_dir = std::path::Path::new(&spotdl_path_clean)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| ".".to_string());
+        let current_path = std::env::var("PATH").unwrap_or_default();
+        let new_path = format!("{};{}", binaries_dir.replace("/", "\\"), current_path);
+        (spotdl_path_clean, binaries_dir, new_path)
+    }
+
+}
+
+
+/// A track within a persisted Spotify job
+struct JobTrack {
+    index: i64,
+    track: serde_json::Value,
+    display_name: String,
+    /// "pending", "completed", "failed", "skipped_drm"
+    status: String,
+}
+
+impl JobTrack {
+    /// Already handled by an earlier run
+    fn is_done(&self) -> bool {
+        self.status == "completed" || self.status == "skipped_drm"
+    }
+}
+
+fn track_display_name(track: &serde_json::Value) -> String {
+    let track_name = track["name"].as_str().unwrap_or("Unknown");
+    let artist = track["artist"].as_str()
+        .or(track["artists"].as_array().and_then(|a| a.first()).and_then(|a| a.as_str()))
+        .unwrap_or("Unknown");
+    format!("{} - {}", artist, track_name)
+}
+
+fn save_job(app_handle: &AppHandle, request: &SpotifyDownloadRequest, tracks: &[JobTrack]) {
+    let Ok(request_json) = serde_json::to_string(request) else {
+        return;
+    };
+    let rows: Vec<(String, String)> = tracks
+        .iter()
+        .map(|t| (t.track.to_string(), t.display_name.clone()))
+        .collect();
+    with_db(app_handle, |db| db.save_spotify_job(&request.id, &request.url, &request_json, &rows));
+}
+
+fn with_db(
+    app_handle: &AppHandle,
+    f: impl FnOnce(&crate::database::Database) -> crate::database::DbResult<()>,
+) {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let Ok(db) = state.db.lock() else {
+        return;
+    };
+    if let Err(e) = f(&db) {
+        println!("[SpotDL] Failed to persist job state: {}", e);
+    }
+}
+
+/// Everything needed to download a job's tracks in the background
+struct TrackRunner {
+    id: String,
+    output_path: String,
+    audio_format: String,
+    concurrent_fragments: String,
+    proxy_args: Vec<String>,
+    yt_dlp_path: String,
+    ffmpeg_path: String,
+    spotdl_path: String,
+    binaries_dir: String,
+    path_env: String,
+}
+
+impl TrackRunner {
+    fn new(spotdl_path: &str, binaries_dir: &str, path_env: &str, request: &SpotifyDownloadRequest) -> Self {
+        Self {
+            id: request.id.clone(),
+            output_path: request.output_path.clone(),
+            audio_format: request.audio_format.clone(),
+            concurrent_fragments: request.threads.unwrap_or(4).clamp(2, 8).to_string(),
+            proxy_args: crate::proxy::proxy_args(),
+            yt_dlp_path: format!("{}/yt-dlp.exe", binaries_dir),
+            ffmpeg_path: format!("{}/ffmpeg.exe", binaries_dir),
+            spotdl_path: spotdl_path.to_string(),
+            binaries_dir: binaries_dir.to_string(),
+            path_env: path_env.to_string(),
+        }
+    }
+
+    fn record_track(&self, app: &AppHandle, track: &JobTrack, status: &str, error: Option<&str>) {
+        with_db(app, |db| db.set_spotify_track_status(&self.id, track.index, status, error));
+    }
+
+    /// Download every track not already done, persisting each track's outcome
+    async fn run(
+        self,
+        app: AppHandle,
+        tracks: Vec<JobTrack>,
+        mut cancel_rx: tokio::sync::oneshot::Receiver<()>,
+    ) {
+        let id = self.id.clone();
+        let total_tracks = tracks.len();
+        let mut completed = tracks.iter().filter(|t| t.status == "completed").count() as i32;
+        let mut skipped_drm = tracks.iter().filter(|t| t.status == "skipped_drm").count();
+        let mut last_error: Option<String> = None;
+
+        for (index, job_track) in tracks.iter().enumerate() {
+            if job_track.is_done() {
+                continue;
+            }
 
+            // Check for cancellation
+            if cancel_rx.try_recv().is_ok() {
+                with_db(&app, |db| db.set_spotify_job_status(&id, "cancelled"));
                 let _ = app.emit("spotify-download-progress", SpotifyDownloadProgress {
                     id: id.clone(),
-                    progress: 10.0 + (completed as f64 / total_tracks as f64) * 85.0,
-                    status: "downloading".to_string(),
-                    current_track: Some(format!("Finding YouTube URL for: {}", display_name)),
+                    progress: (completed as f64 / total_tracks as f64) * 100.0,
+                    status: "cancelled".to_string(),
+                    current_track: None,
                     total_tracks: Some(total_tracks as i32),
                     completed_tracks: Some(completed),
-                    speed: format!("Track {}/{}", index + 1, total_tracks),
+                    speed: String::new(),
                 });
+                return;
+            }
 
-                // Use spotdl url command to get the YouTube URL
-                let url_result = Command::new(&spotdl_for_url)
-                    .args(["url", spotify_url])
-                    .current_dir(&binaries_for_spawn)
-                    .env("PATH", &path_for_spawn)
-                    .output()
-                    .await;
+            let track = &job_track.track;
+            
+            // Get the Spotify URL for this track
+            let spotify_url = track["url"].as_str()
+                .map(|url| url.to_string())
+                .or(track["song_id"].as_str().map(|id| {
+                    // Construct URL from song ID if needed
+                    format!("https://open.spotify.com/track/{}", id)
+                }))
+                .unwrap_or_default();
+            
+            let display_name = job_track.display_name.clone();
+            println!("[SpotDL] Processing track {}/{}: {}", index + 1, total_tracks, display_name);
 
-                let youtube_url = match url_result {
-                    Ok(output) if output.status.success() => {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        // spotdl url outputs one YouTube URL per line
-                        stdout.lines()
-                            .find(|line| line.contains("youtube.com") || line.contains("youtu.be"))
-                            .map(|s| s.trim().to_string())
-                    }
-                    Ok(output) => {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        println!("[SpotDL] Failed to get YouTube URL: {}", stderr);
-                        None
-                    }
-                    Err(e) => {
-                        println!("[SpotDL] Error getting YouTube URL: {}", e);
-                        None
-                    }
-                };
+            let _ = app.emit("spotify-download-progress", SpotifyDownloadProgress {
+                id: id.clone(),
+                progress: 10.0 + (completed as f64 / total_tracks as f64) * 85.0,
+                status: "downloading".to_string(),
+                current_track: Some(format!("Finding YouTube URL for: {}", display_name)),
+                total_tracks: Some(total_tracks as i32),
+                completed_tracks: Some(completed),
+                speed: format!("Track {}/{}", index + 1, total_tracks),
+            });
 
-                if let Some(yt_url) = youtube_url {
-                    println!("[SpotDL] Found YouTube URL: {}", yt_url);
-                    
-                    let _ = app.emit("spotify-download-progress", SpotifyDownloadProgress {
-                        id: id.clone(),
-                        progress: 10.0 + (completed as f64 / total_tracks as f64) * 85.0,
-                        status: "downloading".to_string(),
-                        current_track: Some(format!("Downloading: {}", display_name)),
-                        total_tracks: Some(total_tracks as i32),
-                        completed_tracks: Some(completed),
-                        speed: "Downloading from YouTube...".to_string(),
-                    });
-
-                    // Use yt-dlp to download the audio from YouTube
-                    let safe_name = display_name
-                        .replace("/", "-")
-                        .replace("\\", "-")
-                        .replace(":", "-")
-                        .replace("*", "-")
-                        .replace("?", "")
-                        .replace("\"", "'")
-                        .replace("<", "-")
-                        .replace(">", "-")
-                        .replace("|", "-");
-                    let output_template = format!("{}/{}.%(ext)s", output_path.replace("\\", "/"), safe_name);
-                    
-                    let args = vec![
-                        "-x",  // Extract audio
-                        "--audio-format",
-                        &audio_format,
-                        "--audio-quality",
-                        "0",  // Best quality
-                        "--concurrent-fragments",
-                        &concurrent_fragments,
-                        "--retries",
-                        "4",
-                        "--fragment-retries",
-                        "4",
-                        "--socket-timeout",
-                        "20",
-                        "-o",
-                        &output_template,
-                        "--ffmpeg-location",
-                        &ffmpeg_path,
-                        "--no-playlist",
-                        &yt_url,
-                    ];
-
-                    println!("[SpotDL] Running yt-dlp with args: {:?}", args);
-
-                    let result = Command::new(&yt_dlp_path)
-                        .args(&args)
+            // Use spotdl url command to get the YouTube URL
+            let url_result = match crate::exec_guard::tokio_command(&self.spotdl_path) {
+                Ok(mut cmd) => {
+                    cmd.args(["url", spotify_url.as_str()])
+                        .args(&self.proxy_args)
+                        .current_dir(&self.binaries_dir)
+                        .env("PATH", &self.path_env)
                         .output()
-                        .await;
+                        .await
+                }
+                Err(e) => Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, e)),
+            };
 
-                    match result {
-                        Ok(output) if output.status.success() => {
-                            completed += 1;
-                            println!("[SpotDL] Successfully downloaded: {}", display_name);
-                        }
-                        Ok(output) => {
-                            let stderr = String::from_utf8_lossy(&output.stderr);
-                            let stdout = String::from_utf8_lossy(&output.stdout);
-                            println!("[SpotDL] yt-dlp stdout: {}", stdout);
-                            println!("[SpotDL] yt-dlp stderr: {}", stderr);
-                            last_error = Some(format!("Failed to download {}", display_name));
-                        }
-                        Err(e) => {
-                            println!("[SpotDL] Error running yt-dlp: {}", e);
-                            last_error = Some(format!("Error: {}", e));
-                        }
-                    }
-                } else {
-                    println!("[SpotDL] No YouTube URL found for: {}", display_name);
-                    last_error = Some(format!("No YouTube URL found for: {}", display_name));
+            let youtube_url = match url_result {
+                Ok(output) if output.status.success() => {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    // spotdl url outputs one YouTube URL per line
+                    stdout.lines()
+                        .find(|line| line.contains("youtube.com") || line.contains("youtu.be"))
+                        .map(|s| s.trim().to_string())
                 }
+                Ok(output) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    println!("[SpotDL] Failed to get YouTube URL: {}", stderr);
+                    None
+                }
+                Err(e) => {
+                    println!("[SpotDL] Error getting YouTube URL: {}", e);
+                    None
+                }
+            };
 
+            if let Some(yt_url) = youtube_url {
+                println!("[SpotDL] Found YouTube URL: {}", yt_url);
+                
                 let _ = app.emit("spotify-download-progress", SpotifyDownloadProgress {
                     id: id.clone(),
-                    progress: 10.0 + ((completed + 1) as f64 / total_tracks as f64) * 85.0,
+                    progress: 10.0 + (completed as f64 / total_tracks as f64) * 85.0,
                     status: "downloading".to_string(),
-                    current_track: Some(display_name),
+                    current_track: Some(format!("Downloading: {}", display_name)),
                     total_tracks: Some(total_tracks as i32),
                     completed_tracks: Some(completed),
-                    speed: "Downloading...".to_string(),
+                    speed: "Downloading from YouTube...".to_string(),
                 });
-            }
 
-            // Clean up active downloads
-            {
-                let mut downloads = ACTIVE_SPOTIFY_DOWNLOADS.lock().unwrap();
-                downloads.remove(&id);
+                // Use yt-dlp to download the audio from YouTube
+                let safe_name = display_name
+                    .replace("/", "-")
+                    .replace("\\", "-")
+                    .replace(":", "-")
+                    .replace("*", "-")
+                    .replace("?", "")
+                    .replace("\"", "'")
+                    .replace("<", "-")
+                    .replace(">", "-")
+                    .replace("|", "-");
+                let output_template = format!("{}/{}.%(ext)s", self.output_path.replace("\\", "/"), safe_name);
+                
+                let args = vec![
+                    "-x",  // Extract audio
+                    "--audio-format",
+                    &self.audio_format,
+                    "--audio-quality",
+                    "0",  // Best quality
+                    "--concurrent-fragments",
+                    &self.concurrent_fragments,
+                    "--retries",
+                    "4",
+                    "--fragment-retries",
+                    "4",
+                    "--socket-timeout",
+                    "20",
+                    "-o",
+                    &output_template,
+                    "--ffmpeg-location",
+                    &self.ffmpeg_path,
+                    "--no-playlist",
+                    &yt_url,
+                ];
+
+                println!("[SpotDL] Running yt-dlp with args: {:?}", args);
+
+                let result = match crate::exec_guard::tokio_command(&self.yt_dlp_path) {
+                    Ok(mut cmd) => cmd.args(&args).args(&self.proxy_args).output().await,
+                    Err(e) => Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, e)),
+                };
+
+                match result {
+                    Ok(output) if output.status.success() => {
+                        completed += 1;
+                        self.record_track(&app, job_track, "completed", None);
+                        println!("[SpotDL] Successfully downloaded: {}", display_name);
+                    }
+                    Ok(output) => {
+                        let stderr = String::from_utf8_lossy(&output.stderr);
+                        let stdout = String::from_utf8_lossy(&output.stdout);
+                        println!("[SpotDL] yt-dlp stdout: {}", stdout);
+                        println!("[SpotDL] yt-dlp stderr: {}", stderr);
+                        let error = format!("Failed to download {}", display_name);
+                        if crate::downloader::is_drm_indicator(&stderr) {
+                            skipped_drm += 1;
+                            self.record_track(&app, job_track, "skipped_drm", Some(&error));
+                            println!("[SpotDL] Skipping DRM protected track: {}", display_name);
+                            let _ = app.emit("spotify-download-progress", SpotifyDownloadProgress {
+                                id: id.clone(),
+                                progress: 10.0 + (completed as f64 / total_tracks as f64) * 85.0,
+                                status: "skipped_drm".to_string(),
+                                current_track: Some(format!("Skipped (DRM protected): {}", display_name)),
+                                total_tracks: Some(total_tracks as i32),
+                                completed_tracks: Some(completed),
+                                speed: String::new(),
+                            });
+                        } else {
+                            self.record_track(&app, job_track, "failed", Some(&error));
+                        }
+                        last_error = Some(error);
+                    }
+                    Err(e) => {
+                        println!("[SpotDL] Error running yt-dlp: {}", e);
+                        let error = format!("Error: {}", e);
+                        self.record_track(&app, job_track, "failed", Some(&error));
+                        last_error = Some(error);
+                    }
+                }
+            } else {
+                println!("[SpotDL] No YouTube URL found for: {}", display_name);
+                let error = format!("No YouTube URL found for: {}", display_name);
+                self.record_track(&app, job_track, "failed", Some(&error));
+                last_error = Some(error);
             }
 
-            // Emit final status
-            let final_status = if completed > 0 { "completed" } else { "failed" };
-            
             let _ = app.emit("spotify-download-progress", SpotifyDownloadProgress {
                 id: id.clone(),
-                progress: if completed > 0 { 100.0 } else { 0.0 },
-                status: final_status.to_string(),
-                current_track: if completed > 0 { 
-                    Some(format!("Downloaded {} tracks", completed)) 
-                } else { 
-                    last_error 
-                },
+                progress: 10.0 + ((completed + 1) as f64 / total_tracks as f64) * 85.0,
+                status: "downloading".to_string(),
+                current_track: Some(display_name),
                 total_tracks: Some(total_tracks as i32),
                 completed_tracks: Some(completed),
-                speed: String::new(),
+                speed: "Downloading...".to_string(),
             });
-        });
+        }
 
-        Ok(())
-    }
+        // Clean up active downloads
+        {
+            let mut downloads = ACTIVE_SPOTIFY_DOWNLOADS.lock().unwrap();
+            downloads.remove(&id);
+        }
 
+        // Failed tracks stay resumable; a job is done once every track is
+        let job_status = if completed as usize + skipped_drm == total_tracks { "completed" } else { "failed" };
+        with_db(&app, |db| db.set_spotify_job_status(&id, job_status));
+
+        // Emit final status
+        let final_status = if completed > 0 {
+            "completed"
+        } else if skipped_drm > 0 && skipped_drm == total_tracks {
+            "drm_protected"
+        } else {
+            "failed"
+        };
+        
+        let _ = app.emit("spotify-download-progress", SpotifyDownloadProgress {
+            id: id.clone(),
+            progress: if completed > 0 { 100.0 } else { 0.0 },
+            status: final_status.to_string(),
+            current_track: if completed > 0 && skipped_drm > 0 {
+                Some(format!("Downloaded {} tracks, {} skipped (DRM protected)", completed, skipped_drm))
+            } else if completed > 0 { 
+                Some(format!("Downloaded {} tracks", completed)) 
+            } else if final_status == "drm_protected" {
+                Some(crate::downloader::drm_protected_error())
+            } else { 
+                last_error 
+            },
+            total_tracks: Some(total_tracks as i32),
+            completed_tracks: Some(completed),
+            speed: String::new(),
+        });
+    }
 }
 
 
@@ -917,6 +1127,27 @@ pub async fn start_spotify_download(
     downloader.start_download(request, app_handle).await
 }
 
+/// Resume a Spotify job interrupted by a restart, cancellation or failed tracks
+#[tauri::command]
+pub async fn resume_spotify_download(app_handle: AppHandle, job_id: String) -> Result<(), String> {
+    let downloader = SpotifyDownloader::new(&app_handle);
+    downloader.resume_download(&job_id, app_handle).await
+}
+
+/// Spotify jobs with tracks left to download
+#[tauri::command]
+pub async fn get_resumable_spotify_jobs(state: State<'_, AppState>) -> Result<Vec<SpotifyJob>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_unfinished_spotify_jobs().map_err(|e| e.to_string())
+}
+
+/// Forget a saved Spotify job
+#[tauri::command]
+pub async fn discard_spotify_job(state: State<'_, AppState>, job_id: String) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.delete_spotify_job(&job_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn cancel_spotify_download(id: String) -> Result<(), String> {
     let sender = {
@@ -932,6 +1163,10 @@ pub async fn cancel_spotify_download(id: String) -> Result<(), String> {
     }
 }
 
+pub(crate) fn is_active_download(id: &str) -> bool {
+    ACTIVE_SPOTIFY_DOWNLOADS.lock().unwrap().contains_key(id)
+}
+
 /// Check if a URL is a Spotify URL
 pub fn is_spotify_url(url: &str) -> bool {
     url.contains("spotify.com") || url.contains("open.spotify.com")
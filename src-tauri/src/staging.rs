@@ -0,0 +1,297 @@
+//! Staging Directory
+//!
+//! Lets temporary files live on a drive of the user's choosing instead of the system temp
+//! folder or the app data folder, which are usually on a small OS drive.
+//!
+//! Key Features:
+//! - One settings-controlled folder; unset keeps every temp file where it used to go
+//! - Everything is kept inside an `OwnstashStaging` folder the app creates and marks as its
+//!   own, so cleanup never touches the user's folders; an unmarked one is refused
+//! - Used for yt-dlp's `--paths temp:`, vault downloads and decryption, and transcoding
+//! - Checked for write permission and free space before it's accepted
+//! - Changing it moves what's already staged over to the new location
+
+use crate::commands::AppState;
+use crate::database::Database;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{AppHandle, State};
+
+/// Settings key holding the staging folder path; empty means the defaults
+pub const STAGING_SETTING: &str = "staging_directory";
+
+/// Free space a staging folder must have to be accepted
+const MIN_FREE_SPACE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Folder the app creates inside the chosen staging folder; all temp files go in it
+const STAGING_HOME: &str = "OwnstashStaging";
+
+/// File marking `STAGING_HOME` as created by the app
+const OWNER_MARKER: &str = ".ownstash-staging";
+
+/// Subfolders, one per kind of temp file
+pub const YT_DLP: &str = "yt-dlp";
+pub const VAULT: &str = "vault";
+pub const VAULT_DOWNLOADS: &str = "vault-downloads";
+pub const TRANSCODE: &str = "transcoded";
+/// Scratch space for joining byte-split archives
+pub const SPLIT_ARCHIVES: &str = "split-archives";
+
+lazy_static::lazy_static! {
+    static ref STAGING_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Restore the staging folder at startup. One that can no longer be claimed is ignored, so
+/// temp files go to their defaults.
+pub fn load_from_settings(db: &Database) {
+    let root = db
+        .get_setting(STAGING_SETTING)
+        .ok()
+        .flatten()
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from)
+        .filter(|root| match claim(root) {
+            Ok(()) => true,
+            Err(e) => {
+                println!("[Staging] Not using the staging folder: {}", e);
+                false
+            }
+        });
+    *STAGING_ROOT.write().unwrap() = root;
+}
+
+fn staging_home(root: &Path) -> PathBuf {
+    root.join(STAGING_HOME)
+}
+
+/// Create the app's folder under `root`, or check that an existing one is the app's
+fn claim(root: &Path) -> Result<(), String> {
+    let home = staging_home(root);
+    let marker = home.join(OWNER_MARKER);
+    if home.exists() {
+        if marker.is_file() {
+            return Ok(());
+        }
+        return Err(format!(
+            "{} already exists and wasn't created by Ownstash; pick another folder",
+            home.display()
+        ));
+    }
+    fs::create_dir_all(&home).map_err(|e| format!("Can't create {}: {}", home.display(), e))?;
+    fs::write(&marker, b"Temporary files of Ownstash Downloader; safe to delete while it isn't running\n")
+        .map_err(|e| format!("Can't write to {}: {}", home.display(), e))
+}
+
+/// The configured staging folder, if any
+pub fn root() -> Option<PathBuf> {
+    STAGING_ROOT.read().unwrap().clone()
+}
+
+/// `purpose`'s folder under the configured staging folder; None when unset, so the caller
+/// falls back to its own default
+pub fn dir(purpose: &str) -> Option<PathBuf> {
+    root().map(|root| staging_home(&root).join(purpose))
+}
+
+/// Error unless `path` is an absolute folder the app can write to, on a drive with room
+fn validate(path: &Path, min_free: u64) -> Result<(), String> {
+    if !path.is_absolute() {
+        return Err("The staging folder must be an absolute path".to_string());
+    }
+    fs::create_dir_all(path).map_err(|e| format!("Can't create {}: {}", path.display(), e))?;
+
+    let probe = path.join(format!(".ownstash-write-test-{}", uuid::Uuid::new_v4()));
+    fs::write(&probe, b"ok").map_err(|e| format!("Can't write to {}: {}", path.display(), e))?;
+    let _ = fs::remove_file(&probe);
+    claim(path)?;
+
+    let free = crate::downloader::free_disk_space(path)?;
+    if free < min_free {
+        return Err(format!(
+            "Only {} MB is free on the drive holding {}; staging needs at least {} MB",
+            free / (1024 * 1024),
+            path.display(),
+            min_free / (1024 * 1024)
+        ));
+    }
+    Ok(())
+}
+
+/// Where each kind of temp file goes under the current setting. yt-dlp's default lives in
+/// each output folder, so it's only listed when a staging folder is set.
+fn current_dirs(app_handle: &AppHandle) -> Vec<(&'static str, Option<PathBuf>)> {
+    vec![
+        (YT_DLP, dir(YT_DLP)),
+        (VAULT, Some(crate::vault::get_vault_temp_dir(app_handle))),
+        (VAULT_DOWNLOADS, Some(crate::vault_download::get_vault_download_temp_dir())),
+        (TRANSCODE, crate::commands::transcode_cache_dir(app_handle).ok()),
+    ]
+}
+
+/// Outcome of moving staged files to a new staging folder
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct MigrationReport {
+    pub moved: usize,
+    /// Entries left behind because the new folder already had something by that name, or
+    /// the move failed
+    pub skipped: Vec<String>,
+}
+
+/// Move a file or folder, copying when a rename can't cross drives
+fn move_entry(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if from.is_dir() {
+        fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+        for entry in fs::read_dir(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))? {
+            let entry = entry.map_err(|e| e.to_string())?;
+            move_entry(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::remove_dir(from).map_err(|e| format!("Failed to remove {}: {}", from.display(), e))
+    } else {
+        fs::copy(from, to).map_err(|e| format!("Failed to copy {} to {}: {}", from.display(), to.display(), e))?;
+        fs::remove_file(from).map_err(|e| format!("Failed to remove {} after moving it: {}", from.display(), e))
+    }
+}
+
+/// Move everything in `from` into `to`, leaving entries whose name is already taken
+fn migrate_dir(from: &Path, to: &Path, report: &mut MigrationReport) {
+    let Ok(entries) = fs::read_dir(from) else {
+        return;
+    };
+    if let Err(e) = fs::create_dir_all(to) {
+        report.skipped.push(format!("{}: {}", from.display(), e));
+        return;
+    }
+    for entry in entries.flatten() {
+        let target = to.join(entry.file_name());
+        if target.exists() {
+            report.skipped.push(entry.path().display().to_string());
+            continue;
+        }
+        match move_entry(&entry.path(), &target) {
+            Ok(()) => report.moved += 1,
+            Err(e) => {
+                println!("[Staging] {}", e);
+                report.skipped.push(entry.path().display().to_string());
+            }
+        }
+    }
+    let _ = fs::remove_dir(from);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StagingInfo {
+    /// None when temp files go to their default locations
+    pub path: Option<String>,
+    pub free_bytes: Option<u64>,
+}
+
+#[tauri::command]
+pub async fn get_staging_directory() -> Result<StagingInfo, String> {
+    let path = root();
+    let free_bytes = path.as_deref().and_then(|p| crate::downloader::free_disk_space(p).ok());
+    Ok(StagingInfo { path: path.map(|p| p.to_string_lossy().to_string()), free_bytes })
+}
+
+/// Set (or with None, clear) the staging folder and move staged files over. Refused while
+/// downloads are running, since they're writing into the current folder.
+#[tauri::command]
+pub async fn set_staging_directory(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    path: Option<String>,
+) -> Result<MigrationReport, String> {
+    if crate::downloader::has_active_downloads() || crate::vault_download::has_active_downloads() {
+        return Err("Wait for running downloads to finish before moving the staging folder".to_string());
+    }
+    let new_root = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).map(PathBuf::from);
+    if let Some(new_root) = new_root.clone() {
+        tokio::task::spawn_blocking(move || validate(&new_root, MIN_FREE_SPACE))
+            .await
+            .map_err(|e| format!("Staging folder check failed: {}", e))??;
+    }
+
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let value = new_root.as_ref().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+        db.save_setting(STAGING_SETTING, &value).map_err(|e| e.to_string())?;
+    }
+    let old_dirs = current_dirs(&app_handle);
+    *STAGING_ROOT.write().unwrap() = new_root;
+    let new_dirs = current_dirs(&app_handle);
+    println!("[Staging] Staging folder set to {:?}", root());
+
+    tokio::task::spawn_blocking(move || {
+        let mut report = MigrationReport::default();
+        for ((_, old), (_, new)) in old_dirs.iter().zip(&new_dirs) {
+            // Without a staging folder, yt-dlp stages next to each download again
+            let (Some(old), Some(new)) = (old, new) else { continue };
+            if old != new {
+                migrate_dir(old, new, &mut report);
+            }
+        }
+        println!("[Staging] Moved {} staged entries, left {}", report.moved, report.skipped.len());
+        report
+    })
+    .await
+    .map_err(|e| format!("Moving staged files failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::scratch_dir;
+
+    #[test]
+    fn test_validate() {
+        let dir = scratch_dir();
+        assert!(validate(Path::new("relative/staging"), 0).is_err());
+        assert!(validate(&dir.join("new"), 0).is_ok());
+        assert!(dir.join("new").is_dir());
+        assert!(validate(&dir, u64::MAX).unwrap_err().contains("free"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_claim_refuses_folders_it_did_not_create() {
+        let dir = scratch_dir();
+        claim(&dir).unwrap();
+        assert!(dir.join(STAGING_HOME).join(OWNER_MARKER).is_file());
+        // Claiming again is fine
+        claim(&dir).unwrap();
+
+        let other = scratch_dir();
+        fs::create_dir_all(other.join(STAGING_HOME)).unwrap();
+        fs::write(other.join(STAGING_HOME).join("holiday.jpg"), b"jpg").unwrap();
+        assert!(claim(&other).unwrap_err().contains("wasn't created by Ownstash"));
+        assert!(validate(&other, 0).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+        let _ = fs::remove_dir_all(&other);
+    }
+
+    #[test]
+    fn test_migrate_dir_keeps_existing_entries() {
+        let dir = scratch_dir();
+        let (from, to) = (dir.join("old"), dir.join("new"));
+        fs::create_dir_all(from.join("nested")).unwrap();
+        fs::write(from.join("a.part"), b"a").unwrap();
+        fs::write(from.join("nested").join("b"), b"b").unwrap();
+        fs::write(from.join("taken"), b"old").unwrap();
+        fs::create_dir_all(&to).unwrap();
+        fs::write(to.join("taken"), b"new").unwrap();
+
+        let mut report = MigrationReport::default();
+        migrate_dir(&from, &to, &mut report);
+        assert_eq!(report.moved, 2);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(fs::read(to.join("nested").join("b")).unwrap(), b"b");
+        assert_eq!(fs::read(to.join("taken")).unwrap(), b"new");
+        assert!(from.join("taken").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,180 @@
+//! Stale Download Detection
+//!
+//! History rows left on "downloading" after a crash or a lost engine task otherwise stay
+//! "in progress" forever. They're cross-checked against the engines and failed with a reason.
+//!
+//! Key Features:
+//! - Swept at startup (after crash resume has kept what it can resume) and periodically
+//! - A row counts as stale when no engine (Media Engine/SNDE, SpotDL, health registry) runs it
+//!   and it isn't following another download of the same URL
+//! - While the app runs a row must look stale on two sweeps in a row, so downloads that are
+//!   just starting aren't caught
+//! - Failed rows keep their reason and are listed for recovery until retried or dismissed
+
+use crate::commands::AppState;
+use crate::database::Download;
+use crate::health_metrics::HEALTH_REGISTRY;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Time between sweeps while the app runs
+const SWEEP_INTERVAL: Duration = Duration::from_secs(120);
+
+const STARTUP_REASON: &str = "Interrupted: the app closed while this was downloading";
+const ORPHAN_REASON: &str = "Interrupted: no download engine was running this anymore";
+
+lazy_static::lazy_static! {
+    /// Rows that looked stale on the last sweep
+    static ref SUSPECTS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// A download failed as stale
+#[derive(Debug, Clone, Serialize)]
+pub struct StaleDownload {
+    #[serde(flatten)]
+    pub download: Download,
+    pub reason: String,
+}
+
+/// Everything the recovery list offers: stale rows and partial files that can be resumed
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryList {
+    pub stale: Vec<StaleDownload>,
+    pub incomplete: Vec<crate::crash_resume::IncompleteDownload>,
+}
+
+fn is_live(id: &str) -> bool {
+    crate::downloader::is_active_download(id)
+        || crate::spotify_downloader::is_active_download(id)
+        || HEALTH_REGISTRY.is_registered(id)
+        || crate::coalesce::is_following(id)
+}
+
+/// Of the `downloading` ids, those to fail now. At startup that's every one no engine runs;
+/// later only those that weren't running on the previous sweep either. `suspects` is
+/// updated for the next sweep.
+fn stale_ids(
+    downloading: &[String],
+    is_live: impl Fn(&str) -> bool,
+    suspects: &mut HashSet<String>,
+    startup: bool,
+) -> Vec<String> {
+    let not_running: HashSet<String> = downloading.iter().filter(|id| !is_live(id)).cloned().collect();
+    let stale = not_running
+        .iter()
+        .filter(|id| startup || suspects.contains(*id))
+        .cloned()
+        .collect::<Vec<_>>();
+    *suspects = not_running.into_iter().filter(|id| !stale.contains(id)).collect();
+    stale
+}
+
+/// Fail the rows no engine is running. At startup nothing can be running yet.
+pub fn sweep(app_handle: &AppHandle, startup: bool) {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let Ok(db) = state.db.lock() else {
+        return;
+    };
+    let downloading: Vec<String> = match db.get_downloads() {
+        Ok(downloads) => downloads.into_iter().filter(|d| d.status == "downloading").map(|d| d.id).collect(),
+        Err(e) => {
+            println!("[StaleDownloads] Failed to read downloads: {}", e);
+            return;
+        }
+    };
+
+    let stale = stale_ids(&downloading, is_live, &mut SUSPECTS.lock().unwrap(), startup);
+    let reason = if startup { STARTUP_REASON } else { ORPHAN_REASON };
+    let failed: Vec<String> = stale
+        .into_iter()
+        .filter(|id| db.fail_stale_download(id, reason).unwrap_or(false))
+        .collect();
+    drop(db);
+
+    if !failed.is_empty() {
+        println!("[StaleDownloads] Failed {} stale downloads", failed.len());
+        let _ = app_handle.emit("stale-downloads", &failed);
+    }
+}
+
+/// Sweep for stale downloads periodically; call once at startup. The startup sweep runs
+/// from crash resume, once it has kept the downloads it can resume.
+pub fn install(app_handle: &AppHandle) {
+    let app = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+            sweep(&app, false);
+        }
+    });
+}
+
+/// Downloads failed as stale and partial downloads that can be resumed
+#[tauri::command]
+pub async fn get_recovery_list(state: State<'_, AppState>) -> Result<RecoveryList, String> {
+    let stale = {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.get_stale_downloads().map_err(|e| e.to_string())?
+    };
+    Ok(RecoveryList {
+        stale: stale.into_iter().map(|(download, reason)| StaleDownload { download, reason }).collect(),
+        incomplete: crate::crash_resume::incomplete(),
+    })
+}
+
+/// Take a failed download off the recovery list
+#[tauri::command]
+pub async fn dismiss_stale_download(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.dismiss_stale_download(&id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_needs_two_sweeps_while_running() {
+        let downloading: Vec<String> = ["live", "starting", "orphan"].iter().map(|s| s.to_string()).collect();
+        let mut suspects = HashSet::new();
+
+        let stale = stale_ids(&downloading, |id| id == "live", &mut suspects, false);
+        assert!(stale.is_empty());
+        assert_eq!(suspects.len(), 2);
+
+        // "starting" has been picked up by an engine since
+        let stale = stale_ids(&downloading, |id| id != "orphan", &mut suspects, false);
+        assert_eq!(stale, ["orphan"]);
+        assert!(suspects.is_empty());
+    }
+
+    #[test]
+    fn test_startup_fails_everything_not_running() {
+        let downloading = vec!["a".to_string(), "b".to_string()];
+        let mut stale = stale_ids(&downloading, |_| false, &mut HashSet::new(), true);
+        stale.sort();
+        assert_eq!(stale, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_coalesced_follower_is_live() {
+        let url = format!("https://example.com/{}", uuid::Uuid::new_v4());
+        let primary = crate::test_support::download_request("stale-primary", &url, false);
+        let follower = crate::test_support::download_request("stale-follower", &url, false);
+        assert_eq!(crate::coalesce::attach(&primary), None);
+        assert_eq!(crate::coalesce::attach(&follower).as_deref(), Some("stale-primary"));
+
+        // The follower has no engine of its own but mustn't be failed as stale
+        assert!(is_live("stale-follower"));
+        let downloading = vec!["stale-follower".to_string()];
+        assert!(stale_ids(&downloading, is_live, &mut HashSet::new(), true).is_empty());
+
+        crate::coalesce::detach("stale-follower");
+        assert!(!is_live("stale-follower"));
+    }
+}
@@ -0,0 +1,366 @@
+//! Channel / Playlist Subscriptions
+//!
+//! Keeps a local archive of a channel or playlist: new entries are fetched on an
+//! interval and downloaded with the subscription's preset.
+//!
+//! Key Features:
+//! - Background sync task checks due subscriptions every few minutes
+//! - Entries already in the download archive are skipped, so nothing is fetched twice
+//! - Per-subscription download preset (quality, format, output template, ...)
+//! - `sync_subscription_now` for a manual refresh
+
+use crate::commands::AppState;
+use crate::database::Subscription;
+use crate::downloader::{DownloadRequest, Downloader, PlaylistEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often the background task looks for due subscriptions
+const SYNC_CHECK_INTERVAL_SECS: u64 = 300;
+
+const MIN_SYNC_INTERVAL_MINUTES: i64 = 15;
+const DEFAULT_SYNC_INTERVAL_MINUTES: i64 = 360;
+
+/// Most entries queued by a single sync, so a first sync of a huge channel stays bounded
+const MAX_ENTRIES_PER_SYNC: usize = 50;
+
+lazy_static::lazy_static! {
+    /// Subscriptions with a sync (and its downloads) in progress
+    static ref SYNCING: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Outcome of one sync, also emitted as "subscription-synced"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionSyncResult {
+    pub subscription_id: String,
+    pub total_entries: usize,
+    /// Entries not in the download archive that are now being downloaded
+    pub queued: Vec<PlaylistEntry>,
+}
+
+/// Entries with an id that the archive doesn't already hold, oldest first
+fn new_entries(entries: Vec<PlaylistEntry>, archive: &HashSet<(String, String)>) -> Vec<PlaylistEntry> {
+    let archived_ids: HashSet<&str> = archive.iter().map(|(_, id)| id.as_str()).collect();
+    let mut fresh: Vec<PlaylistEntry> = entries
+        .into_iter()
+        .filter(|entry| entry.url.is_some())
+        .filter(|entry| match (&entry.extractor, &entry.id) {
+            (Some(extractor), Some(id)) => !archive.contains(&(extractor.clone(), id.clone())),
+            (None, Some(id)) => !archived_ids.contains(id.as_str()),
+            (_, None) => false,
+        })
+        .collect();
+    // Channels list newest first: keep the newest, then download in publication order
+    fresh.truncate(MAX_ENTRIES_PER_SYNC);
+    fresh.reverse();
+    fresh
+}
+
+fn is_due(subscription: &Subscription, now: i64) -> bool {
+    subscription.enabled
+        && subscription
+            .last_synced_at
+            .map(|last| now - last >= subscription.interval_minutes * 60)
+            .unwrap_or(true)
+}
+
+/// Download request for one entry, with the subscription's preset applied
+fn entry_request(subscription: &Subscription, entry: &PlaylistEntry, app_handle: &AppHandle) -> Result<DownloadRequest, String> {
+    let url = entry.url.as_deref().ok_or("Entry has no URL")?;
+    let mut request = DownloadRequest::with_defaults(url, &subscription.output_path)?;
+
+    if let Some(preset_id) = &subscription.preset_id {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        crate::presets::get_download_preset(&db, preset_id)?.settings.apply(&mut request);
+    }
+    // Record what was fetched so the next sync skips it
+    request.use_download_archive = Some(true);
+    Ok(request)
+}
+
+/// Download the queued entries one after another, recording each in the history
+async fn download_entries(app_handle: AppHandle, subscription: Subscription, entries: Vec<PlaylistEntry>) {
+    for entry in entries {
+        let request = match entry_request(&subscription, &entry, &app_handle) {
+            Ok(request) => request,
+            Err(e) => {
+                println!("[Subscriptions] {}: {}", subscription.name, e);
+                break;
+            }
+        };
+
+        let history_entry = crate::database::Download {
+            id: request.id.clone(),
+            title: entry.title.clone(),
+            url: request.url.clone(),
+            format: if request.audio_only { request.audio_format.clone() } else { request.video_format.clone() },
+            path: request.output_path.clone(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            status: "downloading".to_string(),
+            size_bytes: None,
+            platform: entry.extractor.clone(),
+            thumbnail: None,
+            on_complete: None,
+        };
+        if let Err(e) = crate::downloader::run_download(&app_handle, request, Some(&history_entry)).await {
+            println!("[Subscriptions] Failed to download {}: {}", entry.title, e);
+        }
+    }
+}
+
+/// Fetch a subscription's entries and start downloading the new ones in the background
+pub async fn sync_subscription(app_handle: &AppHandle, subscription_id: &str) -> Result<SubscriptionSyncResult, String> {
+    if !SYNCING.lock().unwrap().insert(subscription_id.to_string()) {
+        return Err("This subscription is already syncing".to_string());
+    }
+
+    let result = fetch_new_entries(app_handle, subscription_id).await;
+    let error = result.as_ref().err().map(|e| e.as_str());
+    if let Some(state) = app_handle.try_state::<AppState>() {
+        if let Ok(db) = state.db.lock() {
+            let _ = db.record_subscription_sync(subscription_id, error);
+        }
+    }
+
+    match result {
+        Ok((subscription, result)) => {
+            println!(
+                "[Subscriptions] {}: {} new of {} entries",
+                subscription.name,
+                result.queued.len(),
+                result.total_entries
+            );
+            let _ = app_handle.emit("subscription-synced", &result);
+
+            let app = app_handle.clone();
+            let entries = result.queued.clone();
+            tauri::async_runtime::spawn(async move {
+                let id = subscription.id.clone();
+                download_entries(app, subscription, entries).await;
+                SYNCING.lock().unwrap().remove(&id);
+            });
+            Ok(result)
+        }
+        Err(e) => {
+            SYNCING.lock().unwrap().remove(subscription_id);
+            Err(e)
+        }
+    }
+}
+
+async fn fetch_new_entries(
+    app_handle: &AppHandle,
+    subscription_id: &str,
+) -> Result<(Subscription, SubscriptionSyncResult), String> {
+    let (subscription, archive) = {
+        let state = app_handle.state::<AppState>();
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        let subscription = db
+            .get_subscription(subscription_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Subscription {} not found", subscription_id))?;
+        let archive: HashSet<(String, String)> = db
+            .get_archive_entries(None)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|entry| (entry.extractor, entry.item_id))
+            .collect();
+        (subscription, archive)
+    };
+
+    let playlist = Downloader::new(app_handle)
+        .get_playlist_entries(&subscription.url, None)
+        .await?;
+    let total_entries = playlist.entries.len();
+    let queued = new_entries(playlist.entries, &archive);
+    Ok((
+        subscription,
+        SubscriptionSyncResult {
+            subscription_id: subscription_id.to_string(),
+            total_entries,
+            queued,
+        },
+    ))
+}
+
+/// Sync the subscriptions whose interval has elapsed
+async fn sync_due(app_handle: &AppHandle) {
+    let due: Vec<String> = {
+        let Some(state) = app_handle.try_state::<AppState>() else {
+            return;
+        };
+        let Ok(db) = state.db.lock() else {
+            return;
+        };
+        let now = chrono::Utc::now().timestamp();
+        db.get_subscriptions()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|s| is_due(s, now) && !SYNCING.lock().unwrap().contains(&s.id))
+            .map(|s| s.id)
+            .collect()
+    };
+    if !due.is_empty() && !crate::outbound::allowed(crate::outbound::OutboundCall::SubscriptionSync) {
+        return;
+    }
+    for id in due {
+        if let Err(e) = sync_subscription(app_handle, &id).await {
+            println!("[Subscriptions] Sync of {} failed: {}", id, e);
+        }
+    }
+}
+
+/// Periodically sync subscriptions whose interval has elapsed
+pub fn start_subscription_sync_task(app_handle: AppHandle) {
+    crate::scheduler::every(std::time::Duration::from_secs(SYNC_CHECK_INTERVAL_SECS), move || {
+        let app_handle = app_handle.clone();
+        async move { sync_due(&app_handle).await }
+    });
+}
+
+/// Editable fields of a subscription; `id` is omitted when creating one
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscriptionInput {
+    pub id: Option<String>,
+    #[serde(default)]
+    pub name: String,
+    pub url: String,
+    pub output_path: String,
+    #[serde(default)]
+    pub preset_id: Option<String>,
+    #[serde(default)]
+    pub interval_minutes: Option<i64>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// Create a subscription, or update it when `id` is given
+#[tauri::command]
+pub async fn save_subscription(
+    state: State<'_, AppState>,
+    subscription: SubscriptionInput,
+) -> Result<Subscription, String> {
+    let url = subscription.url.trim().to_string();
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err("Subscription URL must be an http(s) link".to_string());
+    }
+    if subscription.output_path.trim().is_empty() {
+        return Err("Subscription output folder is empty".to_string());
+    }
+    let interval_minutes = subscription.interval_minutes.unwrap_or(DEFAULT_SYNC_INTERVAL_MINUTES);
+    if interval_minutes < MIN_SYNC_INTERVAL_MINUTES {
+        return Err(format!("Sync interval must be at least {} minutes", MIN_SYNC_INTERVAL_MINUTES));
+    }
+
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    if let Some(preset_id) = &subscription.preset_id {
+        crate::presets::get_download_preset(&db, preset_id)?;
+    }
+    let existing = match &subscription.id {
+        Some(id) => db.get_subscription(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let name = subscription.name.trim();
+    let saved = Subscription {
+        id: subscription.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        name: if name.is_empty() { url.clone() } else { name.to_string() },
+        url,
+        output_path: subscription.output_path,
+        preset_id: subscription.preset_id,
+        interval_minutes,
+        enabled: subscription.enabled.unwrap_or(true),
+        last_synced_at: existing.as_ref().and_then(|s| s.last_synced_at),
+        last_error: existing.as_ref().and_then(|s| s.last_error.clone()),
+        created_at: existing.map(|s| s.created_at).unwrap_or_else(|| chrono::Utc::now().timestamp()),
+    };
+    db.save_subscription(&saved)
+        .map_err(|e| format!("Failed to save subscription: {}", e))?;
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn list_subscriptions(state: State<'_, AppState>) -> Result<Vec<Subscription>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_subscriptions().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_subscription(state: State<'_, AppState>, subscription_id: String) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.delete_subscription(&subscription_id).map_err(|e| e.to_string())
+}
+
+/// Sync a subscription right away; new entries download in the background
+#[tauri::command]
+pub async fn sync_subscription_now(
+    app_handle: AppHandle,
+    subscription_id: String,
+) -> Result<SubscriptionSyncResult, String> {
+    sync_subscription(&app_handle, &subscription_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(index: usize, id: &str, extractor: Option<&str>) -> PlaylistEntry {
+        PlaylistEntry {
+            index,
+            id: Some(id.to_string()),
+            extractor: extractor.map(|e| e.to_string()),
+            title: id.to_string(),
+            url: Some(format!("https://example.com/{}", id)),
+            duration: None,
+        }
+    }
+
+    #[test]
+    fn test_new_entries_skip_archived() {
+        let archive: HashSet<(String, String)> =
+            [("youtube".to_string(), "old".to_string())].into_iter().collect();
+        let entries = vec![
+            entry(1, "newest", Some("youtube")),
+            entry(2, "old", Some("youtube")),
+            entry(3, "older", None),
+            entry(4, "old", None),
+        ];
+        let ids: Vec<String> = new_entries(entries, &archive).into_iter().filter_map(|e| e.id).collect();
+        assert_eq!(ids, vec!["older", "newest"]);
+    }
+
+    #[test]
+    fn test_new_entries_keep_the_newest() {
+        let entries: Vec<PlaylistEntry> =
+            (0..MAX_ENTRIES_PER_SYNC + 10).map(|i| entry(i + 1, &format!("v{}", i), Some("youtube"))).collect();
+        let fresh = new_entries(entries, &HashSet::new());
+        assert_eq!(fresh.len(), MAX_ENTRIES_PER_SYNC);
+        // Newest (v0) is downloaded last, the oldest kept one first
+        assert_eq!(fresh.last().and_then(|e| e.id.as_deref()), Some("v0"));
+        assert_eq!(fresh[0].id.as_deref(), Some(format!("v{}", MAX_ENTRIES_PER_SYNC - 1).as_str()));
+    }
+
+    #[test]
+    fn test_is_due() {
+        let mut subscription = Subscription {
+            id: "s".to_string(),
+            name: "s".to_string(),
+            url: "https://example.com".to_string(),
+            output_path: "/downloads".to_string(),
+            preset_id: None,
+            interval_minutes: 60,
+            enabled: true,
+            last_synced_at: None,
+            last_error: None,
+            created_at: 0,
+        };
+        assert!(is_due(&subscription, 1000));
+        subscription.last_synced_at = Some(1000);
+        assert!(!is_due(&subscription, 1000 + 59 * 60));
+        assert!(is_due(&subscription, 1000 + 60 * 60));
+        subscription.enabled = false;
+        assert!(!is_due(&subscription, 1000 + 60 * 60));
+    }
+}
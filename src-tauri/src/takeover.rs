@@ -0,0 +1,275 @@
+//! Browser Download Takeover
+//!
+//! Backend for the extension's takeover mode: the extension intercepts browser downloads
+//! above a size threshold and hands them here, with the cookies and headers the page had,
+//! so the file comes down through the app's engines instead of the browser.
+//!
+//! Key Features:
+//! - Off by default; the threshold is shared with the extension through `/takeover/config`
+//! - Handed-over downloads carry the browser's Referer, cookies and headers, so the router's
+//!   probe sees what the browser saw and direct files go to SNDE
+//! - Each takeover gets an id the extension polls for the outcome (downloading, completed,
+//!   failed, ...), with the final path once done
+//! - Downloads below the threshold are declined so the browser keeps them
+
+use crate::commands::AppState;
+use crate::database::Database;
+use crate::download_lifecycle::ProgressUpdate;
+use crate::downloader::DownloadRequest;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, RwLock};
+use tauri::{AppHandle, Emitter, State};
+
+/// Settings key holding the JSON encoded `TakeoverSettings`
+pub const TAKEOVER_SETTING: &str = "browser_takeover";
+
+/// Outcomes kept for the extension to poll
+const MAX_TRACKED: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TakeoverSettings {
+    pub enabled: bool,
+    /// Browser downloads smaller than this stay in the browser
+    pub min_size_bytes: u64,
+}
+
+impl Default for TakeoverSettings {
+    fn default() -> Self {
+        Self { enabled: false, min_size_bytes: 50 * 1024 * 1024 }
+    }
+}
+
+/// Where a handed-over download stands, as reported to the extension
+#[derive(Debug, Clone, Serialize)]
+pub struct TakeoverOutcome {
+    pub takeover_id: String,
+    pub download_id: String,
+    pub url: String,
+    /// "queued", "downloading", "paused", "completed", "failed" or "cancelled"
+    pub status: String,
+    pub error: Option<String>,
+    pub file_path: Option<String>,
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<TakeoverSettings> = RwLock::new(TakeoverSettings::default());
+    static ref OUTCOMES: Mutex<VecDeque<TakeoverOutcome>> = Mutex::new(VecDeque::new());
+}
+
+/// Restore the takeover settings at startup
+pub fn load_from_settings(db: &Database) {
+    let settings = db
+        .get_setting(TAKEOVER_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    *SETTINGS.write().unwrap() = settings;
+}
+
+pub fn settings() -> TakeoverSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+/// Why a download offered by the extension stays in the browser, if it does. Unknown sizes
+/// are taken; the extension only asks when it couldn't tell or the file looked big enough.
+fn decline_reason(settings: &TakeoverSettings, file_size: Option<u64>) -> Option<String> {
+    if !settings.enabled {
+        return Some("Takeover is turned off in the app".to_string());
+    }
+    match file_size {
+        Some(size) if size < settings.min_size_bytes => {
+            Some(format!("Smaller than the {} byte takeover threshold", settings.min_size_bytes))
+        }
+        _ => None,
+    }
+}
+
+fn update_outcome(download_id: &str, update: impl FnOnce(&mut TakeoverOutcome)) {
+    let mut outcomes = OUTCOMES.lock().unwrap();
+    if let Some(outcome) = outcomes.iter_mut().find(|o| o.download_id == download_id) {
+        update(outcome);
+    }
+}
+
+/// Follow a progress event of a taken-over download
+fn record(update: &ProgressUpdate) {
+    update_outcome(&update.id, |outcome| {
+        outcome.status = update.status.clone();
+        if let Some(error) = &update.error {
+            outcome.error = Some(error.clone());
+        }
+        if let Some(path) = &update.file_path {
+            outcome.file_path = Some(path.clone());
+        }
+    });
+}
+
+/// Keep takeover outcomes in step with their downloads; call once at startup
+pub fn install(app_handle: &AppHandle) {
+    crate::download_lifecycle::on_progress(app_handle, record);
+}
+
+/// Take over a browser download described by the extension's request body (`url`,
+/// `filename`, `fileSize`, `referrer`, `cookies`, `headers`). Replies right away; the
+/// download starts in the background.
+pub fn accept(app_handle: &AppHandle, body: &serde_json::Value) -> serde_json::Value {
+    let Some(url) = body.get("url").and_then(|v| v.as_str()).filter(|u| !u.is_empty()) else {
+        return serde_json::json!({ "accepted": false, "reason": "No URL provided" });
+    };
+    let file_size = body.get("fileSize").and_then(|v| v.as_u64()).filter(|s| *s > 0);
+    if let Some(reason) = decline_reason(&settings(), file_size) {
+        return serde_json::json!({ "accepted": false, "reason": reason });
+    }
+
+    let headers = crate::request_headers::from_extension_body(body);
+    if let Err(e) = crate::request_headers::to_header_map(&headers) {
+        return serde_json::json!({ "accepted": false, "reason": e });
+    }
+
+    let takeover_id = uuid::Uuid::new_v4().to_string();
+    let download_id = uuid::Uuid::new_v4().to_string();
+    let title = body
+        .get("filename")
+        .and_then(|v| v.as_str())
+        .filter(|f| !f.is_empty())
+        .unwrap_or(url)
+        .to_string();
+    {
+        let mut outcomes = OUTCOMES.lock().unwrap();
+        if outcomes.len() >= MAX_TRACKED {
+            outcomes.pop_front();
+        }
+        outcomes.push_back(TakeoverOutcome {
+            takeover_id: takeover_id.clone(),
+            download_id: download_id.clone(),
+            url: url.to_string(),
+            status: "queued".to_string(),
+            error: None,
+            file_path: None,
+        });
+    }
+    println!("[Takeover] Taking over {} ({:?} bytes)", url, file_size);
+
+    let app = app_handle.clone();
+    let url = url.to_string();
+    let id = download_id.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = start(&app, &url, &id, &title, file_size, headers).await {
+            println!("[Takeover] Failed to start {}: {}", url, e);
+            update_outcome(&id, |outcome| {
+                outcome.status = "failed".to_string();
+                outcome.error = Some(e);
+            });
+        }
+    });
+
+    serde_json::json!({
+        "accepted": true,
+        "takeover_id": takeover_id,
+        "download_id": download_id,
+    })
+}
+
+async fn start(
+    app: &AppHandle,
+    url: &str,
+    download_id: &str,
+    title: &str,
+    file_size: Option<u64>,
+    headers: HashMap<String, String>,
+) -> Result<(), String> {
+    let output_path = crate::downloader::get_default_download_path(app.clone()).await?;
+    let mut request = DownloadRequest::with_defaults(url, &output_path)?;
+    request.id = download_id.to_string();
+    request.request_headers = headers;
+    request.estimated_size = file_size;
+
+    let history_entry = crate::database::Download {
+        id: request.id.clone(),
+        title: title.to_string(),
+        url: request.url.clone(),
+        format: String::new(),
+        path: request.output_path.clone(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        status: "downloading".to_string(),
+        size_bytes: file_size.map(|s| s as i64),
+        platform: None,
+        thumbnail: None,
+        on_complete: None,
+    };
+    let _ = app.emit("browser-takeover", serde_json::json!({
+        "download_id": request.id,
+        "url": request.url,
+        "title": title,
+    }));
+
+    crate::downloader::run_download(app, request, Some(&history_entry)).await.map(|_| ())
+}
+
+/// Outcome of a takeover, for the extension's `/takeover/<id>` poll
+pub fn outcome(takeover_id: &str) -> Option<TakeoverOutcome> {
+    OUTCOMES.lock().unwrap().iter().find(|o| o.takeover_id == takeover_id).cloned()
+}
+
+#[tauri::command]
+pub async fn get_takeover_settings() -> Result<TakeoverSettings, String> {
+    Ok(settings())
+}
+
+#[tauri::command]
+pub async fn set_takeover_settings(state: State<'_, AppState>, settings: TakeoverSettings) -> Result<(), String> {
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize takeover settings: {}", e))?;
+    {
+        let db = state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(TAKEOVER_SETTING, &json).map_err(|e| e.to_string())?;
+    }
+    *SETTINGS.write().unwrap() = settings;
+    Ok(())
+}
+
+/// Recent takeovers and how they went
+#[tauri::command]
+pub async fn get_takeover_outcomes() -> Result<Vec<TakeoverOutcome>, String> {
+    Ok(OUTCOMES.lock().unwrap().iter().rev().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decline_reason() {
+        let settings = TakeoverSettings { enabled: true, min_size_bytes: 1000 };
+        assert!(decline_reason(&settings, Some(999)).is_some());
+        assert!(decline_reason(&settings, Some(1000)).is_none());
+        assert!(decline_reason(&settings, None).is_none());
+        assert!(decline_reason(&TakeoverSettings::default(), Some(u64::MAX)).is_some());
+    }
+
+    fn progress(payload: serde_json::Value) -> ProgressUpdate {
+        ProgressUpdate::from_download_progress(payload).unwrap()
+    }
+
+    #[test]
+    fn test_outcome_follows_progress() {
+        OUTCOMES.lock().unwrap().push_back(TakeoverOutcome {
+            takeover_id: "to-1".to_string(),
+            download_id: "dl-to-1".to_string(),
+            url: "https://example.com/big.iso".to_string(),
+            status: "queued".to_string(),
+            error: None,
+            file_path: None,
+        });
+
+        record(&progress(serde_json::json!({ "id": "dl-to-1", "status": "downloading" })));
+        assert_eq!(outcome("to-1").unwrap().status, "downloading");
+        record(&progress(serde_json::json!({ "id": "dl-to-1", "status": "completed", "file_path": "/d/big.iso" })));
+        let done = outcome("to-1").unwrap();
+        assert_eq!(done.status, "completed");
+        assert_eq!(done.file_path.as_deref(), Some("/d/big.iso"));
+        assert!(outcome("missing").is_none());
+    }
+}
@@ -0,0 +1,313 @@
+//! Test Support
+//!
+//! A small local HTTP server for integration tests of the download engines, so engine
+//! refactors can be checked against real sockets without reaching the network, and a
+//! throwaway database for the storage tests.
+//!
+//! Key Features:
+//! - Serves an in-memory file with HEAD, `Range` (206) and `Accept-Ranges` support
+//! - Can refuse HEAD or leave out Content-Length, like many real hosts
+//! - Throttled bodies, 429 responses and connections that drop or hang mid-body
+//! - Counts requests so tests can assert on retries
+//! - `TestDb`: a `Database` in its own temp directory, removed when dropped
+
+use crate::database::Database;
+use crate::downloader::DownloadRequest;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Body bytes written per socket write (and per throttle step)
+const WRITE_SIZE: usize = 16 * 1024;
+
+/// How the server misbehaves; the default serves the file with range support
+#[derive(Debug, Clone)]
+pub struct MockBehavior {
+    pub supports_range: bool,
+    /// Answer HEAD with 405 Method Not Allowed
+    pub reject_head: bool,
+    /// Leave out Content-Length and end the body by closing the connection
+    pub omit_content_length: bool,
+    /// Answer the first N GET requests with 429 Too Many Requests
+    pub rate_limit_first: usize,
+    /// Close the connection after this many body bytes...
+    pub drop_after_bytes: Option<usize>,
+    /// ...for the first N GET requests
+    pub drop_first: usize,
+    /// Stop sending, without closing the connection, after this many body bytes
+    pub hang_after_bytes: Option<usize>,
+    /// Pause between body writes
+    pub throttle: Option<Duration>,
+}
+
+impl Default for MockBehavior {
+    fn default() -> Self {
+        Self {
+            supports_range: true,
+            reject_head: false,
+            omit_content_length: false,
+            rate_limit_first: 0,
+            drop_after_bytes: None,
+            drop_first: 0,
+            hang_after_bytes: None,
+            throttle: None,
+        }
+    }
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    get_requests: Arc<AtomicUsize>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Serve `body` at every path until the server is dropped
+    pub async fn start(body: Vec<u8>, behavior: MockBehavior) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+        let addr = listener.local_addr().unwrap();
+        let get_requests = Arc::new(AtomicUsize::new(0));
+        let body = Arc::new(body);
+
+        let counter = get_requests.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let body = body.clone();
+                let behavior = behavior.clone();
+                let counter = counter.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(socket, &body, &behavior, &counter).await;
+                });
+            }
+        });
+
+        Self { addr, get_requests, task }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// GET requests received so far (HEAD requests aren't counted)
+    pub fn get_requests(&self) -> usize {
+        self.get_requests.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Deterministic, non-repeating-per-chunk test content
+pub fn test_body(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+/// Parse "bytes=start-end" (end optional) against the body length
+fn parse_range(value: &str, len: usize) -> Option<(usize, usize)> {
+    let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let start: usize = start.parse().ok()?;
+    let end = match end {
+        "" => len.checked_sub(1)?,
+        end => end.parse::<usize>().ok()?.min(len.checked_sub(1)?),
+    };
+    (start <= end).then_some((start, end))
+}
+
+async fn handle_connection(
+    socket: TcpStream,
+    body: &[u8],
+    behavior: &MockBehavior,
+    get_requests: &AtomicUsize,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(socket);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let method = request_line.split_whitespace().next().unwrap_or("").to_string();
+
+    let mut range = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("range") {
+                range = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut socket = reader.into_inner();
+    let is_head = method == "HEAD";
+    let request_index = if is_head { 0 } else { get_requests.fetch_add(1, Ordering::SeqCst) };
+
+    if !is_head && request_index < behavior.rate_limit_first {
+        socket
+            .write_all(b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .await?;
+        return socket.shutdown().await;
+    }
+
+    if is_head && behavior.reject_head {
+        socket
+            .write_all(b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .await?;
+        return socket.shutdown().await;
+    }
+
+    let accept_ranges = if behavior.supports_range { "Accept-Ranges: bytes\r\n" } else { "" };
+    let partial = range
+        .filter(|_| behavior.supports_range)
+        .and_then(|r| parse_range(&r, body.len()));
+    let (status, content, content_range) = match partial {
+        Some((start, end)) => (
+            "206 Partial Content",
+            &body[start..=end],
+            format!("Content-Range: bytes {}-{}/{}\r\n", start, end, body.len()),
+        ),
+        None => ("200 OK", body, String::new()),
+    };
+
+    let content_length = if behavior.omit_content_length {
+        String::new()
+    } else {
+        format!("Content-Length: {}\r\n", content.len())
+    };
+    let head = format!(
+        "HTTP/1.1 {}\r\n{}Content-Type: application/octet-stream\r\n{}{}Connection: close\r\n\r\n",
+        status,
+        content_length,
+        accept_ranges,
+        content_range
+    );
+    socket.write_all(head.as_bytes()).await?;
+    if is_head {
+        return socket.shutdown().await;
+    }
+
+    let drop_after = behavior.drop_after_bytes.filter(|_| request_index < behavior.drop_first);
+    let mut written = 0;
+    for piece in content.chunks(WRITE_SIZE) {
+        if drop_after.is_some_and(|limit| written >= limit) {
+            // Close mid-body; the client sees a truncated response
+            return Ok(());
+        }
+        if behavior.hang_after_bytes.is_some_and(|limit| written >= limit) {
+            std::future::pending::<()>().await;
+        }
+        socket.write_all(piece).await?;
+        written += piece.len();
+        if let Some(delay) = behavior.throttle {
+            tokio::time::sleep(delay).await;
+        }
+    }
+    socket.shutdown().await
+}
+
+/// A download request for `url` with default options
+pub fn download_request(id: &str, url: &str, audio_only: bool) -> DownloadRequest {
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "url": url,
+        "output_path": "/downloads",
+        "format": null,
+        "audio_only": audio_only,
+        "quality": "best",
+        "embed_thumbnail": false,
+        "embed_metadata": false,
+        "download_subtitles": false,
+        "audio_quality": "0",
+        "audio_format": "mp3",
+        "video_format": "mp4",
+        "use_sponsorblock": false,
+    }))
+    .unwrap()
+}
+
+/// A new, empty directory under the system temp dir
+pub fn scratch_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ownstash-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("create scratch dir");
+    dir
+}
+
+/// A database in its own directory; dereferences to the `Database` and removes the
+/// directory when dropped, so a failing test doesn't leave it behind
+pub struct TestDb {
+    dir: PathBuf,
+    db: Option<Database>,
+}
+
+impl TestDb {
+    pub fn new() -> Self {
+        Self::open(scratch_dir())
+    }
+
+    /// Open (or create) the database in `dir`, which is removed on drop
+    pub fn open(dir: PathBuf) -> Self {
+        let db = Database::new(dir.clone()).expect("open test database");
+        Self { dir, db: Some(db) }
+    }
+
+    /// Close the database and open it again from disk
+    pub fn reopen(&mut self) {
+        self.db = None;
+        self.db = Some(Database::new(self.dir.clone()).expect("reopen test database"));
+    }
+}
+
+impl std::ops::Deref for TestDb {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        self.db.as_ref().unwrap()
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        // Close the connection before removing its files
+        self.db = None;
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-9", 100), Some((0, 9)));
+        assert_eq!(parse_range("bytes=90-", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=90-500", 100), Some((90, 99)));
+        assert_eq!(parse_range("bytes=50-10", 100), None);
+        assert_eq!(parse_range("items=0-1", 100), None);
+    }
+
+    #[tokio::test]
+    async fn test_serves_ranges_and_rate_limits() {
+        let server = MockServer::start(test_body(1000), MockBehavior {
+            rate_limit_first: 1,
+            ..Default::default()
+        })
+        .await;
+        let client = reqwest::Client::new();
+
+        let limited = client.get(server.url("/f")).send().await.unwrap();
+        assert_eq!(limited.status().as_u16(), 429);
+
+        let partial = client.get(server.url("/f")).header("Range", "bytes=10-19").send().await.unwrap();
+        assert_eq!(partial.status().as_u16(), 206);
+        assert_eq!(partial.bytes().await.unwrap().as_ref(), &test_body(1000)[10..20]);
+        assert_eq!(server.get_requests(), 2);
+    }
+}
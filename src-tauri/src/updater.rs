@@ -1,4 +1,5 @@
 // Auto-update functionality for Ownstash Downloader
+use crate::native_integration::{record_notification, NotificationEvent};
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 use tauri_plugin_updater::UpdaterExt;
@@ -19,16 +20,35 @@ pub struct UpdateProgress {
     pub status: String,
 }
 
-/// Check for available updates
+/// Check for available updates. Automatic checks honour offline mode; one the user asked
+/// for (`user_initiated`) always goes out.
 #[tauri::command]
-pub async fn check_for_updates(app: AppHandle) -> Result<UpdateInfo, String> {
+pub async fn check_for_updates(app: AppHandle, user_initiated: Option<bool>) -> Result<UpdateInfo, String> {
     let updater = app.updater().map_err(|e| format!("Failed to get updater: {}", e))?;
     
     // Get current version from Cargo.toml
     let current_version = env!("CARGO_PKG_VERSION").to_string();
-    
+
+    // Offline mode: report no update rather than reaching the release feed
+    if user_initiated != Some(true) && !crate::outbound::allowed(crate::outbound::OutboundCall::AppUpdateCheck) {
+        return Ok(UpdateInfo {
+            version: current_version.clone(),
+            current_version,
+            date: None,
+            body: None,
+            available: false,
+        });
+    }
+
     match updater.check().await {
         Ok(Some(update)) => {
+            record_notification(
+                &app,
+                NotificationEvent::Update,
+                "Update available",
+                &format!("Ownstash Downloader {} is available", update.version),
+                None,
+            );
             Ok(UpdateInfo {
                 version: update.version.clone(),
                 current_version,
@@ -68,6 +88,17 @@ pub async fn check_for_updates(app: AppHandle) -> Result<UpdateInfo, String> {
 /// Download and install update
 #[tauri::command]
 pub async fn download_and_install_update(app: AppHandle) -> Result<(), String> {
+    let result = install_update(&app).await;
+    let (title, message) = match &result {
+        Ok(version) => ("Update installed", format!("Ownstash Downloader {} will be used after a restart", version)),
+        Err(e) => ("Update failed", e.clone()),
+    };
+    record_notification(&app, NotificationEvent::Update, title, &message, None);
+    result.map(|_| ())
+}
+
+/// Download and install the pending update; returns its version
+async fn install_update(app: &AppHandle) -> Result<String, String> {
     let updater = app.updater().map_err(|e| format!("Failed to get updater: {}", e))?;
     
     let update = updater.check().await
@@ -90,7 +121,7 @@ pub async fn download_and_install_update(app: AppHandle) -> Result<(), String> {
     // Install the update
     update.install(bytes).map_err(|e| format!("Failed to install update: {}", e))?;
     
-    Ok(())
+    Ok(update.version.clone())
 }
 
 /// Get current app version
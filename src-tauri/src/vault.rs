@@ -3,7 +3,7 @@
 // Supports both single files and folder uploads (compressed to ZIP then encrypted)
 
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Aes256Gcm, Nonce,
 };
 use argon2::{
@@ -15,7 +15,8 @@ use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use crate::folder_stats::{run_blocking_scan, ScanControl};
+use tauri::{AppHandle, Emitter, Manager};
 use walkdir::WalkDir;
 use zip::{ZipArchive, ZipWriter, write::FileOptions, CompressionMethod};
 
@@ -26,6 +27,28 @@ const LEGACY_EXTENSION: &str = ".vault"; // For backward compatibility
 const NONCE_SIZE: usize = 12;
 const KEY_SIZE: usize = 32;
 
+/// Plaintext bytes per SLV2 chunk
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// SLV2 header: magic, base nonce, size field
+const SLV2_HEADER_LEN: usize = 4 + NONCE_SIZE + 8;
+/// Set in the SLV2 size field when chunks may be zstd compressed. Chunks of such files are
+/// sealed with the header and their length prefix as associated data, so neither flag can
+/// be flipped unnoticed.
+const COMPRESSED_FILE_FLAG: u64 = 1 << 63;
+/// Set in a chunk's length prefix when that chunk was compressed before encryption
+const COMPRESSED_CHUNK_FLAG: u32 = 1 << 31;
+/// Largest valid sealed chunk: a full plaintext chunk plus the AES-GCM tag
+const MAX_SEALED_CHUNK_SIZE: usize = CHUNK_SIZE + 16;
+const ZSTD_LEVEL: i32 = 3;
+/// Settings key enabling compression of newly added vault files
+const VAULT_COMPRESSION_SETTING: &str = "vault_compression_enabled";
+/// Extensions whose contents are already compressed; compressing them again only costs time
+const INCOMPRESSIBLE_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "webm", "mov", "avi", "m4v", "mp3", "m4a", "aac", "ogg", "opus", "flac",
+    "jpg", "jpeg", "png", "gif", "webp", "avif", "heic", "zip", "rar", "7z", "gz", "bz2",
+    "xz", "zst", "docx", "xlsx", "pptx", "apk", "epub",
+];
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct VaultConfig {
     pub pin_hash: String,
@@ -71,23 +94,69 @@ pub struct VaultStatus {
 // Global state for vault session
 lazy_static::lazy_static! {
     static ref VAULT_SESSION: std::sync::Mutex<Option<VaultSession>> = std::sync::Mutex::new(None);
+    /// Held for each load-modify-save of the stats index
+    static ref STATS_INDEX_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    /// Held for each load-modify-save of the trash index and purge schedule
+    static ref TRASH_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
 }
 
 struct VaultSession {
     key: [u8; KEY_SIZE],
     unlocked_at: i64,
+    /// Config the key was derived from, so a swapped config invalidates the session
+    config_path: PathBuf,
+    pin_hash: String,
+    salt: String,
+}
+
+impl VaultSession {
+    fn new(app_handle: &AppHandle, key: [u8; KEY_SIZE], config: &VaultConfig) -> Self {
+        Self {
+            key,
+            unlocked_at: chrono::Utc::now().timestamp(),
+            config_path: get_vault_config_path(app_handle),
+            pin_hash: config.pin_hash.clone(),
+            salt: config.salt.clone(),
+        }
+    }
+
+    fn matches(&self, config: &VaultConfig) -> bool {
+        self.pin_hash == config.pin_hash && self.salt == config.salt
+    }
+
+    /// Whether the config on disk is still the one this session was unlocked with
+    fn matches_config_on_disk(&self) -> bool {
+        fs::read_to_string(&self.config_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<VaultConfig>(&content).ok())
+            .map(|config| self.matches(&config))
+            .unwrap_or(false)
+    }
 }
 
 /// Helper to get the vault key without holding the MutexGuard across await points
-/// Made public for vault_download module
+/// Made public for vault_download module.
+/// Refuses (and locks the vault) if the config was replaced since the session started.
 pub fn get_vault_key() -> Result<[u8; KEY_SIZE], String> {
-    let session = VAULT_SESSION.lock().unwrap();
+    let mut session = VAULT_SESSION.lock().unwrap();
     match &*session {
-        Some(s) => Ok(s.key),
+        Some(s) if s.matches_config_on_disk() => Ok(s.key),
+        Some(_) => {
+            *session = None;
+            println!("[Vault] Vault config changed under an active session; locking");
+            Err("Vault configuration changed. Unlock the vault again.".to_string())
+        }
         None => Err("Vault is locked. Unlock it first.".to_string()),
     }
 }
 
+/// End the current session (used whenever the config is replaced or removed)
+fn invalidate_session(app_handle: &AppHandle) {
+    if let Err(e) = vault_lock(app_handle.clone()) {
+        println!("[Vault] Failed to clear capture protection while locking: {}", e);
+    }
+}
+
 fn get_vault_dir(app_handle: &AppHandle) -> PathBuf {
     let app_data_dir = app_handle
         .path()
@@ -96,6 +165,11 @@ fn get_vault_dir(app_handle: &AppHandle) -> PathBuf {
     app_data_dir.join(VAULT_DIR_NAME)
 }
 
+/// Where decrypted files are staged; the staging folder when one is set
+pub(crate) fn get_vault_temp_dir(app_handle: &AppHandle) -> PathBuf {
+    crate::staging::dir(crate::staging::VAULT).unwrap_or_else(|| get_vault_dir(app_handle).join("temp"))
+}
+
 fn get_vault_config_path(app_handle: &AppHandle) -> PathBuf {
     get_vault_dir(app_handle).join(VAULT_CONFIG_FILE)
 }
@@ -221,10 +295,10 @@ fn load_vault_index_legacy(app_handle: &AppHandle) -> Vec<VaultFile> {
 
 /// Count encrypted files in vault directory (for status without index)
 /// Supports both .slasshy (new) and .vault (legacy) extensions
-fn count_vault_files(app_handle: &AppHandle) -> (usize, u64) {
+fn count_vault_files(app_handle: &AppHandle, control: &ScanControl) -> Result<(usize, u64), String> {
     let files_dir = get_vault_files_dir(app_handle);
     if !files_dir.exists() {
-        return (0, 0);
+        return Ok((0, 0));
     }
 
     let mut count = 0;
@@ -232,6 +306,7 @@ fn count_vault_files(app_handle: &AppHandle) -> (usize, u64) {
 
     if let Ok(entries) = fs::read_dir(&files_dir) {
         for entry in entries.flatten() {
+            control.advance(1)?;
             let path = entry.path();
             // Accept both new .slasshy and legacy .vault extensions
             if path.extension().map_or(false, |ext| ext == "slasshy" || ext == "vault") {
@@ -243,7 +318,7 @@ fn count_vault_files(app_handle: &AppHandle) -> (usize, u64) {
         }
     }
 
-    (count, total_size)
+    Ok((count, total_size))
 }
 
 /// Delete local index.json if it exists (for security migration)
@@ -256,12 +331,27 @@ fn delete_local_index(app_handle: &AppHandle) {
 }
 
 fn encrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBuf) -> Result<(), String> {
+    encrypt_file_with(key, input_path, output_path, false)
+}
+
+/// Associated data for a chunk of a compressed SLV2 file
+fn chunk_aad(header: &[u8; SLV2_HEADER_LEN], chunk_len: u32) -> Vec<u8> {
+    [header.as_slice(), &chunk_len.to_le_bytes()].concat()
+}
+
+/// Encrypt to SLV2, optionally zstd compressing each chunk first.
+/// Chunks that don't shrink are stored uncompressed.
+pub(crate) fn encrypt_file_with(
+    key: &[u8; KEY_SIZE],
+    input_path: &PathBuf,
+    output_path: &PathBuf,
+    compress: bool,
+) -> Result<(), String> {
     let cipher = Aes256Gcm::new_from_slice(key)
         .map_err(|e| format!("Failed to create cipher: {}", e))?;
 
     // Use chunked encryption for large files
     // Each chunk gets its own nonce derived from base nonce + chunk index
-    const CHUNK_SIZE: usize = 1024 * 1024; // 1MB chunks
     const VAULT_MAGIC: &[u8; 4] = b"SLV2"; // Magic header for new format
     
     // Generate random base nonce
@@ -278,12 +368,13 @@ fn encrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBu
         .map_err(|e| format!("Failed to create output file: {}", e))?;
     
     // Write header: [MAGIC (4 bytes)][base_nonce (12 bytes)][file_size (8 bytes)]
-    output_file.write_all(VAULT_MAGIC)
-        .map_err(|e| format!("Failed to write magic header: {}", e))?;
-    output_file.write_all(&base_nonce)
-        .map_err(|e| format!("Failed to write nonce: {}", e))?;
-    output_file.write_all(&file_size.to_le_bytes())
-        .map_err(|e| format!("Failed to write file size: {}", e))?;
+    let size_field = if compress { file_size | COMPRESSED_FILE_FLAG } else { file_size };
+    let mut header = [0u8; SLV2_HEADER_LEN];
+    header[..4].copy_from_slice(VAULT_MAGIC);
+    header[4..4 + NONCE_SIZE].copy_from_slice(&base_nonce);
+    header[4 + NONCE_SIZE..].copy_from_slice(&size_field.to_le_bytes());
+    output_file.write_all(&header)
+        .map_err(|e| format!("Failed to write header: {}", e))?;
     
     let mut buffer = vec![0u8; CHUNK_SIZE];
     let mut chunk_index: u64 = 0;
@@ -304,13 +395,33 @@ fn encrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBu
         }
         let nonce = Nonce::from_slice(&chunk_nonce);
         
+        // Compress when it actually saves space
+        let raw = &buffer[..bytes_read];
+        let compressed = if compress {
+            zstd::bulk::compress(raw, ZSTD_LEVEL)
+                .ok()
+                .filter(|c| c.len() < raw.len())
+        } else {
+            None
+        };
+        let plaintext = compressed.as_deref().unwrap_or(raw);
+
+        // Encrypted chunk size (for decryption): the plaintext plus the 16-byte tag
+        let mut chunk_len = (plaintext.len() + 16) as u32;
+        if compressed.is_some() {
+            chunk_len |= COMPRESSED_CHUNK_FLAG;
+        }
+
         // Encrypt this chunk
-        let plaintext = &buffer[..bytes_read];
-        let ciphertext = cipher.encrypt(nonce, plaintext)
-            .map_err(|e| format!("Encryption failed at chunk {}: {}", chunk_index, e))?;
+        let ciphertext = if compress {
+            let aad = chunk_aad(&header, chunk_len);
+            cipher.encrypt(nonce, Payload { msg: plaintext, aad: &aad })
+        } else {
+            cipher.encrypt(nonce, plaintext)
+        }
+        .map_err(|e| format!("Encryption failed at chunk {}: {}", chunk_index, e))?;
         
-        // Write encrypted chunk size (for decryption) and ciphertext
-        let chunk_len = ciphertext.len() as u32;
+        // Write encrypted chunk size and ciphertext
         output_file.write_all(&chunk_len.to_le_bytes())
             .map_err(|e| format!("Failed to write chunk size: {}", e))?;
         output_file.write_all(&ciphertext)
@@ -322,7 +433,37 @@ fn encrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBu
     Ok(())
 }
 
-fn decrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBuf) -> Result<(), String> {
+/// Whether an SLV2 file was written with compression enabled
+fn is_compressed_vault_file(path: &PathBuf) -> bool {
+    let mut header = [0u8; SLV2_HEADER_LEN];
+    let Ok(mut file) = File::open(path) else {
+        return false;
+    };
+    if file.read_exact(&mut header).is_err() || &header[..4] != b"SLV2" {
+        return false;
+    }
+    let size_field = u64::from_le_bytes(header[4 + NONCE_SIZE..].try_into().unwrap());
+    size_field & COMPRESSED_FILE_FLAG != 0
+}
+
+/// Compress a new vault file when the user enabled compression and the type isn't already compressed
+fn should_compress(app_handle: &AppHandle, file_name: &str) -> bool {
+    let app_state = app_handle.state::<crate::commands::AppState>();
+    let enabled = match app_state.db.lock() {
+        Ok(db) => db.get_setting(VAULT_COMPRESSION_SETTING).ok().flatten(),
+        Err(_) => None,
+    };
+    if enabled.as_deref() != Some("true") {
+        return false;
+    }
+    let extension = std::path::Path::new(file_name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    !INCOMPRESSIBLE_EXTENSIONS.contains(&extension.as_str())
+}
+
+pub(crate) fn decrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBuf) -> Result<(), String> {
     const VAULT_MAGIC: &[u8; 4] = b"SLV2";
     
     let cipher = Aes256Gcm::new_from_slice(key)
@@ -346,7 +487,13 @@ fn decrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBu
         let mut file_size_bytes = [0u8; 8];
         input_file.read_exact(&mut file_size_bytes)
             .map_err(|e| format!("Failed to read file size: {}", e))?;
-        let expected_size = u64::from_le_bytes(file_size_bytes);
+        let size_field = u64::from_le_bytes(file_size_bytes);
+        let is_compressed = size_field & COMPRESSED_FILE_FLAG != 0;
+        let expected_size = size_field & !COMPRESSED_FILE_FLAG;
+        let mut header = [0u8; SLV2_HEADER_LEN];
+        header[..4].copy_from_slice(VAULT_MAGIC);
+        header[4..4 + NONCE_SIZE].copy_from_slice(&base_nonce);
+        header[4 + NONCE_SIZE..].copy_from_slice(&file_size_bytes);
         
         let mut output_file = File::create(output_path)
             .map_err(|e| format!("Failed to create output file: {}", e))?;
@@ -366,11 +513,24 @@ fn decrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBu
                     return Err(format!("Failed to read chunk size: {}", e));
                 }
             }
-            let chunk_size = u32::from_le_bytes(chunk_size_bytes) as usize;
+            let chunk_len = u32::from_le_bytes(chunk_size_bytes);
+            let chunk_compressed = is_compressed && chunk_len & COMPRESSED_CHUNK_FLAG != 0;
+            let chunk_size = if is_compressed {
+                (chunk_len & !COMPRESSED_CHUNK_FLAG) as usize
+            } else {
+                chunk_len as usize
+            };
             
             if chunk_size == 0 {
                 break;
             }
+            // A corrupted length must not turn into a multi-GB allocation
+            if chunk_size > MAX_SEALED_CHUNK_SIZE {
+                return Err(format!(
+                    "Corrupted vault file: chunk {} claims {} bytes",
+                    chunk_index, chunk_size
+                ));
+            }
             
             // Read encrypted chunk
             let mut ciphertext = vec![0u8; chunk_size];
@@ -386,8 +546,19 @@ fn decrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBu
             let nonce = Nonce::from_slice(&chunk_nonce);
             
             // Decrypt this chunk
-            let plaintext = cipher.decrypt(nonce, ciphertext.as_ref())
-                .map_err(|_| format!("Decryption failed at chunk {} - invalid PIN or corrupted file", chunk_index))?;
+            let plaintext = if is_compressed {
+                let aad = chunk_aad(&header, chunk_len);
+                cipher.decrypt(nonce, Payload { msg: &ciphertext, aad: &aad })
+            } else {
+                cipher.decrypt(nonce, ciphertext.as_ref())
+            }
+            .map_err(|_| format!("Decryption failed at chunk {} - invalid PIN or corrupted file", chunk_index))?;
+            let plaintext = if chunk_compressed {
+                zstd::bulk::decompress(&plaintext, CHUNK_SIZE)
+                    .map_err(|e| format!("Failed to decompress chunk {}: {}", chunk_index, e))?
+            } else {
+                plaintext
+            };
             
             output_file.write_all(&plaintext)
                 .map_err(|e| format!("Failed to write decrypted chunk: {}", e))?;
@@ -436,9 +607,15 @@ fn decrypt_file(key: &[u8; KEY_SIZE], input_path: &PathBuf, output_path: &PathBu
 /// Check if vault is set up
 /// NOTE: file_count and total_size_bytes are now estimated from .vault files on disk
 /// The actual file metadata is stored in encrypted Google Drive index
+/// Counting runs on the blocking pool; pass a `scan_id` to allow `cancel_scan`.
 #[tauri::command]
-pub fn vault_get_status(app_handle: AppHandle) -> VaultStatus {
-    let config = load_vault_config(&app_handle);
+pub async fn vault_get_status(app_handle: AppHandle, scan_id: Option<String>) -> Result<VaultStatus, String> {
+    let control = ScanControl::new(&app_handle, scan_id);
+    run_blocking_scan(control, move |control| vault_status(&app_handle, control)).await
+}
+
+fn vault_status(app_handle: &AppHandle, control: &ScanControl) -> Result<VaultStatus, String> {
+    let config = load_vault_config(app_handle);
     let is_setup = config.is_some();
     
     let session = VAULT_SESSION.lock().unwrap();
@@ -447,22 +624,22 @@ pub fn vault_get_status(app_handle: AppHandle) -> VaultStatus {
 
     // Count files from disk (encrypted size, not original)
     let (file_count, total_size_bytes) = if is_setup {
-        count_vault_files(&app_handle)
+        count_vault_files(app_handle, control)?
     } else {
         (0, 0)
     };
 
     // Delete any legacy local index for security
     if is_setup {
-        delete_local_index(&app_handle);
+        delete_local_index(app_handle);
     }
 
-    VaultStatus {
+    Ok(VaultStatus {
         is_setup,
         is_unlocked,
         file_count,
         total_size_bytes,
-    }
+    })
 }
 
 /// Set up the vault with a new PIN
@@ -508,10 +685,7 @@ pub fn vault_setup(app_handle: AppHandle, pin: String) -> Result<(), String> {
     let key = derive_key_from_pin(&pin, salt_bytes);
     
     let mut session = VAULT_SESSION.lock().unwrap();
-    *session = Some(VaultSession {
-        key,
-        unlocked_at: chrono::Utc::now().timestamp(),
-    });
+    *session = Some(VaultSession::new(&app_handle, key, &config));
 
     Ok(())
 }
@@ -535,10 +709,8 @@ pub fn vault_unlock(app_handle: AppHandle, pin: String) -> Result<(), String> {
 
     // Store session
     let mut session = VAULT_SESSION.lock().unwrap();
-    *session = Some(VaultSession {
-        key,
-        unlocked_at: chrono::Utc::now().timestamp(),
-    });
+    *session = Some(VaultSession::new(&app_handle, key, &config));
+    drop(session);
 
     // Update last accessed
     let mut updated_config = config.clone();
@@ -550,9 +722,15 @@ pub fn vault_unlock(app_handle: AppHandle, pin: String) -> Result<(), String> {
 
 /// Lock the vault
 #[tauri::command]
-pub fn vault_lock() -> Result<(), String> {
+pub fn vault_lock(app_handle: AppHandle) -> Result<(), String> {
     let mut session = VAULT_SESSION.lock().unwrap();
     *session = None;
+    drop(session);
+
+    // Nothing from the vault is on screen once it's locked
+    if CAPTURE_PROTECTION_ACTIVE.load(std::sync::atomic::Ordering::SeqCst) {
+        set_windows_content_protected(&app_handle, false)?;
+    }
     Ok(())
 }
 
@@ -587,8 +765,9 @@ pub async fn vault_add_file(
     let source_clone = source.clone();
     let dest_clone = dest_path.clone();
     let key_copy = key; // Copy the key for the closure
+    let compress = should_compress(&app_handle, &original_name);
     tokio::task::spawn_blocking(move || {
-        encrypt_file(&key_copy, &source_clone, &dest_clone)
+        encrypt_file_with(&key_copy, &source_clone, &dest_clone, compress)
     })
     .await
     .map_err(|e| format!("Encryption task failed: {}", e))?
@@ -610,6 +789,7 @@ pub async fn vault_add_file(
     // NOTE: We no longer save to local index.json
     // The frontend will add this file to the encrypted Google Drive index
     println!("[Vault] File encrypted successfully: {}", vault_file.id);
+    record_stats(&app_handle, &key, &[VaultStatsEntry::from(&vault_file)]);
 
     // Optionally delete original
     if delete_original {
@@ -687,7 +867,7 @@ pub async fn vault_get_temp_playback_path(
         .map_err(|_| format!("Encrypted file not found: {}", file_id))?;
     
     // Create temp directory inside vault (more secure than system temp)
-    let temp_dir = get_vault_dir(&app_handle).join("temp");
+    let temp_dir = get_vault_temp_dir(&app_handle);
     fs::create_dir_all(&temp_dir)
         .map_err(|e| format!("Failed to create temp directory: {}", e))?;
     
@@ -716,7 +896,7 @@ pub async fn vault_get_temp_playback_path(
 /// Clean up temporary files
 #[tauri::command]
 pub fn vault_cleanup_temp(app_handle: AppHandle) -> Result<(), String> {
-    let temp_dir = get_vault_dir(&app_handle).join("temp");
+    let temp_dir = get_vault_temp_dir(&app_handle);
     if temp_dir.exists() {
         fs::remove_dir_all(&temp_dir)
             .map_err(|e| format!("Failed to cleanup temp: {}", e))?;
@@ -727,13 +907,17 @@ pub fn vault_cleanup_temp(app_handle: AppHandle) -> Result<(), String> {
 /// Delete a file from the vault by its encrypted filename
 /// NOTE: The frontend now passes the encrypted_name directly since it manages the index
 /// Supports both .slasshy (new) and .vault (legacy) extensions
+/// Files go to the vault trash unless `permanent` is set; the optional name/type are
+/// kept in the encrypted trash index so a restore can re-add the entry to the cloud index
 #[tauri::command]
-pub fn vault_delete_file(app_handle: AppHandle, file_id: String) -> Result<(), String> {
-    let session = VAULT_SESSION.lock().unwrap();
-    if session.is_none() {
-        return Err("Vault is locked. Unlock it first.".to_string());
-    }
-    drop(session);
+pub fn vault_delete_file(
+    app_handle: AppHandle,
+    file_id: String,
+    original_name: Option<String>,
+    file_type: Option<String>,
+    permanent: Option<bool>,
+) -> Result<(), String> {
+    let key = get_vault_key()?;
 
     let files_dir = get_vault_files_dir(&app_handle);
     
@@ -744,17 +928,22 @@ pub fn vault_delete_file(app_handle: AppHandle, file_id: String) -> Result<(), S
     let new_path = files_dir.join(&new_name);
     let legacy_path = files_dir.join(&legacy_name);
 
-    // Delete whichever exists
-    if new_path.exists() {
-        fs::remove_file(&new_path)
-            .map_err(|e| format!("Failed to delete file: {}", e))?;
-        println!("[Vault] Deleted encrypted file: {}", new_name);
+    let (path, name) = if new_path.exists() {
+        (new_path, new_name)
     } else if legacy_path.exists() {
-        fs::remove_file(&legacy_path)
-            .map_err(|e| format!("Failed to delete file: {}", e))?;
-        println!("[Vault] Deleted legacy encrypted file: {}", legacy_name);
+        (legacy_path, legacy_name)
     } else {
         println!("[Vault] Warning: Encrypted file not found: {} or {}", new_name, legacy_name);
+        return Ok(());
+    };
+
+    if permanent.unwrap_or(false) {
+        fs::remove_file(&path)
+            .map_err(|e| format!("Failed to delete file: {}", e))?;
+        println!("[Vault] Permanently deleted encrypted file: {}", name);
+    } else {
+        move_to_trash(&app_handle, &key, &file_id, &path, &name, original_name, file_type)?;
+        println!("[Vault] Moved encrypted file to trash: {}", name);
     }
 
     // NOTE: We no longer update local index - frontend manages via Google Drive
@@ -825,10 +1014,11 @@ pub fn vault_change_pin(
         let temp_reencrypted = temp_dir.join(format!("{}_new", file_id));
 
         // Decrypt with old key
+        let compressed = is_compressed_vault_file(&encrypted_path);
         decrypt_file(&current_key, &encrypted_path, &temp_decrypted)?;
         
         // Re-encrypt with new key
-        encrypt_file(&new_key, &temp_decrypted, &temp_reencrypted)?;
+        encrypt_file_with(&new_key, &temp_decrypted, &temp_reencrypted, compressed)?;
 
         // Replace original encrypted file
         fs::rename(&temp_reencrypted, &encrypted_path)
@@ -838,6 +1028,33 @@ pub fn vault_change_pin(
         let _ = fs::remove_file(&temp_decrypted);
     }
 
+    // Trashed files must stay restorable under the new PIN
+    {
+        let _trash = TRASH_LOCK.lock().unwrap();
+        let trash_entries = load_trash_index(&app_handle, &current_key)?;
+        if !trash_entries.is_empty() {
+            let trash_dir = get_vault_trash_dir(&app_handle);
+            for entry in &trash_entries {
+                let encrypted_path = trash_dir.join(sanitize_encrypted_name(&entry.encrypted_name)?);
+                let temp_decrypted = temp_dir.join(&entry.id);
+                let temp_reencrypted = temp_dir.join(format!("{}_new", entry.id));
+
+                let compressed = is_compressed_vault_file(&encrypted_path);
+                decrypt_file(&current_key, &encrypted_path, &temp_decrypted)?;
+                encrypt_file_with(&new_key, &temp_decrypted, &temp_reencrypted, compressed)?;
+                fs::rename(&temp_reencrypted, &encrypted_path)
+                    .map_err(|e| format!("Failed to replace trashed file: {}", e))?;
+                let _ = fs::remove_file(&temp_decrypted);
+            }
+            save_trash_index(&app_handle, &new_key, &trash_entries)?;
+        }
+    }
+    {
+        let _stats = STATS_INDEX_LOCK.lock().unwrap();
+        let stats_entries = load_stats_index(&app_handle, &current_key)?;
+        save_stats_index(&app_handle, &new_key, &stats_entries)?;
+    }
+
     // Clean up temp directory
     let _ = fs::remove_dir_all(&temp_dir);
 
@@ -852,10 +1069,7 @@ pub fn vault_change_pin(
 
     // Update session with new key
     let mut session = VAULT_SESSION.lock().unwrap();
-    *session = Some(VaultSession {
-        key: new_key,
-        unlocked_at: chrono::Utc::now().timestamp(),
-    });
+    *session = Some(VaultSession::new(&app_handle, new_key, &new_config));
 
     Ok(())
 }
@@ -875,9 +1089,7 @@ pub fn vault_reset(app_handle: AppHandle, pin: String) -> Result<(), String> {
         .map_err(|_| "Invalid PIN".to_string())?;
 
     // Lock vault
-    let mut session = VAULT_SESSION.lock().unwrap();
-    *session = None;
-    drop(session);
+    invalidate_session(&app_handle);
 
     // Delete entire vault directory
     let vault_dir = get_vault_dir(&app_handle);
@@ -895,9 +1107,21 @@ pub fn vault_get_config(app_handle: AppHandle) -> Result<VaultConfig, String> {
     load_vault_config(&app_handle).ok_or("Vault is not set up".to_string())
 }
 
-/// Import a vault configuration from the cloud
+/// Import a vault configuration from the cloud.
+/// A session unlocked with a different config is locked, since its key no longer matches.
 #[tauri::command]
 pub fn vault_import_config(app_handle: AppHandle, config: VaultConfig) -> Result<(), String> {
+    let stale = VAULT_SESSION
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|session| !session.matches(&config))
+        .unwrap_or(false);
+    if stale {
+        println!("[Vault] Imported config differs from the unlocked one; locking vault");
+        invalidate_session(&app_handle);
+    }
+
     save_vault_config(&app_handle, &config)
 }
 
@@ -908,8 +1132,7 @@ pub fn vault_wipe_local_config(app_handle: AppHandle) -> Result<(), String> {
     if config_path.exists() {
         std::fs::remove_file(config_path).map_err(|e| e.to_string())?;
     }
-    let mut session = VAULT_SESSION.lock().unwrap();
-    *session = None;
+    invalidate_session(&app_handle);
     Ok(())
 }
 
@@ -1045,35 +1268,17 @@ fn detect_file_type(extension: &str) -> String {
     }
 }
 
-/// Add a folder to the vault (compresses to ZIP, then encrypts)
-/// Creates a single encrypted .slasshy file containing the entire folder
-#[tauri::command]
-pub async fn vault_add_folder(
-    app_handle: AppHandle,
-    folder_path: String,
-    folder_name: String,
-    delete_original: bool,
-) -> Result<VaultFile, String> {
-    println!("[Vault] Adding folder: {} from path: {}", folder_name, folder_path);
-    
-    // Get the encryption key
-    let key = get_vault_key()?;
-    
-    let source_dir = PathBuf::from(&folder_path);
-    if !source_dir.exists() || !source_dir.is_dir() {
-        return Err("Source folder does not exist or is not a directory".to_string());
-    }
-    
-    // Collect folder entries and calculate total size
+/// Every file and directory under `source_dir` (relative, forward-slash paths) and their total size
+pub(crate) fn collect_folder_entries(source_dir: &PathBuf) -> (Vec<VaultFolderEntry>, u64) {
     let mut folder_entries: Vec<VaultFolderEntry> = Vec::new();
     let mut total_original_size: u64 = 0;
     
-    for entry in WalkDir::new(&source_dir)
+    for entry in WalkDir::new(source_dir)
         .into_iter()
         .filter_map(|e| e.ok())
     {
         let path = entry.path();
-        let relative_path = path.strip_prefix(&source_dir)
+        let relative_path = path.strip_prefix(source_dir)
             .unwrap_or(path)
             .to_string_lossy()
             .to_string();
@@ -1109,6 +1314,74 @@ pub async fn vault_add_folder(
             is_directory: is_dir,
         });
     }
+
+    (folder_entries, total_original_size)
+}
+
+/// Deflate the folder entries into a ZIP at `zip_path`, returning the ZIP size
+pub(crate) fn write_folder_zip(
+    source_dir: &PathBuf,
+    entries: &[VaultFolderEntry],
+    zip_path: &PathBuf,
+) -> Result<u64, String> {
+    let zip_file = File::create(zip_path)
+        .map_err(|e| format!("Failed to create ZIP file: {}", e))?;
+    let mut zip_writer = ZipWriter::new(zip_file);
+    
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(0o755);
+    
+    for entry in entries {
+        let full_path = source_dir.join(&entry.path);
+        
+        if entry.is_directory {
+            // Add directory entry
+            zip_writer.add_directory(&entry.path, options.clone())
+                .map_err(|e| format!("Failed to add directory to ZIP: {}", e))?;
+        } else {
+            // Add file entry
+            zip_writer.start_file(&entry.path, options.clone())
+                .map_err(|e| format!("Failed to start file in ZIP: {}", e))?;
+            
+            let mut file = File::open(&full_path)
+                .map_err(|e| format!("Failed to open file for ZIP: {}", e))?;
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer)
+                .map_err(|e| format!("Failed to read file for ZIP: {}", e))?;
+            zip_writer.write_all(&buffer)
+                .map_err(|e| format!("Failed to write to ZIP: {}", e))?;
+        }
+    }
+    
+    zip_writer.finish()
+        .map_err(|e| format!("Failed to finalize ZIP: {}", e))?;
+    
+    // Get ZIP file size
+    Ok(fs::metadata(zip_path).map(|m| m.len()).unwrap_or(0))
+}
+
+/// Add a folder to the vault (compresses to ZIP, then encrypts)
+/// Creates a single encrypted .slasshy file containing the entire folder
+#[tauri::command]
+pub async fn vault_add_folder(
+    app_handle: AppHandle,
+    folder_path: String,
+    folder_name: String,
+    delete_original: bool,
+) -> Result<VaultFile, String> {
+    println!("[Vault] Adding folder: {} from path: {}", folder_name, folder_path);
+    
+    // Get the encryption key
+    let key = get_vault_key()?;
+    
+    let source_dir = PathBuf::from(&folder_path);
+    if !source_dir.exists() || !source_dir.is_dir() {
+        return Err("Source folder does not exist or is not a directory".to_string());
+    }
+    
+    // Collect folder entries and calculate total size
+    let (folder_entries, total_original_size) = collect_folder_entries(&source_dir);
     
     println!("[Vault] Folder has {} entries, total size: {} bytes", folder_entries.len(), total_original_size);
     
@@ -1118,7 +1391,7 @@ pub async fn vault_add_folder(
     let dest_path = get_vault_files_dir(&app_handle).join(&encrypted_name);
     
     // Create temp ZIP file path
-    let temp_dir = get_vault_dir(&app_handle).join("temp");
+    let temp_dir = get_vault_temp_dir(&app_handle);
     fs::create_dir_all(&temp_dir)
         .map_err(|e| format!("Failed to create temp directory: {}", e))?;
     let temp_zip_path = temp_dir.join(format!("{}.zip", file_id));
@@ -1130,45 +1403,7 @@ pub async fn vault_add_folder(
     
     // Compress folder to ZIP in background thread
     let zip_result = tokio::task::spawn_blocking(move || {
-        let zip_file = File::create(&temp_zip_clone)
-            .map_err(|e| format!("Failed to create ZIP file: {}", e))?;
-        let mut zip_writer = ZipWriter::new(zip_file);
-        
-        let options = FileOptions::default()
-            .compression_method(CompressionMethod::Deflated)
-            .unix_permissions(0o755);
-        
-        for entry in &entries_clone {
-            let full_path = source_clone.join(&entry.path);
-            
-            if entry.is_directory {
-                // Add directory entry
-                zip_writer.add_directory(&entry.path, options.clone())
-                    .map_err(|e| format!("Failed to add directory to ZIP: {}", e))?;
-            } else {
-                // Add file entry
-                zip_writer.start_file(&entry.path, options.clone())
-                    .map_err(|e| format!("Failed to start file in ZIP: {}", e))?;
-                
-                let mut file = File::open(&full_path)
-                    .map_err(|e| format!("Failed to open file for ZIP: {}", e))?;
-                let mut buffer = Vec::new();
-                file.read_to_end(&mut buffer)
-                    .map_err(|e| format!("Failed to read file for ZIP: {}", e))?;
-                zip_writer.write_all(&buffer)
-                    .map_err(|e| format!("Failed to write to ZIP: {}", e))?;
-            }
-        }
-        
-        zip_writer.finish()
-            .map_err(|e| format!("Failed to finalize ZIP: {}", e))?;
-        
-        // Get ZIP file size
-        let zip_size = fs::metadata(&temp_zip_clone)
-            .map(|m| m.len())
-            .unwrap_or(0);
-        
-        Ok::<u64, String>(zip_size)
+        write_folder_zip(&source_clone, &entries_clone, &temp_zip_clone)
     })
     .await
     .map_err(|e| format!("ZIP task failed: {}", e))??;
@@ -1209,6 +1444,7 @@ pub async fn vault_add_folder(
     };
     
     println!("[Vault] Folder encrypted successfully: {} (encrypted size: {} bytes)", vault_file.id, encrypted_size);
+    record_stats(&app_handle, &key, &[VaultStatsEntry::from(&vault_file)]);
     
     // Optionally delete original folder
     if delete_original {
@@ -1238,7 +1474,7 @@ pub async fn vault_extract_folder_file(
         .map_err(|_| format!("Encrypted folder not found: {}", file_id))?;
     
     // Create temp directory for extraction
-    let temp_dir = get_vault_dir(&app_handle).join("temp");
+    let temp_dir = get_vault_temp_dir(&app_handle);
     fs::create_dir_all(&temp_dir)
         .map_err(|e| format!("Failed to create temp directory: {}", e))?;
     
@@ -1491,7 +1727,7 @@ pub async fn vault_list_folder_contents(
         .map_err(|_| format!("Encrypted folder not found: {}", file_id))?;
     
     // Create temp directory
-    let temp_dir = get_vault_dir(&app_handle).join("temp");
+    let temp_dir = get_vault_temp_dir(&app_handle);
     fs::create_dir_all(&temp_dir)
         .map_err(|e| format!("Failed to create temp directory: {}", e))?;
     
@@ -1686,6 +1922,7 @@ pub async fn vault_add_zip(
         is_folder: true,
         folder_entries: Some(folder_entries),
     };
+    record_stats(&app_handle, &key, &[VaultStatsEntry::from(&vault_file)]);
     
     // Optionally delete original ZIP
     if delete_original {
@@ -1725,7 +1962,9 @@ pub async fn vault_convert_to_folder(
         .map_err(|_| format!("Encrypted file not found: {} (checked both extensions)", file_id))?;
     
     // Create temp path for decryption
-    let temp_dir = app_handle.path().temp_dir().map_err(|e| e.to_string())?;
+    let temp_dir = get_vault_temp_dir(&app_handle);
+    fs::create_dir_all(&temp_dir)
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
     let temp_file_path = temp_dir.join(format!("temp_convert_{}.archive", file_id));
     
     // Decrypt to temp
@@ -1955,3 +2194,1258 @@ pub async fn vault_convert_to_folder(
     println!("[Vault] Converted successfully. Found {} entries.", entries.len());
     Ok(entries)
 }
+
+// ============ CAPTURE PROTECTION ============
+
+/// Settings key for paranoid mode (exclude vault playback from screen capture)
+const PARANOID_MODE_SETTING: &str = "vault_paranoid_mode";
+
+static CAPTURE_PROTECTION_ACTIVE: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureProtectionStatus {
+    pub paranoid_mode: bool,
+    pub protection_active: bool,
+    /// Whether the OS can exclude windows from capture (Windows and macOS only)
+    pub supported: bool,
+}
+
+fn is_paranoid_mode_enabled(app_handle: &AppHandle) -> bool {
+    let app_state = app_handle.state::<crate::commands::AppState>();
+    let enabled = match app_state.db.lock() {
+        Ok(db) => db.get_setting(PARANOID_MODE_SETTING).ok().flatten(),
+        Err(_) => None,
+    };
+    enabled.as_deref() == Some("true")
+}
+
+fn capture_protection_supported() -> bool {
+    cfg!(any(target_os = "windows", target_os = "macos"))
+}
+
+fn capture_protection_status(app_handle: &AppHandle) -> CaptureProtectionStatus {
+    CaptureProtectionStatus {
+        paranoid_mode: is_paranoid_mode_enabled(app_handle),
+        protection_active: CAPTURE_PROTECTION_ACTIVE.load(std::sync::atomic::Ordering::SeqCst),
+        supported: capture_protection_supported(),
+    }
+}
+
+/// Toggle content protection on every app window.
+/// Tauri maps this to SetWindowDisplayAffinity(WDA_EXCLUDEFROMCAPTURE) on Windows
+/// and NSWindow.sharingType = none on macOS.
+fn set_windows_content_protected(app_handle: &AppHandle, protected: bool) -> Result<(), String> {
+    for (label, window) in app_handle.webview_windows() {
+        window
+            .set_content_protected(protected)
+            .map_err(|e| format!("Failed to update capture protection on {}: {}", label, e))?;
+    }
+
+    let active = protected && capture_protection_supported();
+    CAPTURE_PROTECTION_ACTIVE.store(active, std::sync::atomic::Ordering::SeqCst);
+    Ok(())
+}
+
+/// Called by the vault player when playback starts (`playing = true`) or stops.
+/// Protection is only applied while paranoid mode is enabled.
+#[tauri::command]
+pub fn vault_set_playback_protection(
+    app_handle: AppHandle,
+    playing: bool,
+) -> Result<CaptureProtectionStatus, String> {
+    let protect = playing && is_paranoid_mode_enabled(&app_handle);
+    set_windows_content_protected(&app_handle, protect)?;
+
+    if protect && !capture_protection_supported() {
+        println!("[Vault] Capture protection requested but not supported on this platform");
+    }
+
+    Ok(capture_protection_status(&app_handle))
+}
+
+/// Enable or disable paranoid mode. Disabling it also lifts any active protection.
+#[tauri::command]
+pub fn vault_set_paranoid_mode(
+    app_handle: AppHandle,
+    enabled: bool,
+) -> Result<CaptureProtectionStatus, String> {
+    {
+        let app_state = app_handle.state::<crate::commands::AppState>();
+        let db = app_state.db.lock().map_err(|e| e.to_string())?;
+        db.save_setting(PARANOID_MODE_SETTING, if enabled { "true" } else { "false" })
+            .map_err(|e| e.to_string())?;
+    }
+
+    if !enabled && CAPTURE_PROTECTION_ACTIVE.load(std::sync::atomic::Ordering::SeqCst) {
+        set_windows_content_protected(&app_handle, false)?;
+    }
+
+    Ok(capture_protection_status(&app_handle))
+}
+
+/// Turn zstd compression of newly added vault files on or off.
+/// Existing files are read either way.
+#[tauri::command]
+pub fn vault_set_compression(app_handle: AppHandle, enabled: bool) -> Result<(), String> {
+    let app_state = app_handle.state::<crate::commands::AppState>();
+    let db = app_state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(VAULT_COMPRESSION_SETTING, if enabled { "true" } else { "false" })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn vault_get_compression(app_handle: AppHandle) -> Result<bool, String> {
+    let app_state = app_handle.state::<crate::commands::AppState>();
+    let db = app_state.db.lock().map_err(|e| e.to_string())?;
+    let enabled = db.get_setting(VAULT_COMPRESSION_SETTING).map_err(|e| e.to_string())?;
+    Ok(enabled.as_deref() == Some("true"))
+}
+
+/// Report whether vault playback is currently hidden from screen capture
+#[tauri::command]
+pub fn vault_get_capture_protection_status(app_handle: AppHandle) -> CaptureProtectionStatus {
+    capture_protection_status(&app_handle)
+}
+
+// ============ PORTABLE EXPORT ============
+
+const PORTABLE_CONFIG_FILE: &str = "portable.json";
+const PORTABLE_FORMAT: &str = "ownstash-portable";
+const PORTABLE_VERSION: u32 = 1;
+const PORTABLE_MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Key derivation parameters written into the bundle so a standalone decryptor can reproduce the key
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PortableKdfParams {
+    pub algorithm: String,
+    pub version: u32,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    /// Base64 encoded salt
+    pub salt: String,
+}
+
+/// Describes how each data file is encrypted
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PortableCipherParams {
+    pub algorithm: String,
+    /// "SLV2": [b"SLV2"][12-byte base nonce][u64 LE size] then repeated [u32 LE len][ciphertext].
+    /// When the size has its top bit set, each chunk's associated data is the header plus its
+    /// length prefix.
+    pub container: String,
+    pub chunk_size: u32,
+    pub nonce_derivation: String,
+}
+
+/// AES-GCM sealed blob (base64 fields)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PortableSealed {
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// The decryptor config stored as portable.json at the root of the bundle
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PortableBundleConfig {
+    pub format: String,
+    pub version: u32,
+    pub created_at: i64,
+    pub kdf: PortableKdfParams,
+    pub cipher: PortableCipherParams,
+    /// Encrypted JSON list of `PortableManifestEntry`
+    pub manifest: PortableSealed,
+}
+
+/// One exported item (only ever stored encrypted inside the manifest)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PortableManifestEntry {
+    /// Path of the data file relative to the bundle root
+    pub file: String,
+    pub original_name: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PortableExportResult {
+    pub bundle_path: String,
+    pub file_count: usize,
+    pub total_size_bytes: u64,
+}
+
+fn derive_portable_key(passphrase: &str, salt: &[u8]) -> Result<([u8; KEY_SIZE], PortableKdfParams), String> {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    let params = argon2::Params::default();
+    let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params.clone());
+
+    let mut key = [0u8; KEY_SIZE];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive bundle key: {}", e))?;
+
+    let kdf = PortableKdfParams {
+        algorithm: "argon2id".to_string(),
+        version: 0x13,
+        memory_kib: params.m_cost(),
+        iterations: params.t_cost(),
+        parallelism: params.p_cost(),
+        salt: STANDARD.encode(salt),
+    };
+
+    Ok((key, kdf))
+}
+
+/// Export selected vault items into a passphrase-protected bundle that can be opened
+/// without this vault (e.g. from a USB stick). Items are re-encrypted with a key derived
+/// from the passphrase; names and sizes live only in the encrypted manifest.
+/// `original_names` maps file IDs to display names (the index is cloud-only).
+#[tauri::command]
+pub async fn vault_export_portable(
+    app_handle: AppHandle,
+    file_ids: Vec<String>,
+    passphrase: String,
+    destination_path: String,
+    original_names: Option<std::collections::HashMap<String, String>>,
+) -> Result<PortableExportResult, String> {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    if file_ids.is_empty() {
+        return Err("No files selected for export".to_string());
+    }
+    if passphrase.chars().count() < PORTABLE_MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Passphrase must be at least {} characters",
+            PORTABLE_MIN_PASSPHRASE_LEN
+        ));
+    }
+
+    let vault_key = get_vault_key()?;
+    let original_names = original_names.unwrap_or_default();
+
+    // Resolve every source first so a bad ID fails before anything is written
+    let mut sources = Vec::with_capacity(file_ids.len());
+    for file_id in &file_ids {
+        let encrypted_name = format!("{}{}", sanitize_file_name(file_id, ""), ENCRYPTED_EXTENSION);
+        let encrypted_path = resolve_encrypted_file_path(&app_handle, &encrypted_name)
+            .map_err(|_| format!("Encrypted file not found: {}", file_id))?;
+        let original_name = original_names
+            .get(file_id)
+            .map(|name| sanitize_file_name(name, file_id))
+            .unwrap_or_else(|| file_id.clone());
+        sources.push((encrypted_path, original_name));
+    }
+
+    let bundle_dir = PathBuf::from(&destination_path).join(format!(
+        "Ownstash Portable {}",
+        chrono::Local::now().format("%Y-%m-%d %H%M%S")
+    ));
+    let data_dir = bundle_dir.join("data");
+    fs::create_dir_all(&data_dir)
+        .map_err(|e| format!("Failed to create bundle directory: {}", e))?;
+
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let (bundle_key, kdf) = derive_portable_key(&passphrase, &salt)?;
+
+    let temp_dir = get_vault_temp_dir(&app_handle);
+    let data_dir_clone = data_dir.clone();
+    let export_result = tokio::task::spawn_blocking(move || {
+        fs::create_dir_all(&temp_dir)
+            .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+
+        let mut manifest = Vec::with_capacity(sources.len());
+        for (index, (encrypted_path, original_name)) in sources.iter().enumerate() {
+            // Decrypt with the vault key, then re-encrypt with the bundle key
+            let plain_path = temp_dir.join(uuid::Uuid::new_v4().to_string());
+            let data_name = format!("{}{}", index, ENCRYPTED_EXTENSION);
+            let result = decrypt_file(&vault_key, encrypted_path, &plain_path).and_then(|_| {
+                let size_bytes = fs::metadata(&plain_path)
+                    .map_err(|e| format!("Failed to read decrypted file: {}", e))?
+                    .len();
+                encrypt_file(&bundle_key, &plain_path, &data_dir_clone.join(&data_name))?;
+                Ok(size_bytes)
+            });
+            let _ = fs::remove_file(&plain_path);
+
+            manifest.push(PortableManifestEntry {
+                file: format!("data/{}", data_name),
+                original_name: original_name.clone(),
+                size_bytes: result?,
+            });
+        }
+
+        Ok::<Vec<PortableManifestEntry>, String>(manifest)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?;
+
+    let manifest = match export_result {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&bundle_dir);
+            return Err(format!("Portable export failed: {}", e));
+        }
+    };
+
+    // Seal the manifest so file names aren't visible on the stick
+    let manifest_json = serde_json::to_vec(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    let cipher = Aes256Gcm::new_from_slice(&bundle_key)
+        .map_err(|e| format!("Failed to create cipher: {}", e))?;
+    let mut manifest_nonce = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut manifest_nonce);
+    let manifest_ciphertext = cipher
+        .encrypt(Nonce::from_slice(&manifest_nonce), manifest_json.as_ref())
+        .map_err(|e| format!("Failed to encrypt manifest: {}", e))?;
+
+    let config = PortableBundleConfig {
+        format: PORTABLE_FORMAT.to_string(),
+        version: PORTABLE_VERSION,
+        created_at: chrono::Utc::now().timestamp(),
+        kdf,
+        cipher: PortableCipherParams {
+            algorithm: "aes-256-gcm".to_string(),
+            container: "SLV2".to_string(),
+            chunk_size: 1024 * 1024,
+            nonce_derivation: "base_nonce[0..8] ^= chunk_index.to_le_bytes()".to_string(),
+        },
+        manifest: PortableSealed {
+            nonce: STANDARD.encode(manifest_nonce),
+            ciphertext: STANDARD.encode(manifest_ciphertext),
+        },
+    };
+
+    let config_json = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize bundle config: {}", e))?;
+    fs::write(bundle_dir.join(PORTABLE_CONFIG_FILE), config_json)
+        .map_err(|e| format!("Failed to write bundle config: {}", e))?;
+
+    let total_size_bytes = manifest.iter().map(|entry| entry.size_bytes).sum();
+    println!("[Vault] Portable bundle created with {} files at {:?}", manifest.len(), bundle_dir);
+
+    Ok(PortableExportResult {
+        bundle_path: bundle_dir.to_string_lossy().to_string(),
+        file_count: manifest.len(),
+        total_size_bytes,
+    })
+}
+
+// ============ TRASH ============
+
+const TRASH_DIR_NAME: &str = "trash";
+/// Encrypted with the vault key: names and types of trashed items. Not `.slasshy`, so it
+/// can't be mistaken for (or overwritten by) a trashed file.
+const TRASH_INDEX_FILE: &str = "index.trash";
+/// Where older versions kept the trash index
+const LEGACY_TRASH_INDEX_FILE: &str = "index.slasshy";
+/// Plaintext purge schedule (encrypted names + timestamps only) so expired items can be
+/// purged while the vault is locked
+const TRASH_SCHEDULE_FILE: &str = "schedule.json";
+const TRASH_RETENTION_SETTING: &str = "vault_trash_retention_days";
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+const TRASH_PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VaultTrashEntry {
+    pub id: String,
+    pub encrypted_name: String,
+    pub original_name: Option<String>,
+    pub file_type: Option<String>,
+    pub size_bytes: u64,
+    pub deleted_at: i64,
+    pub purge_after: i64,
+}
+
+fn get_vault_trash_dir(app_handle: &AppHandle) -> PathBuf {
+    get_vault_dir(app_handle).join(TRASH_DIR_NAME)
+}
+
+fn trash_retention_days(app_handle: &AppHandle) -> i64 {
+    let app_state = app_handle.state::<crate::commands::AppState>();
+    let value = match app_state.db.lock() {
+        Ok(db) => db.get_setting(TRASH_RETENTION_SETTING).ok().flatten(),
+        Err(_) => None,
+    };
+    value
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
+}
+
+fn load_trash_index(app_handle: &AppHandle, key: &[u8; KEY_SIZE]) -> Result<Vec<VaultTrashEntry>, String> {
+    let trash_dir = get_vault_trash_dir(app_handle);
+    let index_path = trash_dir.join(TRASH_INDEX_FILE);
+    let entries = if index_path.exists() {
+        read_trash_index(&index_path, key)?
+    } else {
+        // A legacy index that doesn't decrypt as one is a trashed file of the same name
+        let legacy_path = trash_dir.join(LEGACY_TRASH_INDEX_FILE);
+        if !legacy_path.exists() {
+            return Ok(Vec::new());
+        }
+        read_trash_index(&legacy_path, key).unwrap_or_default()
+    };
+
+    // Drop entries whose files were purged while the vault was locked
+    Ok(entries
+        .into_iter()
+        .filter(|entry| trash_dir.join(&entry.encrypted_name).exists())
+        .collect())
+}
+
+fn read_trash_index(index_path: &PathBuf, key: &[u8; KEY_SIZE]) -> Result<Vec<VaultTrashEntry>, String> {
+    let data = fs::read(index_path)
+        .map_err(|e| format!("Failed to read trash index: {}", e))?;
+    if data.len() < NONCE_SIZE {
+        return Err("Trash index is corrupted".to_string());
+    }
+
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| format!("Failed to create cipher: {}", e))?;
+    let (nonce_bytes, ciphertext) = data.split_at(NONCE_SIZE);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| "Failed to decrypt trash index - invalid PIN or corrupted file".to_string())?;
+
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Failed to parse trash index: {}", e))
+}
+
+fn save_trash_index(
+    app_handle: &AppHandle,
+    key: &[u8; KEY_SIZE],
+    entries: &[VaultTrashEntry],
+) -> Result<(), String> {
+    let trash_dir = get_vault_trash_dir(app_handle);
+    fs::create_dir_all(&trash_dir)
+        .map_err(|e| format!("Failed to create trash directory: {}", e))?;
+
+    let plaintext = serde_json::to_vec(entries)
+        .map_err(|e| format!("Failed to serialize trash index: {}", e))?;
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| format!("Failed to create cipher: {}", e))?;
+    let mut nonce_bytes = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_ref())
+        .map_err(|e| format!("Failed to encrypt trash index: {}", e))?;
+
+    let mut data = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
+    data.extend_from_slice(&nonce_bytes);
+    data.extend_from_slice(&ciphertext);
+    fs::write(trash_dir.join(TRASH_INDEX_FILE), data)
+        .map_err(|e| format!("Failed to write trash index: {}", e))?;
+    if !entries.iter().any(|entry| entry.encrypted_name == LEGACY_TRASH_INDEX_FILE) {
+        let _ = fs::remove_file(trash_dir.join(LEGACY_TRASH_INDEX_FILE));
+    }
+
+    // Keep the plaintext schedule in sync
+    let schedule: std::collections::HashMap<String, i64> = entries
+        .iter()
+        .map(|entry| (entry.encrypted_name.clone(), entry.purge_after))
+        .collect();
+    let schedule_json = serde_json::to_string(&schedule)
+        .map_err(|e| format!("Failed to serialize trash schedule: {}", e))?;
+    fs::write(trash_dir.join(TRASH_SCHEDULE_FILE), schedule_json)
+        .map_err(|e| format!("Failed to write trash schedule: {}", e))
+}
+
+fn move_to_trash(
+    app_handle: &AppHandle,
+    key: &[u8; KEY_SIZE],
+    file_id: &str,
+    path: &PathBuf,
+    encrypted_name: &str,
+    original_name: Option<String>,
+    file_type: Option<String>,
+) -> Result<(), String> {
+    let trash_dir = get_vault_trash_dir(app_handle);
+    fs::create_dir_all(&trash_dir)
+        .map_err(|e| format!("Failed to create trash directory: {}", e))?;
+
+    let _trash = TRASH_LOCK.lock().unwrap();
+    let mut entries = load_trash_index(app_handle, key)?;
+    let size_bytes = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+    fs::rename(path, trash_dir.join(encrypted_name))
+        .map_err(|e| format!("Failed to move file to trash: {}", e))?;
+
+    let now = chrono::Utc::now().timestamp();
+    entries.retain(|entry| entry.id != file_id);
+    entries.push(VaultTrashEntry {
+        id: file_id.to_string(),
+        encrypted_name: encrypted_name.to_string(),
+        original_name,
+        file_type,
+        size_bytes,
+        deleted_at: now,
+        purge_after: now + trash_retention_days(app_handle) * 24 * 60 * 60,
+    });
+
+    save_trash_index(app_handle, key, &entries)
+}
+
+/// Delete trashed files whose retention has expired. Works while the vault is locked
+/// because it only needs the plaintext schedule.
+pub fn purge_expired_trash(app_handle: &AppHandle) -> usize {
+    let _trash = TRASH_LOCK.lock().unwrap();
+    let trash_dir = get_vault_trash_dir(app_handle);
+    let schedule_path = trash_dir.join(TRASH_SCHEDULE_FILE);
+    let schedule: std::collections::HashMap<String, i64> = match fs::read_to_string(&schedule_path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(_) => return 0,
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let mut purged = 0;
+    let mut remaining = std::collections::HashMap::new();
+
+    for (encrypted_name, purge_after) in schedule {
+        let Ok(safe_name) = sanitize_encrypted_name(&encrypted_name) else {
+            continue;
+        };
+        if purge_after <= now {
+            if fs::remove_file(trash_dir.join(&safe_name)).is_ok() {
+                purged += 1;
+            }
+        } else {
+            remaining.insert(safe_name, purge_after);
+        }
+    }
+
+    if let Ok(content) = serde_json::to_string(&remaining) {
+        let _ = fs::write(&schedule_path, content);
+    }
+
+    if purged > 0 {
+        println!("[Vault] Purged {} expired trash item(s)", purged);
+    }
+    purged
+}
+
+/// Periodically purge expired trash items in the background
+pub fn start_trash_purge_task(app_handle: AppHandle) {
+    crate::scheduler::every(TRASH_PURGE_INTERVAL, move || {
+        let handle = app_handle.clone();
+        async move {
+            let _ = tokio::task::spawn_blocking(move || purge_expired_trash(&handle)).await;
+        }
+    });
+}
+
+/// List items in the vault trash
+#[tauri::command]
+pub fn vault_list_trash(app_handle: AppHandle) -> Result<Vec<VaultTrashEntry>, String> {
+    let key = get_vault_key()?;
+    load_trash_index(&app_handle, &key)
+}
+
+/// Move a trashed file back into the vault. Returns the entry so the frontend can
+/// re-add it to the cloud index.
+#[tauri::command]
+pub fn vault_restore_deleted(app_handle: AppHandle, file_id: String) -> Result<VaultTrashEntry, String> {
+    let key = get_vault_key()?;
+    let _trash = TRASH_LOCK.lock().unwrap();
+    let mut entries = load_trash_index(&app_handle, &key)?;
+
+    let position = entries
+        .iter()
+        .position(|entry| entry.id == file_id)
+        .ok_or_else(|| format!("File not found in trash: {}", file_id))?;
+    let entry = entries.remove(position);
+
+    let safe_name = sanitize_encrypted_name(&entry.encrypted_name)?;
+    let files_dir = get_vault_files_dir(&app_handle);
+    fs::create_dir_all(&files_dir)
+        .map_err(|e| format!("Failed to create vault files directory: {}", e))?;
+    fs::rename(get_vault_trash_dir(&app_handle).join(&safe_name), files_dir.join(&safe_name))
+        .map_err(|e| format!("Failed to restore file: {}", e))?;
+
+    save_trash_index(&app_handle, &key, &entries)?;
+    println!("[Vault] Restored file from trash: {}", safe_name);
+    Ok(entry)
+}
+
+/// Permanently delete everything in the vault trash. Returns the number of files removed.
+#[tauri::command]
+pub fn vault_purge_trash(app_handle: AppHandle) -> Result<usize, String> {
+    let key = get_vault_key()?;
+    let _trash = TRASH_LOCK.lock().unwrap();
+    let entries = load_trash_index(&app_handle, &key)?;
+    let trash_dir = get_vault_trash_dir(&app_handle);
+
+    let mut purged = 0;
+    for entry in &entries {
+        if let Ok(safe_name) = sanitize_encrypted_name(&entry.encrypted_name) {
+            if fs::remove_file(trash_dir.join(&safe_name)).is_ok() {
+                purged += 1;
+            }
+        }
+    }
+
+    save_trash_index(&app_handle, &key, &[])?;
+    println!("[Vault] Emptied trash ({} files)", purged);
+    Ok(purged)
+}
+
+// ============ VAULT STATISTICS ============
+
+/// Encrypted index of what each vault file is, so statistics don't need the contents
+const STATS_INDEX_FILE: &str = "stats.slasshy";
+/// Items listed in `VaultStats::largest`
+const LARGEST_ITEMS: usize = 10;
+
+/// What the stats index keeps per file. No names: the frontend maps ids to its cloud index.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VaultStatsEntry {
+    pub id: String,
+    pub file_type: String,
+    /// Original (unencrypted) size
+    pub size_bytes: u64,
+    pub added_at: i64,
+}
+
+impl From<&VaultFile> for VaultStatsEntry {
+    fn from(file: &VaultFile) -> Self {
+        Self {
+            id: file.id.clone(),
+            file_type: file.file_type.clone(),
+            size_bytes: file.size_bytes,
+            added_at: file.added_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VaultTypeStats {
+    pub file_type: String,
+    pub file_count: usize,
+    pub total_size_bytes: u64,
+}
+
+/// Files added in one month, and the vault's size at its end
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VaultGrowthPoint {
+    /// "YYYY-MM"
+    pub month: String,
+    pub files_added: usize,
+    pub bytes_added: u64,
+    pub total_size_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VaultStats {
+    pub file_count: usize,
+    pub total_size_bytes: u64,
+    /// Biggest share first
+    pub by_type: Vec<VaultTypeStats>,
+    pub largest: Vec<VaultStatsEntry>,
+    /// Oldest month first; only files the index knows when they were added
+    pub growth: Vec<VaultGrowthPoint>,
+    /// Files the index doesn't describe (added before it existed, or synced from another
+    /// device); counted as "unknown" at their encrypted size
+    pub unindexed_files: usize,
+}
+
+fn get_stats_index_path(app_handle: &AppHandle) -> PathBuf {
+    get_vault_dir(app_handle).join(STATS_INDEX_FILE)
+}
+
+fn load_stats_index(app_handle: &AppHandle, key: &[u8; KEY_SIZE]) -> Result<Vec<VaultStatsEntry>, String> {
+    let index_path = get_stats_index_path(app_handle);
+    if !index_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let data = fs::read(&index_path)
+        .map_err(|e| format!("Failed to read stats index: {}", e))?;
+    if data.len() < NONCE_SIZE {
+        return Err("Stats index is corrupted".to_string());
+    }
+
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| format!("Failed to create cipher: {}", e))?;
+    let (nonce_bytes, ciphertext) = data.split_at(NONCE_SIZE);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| "Failed to decrypt stats index - invalid PIN or corrupted file".to_string())?;
+
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Failed to parse stats index: {}", e))
+}
+
+fn save_stats_index(app_handle: &AppHandle, key: &[u8; KEY_SIZE], entries: &[VaultStatsEntry]) -> Result<(), String> {
+    let plaintext = serde_json::to_vec(entries)
+        .map_err(|e| format!("Failed to serialize stats index: {}", e))?;
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| format!("Failed to create cipher: {}", e))?;
+    let mut nonce_bytes = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_ref())
+        .map_err(|e| format!("Failed to encrypt stats index: {}", e))?;
+
+    let mut data = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
+    data.extend_from_slice(&nonce_bytes);
+    data.extend_from_slice(&ciphertext);
+    fs::write(get_stats_index_path(app_handle), data)
+        .map_err(|e| format!("Failed to write stats index: {}", e))
+}
+
+/// Ids of the encrypted files in `dir`, by file stem, so a `.vault` -> `.slasshy` rename
+/// keeps its entry
+fn vault_file_ids(dir: &PathBuf) -> std::collections::HashSet<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| Some(entry.path().file_stem()?.to_string_lossy().to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Add or replace entries for `files`, dropping entries whose file is gone from the vault
+/// and its trash. Failures are logged; they never fail the operation that added the files.
+pub(crate) fn record_stats(app_handle: &AppHandle, key: &[u8; KEY_SIZE], files: &[VaultStatsEntry]) {
+    let _stats = STATS_INDEX_LOCK.lock().unwrap();
+    let result = load_stats_index(app_handle, key).and_then(|mut entries| {
+        let mut present = vault_file_ids(&get_vault_files_dir(app_handle));
+        present.extend(vault_file_ids(&get_vault_trash_dir(app_handle)));
+        entries.retain(|entry| present.contains(&entry.id) && !files.iter().any(|f| f.id == entry.id));
+        entries.extend(files.iter().cloned());
+        save_stats_index(app_handle, key, &entries)
+    });
+    if let Err(e) = result {
+        println!("[Vault] Failed to update stats index: {}", e);
+    }
+}
+
+/// Statistics over the files on disk (`(id, encrypted size)`), described by `entries` where
+/// the index knows them
+fn compute_stats(entries: &[VaultStatsEntry], on_disk: &[(String, u64)]) -> VaultStats {
+    let known: std::collections::HashMap<&str, &VaultStatsEntry> =
+        entries.iter().map(|entry| (entry.id.as_str(), entry)).collect();
+    let mut by_type: std::collections::HashMap<String, VaultTypeStats> = std::collections::HashMap::new();
+    let mut indexed: Vec<&VaultStatsEntry> = Vec::new();
+    let mut unindexed_files = 0;
+    let mut total_size_bytes = 0;
+
+    for (id, encrypted_size) in on_disk {
+        let (file_type, size) = match known.get(id.as_str()) {
+            Some(entry) => {
+                indexed.push(entry);
+                (entry.file_type.as_str(), entry.size_bytes)
+            }
+            None => {
+                unindexed_files += 1;
+                ("unknown", *encrypted_size)
+            }
+        };
+        total_size_bytes += size;
+        let stats = by_type.entry(file_type.to_string()).or_insert_with(|| VaultTypeStats {
+            file_type: file_type.to_string(),
+            file_count: 0,
+            total_size_bytes: 0,
+        });
+        stats.file_count += 1;
+        stats.total_size_bytes += size;
+    }
+
+    let mut by_type: Vec<VaultTypeStats> = by_type.into_values().collect();
+    by_type.sort_by(|a, b| b.total_size_bytes.cmp(&a.total_size_bytes).then(a.file_type.cmp(&b.file_type)));
+
+    indexed.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
+    let largest = indexed.iter().take(LARGEST_ITEMS).map(|entry| (*entry).clone()).collect();
+
+    let mut months: std::collections::BTreeMap<String, (usize, u64)> = std::collections::BTreeMap::new();
+    for entry in &indexed {
+        let month = chrono::DateTime::from_timestamp(entry.added_at, 0)
+            .map(|date| date.format("%Y-%m").to_string())
+            .unwrap_or_default();
+        let (files, bytes) = months.entry(month).or_default();
+        *files += 1;
+        *bytes += entry.size_bytes;
+    }
+    let mut running_total = 0;
+    let growth = months
+        .into_iter()
+        .map(|(month, (files_added, bytes_added))| {
+            running_total += bytes_added;
+            VaultGrowthPoint { month, files_added, bytes_added, total_size_bytes: running_total }
+        })
+        .collect();
+
+    VaultStats {
+        file_count: on_disk.len(),
+        total_size_bytes,
+        by_type,
+        largest,
+        growth,
+        unindexed_files,
+    }
+}
+
+/// Counts and sizes by type, the largest items and growth per month, from the encrypted
+/// stats index; no file is decrypted. Pass a `scan_id` to allow `cancel_scan`.
+#[tauri::command]
+pub async fn vault_get_stats(app_handle: AppHandle, scan_id: Option<String>) -> Result<VaultStats, String> {
+    let key = get_vault_key()?;
+    let control = ScanControl::new(&app_handle, scan_id);
+    run_blocking_scan(control, move |control| {
+        let entries = load_stats_index(&app_handle, &key)?;
+        let mut on_disk = Vec::new();
+        if let Ok(files) = fs::read_dir(get_vault_files_dir(&app_handle)) {
+            for file in files.flatten() {
+                control.advance(1)?;
+                let path = file.path();
+                if !path.extension().map_or(false, |ext| ext == "slasshy" || ext == "vault") {
+                    continue;
+                }
+                if let Some(id) = path.file_stem() {
+                    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    on_disk.push((id.to_string_lossy().to_string(), size));
+                }
+            }
+        }
+        Ok(compute_stats(&entries, &on_disk))
+    })
+    .await
+}
+
+/// Describe files the stats index doesn't know yet, e.g. from the cloud index after files
+/// were synced from another device
+#[tauri::command]
+pub fn vault_sync_stats(app_handle: AppHandle, files: Vec<VaultFile>) -> Result<(), String> {
+    let key = get_vault_key()?;
+    let entries: Vec<VaultStatsEntry> = files.iter().map(VaultStatsEntry::from).collect();
+    record_stats(&app_handle, &key, &entries);
+    Ok(())
+}
+
+// ============ LEGACY FORMAT MIGRATION ============
+
+const MIGRATION_DIR_NAME: &str = "migration_temp";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LegacyVaultStatus {
+    /// Files still in the single-chunk legacy format
+    pub legacy_files: usize,
+    pub legacy_size_bytes: u64,
+    pub total_files: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LegacyMigrationProgress {
+    pub current: usize,
+    pub total: usize,
+    pub encrypted_name: String,
+    /// "migrating", "completed", "rolled_back"
+    pub status: String,
+}
+
+/// Whether an encrypted file predates the chunked SLV2 format
+fn is_legacy_format(path: &PathBuf) -> Result<bool, String> {
+    let mut magic = [0u8; 4];
+    let mut file = File::open(path)
+        .map_err(|e| format!("Failed to open encrypted file: {}", e))?;
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(&magic != b"SLV2"),
+        // Too short to be SLV2; the legacy decrypt path will reject it if it's garbage
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(true),
+        Err(e) => Err(format!("Failed to read file header: {}", e)),
+    }
+}
+
+/// Every vault file, paired with whether it still uses the legacy format
+fn scan_vault_formats(app_handle: &AppHandle) -> Vec<(PathBuf, bool)> {
+    let files_dir = get_vault_files_dir(app_handle);
+    let Ok(entries) = fs::read_dir(&files_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "slasshy" || ext == "vault"))
+        .map(|path| {
+            let legacy = is_legacy_format(&path).unwrap_or(false);
+            (path, legacy)
+        })
+        .collect()
+}
+
+/// Count vault files that still need the slow legacy decrypt path
+#[tauri::command]
+pub async fn vault_get_legacy_status(app_handle: AppHandle) -> Result<LegacyVaultStatus, String> {
+    let files = tokio::task::spawn_blocking(move || scan_vault_formats(&app_handle))
+        .await
+        .map_err(|e| format!("Scan task failed: {}", e))?;
+
+    let legacy: Vec<&PathBuf> = files.iter().filter(|(_, legacy)| *legacy).map(|(p, _)| p).collect();
+    Ok(LegacyVaultStatus {
+        legacy_files: legacy.len(),
+        legacy_size_bytes: legacy.iter().filter_map(|p| fs::metadata(p).ok()).map(|m| m.len()).sum(),
+        total_files: files.len(),
+    })
+}
+
+/// Put back every original replaced so far
+fn rollback_migration(migrated: &[(PathBuf, PathBuf)]) {
+    for (original, backup) in migrated.iter().rev() {
+        if let Err(e) = fs::rename(backup, original) {
+            println!("[Vault] Failed to restore {:?} during rollback: {}", original, e);
+        }
+    }
+}
+
+/// Re-encrypt legacy files in place (same file names, so the cloud index stays valid).
+/// Originals are kept until every file succeeds; any failure restores them all.
+fn migrate_legacy_files(
+    app_handle: &AppHandle,
+    key: &[u8; KEY_SIZE],
+    legacy: &[PathBuf],
+) -> Result<usize, String> {
+    let work_dir = get_vault_dir(app_handle).join(MIGRATION_DIR_NAME);
+    let _ = fs::remove_dir_all(&work_dir);
+    fs::create_dir_all(&work_dir)
+        .map_err(|e| format!("Failed to create migration dir: {}", e))?;
+
+    let mut migrated: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (index, path) in legacy.iter().enumerate() {
+        let encrypted_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let _ = app_handle.emit("vault-migration-progress", LegacyMigrationProgress {
+            current: index + 1,
+            total: legacy.len(),
+            encrypted_name: encrypted_name.clone(),
+            status: "migrating".to_string(),
+        });
+
+        let plaintext = work_dir.join(format!("{}.plain", index));
+        let reencrypted = work_dir.join(format!("{}.new", index));
+        let backup = work_dir.join(format!("{}.orig", index));
+
+        let result = decrypt_file(key, path, &plaintext)
+            .and_then(|_| encrypt_file(key, &plaintext, &reencrypted))
+            .and_then(|_| {
+                fs::rename(path, &backup).map_err(|e| format!("Failed to back up original: {}", e))
+            })
+            .and_then(|_| {
+                fs::rename(&reencrypted, path).map_err(|e| {
+                    // Undo this file's backup before the batch rollback
+                    let _ = fs::rename(&backup, path);
+                    format!("Failed to replace encrypted file: {}", e)
+                })
+            });
+        let _ = fs::remove_file(&plaintext);
+
+        if let Err(e) = result {
+            rollback_migration(&migrated);
+            let _ = fs::remove_dir_all(&work_dir);
+            let _ = app_handle.emit("vault-migration-progress", LegacyMigrationProgress {
+                current: index + 1,
+                total: legacy.len(),
+                encrypted_name,
+                status: "rolled_back".to_string(),
+            });
+            return Err(format!("Migration of {} failed, no files were changed: {}", path.display(), e));
+        }
+        migrated.push((path.clone(), backup));
+    }
+
+    let _ = fs::remove_dir_all(&work_dir);
+    let _ = app_handle.emit("vault-migration-progress", LegacyMigrationProgress {
+        current: legacy.len(),
+        total: legacy.len(),
+        encrypted_name: String::new(),
+        status: "completed".to_string(),
+    });
+    Ok(migrated.len())
+}
+
+/// Re-encrypt all legacy single-chunk files to the chunked SLV2 format.
+/// Returns the number of files migrated.
+#[tauri::command]
+pub async fn vault_migrate_legacy_files(app_handle: AppHandle) -> Result<usize, String> {
+    let key = get_vault_key()?;
+
+    let handle = app_handle.clone();
+    let migrated = tokio::task::spawn_blocking(move || {
+        let legacy: Vec<PathBuf> = scan_vault_formats(&handle)
+            .into_iter()
+            .filter(|(_, legacy)| *legacy)
+            .map(|(path, _)| path)
+            .collect();
+        if legacy.is_empty() {
+            return Ok(0);
+        }
+        println!("[Vault] Migrating {} legacy files to SLV2", legacy.len());
+        migrate_legacy_files(&handle, &key, &legacy)
+    })
+    .await
+    .map_err(|e| format!("Migration task failed: {}", e))??;
+
+    println!("[Vault] Migrated {} legacy files", migrated);
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::OnceLock;
+
+    const TEST_KEY: [u8; KEY_SIZE] = [7u8; KEY_SIZE];
+    const HEADER_LEN: usize = SLV2_HEADER_LEN;
+
+    /// Scratch directory removed on drop, so failing proptest cases don't leak files
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("ownstash-vault-test-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Compressible data repeats a short pattern; otherwise it's seeded random bytes
+    fn sample_data(len: usize, seed: u64, compressible: bool) -> Vec<u8> {
+        if compressible {
+            return (0..len).map(|i| (i * 31 % 251) as u8).collect();
+        }
+        let mut data = vec![0u8; len];
+        StdRng::seed_from_u64(seed).fill_bytes(&mut data);
+        data
+    }
+
+    fn encrypt_bytes(data: &[u8], compress: bool) -> Vec<u8> {
+        let scratch = Scratch::new();
+        let (plain, sealed) = (scratch.0.join("plain"), scratch.0.join("sealed"));
+        fs::write(&plain, data).unwrap();
+        encrypt_file_with(&TEST_KEY, &plain, &sealed, compress).unwrap();
+        fs::read(&sealed).unwrap()
+    }
+
+    fn decrypt_bytes(key: &[u8; KEY_SIZE], sealed: &[u8]) -> Result<Vec<u8>, String> {
+        let scratch = Scratch::new();
+        let (input, output) = (scratch.0.join("sealed"), scratch.0.join("plain"));
+        fs::write(&input, sealed).unwrap();
+        decrypt_file(key, &input, &output)?;
+        Ok(fs::read(&output).unwrap())
+    }
+
+    /// Two-chunk sample (plaintext, SLV2 bytes), encrypted once per compression mode
+    fn sealed_sample(compress: bool) -> &'static (Vec<u8>, Vec<u8>) {
+        static PLAIN: OnceLock<(Vec<u8>, Vec<u8>)> = OnceLock::new();
+        static COMPRESSED: OnceLock<(Vec<u8>, Vec<u8>)> = OnceLock::new();
+        let cell = if compress { &COMPRESSED } else { &PLAIN };
+        cell.get_or_init(|| {
+            let data = sample_data(CHUNK_SIZE + 4096, 1, compress);
+            let sealed = encrypt_bytes(&data, compress);
+            (data, sealed)
+        })
+    }
+
+    /// Offsets of every chunk length prefix in an SLV2 file
+    fn chunk_offsets(sealed: &[u8]) -> Vec<usize> {
+        let mut offsets = Vec::new();
+        let mut offset = HEADER_LEN;
+        while offset + 4 <= sealed.len() {
+            offsets.push(offset);
+            let len = u32::from_le_bytes(sealed[offset..offset + 4].try_into().unwrap());
+            offset += 4 + (len & !COMPRESSED_CHUNK_FLAG) as usize;
+        }
+        offsets
+    }
+
+    /// Lengths around the chunk boundaries plus anything up to three chunks
+    fn boundary_len() -> impl Strategy<Value = usize> {
+        prop_oneof![
+            0..64usize,
+            (CHUNK_SIZE - 2)..(CHUNK_SIZE + 3),
+            (2 * CHUNK_SIZE - 2)..(2 * CHUNK_SIZE + 3),
+            0..(3 * CHUNK_SIZE),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(24))]
+
+        #[test]
+        fn prop_round_trip_across_chunk_boundaries(
+            len in boundary_len(),
+            seed in any::<u64>(),
+            compress in any::<bool>(),
+            compressible in any::<bool>(),
+        ) {
+            let data = sample_data(len, seed, compressible);
+            let sealed = encrypt_bytes(&data, compress);
+            let decrypted = decrypt_bytes(&TEST_KEY, &sealed).unwrap();
+            prop_assert!(decrypted == data, "round trip changed {} bytes of content", len);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+
+        #[test]
+        fn prop_truncated_file_is_rejected(cut in 0usize..1_000_000, compress in any::<bool>()) {
+            let (_, sealed) = sealed_sample(compress);
+            let cut = cut % sealed.len();
+            prop_assert!(decrypt_bytes(&TEST_KEY, &sealed[..cut]).is_err());
+        }
+
+        #[test]
+        fn prop_flipped_bit_is_rejected(
+            position in any::<prop::sample::Index>(),
+            bit in 0u8..8,
+            compress in any::<bool>(),
+        ) {
+            let (_, sealed) = sealed_sample(compress);
+            let position = position.index(sealed.len());
+            let mut corrupted = sealed.clone();
+            corrupted[position] ^= 1 << bit;
+
+            // The compression flags are authenticated too, so no flip goes unnoticed
+            prop_assert!(decrypt_bytes(&TEST_KEY, &corrupted).is_err(), "tampered byte {} was accepted", position);
+        }
+
+        #[test]
+        fn prop_garbage_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..4096), slv2 in any::<bool>()) {
+            let bytes = if slv2 { [b"SLV2".as_slice(), &bytes].concat() } else { bytes };
+            // Without the key nothing can authenticate, so at most an empty file decrypts
+            if let Ok(decrypted) = decrypt_bytes(&TEST_KEY, &bytes) {
+                prop_assert!(decrypted.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncation_at_every_boundary() {
+        for compress in [false, true] {
+            let (_, sealed) = sealed_sample(compress);
+            let mut cuts = vec![0, 3, 4, 4 + NONCE_SIZE, HEADER_LEN - 1, HEADER_LEN, sealed.len() - 1];
+            for offset in chunk_offsets(sealed) {
+                cuts.extend([offset, offset + 2, offset + 4, offset + 5]);
+            }
+            for cut in cuts {
+                assert!(decrypt_bytes(&TEST_KEY, &sealed[..cut]).is_err(), "cut at {} accepted", cut);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrong_size_field_is_rejected() {
+        let (_, sealed) = sealed_sample(false);
+        let size = u64::from_le_bytes(sealed[HEADER_LEN - 8..HEADER_LEN].try_into().unwrap());
+        for wrong in [0, size - 1, size + 1, u64::MAX >> 1] {
+            let mut corrupted = sealed.clone();
+            corrupted[HEADER_LEN - 8..HEADER_LEN].copy_from_slice(&wrong.to_le_bytes());
+            let err = decrypt_bytes(&TEST_KEY, &corrupted).unwrap_err();
+            assert!(err.contains("size mismatch"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_compression_flags_are_authenticated() {
+        for compress in [false, true] {
+            let (_, sealed) = sealed_sample(compress);
+            let mut file_flag = sealed.clone();
+            file_flag[HEADER_LEN - 1] ^= 0x80;
+            assert!(decrypt_bytes(&TEST_KEY, &file_flag).is_err());
+        }
+        let (_, sealed) = sealed_sample(true);
+        let mut chunk_flag = sealed.clone();
+        chunk_flag[HEADER_LEN + 3] ^= 0x80;
+        let err = decrypt_bytes(&TEST_KEY, &chunk_flag).unwrap_err();
+        assert!(err.contains("Decryption failed at chunk 0"), "{}", err);
+    }
+
+    #[test]
+    fn test_oversized_chunk_length_is_rejected() {
+        for compress in [false, true] {
+            let (_, sealed) = sealed_sample(compress);
+            let mut corrupted = sealed.clone();
+            corrupted[HEADER_LEN..HEADER_LEN + 4].copy_from_slice(&(u32::MAX >> 1).to_le_bytes());
+            let err = decrypt_bytes(&TEST_KEY, &corrupted).unwrap_err();
+            assert!(err.contains("Corrupted vault file"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_wrong_key_is_rejected() {
+        let (_, sealed) = sealed_sample(true);
+        let err = decrypt_bytes(&[8u8; KEY_SIZE], sealed).unwrap_err();
+        assert!(err.contains("invalid PIN"), "{}", err);
+    }
+
+    #[test]
+    fn test_trash_index_is_not_a_vault_file_name() {
+        // Trashed files keep their vault names, which must end in the vault extension
+        assert!(sanitize_encrypted_name(TRASH_INDEX_FILE).is_err());
+
+        // A trashed file under the legacy index name doesn't read as an index
+        let scratch = Scratch::new();
+        let legacy = scratch.0.join(LEGACY_TRASH_INDEX_FILE);
+        fs::write(&legacy, &sealed_sample(false).1).unwrap();
+        assert!(read_trash_index(&legacy, &TEST_KEY).is_err());
+    }
+
+    #[test]
+    fn test_legacy_format() {
+        let data = sample_data(5000, 3, false);
+        let nonce = [9u8; NONCE_SIZE];
+        let cipher = Aes256Gcm::new_from_slice(&TEST_KEY).unwrap();
+        let mut sealed = nonce.to_vec();
+        sealed.extend(cipher.encrypt(Nonce::from_slice(&nonce), data.as_ref()).unwrap());
+
+        assert_eq!(decrypt_bytes(&TEST_KEY, &sealed).unwrap(), data);
+        for cut in [0, 3, NONCE_SIZE - 1, NONCE_SIZE, sealed.len() - 1] {
+            assert!(decrypt_bytes(&TEST_KEY, &sealed[..cut]).is_err(), "cut at {} accepted", cut);
+        }
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let entry = |id: &str, file_type: &str, size_bytes: u64, added_at: i64| VaultStatsEntry {
+            id: id.to_string(),
+            file_type: file_type.to_string(),
+            size_bytes,
+            added_at,
+        };
+        // Jan 2024, Jan 2024, Mar 2024; "trashed" isn't on disk
+        let entries = vec![
+            entry("a", "video", 700, 1_704_100_000),
+            entry("b", "image", 50, 1_704_200_000),
+            entry("c", "video", 300, 1_709_500_000),
+            entry("trashed", "audio", 9999, 1_709_500_000),
+        ];
+        let on_disk: Vec<(String, u64)> =
+            [("a", 720), ("b", 60), ("c", 310), ("x", 40)].iter().map(|(id, size)| (id.to_string(), *size)).collect();
+
+        let stats = compute_stats(&entries, &on_disk);
+        assert_eq!(stats.file_count, 4);
+        assert_eq!(stats.total_size_bytes, 700 + 50 + 300 + 40);
+        assert_eq!(stats.unindexed_files, 1);
+        assert_eq!(stats.by_type[0], VaultTypeStats { file_type: "video".to_string(), file_count: 2, total_size_bytes: 1000 });
+        assert_eq!(stats.by_type.iter().find(|t| t.file_type == "unknown").unwrap().total_size_bytes, 40);
+        assert!(stats.by_type.iter().all(|t| t.file_type != "audio"));
+        assert_eq!(stats.largest.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["a", "c", "b"]);
+
+        assert_eq!(stats.growth.len(), 2);
+        assert_eq!(stats.growth[0].month, "2024-01");
+        assert_eq!((stats.growth[0].files_added, stats.growth[0].bytes_added), (2, 750));
+        assert_eq!(stats.growth[1].total_size_bytes, 1050);
+    }
+}
@@ -0,0 +1,164 @@
+//! Vault Write Consent
+//!
+//! Vault downloads that come from outside the app UI (the browser extension) need the user
+//! to approve each one in the app first, even while the vault is unlocked.
+//!
+//! Key Features:
+//! - Per-request `vault-write-confirmation` event answered with `respond_vault_write`
+//! - Unanswered requests are denied after a timeout
+//! - Approval yields a single-use grant that `vault_direct_download` redeems for that URL
+//! - "deny" policy setting rejects every external vault write without asking
+
+use crate::commands::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::oneshot;
+
+/// Settings key: "ask" (default) or "deny"
+pub const VAULT_EXTERNAL_POLICY_SETTING: &str = "vault_external_requests";
+
+/// How long the user has to answer a confirmation
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long an approval stays redeemable
+const GRANT_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalVaultPolicy {
+    #[default]
+    Ask,
+    Deny,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultWriteConfirmation {
+    pub request_id: String,
+    pub source: String,
+    pub url: String,
+    pub filename: String,
+    pub timeout_secs: u64,
+}
+
+struct Grant {
+    url: String,
+    issued_at: Instant,
+}
+
+lazy_static::lazy_static! {
+    static ref PENDING: Mutex<HashMap<String, oneshot::Sender<bool>>> = Mutex::new(HashMap::new());
+    static ref GRANTS: Mutex<HashMap<String, Grant>> = Mutex::new(HashMap::new());
+}
+
+fn policy(app_handle: &AppHandle) -> ExternalVaultPolicy {
+    crate::commands::read_setting(app_handle, VAULT_EXTERNAL_POLICY_SETTING)
+        .and_then(|v| serde_json::from_value(serde_json::Value::String(v)).ok())
+        .unwrap_or_default()
+}
+
+fn issue_grant(url: &str) -> String {
+    let token = uuid::Uuid::new_v4().to_string();
+    let mut grants = GRANTS.lock().unwrap();
+    grants.retain(|_, g| g.issued_at.elapsed() < GRANT_TTL);
+    grants.insert(token.clone(), Grant { url: url.to_string(), issued_at: Instant::now() });
+    token
+}
+
+/// Use up a grant; it must be for `url` and not have expired
+pub fn redeem_grant(token: &str, url: &str) -> Result<(), String> {
+    let grant = GRANTS
+        .lock()
+        .unwrap()
+        .remove(token)
+        .ok_or("This vault download wasn't approved")?;
+    if grant.issued_at.elapsed() >= GRANT_TTL {
+        return Err("The approval for this vault download has expired".to_string());
+    }
+    if grant.url != url {
+        return Err("The approval was for a different URL".to_string());
+    }
+    Ok(())
+}
+
+/// Ask the user to approve an external vault write. Returns a grant for `url` once approved.
+pub async fn request_consent(app_handle: &AppHandle, source: &str, url: &str, filename: &str) -> Result<String, String> {
+    if policy(app_handle) == ExternalVaultPolicy::Deny {
+        return Err("External vault downloads are turned off".to_string());
+    }
+
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = oneshot::channel();
+    PENDING.lock().unwrap().insert(request_id.clone(), tx);
+    let _ = app_handle.emit("vault-write-confirmation", VaultWriteConfirmation {
+        request_id: request_id.clone(),
+        source: source.to_string(),
+        url: url.to_string(),
+        filename: filename.to_string(),
+        timeout_secs: CONFIRMATION_TIMEOUT.as_secs(),
+    });
+
+    let answer = tokio::time::timeout(CONFIRMATION_TIMEOUT, rx).await;
+    PENDING.lock().unwrap().remove(&request_id);
+    match answer {
+        Ok(Ok(true)) => Ok(issue_grant(url)),
+        Ok(_) => Err("Vault download was denied".to_string()),
+        Err(_) => {
+            let _ = app_handle.emit("vault-write-confirmation-expired", &request_id);
+            Err("Vault download wasn't confirmed in time".to_string())
+        }
+    }
+}
+
+/// Answer a `vault-write-confirmation` event
+#[tauri::command]
+pub async fn respond_vault_write(request_id: String, approved: bool) -> Result<(), String> {
+    let sender = PENDING
+        .lock()
+        .unwrap()
+        .remove(&request_id)
+        .ok_or("Confirmation not found or already expired")?;
+    let _ = sender.send(approved);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_vault_external_policy(app_handle: AppHandle) -> Result<ExternalVaultPolicy, String> {
+    Ok(policy(&app_handle))
+}
+
+#[tauri::command]
+pub async fn set_vault_external_policy(state: State<'_, AppState>, policy: ExternalVaultPolicy) -> Result<(), String> {
+    let value = serde_json::to_value(policy)
+        .ok()
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .unwrap_or_default();
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.save_setting(VAULT_EXTERNAL_POLICY_SETTING, &value)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grants_are_single_use_and_bound_to_url() {
+        let token = issue_grant("https://example.com/a.mp4");
+        assert!(redeem_grant(&token, "https://example.com/other.mp4").is_err());
+        // A failed redemption still uses it up
+        assert!(redeem_grant(&token, "https://example.com/a.mp4").is_err());
+
+        let token = issue_grant("https://example.com/a.mp4");
+        assert!(redeem_grant(&token, "https://example.com/a.mp4").is_ok());
+        assert!(redeem_grant(&token, "https://example.com/a.mp4").is_err());
+        assert!(redeem_grant("made-up", "https://example.com/a.mp4").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_respond_unknown_request() {
+        assert!(respond_vault_write("missing".to_string(), true).await.is_err());
+    }
+}
@@ -15,7 +15,7 @@ use std::sync::Arc;
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::process::Command;
 
-use crate::vault::{get_vault_key, VaultFile, ENCRYPTED_EXTENSION};
+use crate::vault::{get_vault_key, VaultFile, VaultStatsEntry, ENCRYPTED_EXTENSION};
 
 // Constants
 const CHUNK_SIZE: usize = 1024 * 1024; // 1MB chunks for encryption
@@ -39,6 +39,12 @@ pub struct VaultDownloadRequest {
     pub audio_format: String,
     pub embed_metadata: bool,
     pub use_sponsorblock: bool,
+    /// Where the request came from when not the app UI (e.g. "extension")
+    #[serde(default)]
+    pub origin: Option<String>,
+    /// Single-use approval from the vault write confirmation
+    #[serde(default)]
+    pub grant: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -61,6 +67,16 @@ lazy_static::lazy_static! {
 
 // ============ Helper Functions ============
 
+/// Where vault downloads land before they're encrypted; the staging folder when one is set
+pub(crate) fn get_vault_download_temp_dir() -> PathBuf {
+    crate::staging::dir(crate::staging::VAULT_DOWNLOADS)
+        .unwrap_or_else(|| std::env::temp_dir().join("ownstash_vault_temp"))
+}
+
+pub(crate) fn has_active_downloads() -> bool {
+    !ACTIVE_VAULT_DOWNLOADS.lock().unwrap().is_empty()
+}
+
 fn get_vault_files_dir(app_handle: &AppHandle) -> PathBuf {
     let app_data_dir = app_handle
         .path()
@@ -71,18 +87,19 @@ fn get_vault_files_dir(app_handle: &AppHandle) -> PathBuf {
 
 // ============ yt-dlp Integration ============
 
-/// Creates a hidden Command (no console window on Windows)
+/// Creates a hidden Command (no console window on Windows) for a program that passed the
+/// execution guard
 #[cfg(windows)]
-fn create_hidden_command(program: &str) -> Command {
+fn create_hidden_command(program: &str) -> Result<Command, String> {
     use std::os::windows::process::CommandExt;
-    let mut cmd = Command::new(program);
+    let mut cmd = crate::exec_guard::tokio_command(program)?;
     cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-    cmd
+    Ok(cmd)
 }
 
 #[cfg(not(windows))]
-fn create_hidden_command(program: &str) -> Command {
-    Command::new(program)
+fn create_hidden_command(program: &str) -> Result<Command, String> {
+    crate::exec_guard::tokio_command(program)
 }
 
 /// Find yt-dlp binary path
@@ -108,9 +125,8 @@ fn find_yt_dlp(app_handle: &AppHandle) -> Option<String> {
     }
 
     // Fallback: try system PATH
-    if let Ok(output) = std::process::Command::new(if cfg!(windows) { "where" } else { "which" })
-        .arg("yt-dlp")
-        .output()
+    if let Ok(output) = crate::exec_guard::command(if cfg!(windows) { "where" } else { "which" })
+        .and_then(|mut cmd| cmd.arg("yt-dlp").output().map_err(|e| e.to_string()))
     {
         if output.status.success() {
             let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -136,6 +152,15 @@ pub async fn vault_direct_download(
     println!("[VaultDownload] Starting vault download for: {}", request.original_name);
     println!("[VaultDownload] URL: {}", request.url);
 
+    // Writes that didn't start in the app UI need the user's approval
+    if request.origin.is_some() || request.grant.is_some() {
+        let grant = request
+            .grant
+            .as_deref()
+            .ok_or("Vault downloads from outside the app need confirmation")?;
+        crate::vault_consent::redeem_grant(grant, &request.url)?;
+    }
+
     // Get vault encryption key (vault must be unlocked)
     let key = get_vault_key()?;
 
@@ -146,7 +171,7 @@ pub async fn vault_direct_download(
     let output_path = vault_files_dir.join(&encrypted_name);
 
     // Create temp directory path with random name
-    let temp_dir = std::env::temp_dir().join("ownstash_vault_temp");
+    let temp_dir = get_vault_download_temp_dir();
     tokio::fs::create_dir_all(&temp_dir).await
         .map_err(|e| format!("Failed to create temp directory: {}", e))?;
     
@@ -306,6 +331,7 @@ pub async fn vault_direct_download(
         is_folder: false,
         folder_entries: None,
     };
+    crate::vault::record_stats(&app_handle, &key, &[VaultStatsEntry::from(&vault_file)]);
 
     // Clean up active download tracking
     {
@@ -385,8 +411,7 @@ async fn download_direct_url(
     println!("[VaultDownload] Starting direct HTTP download for: {}", request.url);
     
     // Create HTTP client
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
+    let client = crate::proxy::apply_to_client(reqwest::Client::builder())
         .redirect(reqwest::redirect::Policy::limited(10))
         .timeout(std::time::Duration::from_secs(3600)) // 1 hour timeout
         .build()
@@ -401,6 +426,7 @@ async fn download_direct_url(
     
     // Start request
     let response = client.get(&download_url)
+        .headers(crate::header_profiles::headers_for_url(&download_url))
         .send()
         .await
         .map_err(|e| format!("HTTP request failed: {}", e))?;
@@ -664,6 +690,10 @@ async fn download_to_temp(
 
     println!("[VaultDownload] Running yt-dlp with args: {:?}", args);
 
+    // Added after logging so proxy credentials stay out of the log
+    args.extend(crate::proxy::proxy_args());
+    args.extend(crate::extractor_options::extractor_args());
+
     // Emit downloading status
     let _ = app_handle.emit("vault-download-progress", VaultDownloadProgress {
         id: request.id.clone(),
@@ -677,8 +707,21 @@ async fn download_to_temp(
     });
 
 
-    // Run yt-dlp
-    let mut child = create_hidden_command(&yt_dlp_path)
+    // Run yt-dlp with an isolated environment and app-managed cache
+    let mut cmd = create_hidden_command(&yt_dlp_path)?;
+    let session_dir = crate::downloader::yt_dlp_runtime_dir(app_handle);
+    if let Some(runtime_dir) = &session_dir {
+        args.extend([
+            "--ignore-config".to_string(),
+            "--cache-dir".to_string(),
+            runtime_dir.join("cache").to_string_lossy().to_string(),
+        ]);
+    }
+    let session_dir = session_dir.map(|dir| dir.join("sessions").join(format!("vault-{}", request.id)));
+    if let Some(session_dir) = &session_dir {
+        crate::downloader::isolate_yt_dlp_env(&mut cmd, session_dir, false);
+    }
+    let mut child = cmd
         .args(&args)
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
@@ -697,6 +740,9 @@ async fn download_to_temp(
     loop {
         if cancel_flag.load(Ordering::Relaxed) {
             let _ = child.kill().await;
+            if let Some(session_dir) = &session_dir {
+                let _ = std::fs::remove_dir_all(session_dir);
+            }
             return Err("Download cancelled".to_string());
         }
 
@@ -725,8 +771,11 @@ async fn download_to_temp(
     }
 
     // Wait for completion
-    let status = child.wait().await
-        .map_err(|e| format!("yt-dlp process error: {}", e))?;
+    let status = child.wait().await;
+    if let Some(session_dir) = &session_dir {
+        let _ = std::fs::remove_dir_all(session_dir);
+    }
+    let status = status.map_err(|e| format!("yt-dlp process error: {}", e))?;
 
     if !status.success() {
         return Err(format!("yt-dlp exited with code: {:?}", status.code()));
@@ -0,0 +1,371 @@
+//! WARC Capture
+//!
+//! Archive mode for direct (SNDE) downloads: alongside the file, write a WARC web archive
+//! holding the HTTP exchange and the payload, so preservationists get a standards-compliant
+//! record of where the file came from and what the server said about it.
+//!
+//! Key Features:
+//! - WARC/1.1 `warcinfo`, `request` and `response` records in `<name>.warc.gz`
+//! - One gzip member per record, as archive tooling expects
+//! - The headers of the first GET that delivered bytes, as sent and as answered; credentials
+//!   (`Authorization`, `Cookie`, `Set-Cookie`...) are redacted
+//! - `WARC-Block-Digest` / `WARC-Payload-Digest` as base32 SHA-256
+//! - The payload is read back from the finished file; nothing is downloaded twice
+//!
+//! Only SNDE downloads are captured. yt-dlp, aria2c, gallery-dl, spotDL and the FTP engine
+//! make their requests themselves, so there is no HTTP exchange to record; archive mode
+//! leaves their downloads as they are.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::header::HeaderMap;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Setting key: capture WARCs for direct downloads unless a request says otherwise
+pub const WARC_CAPTURE_SETTING: &str = "warc_capture_enabled";
+
+/// Response headers that describe how SNDE's range responses were framed, not the file
+const FRAMING_HEADERS: &[&str] = &["content-length", "content-range", "transfer-encoding"];
+
+/// Headers whose values are credentials; archives get the name with a placeholder
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+const REDACTED: &str = "[redacted]";
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// The HTTP exchange a direct download was made from
+#[derive(Debug, Clone)]
+pub struct HttpExchange {
+    pub date: DateTime<Utc>,
+    pub request_headers: Vec<(String, String)>,
+    pub status: u16,
+    pub reason: String,
+    pub response_headers: Vec<(String, String)>,
+}
+
+impl HttpExchange {
+    /// Capture the headers SNDE sent with a GET and the ones the server answered with
+    pub fn capture(request_headers: &HeaderMap, response: &reqwest::Response) -> Self {
+        let status = response.status();
+        Self {
+            date: Utc::now(),
+            request_headers: header_pairs(request_headers),
+            status: status.as_u16(),
+            reason: status.canonical_reason().unwrap_or("").to_string(),
+            response_headers: header_pairs(response.headers()),
+        }
+    }
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if CREDENTIAL_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).to_string()
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+/// Whether to capture a WARC: the request's choice, else the saved setting
+pub fn enabled(app_handle: &AppHandle, requested: Option<bool>) -> bool {
+    requested.unwrap_or_else(|| {
+        crate::commands::read_setting(app_handle, WARC_CAPTURE_SETTING).as_deref() == Some("true")
+    })
+}
+
+/// `<file name>.warc.gz` next to the downloaded file
+pub fn warc_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".warc.gz");
+    PathBuf::from(name)
+}
+
+/// RFC 4648 base32, the encoding WARC digests use
+fn base32(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    for group in bytes.chunks(5) {
+        let mut buffer = [0u8; 5];
+        buffer[..group.len()].copy_from_slice(group);
+        let bits = buffer.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+        let chars = (group.len() * 8).div_ceil(5);
+        for i in 0..8 {
+            if i < chars {
+                out.push(BASE32_ALPHABET[((bits >> (35 - i * 5)) & 0x1f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn sha256_digest(hasher: Sha256) -> String {
+    format!("sha256:{}", base32(&hasher.finalize()))
+}
+
+fn record_id() -> String {
+    format!("<urn:uuid:{}>", uuid::Uuid::new_v4())
+}
+
+/// The GET SNDE's workers sent, minus their per-worker `Range` header
+fn request_block(url: &str, exchange: &HttpExchange) -> Result<Vec<u8>, String> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    let mut target = parsed.path().to_string();
+    if let Some(query) = parsed.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+    let host = match (parsed.host_str(), parsed.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(format!("URL has no host: {}", url)),
+    };
+
+    let mut block = format!("GET {} HTTP/1.1\r\nHost: {}\r\n", target, host);
+    for (name, value) in &exchange.request_headers {
+        if !name.eq_ignore_ascii_case("host") && !name.eq_ignore_ascii_case("range") {
+            block.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    block.push_str("\r\n");
+    Ok(block.into_bytes())
+}
+
+/// Status line and headers describing the whole file as a single 200 response
+fn response_head(exchange: &HttpExchange, payload_len: u64) -> Vec<u8> {
+    // A range response stands for the whole file here
+    let mut head = if exchange.status == 206 {
+        "HTTP/1.1 200 OK\r\n".to_string()
+    } else {
+        format!("HTTP/1.1 {} {}\r\n", exchange.status, exchange.reason)
+    };
+    for (name, value) in &exchange.response_headers {
+        if !FRAMING_HEADERS.iter().any(|h| name.eq_ignore_ascii_case(h)) {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    head.push_str(&format!("Content-Length: {}\r\n\r\n", payload_len));
+    head.into_bytes()
+}
+
+/// Write one record as its own gzip member
+fn write_record(
+    out: &mut File,
+    fields: &[(&str, String)],
+    block_head: &[u8],
+    payload: Option<&Path>,
+    payload_len: u64,
+) -> Result<(), String> {
+    let mut header = String::from("WARC/1.1\r\n");
+    for (name, value) in fields {
+        header.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    header.push_str(&format!("Content-Length: {}\r\n\r\n", block_head.len() as u64 + payload_len));
+
+    let mut gz = GzEncoder::new(out, Compression::default());
+    let io_err = |e: std::io::Error| format!("Failed to write WARC: {}", e);
+    gz.write_all(header.as_bytes()).map_err(io_err)?;
+    gz.write_all(block_head).map_err(io_err)?;
+    if let Some(path) = payload {
+        let mut file = File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+        std::io::copy(&mut file, &mut gz).map_err(io_err)?;
+    }
+    gz.write_all(b"\r\n\r\n").map_err(io_err)?;
+    gz.finish().map_err(io_err)?;
+    Ok(())
+}
+
+/// Block and payload digests for a response: the head plus the file, and the file alone
+fn response_digests(head: &[u8], payload: &Path) -> Result<(String, String), String> {
+    let mut block = Sha256::new();
+    let mut body = Sha256::new();
+    block.update(head);
+    let mut file = File::open(payload).map_err(|e| format!("Failed to open {:?}: {}", payload, e))?;
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("Failed to read {:?}: {}", payload, e))?;
+        if read == 0 {
+            break;
+        }
+        block.update(&buffer[..read]);
+        body.update(&buffer[..read]);
+    }
+    Ok((sha256_digest(block), sha256_digest(body)))
+}
+
+/// Write `<payload>.warc.gz` for a finished direct download and return its path
+pub fn write_warc(url: &str, exchange: &HttpExchange, payload: &Path) -> Result<PathBuf, String> {
+    let output = warc_path(payload);
+    let payload_len = payload
+        .metadata()
+        .map_err(|e| format!("Failed to read {:?}: {}", payload, e))?
+        .len();
+    let date = exchange.date.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let warcinfo_id = record_id();
+    let request_id = record_id();
+    let response_id = record_id();
+
+    let info = format!(
+        "software: Ownstash Downloader {}\r\n\
+         format: WARC File Format 1.1\r\n\
+         conformsTo: https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/\r\n\
+         description: Payload assembled from parallel range requests; headers are from the first of them, credentials redacted\r\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    let request = request_block(url, exchange)?;
+    let head = response_head(exchange, payload_len);
+    let (block_digest, payload_digest) = response_digests(&head, payload)?;
+
+    let mut out = File::create(&output).map_err(|e| format!("Failed to create {:?}: {}", output, e))?;
+    let file_name = output
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    write_record(
+        &mut out,
+        &[
+            ("WARC-Type", "warcinfo".to_string()),
+            ("WARC-Record-ID", warcinfo_id.clone()),
+            ("WARC-Date", date.clone()),
+            ("WARC-Filename", file_name),
+            ("Content-Type", "application/warc-fields".to_string()),
+        ],
+        info.as_bytes(),
+        None,
+        0,
+    )?;
+    write_record(
+        &mut out,
+        &[
+            ("WARC-Type", "response".to_string()),
+            ("WARC-Record-ID", response_id.clone()),
+            ("WARC-Warcinfo-ID", warcinfo_id.clone()),
+            ("WARC-Date", date.clone()),
+            ("WARC-Target-URI", url.to_string()),
+            ("Content-Type", "application/http;msgtype=response".to_string()),
+            ("WARC-Block-Digest", block_digest),
+            ("WARC-Payload-Digest", payload_digest),
+        ],
+        &head,
+        Some(payload),
+        payload_len,
+    )?;
+    let mut request_digest = Sha256::new();
+    request_digest.update(&request);
+    write_record(
+        &mut out,
+        &[
+            ("WARC-Type", "request".to_string()),
+            ("WARC-Record-ID", request_id),
+            ("WARC-Warcinfo-ID", warcinfo_id),
+            ("WARC-Concurrent-To", response_id),
+            ("WARC-Date", date),
+            ("WARC-Target-URI", url.to_string()),
+            ("Content-Type", "application/http;msgtype=request".to_string()),
+            ("WARC-Block-Digest", sha256_digest(request_digest)),
+        ],
+        &request,
+        None,
+        0,
+    )?;
+    out.sync_all().map_err(|e| format!("Failed to write WARC: {}", e))?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange() -> HttpExchange {
+        HttpExchange {
+            date: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            request_headers: vec![
+                ("user-agent".to_string(), "Mozilla/5.0".to_string()),
+                ("range".to_string(), "bytes=0-99".to_string()),
+            ],
+            status: 200,
+            reason: "OK".to_string(),
+            response_headers: vec![
+                ("content-type".to_string(), "application/zip".to_string()),
+                ("content-length".to_string(), "999".to_string()),
+                ("accept-ranges".to_string(), "bytes".to_string()),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_base32() {
+        assert_eq!(base32(b""), "");
+        assert_eq!(base32(b"f"), "MY======");
+        assert_eq!(base32(b"foob"), "MZXW6YQ=");
+        assert_eq!(base32(b"foobar"), "MZXW6YTBOI======");
+    }
+
+    #[test]
+    fn test_request_and_response_blocks() {
+        let request = request_block("https://example.com:8443/files/a.zip?x=1", &exchange()).unwrap();
+        assert_eq!(
+            String::from_utf8(request).unwrap(),
+            "GET /files/a.zip?x=1 HTTP/1.1\r\nHost: example.com:8443\r\nuser-agent: Mozilla/5.0\r\n\r\n"
+        );
+        let head = response_head(&exchange(), 5);
+        assert_eq!(
+            String::from_utf8(head).unwrap(),
+            "HTTP/1.1 200 OK\r\ncontent-type: application/zip\r\naccept-ranges: bytes\r\nContent-Length: 5\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_credentials_are_redacted() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer secret".parse().unwrap());
+        headers.insert("cookie", "session=secret".parse().unwrap());
+        headers.insert("user-agent", "Mozilla/5.0".parse().unwrap());
+        let pairs = header_pairs(&headers);
+        assert!(pairs.iter().all(|(_, value)| !value.contains("secret")));
+        assert!(pairs.contains(&("authorization".to_string(), REDACTED.to_string())));
+        assert!(pairs.contains(&("user-agent".to_string(), "Mozilla/5.0".to_string())));
+
+        let ranged = HttpExchange { status: 206, reason: "Partial Content".to_string(), ..exchange() };
+        assert!(String::from_utf8(response_head(&ranged, 5)).unwrap().starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn test_write_warc() {
+        let dir = std::env::temp_dir().join(format!("ownstash-warc-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let payload = dir.join("a.v1.zip");
+        std::fs::write(&payload, b"hello").unwrap();
+
+        let output = write_warc("https://example.com/a.v1.zip", &exchange(), &payload).unwrap();
+        assert_eq!(output, dir.join("a.v1.zip.warc.gz"));
+
+        let mut warc = String::new();
+        flate2::read::MultiGzDecoder::new(File::open(&output).unwrap())
+            .read_to_string(&mut warc)
+            .unwrap();
+        let records: Vec<&str> = warc.split("WARC/1.1\r\n").skip(1).collect();
+        assert_eq!(records.len(), 3);
+        assert!(records[0].contains("WARC-Type: warcinfo\r\n"));
+        assert!(records[1].contains("WARC-Type: response\r\n"));
+        assert!(records[1].contains("WARC-Date: 2023-11-14T22:13:20Z\r\n"));
+        assert!(records[1].contains("Content-Length: 5\r\n\r\nhello\r\n\r\n"));
+        // sha256("hello") in base32
+        assert!(records[1].contains("WARC-Payload-Digest: sha256:FTZE3OS7WCRQ4JXIHMVMLOPCTYNRMHS4D6TUEXTTAQZWFE4LTASA====\r\n"));
+        assert!(records[2].contains("WARC-Type: request\r\n"));
+        assert!(records[2].contains("GET /a.v1.zip HTTP/1.1\r\n"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -6,9 +6,14 @@
 //! - Safe Mode toggling
 //! - Health-based interventions
 //!
+//! Collapses and Safe Mode reach running SNDE downloads through `snde::collapse_connections`
+//! and `snde::enable_safe_mode`; SNDE registers its downloads with the watchdog started by
+//! `install`.
+//!
 //! Key principle: SNDE → SNDE Safe is automatic; SNDE → Media Engine is NEVER
 //! automatic mid-flight (must be user-visible action after failure).
 
+use crate::commands::AppState;
 use crate::health_metrics::{
     DownloadHealth, DownloadPhase, WatchdogAction, HEALTH_REGISTRY,
 };
@@ -19,7 +24,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::mpsc;
 use tokio::time::interval;
 
@@ -102,6 +107,25 @@ pub enum WatchdogCommand {
     Shutdown,
 }
 
+lazy_static::lazy_static! {
+    /// Command channel of the app-wide watchdog, once it's running
+    static ref COMMANDS: std::sync::Mutex<Option<mpsc::Sender<WatchdogCommand>>> = std::sync::Mutex::new(None);
+}
+
+/// Start the app-wide watchdog; call once at startup
+pub fn install(app_handle: &AppHandle) {
+    let watchdog = Watchdog::new();
+    *COMMANDS.lock().unwrap() = Some(watchdog.get_command_sender());
+    tauri::async_runtime::spawn(watchdog.run(app_handle.clone(), None, None));
+}
+
+/// Send a command to the app-wide watchdog; dropped if it isn't running
+pub fn send(command: WatchdogCommand) {
+    if let Some(tx) = COMMANDS.lock().unwrap().as_ref() {
+        let _ = tx.try_send(command);
+    }
+}
+
 /// Callback type for connection collapse
 pub type CollapseCallback = Box<dyn Fn(&str, u8) + Send + Sync>;
 
@@ -291,13 +315,30 @@ impl Watchdog {
                             self.stop_monitoring(&id);
                         }
                         WatchdogCommand::ForceCollapse(id, count) => {
+                            crate::snde::collapse_connections(&id, count);
                             HEALTH_REGISTRY.record_collapse(&id, count);
+                            record_intervention(&app_handle, &WatchdogEvent {
+                                download_id: id.clone(),
+                                event_type: WatchdogEventType::ConnectionsCollapsed,
+                                message: format!("Connections reduced to {} on request", count),
+                                health: HEALTH_REGISTRY.get_health(&id),
+                                user_action: None,
+                            });
                             if let Some(ref cb) = collapse_callback {
                                 cb(&id, count);
                             }
                         }
                         WatchdogCommand::ForceSafeMode(id) => {
+                            crate::snde::enable_safe_mode(&id);
                             HEALTH_REGISTRY.set_safe_mode(&id, true);
+                            HEALTH_REGISTRY.set_active_connections(&id, 1);
+                            record_intervention(&app_handle, &WatchdogEvent {
+                                download_id: id.clone(),
+                                event_type: WatchdogEventType::SafeModeActivated,
+                                message: "Safe Mode enabled on request".to_string(),
+                                health: HEALTH_REGISTRY.get_health(&id),
+                                user_action: None,
+                            });
                             if let Some(ref cb) = safe_mode_callback {
                                 cb(&id);
                             }
@@ -315,6 +356,7 @@ impl Watchdog {
                     for (download_id, action) in actions {
                         match action {
                             WatchdogAction::CollapseConnections(new_count) => {
+                                crate::snde::collapse_connections(&download_id, new_count);
                                 HEALTH_REGISTRY.record_collapse(&download_id, new_count);
                                 
                                 // Emit event to frontend
@@ -325,7 +367,7 @@ impl Watchdog {
                                     health: HEALTH_REGISTRY.get_health(&download_id),
                                     user_action: None,
                                 };
-                                let _ = app_handle.emit("watchdog-event", &event);
+                                record_intervention(&app_handle, &event);
                                 
                                 // Call callback
                                 if let Some(ref cb) = collapse_callback {
@@ -333,7 +375,9 @@ impl Watchdog {
                                 }
                             }
                             WatchdogAction::EnableSafeMode => {
+                                crate::snde::enable_safe_mode(&download_id);
                                 HEALTH_REGISTRY.set_safe_mode(&download_id, true);
+                                HEALTH_REGISTRY.set_active_connections(&download_id, 1);
                                 
                                 let event = WatchdogEvent {
                                     download_id: download_id.clone(),
@@ -342,7 +386,7 @@ impl Watchdog {
                                     health: HEALTH_REGISTRY.get_health(&download_id),
                                     user_action: None,
                                 };
-                                let _ = app_handle.emit("watchdog-event", &event);
+                                record_intervention(&app_handle, &event);
                                 
                                 if let Some(ref cb) = safe_mode_callback {
                                     cb(&download_id);
@@ -356,7 +400,7 @@ impl Watchdog {
                                     health: HEALTH_REGISTRY.get_health(&download_id),
                                     user_action: Some("Switch to Media Engine".to_string()),
                                 };
-                                let _ = app_handle.emit("watchdog-event", &event);
+                                record_intervention(&app_handle, &event);
                             }
                             WatchdogAction::CriticalFailure(reason) => {
                                 let event = WatchdogEvent {
@@ -366,7 +410,7 @@ impl Watchdog {
                                     health: HEALTH_REGISTRY.get_health(&download_id),
                                     user_action: Some("Retry with Media Engine".to_string()),
                                 };
-                                let _ = app_handle.emit("watchdog-event", &event);
+                                record_intervention(&app_handle, &event);
                             }
                             WatchdogAction::NoAction => {}
                         }
@@ -383,6 +427,46 @@ impl Default for Watchdog {
     }
 }
 
+/// Emit an intervention event and keep it in the download's audit trail
+fn record_intervention(app_handle: &AppHandle, event: &WatchdogEvent) {
+    let _ = app_handle.emit("watchdog-event", event);
+
+    // Events that ask the user to act are worth an OS notification (subject to preferences)
+    if event.user_action.is_some() {
+        let _ = crate::native_integration::show_notification(
+            app_handle,
+            crate::native_integration::NotificationEvent::WatchdogWarning,
+            "Download needs attention",
+            &event.message,
+        );
+    }
+    let title = if event.user_action.is_some() { "Download needs attention" } else { "Download adjusted" };
+    crate::native_integration::record_notification(
+        app_handle,
+        crate::native_integration::NotificationEvent::WatchdogWarning,
+        title,
+        &event.message,
+        Some(&event.download_id),
+    );
+
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let Ok(db) = state.db.lock() else {
+        return;
+    };
+    let result = db.add_intervention(
+        &event.download_id,
+        &format!("{:?}", event.event_type),
+        &event.message,
+        event.health.as_ref().map(|h| h.total_throughput_bps as i64),
+        event.health.as_ref().map(|h| h.active_connections as i64),
+    );
+    if let Err(e) = result {
+        println!("[Watchdog] Failed to record intervention: {}", e);
+    }
+}
+
 /// Create and emit a health update event
 pub fn emit_health_update(app_handle: &AppHandle, download_id: &str) {
     if let Some(health) = HEALTH_REGISTRY.get_health(download_id) {
@@ -403,7 +487,7 @@ pub fn emit_health_update(app_handle: &AppHandle, download_id: &str) {
 }
 
 /// Format bytes to human-readable string
-fn format_bytes(bytes: u64) -> String {
+pub(crate) fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
     const GB: u64 = MB * 1024;
@@ -348,7 +348,8 @@ export function SettingsPage() {
     const checkForUpdates = async (silent: boolean = false) => {
         setUpdateChecking(true);
         try {
-            const info = await api.checkForUpdates();
+            // A check the user asked for goes out even in offline mode
+            const info = await api.checkForUpdates(!silent);
             setUpdateInfo(info);
             if (info.available && !silent) {
                 toast.success(`Update available: v${info.version}`);
@@ -369,8 +369,8 @@ export const api = {
     },
 
     // App Updates - Rust backend
-    async checkForUpdates(): Promise<UpdateInfo> {
-        return invoke('check_for_updates');
+    async checkForUpdates(userInitiated: boolean = false): Promise<UpdateInfo> {
+        return invoke('check_for_updates', { userInitiated });
     },
 
     async downloadAndInstallUpdate(): Promise<void> {